@@ -0,0 +1,337 @@
+// ============================================
+// BirchVault Desktop - File Attachments
+// ============================================
+//
+// Each attachment gets its own random file key; the file key and file name
+// are encrypted with the vault key and stored in `attachments`. The blob is
+// encrypted in 1 MB chunks so large files never have to fit in memory:
+//
+//   "BVAT" | version (1) | chunks...
+//   chunk: last flag (1) | iv (12) | length (4, BE) | ciphertext
+//
+// Each chunk authenticates its index and last flag as associated data, so
+// chunks can't be reordered, dropped or the file cut short unnoticed.
+// Blobs are uploaded to Supabase Storage on sync and fetched on demand.
+
+use crate::crypto::{self, IV_LENGTH};
+use crate::db::{Attachment, Database};
+use crate::error::{AppError, Result};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+const MAGIC: &[u8; 4] = b"BVAT";
+const VERSION: u8 = 1;
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Largest file that can be attached (matches the storage bucket limit)
+pub const MAX_ATTACHMENT_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    pub id: String,
+    pub item_id: String,
+    pub file_name: String,
+    pub size: i64,
+    pub created_at: String,
+    pub synced: bool,
+    /// Whether the blob is on this device yet
+    pub downloaded: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentPhase {
+    Encrypting,
+    Downloading,
+    Decrypting,
+}
+
+/// Emitted as `attachment-progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentProgress {
+    pub attachment_id: String,
+    pub phase: AttachmentPhase,
+    pub processed: u64,
+    pub total: u64,
+}
+
+// ============================================
+// Operations
+// ============================================
+
+/// Encrypt `source` and attach it to `item_id`; the upload happens on the next sync
+pub fn add(
+    db: &Database,
+    item_id: &str,
+    source: &Path,
+    vault_key: &[u8],
+    on_progress: impl Fn(AttachmentProgress),
+) -> Result<AttachmentInfo> {
+    db.get_vault_item(item_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Vault item {}", item_id)))?;
+
+    let size = std::fs::metadata(source)?.len();
+    if size > MAX_ATTACHMENT_BYTES {
+        return Err(AppError::InvalidOperation(format!(
+            "Attachments can be at most {} MB",
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        )));
+    }
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::InvalidOperation("Not a file".to_string()))?;
+
+    let id = Uuid::new_v4().to_string();
+    let file_key = crypto::generate_key();
+    let blob_path = db.attachment_blob_path(&id);
+    if let Some(parent) = blob_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    encrypt_file(source, &blob_path, &file_key, |processed| {
+        on_progress(AttachmentProgress {
+            attachment_id: id.clone(),
+            phase: AttachmentPhase::Encrypting,
+            processed,
+            total: size,
+        })
+    })?;
+
+    let attachment = Attachment {
+        id,
+        item_id: item_id.to_string(),
+        encrypted_name: crypto::encrypt_to_json(&file_name, vault_key)?,
+        encrypted_key: crypto::encrypt_to_json(&BASE64.encode(file_key), vault_key)?,
+        size: size as i64,
+        created_at: Utc::now().to_rfc3339(),
+        synced_at: None,
+    };
+    if let Err(e) = db.insert_attachment(&attachment) {
+        let _ = std::fs::remove_file(&blob_path);
+        return Err(e);
+    }
+
+    info(db, attachment, vault_key)
+}
+
+pub fn list(db: &Database, item_id: &str, vault_key: &[u8]) -> Result<Vec<AttachmentInfo>> {
+    db.get_attachments(item_id)?
+        .into_iter()
+        .map(|attachment| info(db, attachment, vault_key))
+        .collect()
+}
+
+fn info(db: &Database, attachment: Attachment, vault_key: &[u8]) -> Result<AttachmentInfo> {
+    Ok(AttachmentInfo {
+        file_name: crypto::decrypt_from_json(&attachment.encrypted_name, vault_key)?,
+        downloaded: db.attachment_blob_path(&attachment.id).exists(),
+        synced: attachment.synced_at.is_some(),
+        id: attachment.id,
+        item_id: attachment.item_id,
+        size: attachment.size,
+        created_at: attachment.created_at,
+    })
+}
+
+/// Decrypt a downloaded attachment to `destination`
+pub fn save_to(
+    db: &Database,
+    attachment: &Attachment,
+    destination: &Path,
+    vault_key: &[u8],
+    on_progress: impl Fn(AttachmentProgress),
+) -> Result<()> {
+    let file_key = BASE64
+        .decode(crypto::decrypt_from_json(
+            &attachment.encrypted_key,
+            vault_key,
+        )?)
+        .map_err(|_| AppError::Encryption("Invalid attachment key".to_string()))?;
+
+    decrypt_file(
+        &db.attachment_blob_path(&attachment.id),
+        destination,
+        &file_key,
+        |processed| {
+            on_progress(AttachmentProgress {
+                attachment_id: attachment.id.clone(),
+                phase: AttachmentPhase::Decrypting,
+                processed,
+                total: attachment.size as u64,
+            })
+        },
+    )
+}
+
+/// Remove the attachment here and, on the next sync, from the server
+pub fn delete(db: &Database, id: &str) -> Result<()> {
+    db.delete_attachment(id)?;
+    match std::fs::remove_file(db.attachment_blob_path(id)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// ============================================
+// Chunked Encryption
+// ============================================
+
+fn chunk_aad(index: u64, last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&index.to_be_bytes());
+    aad[8] = last as u8;
+    aad
+}
+
+/// Fill `buf` as far as the reader allows, returning how much was read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Write via a temporary file next to `dest`, renamed into place on success
+fn write_atomically(
+    dest: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let mut temp = dest.as_os_str().to_os_string();
+    temp.push(".part");
+    let temp = std::path::PathBuf::from(temp);
+    let result = (|| {
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => Ok(std::fs::rename(&temp, dest)?),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+fn encrypt_file(source: &Path, dest: &Path, key: &[u8], on_progress: impl Fn(u64)) -> Result<()> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+    let mut reader = BufReader::new(File::open(source)?);
+
+    write_atomically(dest, |writer| {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        let mut current = vec![0u8; CHUNK_SIZE];
+        let mut current_len = read_full(&mut reader, &mut current)?;
+        let mut next = vec![0u8; CHUNK_SIZE];
+        let mut processed = 0u64;
+
+        for index in 0u64.. {
+            // Read ahead so the final chunk can be marked as such
+            let next_len = if current_len == CHUNK_SIZE {
+                read_full(&mut reader, &mut next)?
+            } else {
+                0
+            };
+            let last = next_len == 0;
+
+            let mut iv = [0u8; IV_LENGTH];
+            rand::thread_rng().fill_bytes(&mut iv);
+            let ciphertext = cipher
+                .encrypt(
+                    Nonce::from_slice(&iv),
+                    Payload {
+                        msg: &current[..current_len],
+                        aad: &chunk_aad(index, last),
+                    },
+                )
+                .map_err(|_| AppError::Encryption("Encryption failed".to_string()))?;
+
+            writer.write_all(&[last as u8])?;
+            writer.write_all(&iv)?;
+            writer.write_all(&(ciphertext.len() as u32).to_be_bytes())?;
+            writer.write_all(&ciphertext)?;
+
+            processed += current_len as u64;
+            on_progress(processed);
+
+            if last {
+                break;
+            }
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+        }
+        Ok(())
+    })
+}
+
+fn decrypt_file(source: &Path, dest: &Path, key: &[u8], on_progress: impl Fn(u64)) -> Result<()> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+    let corrupt = || AppError::Encryption("Attachment is corrupt or incomplete".to_string());
+
+    let mut reader = BufReader::new(File::open(source)?);
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header).map_err(|_| corrupt())?;
+    if &header[..4] != MAGIC || header[4] != VERSION {
+        return Err(corrupt());
+    }
+
+    write_atomically(dest, |writer| {
+        let mut processed = 0u64;
+
+        for index in 0u64.. {
+            let mut chunk_header = [0u8; 1 + IV_LENGTH + 4];
+            reader
+                .read_exact(&mut chunk_header)
+                .map_err(|_| corrupt())?;
+            let last = chunk_header[0] == 1;
+            let iv = &chunk_header[1..1 + IV_LENGTH];
+            let mut length = [0u8; 4];
+            length.copy_from_slice(&chunk_header[1 + IV_LENGTH..]);
+            let length = u32::from_be_bytes(length) as usize;
+            if length > CHUNK_SIZE + 16 {
+                return Err(corrupt());
+            }
+
+            let mut ciphertext = vec![0u8; length];
+            reader.read_exact(&mut ciphertext).map_err(|_| corrupt())?;
+            let plaintext = cipher
+                .decrypt(
+                    Nonce::from_slice(iv),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &chunk_aad(index, last),
+                    },
+                )
+                .map_err(|_| corrupt())?;
+            writer.write_all(&plaintext)?;
+
+            processed += plaintext.len() as u64;
+            on_progress(processed);
+
+            if last {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
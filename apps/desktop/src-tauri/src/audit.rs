@@ -0,0 +1,47 @@
+// ============================================
+// BirchVault Desktop - Audit Log
+// ============================================
+//
+// A local record of security-relevant events: unlocks, failed unlocks,
+// exports, views of hidden fields and master password changes. Each entry carries a SHA-256 hash of
+// itself and the entry before it, and the table refuses updates and deletes,
+// so an edited or removed entry shows up when the chain is verified. The log
+// lives in the encrypted vault database and is kept through sign-out.
+
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Unlock,
+    UnlockFailed,
+    Export,
+    HiddenFieldViewed,
+    MasterPasswordChanged,
+}
+
+impl AuditEvent {
+    /// The name stored in `audit_log.event`, as serialised
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEvent::Unlock => "unlock",
+            AuditEvent::UnlockFailed => "unlock_failed",
+            AuditEvent::Export => "export",
+            AuditEvent::HiddenFieldViewed => "hidden_field_viewed",
+            AuditEvent::MasterPasswordChanged => "master_password_changed",
+        }
+    }
+}
+
+/// Append an event to the log. Failing to record it is logged rather than
+/// failing whatever was being done, so a database error can't lock anyone out.
+pub fn record(db: &Database, event: AuditEvent, item_id: Option<&str>, detail: Option<&str>) {
+    if let Err(e) = db.append_audit_entry(event.as_str(), item_id, detail) {
+        log::warn!(
+            "Failed to record {} in the audit log: {}",
+            event.as_str(),
+            e
+        );
+    }
+}
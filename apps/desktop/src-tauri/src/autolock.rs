@@ -0,0 +1,176 @@
+// ============================================
+// BirchVault Desktop - Auto-Lock
+// ============================================
+//
+// Enforces `auto_lock_minutes` from the backend, so the vault locks even when
+// the webview's timer is throttled or never fires. The webview reports user
+// activity with `report_activity`; the monitor also locks when the system
+// sleeps and, on Windows, when the session is locked or the screensaver runs.
+
+use crate::commands::AppState;
+use crate::privacy;
+use crate::tray;
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL_SECS: u64 = 5;
+
+/// A wall-clock gap this much longer than the check interval means the
+/// machine was asleep (the interval itself doesn't count suspended time)
+const SLEEP_GAP_SECS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockReason {
+    Tray,
+    Idle,
+    Sleep,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    SessionLocked,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Screensaver,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLocked {
+    pub reason: LockReason,
+}
+
+/// Record user activity now, pushing back the idle lock
+pub fn touch(state: &AppState) {
+    state
+        .last_activity
+        .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+}
+
+/// Lock the vault and tell the webview why
+pub async fn lock(app: &AppHandle, reason: LockReason) {
+    let state = app.state::<AppState>();
+    if let Err(e) = state.lock().await {
+        log::warn!("Failed to lock vault ({:?}): {}", reason, e);
+    }
+    let _ = app.emit("vault-locked", VaultLocked { reason });
+    tray::refresh(app);
+    privacy::refresh(app);
+}
+
+pub fn spawn_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        let mut last_check = Utc::now();
+        let mut was_locked = true;
+
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            let slept =
+                (now - last_check).num_seconds() > CHECK_INTERVAL_SECS as i64 + SLEEP_GAP_SECS;
+            last_check = now;
+
+            let state = app.state::<AppState>();
+            if *state.is_locked.read().await {
+                was_locked = true;
+                continue;
+            }
+            // Idle time counts from the unlock, not from before it
+            if was_locked {
+                was_locked = false;
+                touch(&state);
+                continue;
+            }
+
+            let minutes = match state.db.get_settings() {
+                Ok(settings) => settings.auto_lock_minutes,
+                Err(e) => {
+                    log::warn!("Failed to read auto-lock setting: {}", e);
+                    continue;
+                }
+            };
+            // 0 turns auto-lock off, as in the webview
+            if minutes <= 0 {
+                continue;
+            }
+
+            let reason = if slept {
+                Some(LockReason::Sleep)
+            } else if let Some(reason) = platform::system_lock_reason() {
+                Some(reason)
+            } else {
+                let idle_ms = now.timestamp_millis() - state.last_activity.load(Ordering::Relaxed);
+                (idle_ms >= i64::from(minutes) * 60_000).then_some(LockReason::Idle)
+            };
+
+            if let Some(reason) = reason {
+                lock(&app, reason).await;
+                was_locked = true;
+            }
+        }
+    });
+}
+
+// ============================================
+// Platform
+// ============================================
+
+#[cfg(windows)]
+mod platform {
+    use super::LockReason;
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_SWITCHDESKTOP,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETSCREENSAVERRUNNING,
+    };
+
+    pub fn system_lock_reason() -> Option<LockReason> {
+        if session_locked() {
+            Some(LockReason::SessionLocked)
+        } else if screensaver_running() {
+            Some(LockReason::Screensaver)
+        } else {
+            None
+        }
+    }
+
+    /// The input desktop can't be opened while the lock screen (Winlogon's
+    /// secure desktop) has it
+    fn session_locked() -> bool {
+        // SAFETY: plain call; the handle is closed below if one is returned
+        let desktop = unsafe { OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP) };
+        if desktop.is_null() {
+            return true;
+        }
+        // SAFETY: `desktop` came from OpenInputDesktop and is closed once
+        unsafe { CloseDesktop(desktop) };
+        false
+    }
+
+    fn screensaver_running() -> bool {
+        let mut running: i32 = 0;
+        // SAFETY: SPI_GETSCREENSAVERRUNNING writes a BOOL to the pointer given
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETSCREENSAVERRUNNING,
+                0,
+                &mut running as *mut i32 as *mut core::ffi::c_void,
+                0,
+            )
+        };
+        ok != 0 && running != 0
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::LockReason;
+
+    /// Session lock and screensaver detection are Windows-only for now; sleep
+    /// and idle still lock everywhere
+    pub fn system_lock_reason() -> Option<LockReason> {
+        None
+    }
+}
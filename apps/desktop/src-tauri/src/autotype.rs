@@ -0,0 +1,243 @@
+// ============================================
+// BirchVault Desktop - Auto-Type
+// ============================================
+//
+// Types an item's credentials into whichever window has focus, for apps the
+// browser extension can't reach. Sequences use KeePass-style placeholders:
+// `{USERNAME}{TAB}{PASSWORD}{ENTER}` by default, overridable per item via
+// the payload's `autoTypeSequence`.
+//
+// Keystrokes are injected with SendInput on Windows. Other platforms would
+// need accessibility permissions (macOS) or a compositor protocol (Wayland),
+// so auto-type reports itself as unsupported there.
+
+use crate::error::{AppError, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+
+/// Time for focus to return to the target window once ours is minimised
+pub const FOCUS_DELAY_MS: u64 = 400;
+
+/// Upper bound for a single `{DELAY n}`
+const MAX_DELAY_MS: u64 = 10_000;
+
+const MAX_SEQUENCE_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Tab,
+    Enter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Text(String),
+    Key(Key),
+    Delay(u64),
+    Username,
+    Password,
+}
+
+fn invalid(detail: &str) -> AppError {
+    AppError::InvalidOperation(format!("Invalid auto-type sequence: {}", detail))
+}
+
+/// Split a sequence into literal text and placeholders. `{{}` and `{}}` type
+/// literal braces, as in KeePass.
+pub fn parse(sequence: &str) -> Result<Vec<Step>> {
+    if sequence.len() > MAX_SEQUENCE_LENGTH {
+        return Err(invalid("too long"));
+    }
+
+    let mut steps = Vec::new();
+    let mut text = String::new();
+    let mut rest = sequence;
+
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // "{}}" would otherwise end at its first brace
+        let end = if rest.starts_with("{}}") {
+            2
+        } else {
+            rest.find('}').ok_or_else(|| invalid("unclosed \"{\""))?
+        };
+        let token = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let step = match token.to_uppercase().as_str() {
+            "{" | "}" => {
+                text.push_str(token);
+                continue;
+            }
+            "USERNAME" => Step::Username,
+            "PASSWORD" => Step::Password,
+            "TAB" => Step::Key(Key::Tab),
+            "ENTER" => Step::Key(Key::Enter),
+            other => match other.strip_prefix("DELAY ") {
+                Some(ms) => {
+                    let ms: u64 = ms.trim().parse().map_err(|_| invalid(token))?;
+                    Step::Delay(ms.min(MAX_DELAY_MS))
+                }
+                None => return Err(invalid(&format!("unknown placeholder {{{}}}", token))),
+            },
+        };
+
+        if !text.is_empty() {
+            steps.push(Step::Text(std::mem::take(&mut text)));
+        }
+        steps.push(step);
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        steps.push(Step::Text(text));
+    }
+    Ok(steps)
+}
+
+/// The item's own sequence, falling back to the default
+pub fn item_sequence(data: &Value) -> &str {
+    data.get("autoTypeSequence")
+        .and_then(Value::as_str)
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or(DEFAULT_SEQUENCE)
+}
+
+/// Replace the credential placeholders with the item's values
+pub fn resolve(steps: Vec<Step>, data: &Value) -> Vec<Step> {
+    let field = |pointer: &str| {
+        data.pointer(pointer)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    steps
+        .into_iter()
+        .map(|step| match step {
+            Step::Username => Step::Text(field("/login/username")),
+            Step::Password => Step::Text(field("/login/password")),
+            other => other,
+        })
+        .collect()
+}
+
+/// Send resolved steps to the focused window, pausing `delay_ms` between keystrokes.
+/// Blocks for the duration, so run it off the async runtime.
+pub fn send(steps: &[Step], delay_ms: u32) -> Result<()> {
+    let delay = Duration::from_millis(delay_ms as u64);
+
+    for step in steps {
+        match step {
+            Step::Text(text) => {
+                for unit in text.encode_utf16() {
+                    platform::send_unicode(unit)?;
+                    std::thread::sleep(delay);
+                }
+            }
+            Step::Key(key) => {
+                platform::send_key(*key)?;
+                std::thread::sleep(delay);
+            }
+            Step::Delay(ms) => std::thread::sleep(Duration::from_millis(*ms)),
+            Step::Username | Step::Password => {
+                return Err(AppError::InvalidOperation(
+                    "Auto-type sequence was not resolved".to_string(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn is_supported() -> bool {
+    cfg!(windows)
+}
+
+// ============================================
+// Platform
+// ============================================
+
+#[cfg(windows)]
+mod platform {
+    use super::Key;
+    use crate::error::{AppError, Result};
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_RETURN, VK_TAB,
+    };
+
+    fn keyboard_input(vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    /// Press and release in one call so nothing can interleave
+    fn press(vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> Result<()> {
+        let inputs = [
+            keyboard_input(vk, scan, flags),
+            keyboard_input(vk, scan, flags | KEYEVENTF_KEYUP),
+        ];
+        // SAFETY: `inputs` is a valid array of INPUT for the length and size passed
+        let sent = unsafe {
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_ptr(),
+                std::mem::size_of::<INPUT>() as i32,
+            )
+        };
+
+        // Blocked by UIPI when the target runs elevated and we don't
+        if sent as usize != inputs.len() {
+            return Err(AppError::InvalidOperation(
+                "The focused window did not accept typed input".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// One UTF-16 code unit, independent of the keyboard layout
+    pub fn send_unicode(unit: u16) -> Result<()> {
+        press(0, unit, KEYEVENTF_UNICODE)
+    }
+
+    pub fn send_key(key: Key) -> Result<()> {
+        let vk = match key {
+            Key::Tab => VK_TAB,
+            Key::Enter => VK_RETURN,
+        };
+        press(vk, 0, 0)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::Key;
+    use crate::error::{AppError, Result};
+
+    fn unsupported() -> AppError {
+        AppError::InvalidOperation("Auto-type is not supported on this platform".to_string())
+    }
+
+    pub fn send_unicode(_unit: u16) -> Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn send_key(_key: Key) -> Result<()> {
+        Err(unsupported())
+    }
+}
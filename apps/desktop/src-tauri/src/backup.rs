@@ -0,0 +1,468 @@
+// ============================================
+// BirchVault Desktop - Encrypted Backups
+// ============================================
+//
+// File layout (all integers little-endian):
+//   magic "BVBK" | version u8 | kdf u8 | m_cost u32 | t_cost u32 | p_cost u32
+//   | salt [16] | iv [12] | AES-256-GCM ciphertext of the JSON payload
+// The header is authenticated as associated data. Items stay encrypted with
+// the vault key inside the payload, so a backup is only useful to its account.
+// Manual exports use an Argon2id password key; scheduled backups run
+// unattended, so they are sealed with a key derived from the vault key.
+
+use crate::commands::AppState;
+use crate::crypto::{IV_LENGTH, KEY_LENGTH};
+use crate::db::{AppSettings, Database, Folder, VaultItem};
+use crate::error::{AppError, Result};
+use crate::profiles;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAGIC: &[u8; 4] = b"BVBK";
+const FORMAT_VERSION: u8 = 1;
+const KDF_ARGON2ID: u8 = 1;
+const KDF_VAULT_KEY: u8 = 2;
+const SALT_LENGTH: usize = 16;
+const HEADER_LENGTH: usize = 4 + 1 + 1 + 4 * 3 + SALT_LENGTH + IV_LENGTH;
+
+// Argon2id cost for new backups; restores use whatever the header says
+const ARGON2_M_COST_KIB: u32 = 64 * 1024;
+const ARGON2_T_COST: u32 = 3;
+const ARGON2_P_COST: u32 = 4;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// What a backup file is sealed with
+#[derive(Clone, Copy)]
+pub enum BackupKey<'a> {
+    Password(&'a str),
+    VaultKey(&'a [u8]),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupPayload {
+    created_at: String,
+    app_version: String,
+    folders: Vec<Folder>,
+    items: Vec<VaultItem>,
+    settings: AppSettings,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub created_at: String,
+    pub items: usize,
+    pub folders: usize,
+}
+
+// ============================================
+// Key Derivation
+// ============================================
+
+fn derive_key(
+    password: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<Vec<u8>> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_LENGTH))
+        .map_err(|e| AppError::Encryption(format!("Invalid backup parameters: {}", e)))?;
+
+    let mut key = vec![0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::Encryption(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// File key for scheduled backups; the salt keeps it distinct per file
+fn derive_vault_file_key(vault_key: &[u8], salt: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(b"birchvault-backup")
+        .chain_update(salt)
+        .chain_update(vault_key)
+        .finalize()
+        .to_vec()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+// ============================================
+// Export / Restore
+// ============================================
+
+/// Write items, folders (including trashed items) and settings to an encrypted backup file
+pub fn export(
+    db: &Database,
+    path: &Path,
+    key: BackupKey,
+    app_version: &str,
+) -> Result<BackupSummary> {
+    if let BackupKey::Password(password) = key {
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AppError::InvalidOperation(format!(
+                "Backup password must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+    }
+
+    let mut items = db.get_all_vault_items()?;
+    items.extend(db.get_trashed_items()?);
+
+    let payload = BackupPayload {
+        created_at: Utc::now().to_rfc3339(),
+        app_version: app_version.to_string(),
+        folders: db.get_all_folders()?,
+        items,
+        settings: db.get_settings()?,
+    };
+
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut iv = [0u8; IV_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut iv);
+
+    let (kdf, costs) = match key {
+        BackupKey::Password(_) => (
+            KDF_ARGON2ID,
+            [ARGON2_M_COST_KIB, ARGON2_T_COST, ARGON2_P_COST],
+        ),
+        BackupKey::VaultKey(_) => (KDF_VAULT_KEY, [0; 3]),
+    };
+
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(MAGIC);
+    header.push(FORMAT_VERSION);
+    header.push(kdf);
+    for cost in costs {
+        header.extend_from_slice(&cost.to_le_bytes());
+    }
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&iv);
+
+    let key = match key {
+        BackupKey::Password(password) => derive_key(password, &salt, costs[0], costs[1], costs[2])?,
+        BackupKey::VaultKey(vault_key) => derive_vault_file_key(vault_key, &salt),
+    };
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&iv),
+            Payload {
+                msg: &serde_json::to_vec(&payload)?,
+                aad: &header,
+            },
+        )
+        .map_err(|_| AppError::Encryption("Encryption failed".to_string()))?;
+
+    let mut file = header;
+    file.extend_from_slice(&ciphertext);
+    std::fs::write(path, file)?;
+
+    Ok(BackupSummary {
+        created_at: payload.created_at,
+        items: payload.items.len(),
+        folders: payload.folders.len(),
+    })
+}
+
+fn decrypt_file(
+    contents: &[u8],
+    password: Option<&str>,
+    vault_key: Option<&[u8]>,
+) -> Result<BackupPayload> {
+    if contents.len() < HEADER_LENGTH || &contents[..4] != MAGIC {
+        return Err(AppError::InvalidOperation(
+            "Not a BirchVault backup".to_string(),
+        ));
+    }
+    if contents[4] != FORMAT_VERSION {
+        return Err(AppError::InvalidOperation(format!(
+            "Unsupported backup version {}",
+            contents[4]
+        )));
+    }
+
+    let (header, ciphertext) = contents.split_at(HEADER_LENGTH);
+    let salt = &header[18..18 + SALT_LENGTH];
+    let iv = &header[18 + SALT_LENGTH..];
+
+    let key = match header[5] {
+        KDF_ARGON2ID => {
+            let password = password.ok_or_else(|| {
+                AppError::InvalidOperation("This backup needs its password".to_string())
+            })?;
+            derive_key(
+                password,
+                salt,
+                read_u32(header, 6),
+                read_u32(header, 10),
+                read_u32(header, 14),
+            )?
+        }
+        KDF_VAULT_KEY => derive_vault_file_key(vault_key.ok_or(AppError::VaultLocked)?, salt),
+        other => {
+            return Err(AppError::InvalidOperation(format!(
+                "Unsupported backup key type {}",
+                other
+            )))
+        }
+    };
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(iv),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| AppError::Encryption("Wrong backup password or corrupted file".to_string()))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Merge a backup into the local store; restored records are queued for sync
+pub fn restore(
+    db: &Database,
+    path: &Path,
+    password: Option<&str>,
+    vault_key: Option<&[u8]>,
+) -> Result<BackupSummary> {
+    let contents = std::fs::read(path)?;
+    let payload = decrypt_file(&contents, password, vault_key)?;
+
+    db.restore_records(&payload.folders, &payload.items)?;
+    db.save_settings(&payload.settings)?;
+
+    Ok(BackupSummary {
+        created_at: payload.created_at,
+        items: payload.items.len(),
+        folders: payload.folders.len(),
+    })
+}
+
+// ============================================
+// Scheduled Backups
+// ============================================
+
+pub const FILE_EXTENSION: &str = "bvbackup";
+const FILE_PREFIX: &str = "birchvault-";
+
+const META_LAST_SCHEDULED_BACKUP: &str = "last_scheduled_backup";
+const CHECK_INTERVAL_SECS: u64 = 15 * 60;
+
+pub const MAX_RETENTION_COUNT: u32 = 365;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    pub file_name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+}
+
+fn schedule_interval(schedule: &str) -> Option<Duration> {
+    match schedule {
+        "daily" => Some(Duration::days(1)),
+        "weekly" => Some(Duration::weeks(1)),
+        _ => None,
+    }
+}
+
+pub fn validate_schedule(settings: &AppSettings) -> Result<()> {
+    if !matches!(
+        settings.backup_schedule.as_str(),
+        "off" | "daily" | "weekly"
+    ) {
+        return Err(AppError::InvalidOperation(format!(
+            "Unknown backup schedule \"{}\"",
+            settings.backup_schedule
+        )));
+    }
+    if !(1..=MAX_RETENTION_COUNT).contains(&settings.backup_retention_count) {
+        return Err(AppError::InvalidOperation(format!(
+            "Backups kept must be between 1 and {}",
+            MAX_RETENTION_COUNT
+        )));
+    }
+    Ok(())
+}
+
+/// The configured backup directory, or `backups` in the profile's data directory
+pub fn backup_dir(app: &AppHandle, settings: &AppSettings) -> Result<PathBuf> {
+    match &settings.backup_directory {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|dir| profiles::data_dir(&dir, &profiles::active_id()).join("backups"))
+            .map_err(|e| AppError::InvalidOperation(e.to_string())),
+    }
+}
+
+fn is_backup_file_name(file_name: &str) -> bool {
+    file_name.starts_with(FILE_PREFIX)
+        && file_name.ends_with(&format!(".{}", FILE_EXTENSION))
+        && !file_name.contains(['/', '\\'])
+}
+
+/// Scheduled backups in `dir`, newest first
+pub fn list(dir: &Path) -> Result<Vec<BackupFile>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !is_backup_file_name(&file_name) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let created_at: DateTime<Utc> = metadata.modified()?.into();
+        files.push(BackupFile {
+            file_name,
+            path: entry.path().to_string_lossy().to_string(),
+            created_at: created_at.to_rfc3339(),
+            size_bytes: metadata.len(),
+        });
+    }
+
+    files.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(files)
+}
+
+/// Delete a scheduled backup by file name (never a path, so nothing outside `dir`)
+pub fn delete(dir: &Path, file_name: &str) -> Result<()> {
+    if !is_backup_file_name(file_name) {
+        return Err(AppError::InvalidOperation(format!(
+            "Not a scheduled backup: {}",
+            file_name
+        )));
+    }
+    std::fs::remove_file(dir.join(file_name))?;
+    Ok(())
+}
+
+/// Write a backup sealed with the vault key and drop any beyond the retention count
+fn run_scheduled(
+    db: &Database,
+    dir: &Path,
+    vault_key: &[u8],
+    keep: u32,
+    app_version: &str,
+) -> Result<BackupSummary> {
+    std::fs::create_dir_all(dir)?;
+    let file_name = format!(
+        "{}{}.{}",
+        FILE_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        FILE_EXTENSION
+    );
+    let summary = export(
+        db,
+        &dir.join(file_name),
+        BackupKey::VaultKey(vault_key),
+        app_version,
+    )?;
+
+    for old in list(dir)?.into_iter().skip(keep as usize) {
+        if let Err(e) = delete(dir, &old.file_name) {
+            log::warn!("Failed to remove old backup {}: {}", old.file_name, e);
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Make the next scheduled backup due straight away, e.g. once a key rotation
+/// has left the earlier ones sealed with a key the vault no longer uses
+pub fn run_soon(db: &Database) -> Result<()> {
+    db.delete_meta(META_LAST_SCHEDULED_BACKUP)
+}
+
+/// Back up on schedule while the vault is unlocked, emitting `backup-created`/`backup-failed`
+pub fn spawn_schedule_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppState>();
+            let Ok(settings) = state.db.get_settings() else {
+                continue;
+            };
+            let Some(every) = schedule_interval(&settings.backup_schedule) else {
+                continue;
+            };
+            // Unattended backups need the vault key, so they only run while unlocked
+            if *state.is_locked.read().await {
+                continue;
+            }
+            let Ok(vault_key) = state.encryption_key().await else {
+                continue;
+            };
+
+            let due = state
+                .db
+                .get_meta(META_LAST_SCHEDULED_BACKUP)
+                .ok()
+                .flatten()
+                .and_then(|last| DateTime::parse_from_rfc3339(&last).ok())
+                .map(|last| last.with_timezone(&Utc) + every <= Utc::now())
+                .unwrap_or(true);
+            if !due {
+                continue;
+            }
+
+            let app_version = app.package_info().version.to_string();
+            let result = backup_dir(&app, &settings).and_then(|dir| {
+                run_scheduled(
+                    &state.db,
+                    &dir,
+                    &vault_key,
+                    settings.backup_retention_count,
+                    &app_version,
+                )
+            });
+
+            match result {
+                Ok(summary) => {
+                    let _ = state
+                        .db
+                        .set_meta(META_LAST_SCHEDULED_BACKUP, &summary.created_at);
+                    let _ = app.emit("backup-created", summary);
+                }
+                Err(e) => {
+                    log::warn!("Scheduled backup failed: {}", e);
+                    let _ = app.emit("backup-failed", e.to_string());
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,182 @@
+// ============================================
+// BirchVault Desktop - Breach Checking (Have I Been Pwned)
+// ============================================
+//
+// Uses the HIBP range API with k-anonymity: only the first five hex digits
+// of a password's SHA-1 leave the machine. Range responses are cached so a
+// vault scan doesn't re-download ranges it has already seen.
+
+use crate::crypto;
+use crate::db::{Database, VaultItem};
+use crate::error::{AppError, Result};
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+const RANGE_URL: &str = "https://api.pwnedpasswords.com/range";
+const PREFIX_LENGTH: usize = 5;
+const CACHE_HOURS: i64 = 24;
+
+/// Minimum gap between requests to the API
+const MIN_REQUEST_INTERVAL_MS: u64 = 150;
+
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachCheck {
+    pub breached: bool,
+    /// Times the password appears in known breaches
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachedItem {
+    pub item_id: String,
+    pub name: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachScanReport {
+    pub scanned: usize,
+    pub breached: Vec<BreachedItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachScanProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect()
+}
+
+// ============================================
+// Range API
+// ============================================
+
+async fn fetch_range(db: &Database, client: &Client, prefix: &str) -> Result<String> {
+    let fresh_after = (Utc::now() - Duration::hours(CACHE_HOURS)).to_rfc3339();
+    if let Some(cached) = db.get_breach_range(prefix, &fresh_after)? {
+        return Ok(cached);
+    }
+
+    {
+        let mut last = LAST_REQUEST.lock().await;
+        if let Some(last) = *last {
+            let next = last + tokio::time::Duration::from_millis(MIN_REQUEST_INTERVAL_MS);
+            tokio::time::sleep_until(next).await;
+        }
+        *last = Some(Instant::now());
+    }
+
+    let response = client
+        .get(format!("{}/{}", RANGE_URL, prefix))
+        .header("User-Agent", "BirchVault-Desktop")
+        // Padded responses hide which prefix was asked for by size
+        .header("Add-Padding", "true")
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(AppError::InvalidOperation(format!(
+            "Breach check failed: {}",
+            response.status()
+        )));
+    }
+
+    let body = response.text().await?;
+    db.save_breach_range(prefix, &body)?;
+    Ok(body)
+}
+
+/// Count for `suffix` in a range response ("SUFFIX:COUNT" lines; padding has count 0)
+fn count_in_range(range: &str, suffix: &str) -> u64 {
+    range
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+pub async fn check_password(db: &Database, client: &Client, password: &str) -> Result<BreachCheck> {
+    let hash = sha1_hex(password);
+    let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+    let range = fetch_range(db, client, prefix).await?;
+    let count = count_in_range(&range, suffix);
+
+    Ok(BreachCheck {
+        breached: count > 0,
+        count,
+    })
+}
+
+// ============================================
+// Vault Scan
+// ============================================
+
+/// Name and password of each decryptable login that has a password
+fn login_passwords(items: &[VaultItem], key: &[u8]) -> Vec<(String, String, String)> {
+    items
+        .iter()
+        .filter(|item| item.item_type == "login")
+        .filter_map(|item| {
+            let plaintext = crypto::decrypt_from_json(&item.encrypted_data, key).ok()?;
+            let data: Value = serde_json::from_str(&plaintext).ok()?;
+            let password = data["login"]["password"].as_str()?.to_string();
+            let name = data["name"].as_str().unwrap_or_default().to_string();
+            (!password.is_empty()).then(|| (item.id.clone(), name, password))
+        })
+        .collect()
+}
+
+/// Check every login password; each distinct password is looked up once
+pub async fn scan_vault(
+    db: &Database,
+    client: &Client,
+    key: &[u8],
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<BreachScanReport> {
+    let logins = login_passwords(&db.get_all_vault_items()?, key);
+    let total = logins.len();
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    let mut report = BreachScanReport {
+        scanned: total,
+        ..Default::default()
+    };
+
+    for (index, (item_id, name, password)) in logins.into_iter().enumerate() {
+        let count = match counts.get(&password) {
+            Some(count) => *count,
+            None => {
+                let count = check_password(db, client, &password).await?.count;
+                counts.insert(password, count);
+                count
+            }
+        };
+
+        if count > 0 {
+            report.breached.push(BreachedItem {
+                item_id,
+                name,
+                count,
+            });
+        }
+        on_progress(index + 1, total);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,162 @@
+// ============================================
+// BirchVault Desktop - Clipboard
+// ============================================
+//
+// On Windows, copied secrets carry the formats that keep them out of
+// clipboard history (Win+V), Cloud Clipboard and clipboard monitors. An
+// excluded copy never reaches history, so there is nothing there to purge
+// later; Win32 can't remove a single history entry anyway, and clearing the
+// whole history would take the user's own entries with it. Clearing empties
+// the live clipboard and then checks that it really is empty. Elsewhere the
+// clipboard plugin writes plain text as before.
+
+use crate::error::{AppError, Result};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Put a secret on the clipboard, excluded from history where the platform allows
+pub fn write_secret(app: &AppHandle, text: &str) -> Result<()> {
+    platform::write(app, Some(text))
+}
+
+/// Empty the clipboard and make sure nothing is left on it
+pub fn clear(app: &AppHandle) -> Result<()> {
+    platform::write(app, None)?;
+
+    // An empty clipboard reads back as an error on some platforms
+    match app.clipboard().read_text() {
+        Ok(current) if !current.is_empty() => Err(AppError::InvalidOperation(
+            "The clipboard could not be cleared".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// ============================================
+// Platform
+// ============================================
+
+#[cfg(windows)]
+mod platform {
+    use crate::error::{AppError, Result};
+    use tauri::AppHandle;
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows_sys::Win32::System::Memory::{
+        GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE,
+    };
+    use windows_sys::Win32::System::Ole::CF_UNICODETEXT;
+
+    /// Another app may hold the clipboard for a moment; retry before giving up
+    const OPEN_ATTEMPTS: u32 = 10;
+    const OPEN_RETRY_MS: u64 = 20;
+
+    /// Open for the lifetime of the value
+    struct OpenedClipboard;
+
+    impl OpenedClipboard {
+        fn open() -> Result<Self> {
+            for _ in 0..OPEN_ATTEMPTS {
+                // SAFETY: a null owner window is allowed; closed on drop
+                if unsafe { OpenClipboard(std::ptr::null_mut()) } != 0 {
+                    return Ok(Self);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(OPEN_RETRY_MS));
+            }
+            Err(clipboard_error(
+                "The clipboard is in use by another application",
+            ))
+        }
+    }
+
+    impl Drop for OpenedClipboard {
+        fn drop(&mut self) {
+            // SAFETY: only constructed after OpenClipboard succeeded
+            unsafe { CloseClipboard() };
+        }
+    }
+
+    pub fn write(_app: &AppHandle, text: Option<&str>) -> Result<()> {
+        let _clipboard = OpenedClipboard::open()?;
+        // SAFETY: the clipboard is open on this thread
+        if unsafe { EmptyClipboard() } == 0 {
+            return Err(clipboard_error("Failed to empty the clipboard"));
+        }
+
+        if let Some(text) = text {
+            let bytes: Vec<u8> = text
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            set_data(CF_UNICODETEXT as u32, &bytes)?;
+        }
+
+        // Any data under this format keeps history, cloud sync and monitors away
+        set_data(
+            register("ExcludeClipboardContentFromMonitorProcessing")?,
+            &0u32.to_le_bytes(),
+        )?;
+        set_data(
+            register("CanIncludeInClipboardHistory")?,
+            &0u32.to_le_bytes(),
+        )?;
+        set_data(register("CanUploadToCloudClipboard")?, &0u32.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn register(name: &str) -> Result<u32> {
+        let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        // SAFETY: `wide` is a NUL-terminated UTF-16 string that outlives the call
+        let format = unsafe { RegisterClipboardFormatW(wide.as_ptr()) };
+        if format == 0 {
+            return Err(clipboard_error("Failed to register a clipboard format"));
+        }
+        Ok(format)
+    }
+
+    /// Hand a copy of `bytes` to the open clipboard, which then owns it
+    fn set_data(format: u32, bytes: &[u8]) -> Result<()> {
+        // SAFETY: the block is allocated with the requested size, written only
+        // within it while locked, and freed here unless the clipboard took it
+        unsafe {
+            let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+            if memory.is_null() {
+                return Err(clipboard_error("Failed to allocate clipboard memory"));
+            }
+            let target = GlobalLock(memory);
+            if target.is_null() {
+                GlobalFree(memory);
+                return Err(clipboard_error("Failed to allocate clipboard memory"));
+            }
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), target as *mut u8, bytes.len());
+            GlobalUnlock(memory);
+
+            if SetClipboardData(format, memory).is_null() {
+                GlobalFree(memory);
+                return Err(clipboard_error("Failed to write to the clipboard"));
+            }
+        }
+        Ok(())
+    }
+
+    fn clipboard_error(message: &str) -> AppError {
+        AppError::InvalidOperation(message.to_string())
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use crate::error::{AppError, Result};
+    use tauri::AppHandle;
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    /// No history exclusion outside Windows yet
+    pub fn write(app: &AppHandle, text: Option<&str>) -> Result<()> {
+        app.clipboard()
+            .write_text(text.unwrap_or_default())
+            .map_err(|e| AppError::InvalidOperation(e.to_string()))
+    }
+}
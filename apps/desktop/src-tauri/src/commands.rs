@@ -2,17 +2,82 @@
 // BirchVault Desktop - Tauri Commands
 // ============================================
 
-use crate::db::{AppSettings, Database, Folder, UserSession, VaultItem};
+use crate::attachments::{self, AttachmentInfo, AttachmentPhase, AttachmentProgress};
+use crate::audit::{self, AuditEvent};
+use crate::autolock;
+use crate::autotype;
+use crate::backup::{self, BackupFile, BackupKey, BackupSummary};
+use crate::breach::{self, BreachCheck, BreachScanProgress, BreachScanReport};
+use crate::clipboard;
+use crate::crypto::{self, Verification};
+use crate::db::{
+    AppSettings, AuditEntry, AuditFilter, AuditVerification, Collection, ConflictResolution,
+    Database, Folder, ItemFlags, ItemRevision, ItemSort, OrgItem, Organization, PairedDevice,
+    RecoverableDeletion, SyncConflict, SyncError, SyncHistoryEntry, TrashFilter, UserSession,
+    VaultItem, VaultItemData, VaultItemSummary,
+};
+use crate::deferred::{self, DeferredSignIn};
+use crate::devices::{self, DevicePairing};
+use crate::diagnostics::{self, SchemaReport, SupportBundle};
+use crate::emergency::{self, EmergencyAccess, EmergencyVaultItem};
 use crate::error::{AppError, Result};
-use crate::sync::{SupabaseConfig, SyncEngine, SyncStatus};
+use crate::export::{self, CsvExportSummary};
+use crate::fields;
+use crate::filesync::{self, BackendConfig};
+use crate::keypair;
+use crate::i18n::{self, LocaleInfo};
+use crate::icons;
+use crate::items::{self, Card, CardBrand, CardDisplay, CardExpiry, Identity, IdentityDisplay, ItemType};
+use crate::import::{self, ImportFormat, ImportProgress, ImportSummary};
+use crate::notify::{self, NotificationCategory};
+use crate::offline::{self, OfflineProfile};
+use crate::onboarding::{self, OnboardingState, OnboardingStep};
+use crate::organizations::{self, CollectionPermission, OrgMember, OrgRole, OrgVaultItem};
+use crate::passkeys::{self, NewPasskey, PasskeyAssertion, PasskeyRegistration, PasskeySummary};
+use crate::pin::{self, PinStatus};
+use crate::plugins::{self, PendingConsent, PluginActionResult, PluginConsentRequest, PluginInfo};
+use crate::privacy;
+use crate::profiles::{self, Profile};
+use crate::recovery;
+use crate::registration;
+use crate::resume::{self, ResumePayload};
+use crate::retention::{self, RetentionReport, RetentionWarning};
+use crate::rotation::{self, RotationSummary};
+use crate::search::{self, SearchEntry, SearchIndex, SearchResult};
+use crate::sends::{self, NewSend, Send};
+use crate::server::{self, ServerConfig};
+use crate::sharing::{self, ItemShare, SharePermission, SharedItem};
+use crate::strength::{self, MasterPasswordCheck, PasswordAnalysis};
+use crate::sync::{SchemaStatus, SupabaseConfig, SyncEngine, SyncStatus};
+use crate::tray;
+use crate::urimatch::PageUrl;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
 use keyring::Entry;
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
-use tauri::State;
+use tauri::{Emitter, Manager, State};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+/// How long a force-deleted unsynced item can still be recovered
+const RECOVERABLE_DELETE_HOURS: i64 = 72;
+
+/// `app_meta` key set once favourite/type flags have been backfilled from encrypted data
+const META_LEGACY_FLAGS_MIGRATED: &str = "legacy_item_flags_migrated";
+
+/// Limits for the device-local UI state store
+const UI_STATE_MAX_KEY_LENGTH: usize = 128;
+const UI_STATE_MAX_VALUE_BYTES: usize = 64 * 1024;
+
+/// Page size for paged listings when the caller doesn't ask for one, and the most it may ask for
+const PAGE_DEFAULT: u32 = 50;
+const PAGE_MAX: u32 = 500;
+
 // ============================================
 // App State
 // ============================================
@@ -22,6 +87,20 @@ pub struct AppState {
     pub sync_engine: Arc<SyncEngine>,
     pub is_locked: Arc<RwLock<bool>>,
     pub master_key_hash: Arc<RwLock<Option<String>>>,
+    /// Bumped on every copy/clear so a stale auto-clear countdown stops itself
+    pub clipboard_generation: Arc<AtomicU64>,
+    /// Item behind the most recent copy, for the tray's "copy last used" action
+    pub last_copied_item_id: Arc<RwLock<Option<String>>>,
+    /// Raw vault key handed over by the webview, for backend-side encryption (import etc.)
+    pub encryption_key: Arc<RwLock<Option<Vec<u8>>>>,
+    /// Outstanding plugin consents by token; each is redeemed at most once
+    pub plugin_consents: Arc<RwLock<HashMap<String, PendingConsent>>>,
+    /// Decrypted name/username/URL index, only while unlocked
+    pub search_index: Arc<RwLock<Option<SearchIndex>>>,
+    /// Unix millis of the last user activity the webview reported (see autolock.rs)
+    pub last_activity: Arc<AtomicI64>,
+    /// Server work put off by unlocking while Supabase was unreachable (see deferred.rs)
+    pub deferred_sign_in: Arc<RwLock<Option<DeferredSignIn>>>,
 }
 
 impl AppState {
@@ -32,6 +111,95 @@ impl AppState {
             sync_engine,
             is_locked: Arc::new(RwLock::new(true)),
             master_key_hash: Arc::new(RwLock::new(None)),
+            clipboard_generation: Arc::new(AtomicU64::new(0)),
+            last_copied_item_id: Arc::new(RwLock::new(None)),
+            encryption_key: Arc::new(RwLock::new(None)),
+            plugin_consents: Arc::new(RwLock::new(HashMap::new())),
+            search_index: Arc::new(RwLock::new(None)),
+            last_activity: Arc::new(AtomicI64::new(Utc::now().timestamp_millis())),
+            deferred_sign_in: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// The vault key, or `VaultLocked` if the webview hasn't provided it
+    pub async fn encryption_key(&self) -> Result<Vec<u8>> {
+        self.encryption_key
+            .read()
+            .await
+            .clone()
+            .ok_or(AppError::VaultLocked)
+    }
+
+    /// Run `f` against the search index, rebuilding it first if the vault has changed
+    pub async fn with_search_index<T>(&self, f: impl FnOnce(&SearchIndex) -> T) -> Result<T> {
+        let revision = self.db.vault_revision()?;
+        {
+            let index = self.search_index.read().await;
+            if let Some(index) = index.as_ref().filter(|i| i.revision == revision) {
+                return Ok(f(index));
+            }
+        }
+
+        let key = self.encryption_key().await?;
+        let index = SearchIndex::build(&self.db, &key, revision)?;
+        let output = f(&index);
+        *self.search_index.write().await = Some(index);
+        Ok(output)
+    }
+
+    /// Give SQL the item names to sort by, unless it has this revision's already
+    pub async fn load_sort_names(&self) -> Result<()> {
+        if self.db.sort_names_revision()? == Some(self.db.vault_revision()?) {
+            return Ok(());
+        }
+        self.with_search_index(|index| self.db.load_sort_names(index.revision, index.names()))
+            .await?
+    }
+
+    /// Lock the vault and drop everything that could unlock it again
+    pub async fn lock(&self) -> Result<()> {
+        {
+            let mut locked = self.is_locked.write().await;
+            *locked = true;
+        }
+
+        {
+            let mut key_hash = self.master_key_hash.write().await;
+            *key_hash = None;
+        }
+
+        {
+            let mut encryption_key = self.encryption_key.write().await;
+            *encryption_key = None;
+        }
+
+        self.plugin_consents.write().await.clear();
+        *self.deferred_sign_in.write().await = None;
+
+        {
+            let mut search_index = self.search_index.write().await;
+            *search_index = None;
+        }
+        if let Err(e) = self.db.clear_sort_names() {
+            log::warn!("Failed to clear the item names kept for sorting: {}", e);
+        }
+
+        // A running sync needs no key material; let it finish unless told not to
+        if !self.db.get_settings()?.sync_after_lock {
+            self.sync_engine.abort();
+        }
+
+        resume::discard(&self.db)
+    }
+
+    /// Locked, and whether a sync begun before the lock is still running
+    pub async fn lock_state(&self) -> LockState {
+        if !*self.is_locked.read().await {
+            LockState::Unlocked
+        } else if self.sync_engine.get_status().await.is_syncing {
+            LockState::UiLocked
+        } else {
+            LockState::CryptoLocked
         }
     }
 }
@@ -48,12 +216,98 @@ pub struct LoginRequest {
     pub master_key_hash: String,
 }
 
+/// As `LoginRequest`, plus the master password itself, which has to pass
+/// the strength gate before the account is created
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password_hash: String,
+    pub master_key_hash: String,
+    pub master_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterResponse {
+    /// Sign in once the address is confirmed
+    pub confirmation_required: bool,
+    pub login: Option<LoginResponse>,
+    /// Shown once to print; `create_recovery_key` makes one later if the
+    /// address had to be confirmed first
+    pub recovery_key: Option<String>,
+}
+
+/// A forgotten master password's replacement, derived in the webview
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryRequest {
+    pub email: String,
+    pub recovery_key: String,
+    pub new_password_hash: String,
+    pub new_master_key_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryResponse {
+    pub login: LoginResponse,
+    /// Replaces the one used, which no longer fits the master key
+    pub recovery_key: String,
+}
+
+/// Keys derived in the webview from the current and new master passwords,
+/// plus the new password for the strength gate
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeMasterPasswordRequest {
+    pub current_master_key_hash: String,
+    pub new_password_hash: String,
+    pub new_master_key_hash: String,
+    pub new_master_password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginResponse {
     pub user_id: String,
     pub email: String,
     pub access_token: String,
+    /// Local-only profile: no account, no sync, no network features
+    pub offline_profile: bool,
+    /// Unlocked without reaching the server, which is signed in to and synced
+    /// with once it can be; the vault key comes from `get_wrapped_vault_key`
+    #[serde(default)]
+    pub degraded: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinUnlockResponse {
+    #[serde(flatten)]
+    pub account: LoginResponse,
+    /// The vault key, handed back so the webview can decrypt again
+    pub encryption_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeResponse {
+    pub user_id: String,
+    pub email: String,
+    pub access_token: String,
+    pub encryption_key: Option<String>,
+}
+
+/// Key material is dropped on every lock; `UiLocked` only means the sync engine
+/// is still finishing a run with the stored session, so commands that serve
+/// items are refused but queued ciphertext keeps moving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockState {
+    Unlocked,
+    UiLocked,
+    CryptoLocked,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -75,10 +329,27 @@ pub struct UpdateVaultItemRequest {
     pub is_favorite: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePasskeyRequest {
+    #[serde(flatten)]
+    pub passkey: NewPasskey,
+    pub folder_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyCreated {
+    pub item: VaultItem,
+    pub registration: PasskeyRegistration,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateFolderRequest {
     pub name: String,
+    #[serde(default)]
+    pub retention_days: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -94,428 +365,4235 @@ pub struct UpdateFolderRequest {
 
 #[tauri::command]
 pub async fn login(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     request: LoginRequest,
 ) -> std::result::Result<LoginResponse, String> {
     let result: Result<LoginResponse> = async {
+        if offline::is_active(&state.db) {
+            return Err(AppError::InvalidOperation(i18n::t("auth.profile_exists")));
+        }
+
         // Authenticate with Supabase
-        let session = state
+        let session = match state
             .sync_engine
             .authenticate(&request.email, &request.password_hash)
-            .await?;
-
-        // Save session to database
-        state.db.save_session(&session)?;
+            .await
+        {
+            Ok(session) => session,
+            Err(e) if deferred::is_unreachable(&e) => {
+                return sign_in_degraded(&state, request).await?.ok_or(e);
+            }
+            Err(e) => return Err(e),
+        };
 
-        // Store master key hash in keyring for biometric unlock later
-        if let Ok(entry) = Entry::new("birchvault", &request.email) {
-            let _ = entry.set_password(&request.master_key_hash);
-        }
+        sign_in(&state, session, request.master_key_hash).await
+    }
+    .await;
 
-        // Store master key hash in memory
-        {
-            let mut key_hash = state.master_key_hash.write().await;
-            *key_hash = Some(request.master_key_hash);
-        }
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
 
-        // Unlock the vault
-        {
-            let mut locked = state.is_locked.write().await;
-            *locked = false;
+/// Create an account and sign in to it. If the server wants the email
+/// address confirmed first, nothing is signed in: `login` finishes setting
+/// up the account after confirming.
+#[tauri::command]
+pub async fn register(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    request: RegisterRequest,
+) -> std::result::Result<RegisterResponse, String> {
+    let result: Result<RegisterResponse> = async {
+        if offline::is_active(&state.db) || state.db.get_session()?.is_some() {
+            return Err(AppError::InvalidOperation(i18n::t("auth.profile_exists")));
         }
+        strength::require_master_password(
+            &request.master_password,
+            &[&request.email],
+            state.db.get_settings()?.min_master_password_score,
+        )?;
+        let master_key = decode_master_key(&request.master_key_hash)?;
 
-        // Perform initial sync
-        state.sync_engine.initial_sync(&session).await?;
+        let session = state
+            .sync_engine
+            .register(&request.email, &request.password_hash)
+            .await?;
+        registration::start(&state.db, &request.email, &master_key)?;
+        onboarding::record(
+            &state.db,
+            &[
+                OnboardingStep::ServerConfigured,
+                OnboardingStep::AccountCreated,
+                OnboardingStep::MasterPasswordSet,
+            ],
+            false,
+        );
 
-        Ok(LoginResponse {
-            user_id: session.user_id,
-            email: session.email,
-            access_token: session.access_token,
+        let Some(session) = session else {
+            return Ok(RegisterResponse {
+                confirmation_required: true,
+                login: None,
+                recovery_key: None,
+            });
+        };
+        let login = sign_in(&state, session, request.master_key_hash).await?;
+        let recovery_key = match recovery::create(&state.sync_engine, &master_key).await {
+            Ok(recovery_key) => Some(recovery_key),
+            Err(e) => {
+                log::warn!("Failed to create a recovery key: {}", e);
+                None
+            }
+        };
+        Ok(RegisterResponse {
+            confirmation_required: false,
+            login: Some(login),
+            recovery_key,
         })
     }
     .await;
 
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
     result.map_err(|e| e.to_string())
 }
 
+/// Unlock the account this device is already signed in to, when the server
+/// can't be reached to sign in again. Signing in waits for the network.
+/// `None` if the device has no session for the account to fall back on.
+async fn sign_in_degraded(
+    state: &AppState,
+    request: LoginRequest,
+) -> Result<Option<LoginResponse>> {
+    let signed_in = state
+        .db
+        .get_session()?
+        .is_some_and(|session| session.email.eq_ignore_ascii_case(request.email.trim()));
+    if !signed_in {
+        return Ok(None);
+    }
+    // Without it the vault key couldn't be unwrapped offline anyway
+    let Some(wrapped) = deferred::wrapped_key(&state.db)? else {
+        return Ok(None);
+    };
+
+    // The keyring verifier may be missing, so also check the master key opens the vault key
+    let verified = verify_master_key(&state.db, &request.master_key_hash).and_then(|response| {
+        let master_key = decode_master_key(&request.master_key_hash)?;
+        crypto::decrypt_from_json(&wrapped, &master_key)
+            .map_err(|_| AppError::Auth(i18n::t("auth.invalid_master_password")))?;
+        Ok(response)
+    });
+    let mut response = match verified {
+        Ok(response) => response,
+        Err(e) => {
+            audit::record(
+                &state.db,
+                AuditEvent::UnlockFailed,
+                None,
+                Some("sign_in_offline"),
+            );
+            return Err(e);
+        }
+    };
+
+    {
+        let mut key_hash = state.master_key_hash.write().await;
+        *key_hash = Some(request.master_key_hash);
+    }
+    {
+        let mut locked = state.is_locked.write().await;
+        *locked = false;
+    }
+    audit::record(&state.db, AuditEvent::Unlock, None, Some("sign_in_offline"));
+
+    state.sync_engine.set_online(false).await;
+    *state.deferred_sign_in.write().await = Some(DeferredSignIn {
+        email: response.email.clone(),
+        password_hash: Some(request.password_hash),
+    });
+    response.degraded = true;
+    Ok(Some(response))
+}
+
+/// Save a new session and unlock the vault with it
+async fn sign_in(
+    state: &AppState,
+    session: UserSession,
+    master_key_hash: String,
+) -> Result<LoginResponse> {
+    // Save session to database
+    state.db.save_session(&session)?;
+
+    // An account registered here still needs its vault key uploading
+    if registration::complete(&state.sync_engine, &state.db, &session.email).await? {
+        log::info!("Set up the vault key for the new account");
+    }
+    deferred::refresh_wrapped_key(&state.sync_engine, &state.db).await;
+    *state.deferred_sign_in.write().await = None;
+
+    onboarding::record(
+        &state.db,
+        &[
+            OnboardingStep::ServerConfigured,
+            OnboardingStep::AccountCreated,
+            OnboardingStep::MasterPasswordSet,
+        ],
+        false,
+    );
+
+    // Store a verifier for the master key hash so unlock can check it locally
+    if let Ok(entry) = account_verifier_entry(&profiles::active_id(), &session.email) {
+        if let Ok(verifier) = crypto::create_verifier(&master_key_hash) {
+            let _ = entry.set_password(&verifier);
+        }
+    }
+
+    // Store master key hash in memory
+    {
+        let mut key_hash = state.master_key_hash.write().await;
+        *key_hash = Some(master_key_hash);
+    }
+
+    // Unlock the vault
+    {
+        let mut locked = state.is_locked.write().await;
+        *locked = false;
+    }
+    audit::record(&state.db, AuditEvent::Unlock, None, Some("sign_in"));
+
+    // Perform initial sync
+    state.sync_engine.initial_sync(&session).await?;
+    onboarding::record(&state.db, &[OnboardingStep::FirstSyncDone], false);
+
+    Ok(LoginResponse {
+        user_id: session.user_id,
+        email: session.email,
+        access_token: session.access_token,
+        offline_profile: false,
+        degraded: false,
+    })
+}
+
 #[tauri::command]
-pub async fn logout(state: State<'_, AppState>) -> std::result::Result<(), String> {
+pub async fn logout(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
     let result: Result<()> = async {
-        // Lock the vault
+        // Lock the vault and clear the master key hash
+        state.lock().await?;
+
         {
-            let mut locked = state.is_locked.write().await;
-            *locked = true;
+            let mut last_copied = state.last_copied_item_id.write().await;
+            *last_copied = None;
         }
 
-        // Clear master key hash
-        {
-            let mut key_hash = state.master_key_hash.write().await;
-            *key_hash = None;
+        devices::unpair_all(&state.db)?;
+
+        if let Some(profile) = offline::get(&state.db)? {
+            offline::remove(&profile);
         }
 
-        // Clear all local data
+        // Clear all local data. The profile's server outlives the account.
+        let server_config = server::saved(&state.db)?;
         state.sync_engine.logout().await?;
+        server::save(&state.db, server_config.as_ref())?;
+
+        let attachments_dir = state.db.attachments_dir();
+        if attachments_dir.exists() {
+            std::fs::remove_dir_all(attachments_dir)?;
+        }
+        icons::clear_cache(&state.db)?;
 
         Ok(())
     }
     .await;
 
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
     result.map_err(|e| e.to_string())
 }
 
+/// Start a local-only vault with no account; unlocks it straight away
 #[tauri::command]
-pub async fn unlock_vault(
+pub async fn create_offline_profile(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
+    name: String,
     master_key_hash: String,
+    master_password: String,
 ) -> std::result::Result<LoginResponse, String> {
     let result: Result<LoginResponse> = async {
-        let session = state
-            .db
-            .get_session()?
-            .ok_or(AppError::Auth("No session found".to_string()))?;
-
-        // Verify master key hash matches stored hash
-        if let Ok(entry) = Entry::new("birchvault", &session.email) {
-            if let Ok(stored_hash) = entry.get_password() {
-                if stored_hash != master_key_hash {
-                    return Err(AppError::Auth("Invalid master password".to_string()));
-                }
-            }
-        }
+        strength::require_master_password(
+            &master_password,
+            &[&name],
+            state.db.get_settings()?.min_master_password_score,
+        )?;
+        let profile = offline::create(&state.db, &name, &master_key_hash)?;
+        onboarding::record(
+            &state.db,
+            &[OnboardingStep::AccountCreated, OnboardingStep::MasterPasswordSet],
+            false,
+        );
+        // No server to configure or sync with
+        onboarding::record(
+            &state.db,
+            &[OnboardingStep::ServerConfigured, OnboardingStep::FirstSyncDone],
+            true,
+        );
 
-        // Store master key hash in memory
         {
             let mut key_hash = state.master_key_hash.write().await;
             *key_hash = Some(master_key_hash);
         }
 
-        // Unlock the vault
         {
             let mut locked = state.is_locked.write().await;
             *locked = false;
         }
 
         Ok(LoginResponse {
-            user_id: session.user_id,
-            email: session.email,
-            access_token: session.access_token,
+            user_id: profile.id,
+            email: profile.name,
+            access_token: String::new(),
+            offline_profile: true,
+            degraded: false,
         })
     }
     .await;
 
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
     result.map_err(|e| e.to_string())
 }
 
+/// The offline profile, if this device has one instead of an account
 #[tauri::command]
-pub async fn lock_vault(state: State<'_, AppState>) -> std::result::Result<(), String> {
-    let mut locked = state.is_locked.write().await;
-    *locked = true;
-
-    let mut key_hash = state.master_key_hash.write().await;
-    *key_hash = None;
-
-    Ok(())
+pub async fn get_offline_profile(
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<OfflineProfile>, String> {
+    offline::get(&state.db).map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub async fn is_vault_locked(state: State<'_, AppState>) -> std::result::Result<bool, String> {
-    let locked = state.is_locked.read().await;
-    Ok(*locked)
+/// Keyring entry holding an account's master key verifier within a profile
+fn account_verifier_entry(profile_id: &str, email: &str) -> keyring::Result<Entry> {
+    Entry::new("birchvault", &profiles::keyring_user_for(profile_id, email))
 }
 
-#[tauri::command]
-pub async fn get_session(
-    state: State<'_, AppState>,
-) -> std::result::Result<Option<UserSession>, String> {
-    state.db.get_session().map_err(|e| e.to_string())
+/// Check a master key hash against the account's keyring verifier. With no
+/// verifier stored (keyring unavailable) the server session stands in, as
+/// before. A plain hash stored by older versions is upgraded on success.
+fn check_account_verifier(email: &str, master_key_hash: &str) -> bool {
+    account_verifier_matches(email, master_key_hash).unwrap_or(true)
 }
 
-#[tauri::command]
-pub async fn has_stored_session(state: State<'_, AppState>) -> std::result::Result<bool, String> {
-    let session = state.db.get_session().map_err(|e| e.to_string())?;
-    Ok(session.is_some())
+/// Whether a master key hash matches the account's keyring verifier, or
+/// `None` if there's no verifier to check it against
+fn account_verifier_matches(email: &str, master_key_hash: &str) -> Option<bool> {
+    let entry = account_verifier_entry(&profiles::active_id(), email).ok()?;
+    let stored = entry.get_password().ok()?;
+
+    match crypto::verify_secret(&stored, master_key_hash) {
+        Verification::Valid => Some(true),
+        Verification::ValidLegacy => {
+            if let Ok(verifier) = crypto::create_verifier(master_key_hash) {
+                let _ = entry.set_password(&verifier);
+            }
+            Some(true)
+        }
+        Verification::Invalid => Some(false),
+    }
 }
 
-// ============================================
-// Vault Items Commands
-// ============================================
+/// Confirm the master password before something that can't be taken back,
+/// like writing the vault out in plaintext. Unlike `verify_master_key` this
+/// never lets a missing verifier through: without one the account's wrapped
+/// vault key is fetched and has to open with the master key.
+async fn confirm_master_key(state: &AppState, master_key_hash: &str) -> Result<()> {
+    let Some(session) = state.db.get_session()? else {
+        return verify_master_key(&state.db, master_key_hash).map(|_| ());
+    };
 
-fn check_locked(is_locked: bool) -> Result<()> {
-    if is_locked {
-        Err(AppError::VaultLocked)
-    } else {
-        Ok(())
+    let confirmed = match account_verifier_matches(&session.email, master_key_hash) {
+        Some(matches) => matches,
+        None => {
+            let master_key = decode_master_key(master_key_hash)?;
+            let wrapped = state.sync_engine.wrapped_vault_key().await?;
+            crypto::decrypt_from_json(&wrapped, &master_key).is_ok()
+        }
+    };
+    if !confirmed {
+        return Err(AppError::Auth(i18n::t("auth.invalid_master_password")));
     }
+    Ok(())
 }
 
-#[tauri::command]
-pub async fn get_vault_items(
-    state: State<'_, AppState>,
-) -> std::result::Result<Vec<VaultItem>, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+/// Check a master key hash for the account or offline profile, returning who it unlocks
+fn verify_master_key(db: &Database, master_key_hash: &str) -> Result<LoginResponse> {
+    match db.get_session()? {
+        Some(session) => {
+            if !check_account_verifier(&session.email, master_key_hash) {
+                return Err(AppError::Auth(i18n::t("auth.invalid_master_password")));
+            }
+
+            Ok(LoginResponse {
+                user_id: session.user_id,
+                email: session.email,
+                access_token: session.access_token,
+                offline_profile: false,
+                degraded: false,
+            })
+        }
+        None => {
+            // No server to fall back on, so the keyring verifier is mandatory
+            let profile = offline::get(db)?.ok_or(AppError::Auth(i18n::t("auth.no_session")))?;
+            offline::verify(&profile, master_key_hash)?;
 
-    state.db.get_all_vault_items().map_err(|e| e.to_string())
+            Ok(LoginResponse {
+                user_id: profile.id,
+                email: profile.name,
+                access_token: String::new(),
+                offline_profile: true,
+                degraded: false,
+            })
+        }
+    }
 }
 
 #[tauri::command]
-pub async fn get_trashed_items(
+pub async fn unlock_vault(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> std::result::Result<Vec<VaultItem>, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+    master_key_hash: String,
+) -> std::result::Result<LoginResponse, String> {
+    let result: Result<LoginResponse> = async {
+        let response = match verify_master_key(&state.db, &master_key_hash) {
+            Ok(response) => response,
+            Err(e) => {
+                audit::record(&state.db, AuditEvent::UnlockFailed, None, Some("password"));
+                return Err(e);
+            }
+        };
 
-    state.db.get_trashed_items().map_err(|e| e.to_string())
-}
+        // Store master key hash in memory
+        {
+            let mut key_hash = state.master_key_hash.write().await;
+            *key_hash = Some(master_key_hash);
+        }
 
-#[tauri::command]
-pub async fn get_vault_item(
-    state: State<'_, AppState>,
-    id: String,
-) -> std::result::Result<Option<VaultItem>, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+        // Unlock the vault
+        {
+            let mut locked = state.is_locked.write().await;
+            *locked = false;
+        }
+        audit::record(&state.db, AuditEvent::Unlock, None, Some("password"));
 
-    state.db.get_vault_item(&id).map_err(|e| e.to_string())
+        // Known offline: sync once the connectivity monitor reaches the server
+        let mut response = response;
+        if !response.offline_profile && !state.sync_engine.get_status().await.is_online {
+            *state.deferred_sign_in.write().await = Some(DeferredSignIn {
+                email: response.email.clone(),
+                password_hash: None,
+            });
+            response.degraded = true;
+        }
+
+        Ok(response)
+    }
+    .await;
+
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
 }
 
+/// Unlock with the device PIN instead of the master password
 #[tauri::command]
-pub async fn create_vault_item(
+pub async fn unlock_with_pin(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    request: CreateVaultItemRequest,
-) -> std::result::Result<VaultItem, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+    pin: String,
+) -> std::result::Result<PinUnlockResponse, String> {
+    let result: Result<PinUnlockResponse> = async {
+        let keys = match pin::unlock(&state.db, &pin) {
+            Ok(keys) => keys,
+            Err(e) => {
+                audit::record(&state.db, AuditEvent::UnlockFailed, None, Some("pin"));
+                return Err(e);
+            }
+        };
 
-    let now = Utc::now().to_rfc3339();
-    let item = VaultItem {
-        id: Uuid::new_v4().to_string(),
-        encrypted_data: request.encrypted_data,
-        item_type: request.item_type,
-        folder_id: request.folder_id,
-        is_favorite: request.is_favorite,
-        deleted_at: None,
-        synced_at: None,
-        local_updated_at: now,
-        server_updated_at: None,
+        // Sealed before a master password change; the PIN has to be set up again
+        let account = match verify_master_key(&state.db, &keys.master_key_hash) {
+            Ok(account) => account,
+            Err(e) => {
+                audit::record(&state.db, AuditEvent::UnlockFailed, None, Some("pin"));
+                pin::disable(&state.db)?;
+                return Err(e);
+            }
+        };
+
+        {
+            let mut key_hash = state.master_key_hash.write().await;
+            *key_hash = Some(keys.master_key_hash);
+        }
+
+        {
+            let mut encryption_key = state.encryption_key.write().await;
+            *encryption_key = Some(keys.vault_key.clone());
+        }
+
+        {
+            let mut locked = state.is_locked.write().await;
+            *locked = false;
+        }
+        audit::record(&state.db, AuditEvent::Unlock, None, Some("pin"));
+
+        Ok(PinUnlockResponse {
+            account,
+            encryption_key: BASE64.encode(&keys.vault_key),
+        })
+    }
+    .await;
+
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_pin_status(state: State<'_, AppState>) -> std::result::Result<PinStatus, String> {
+    pin::status(&state.db).map_err(|e| e.to_string())
+}
+
+/// Set up (or replace) the PIN from the unlocked vault's keys
+#[tauri::command]
+pub async fn enable_pin_unlock(
+    state: State<'_, AppState>,
+    pin: String,
+    max_attempts: Option<u32>,
+) -> std::result::Result<PinStatus, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<PinStatus> = async {
+        let master_key_hash = state
+            .master_key_hash
+            .read()
+            .await
+            .clone()
+            .ok_or(AppError::VaultLocked)?;
+        let vault_key = state.encryption_key().await?;
+
+        pin::enable(
+            &state.db,
+            &pin,
+            max_attempts.unwrap_or(pin::DEFAULT_MAX_ATTEMPTS),
+            &master_key_hash,
+            &vault_key,
+        )
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn disable_pin_unlock(state: State<'_, AppState>) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    pin::disable(&state.db).map_err(|e| e.to_string())
+}
+
+/// Change the master password: the vault key is re-wrapped with the new
+/// master key, then the account password and this device's verifier are
+/// replaced. Other devices unlock with the new password after signing in
+/// again. The PIN is turned off, as it seals the old master key.
+#[tauri::command]
+pub async fn change_master_password(
+    state: State<'_, AppState>,
+    request: ChangeMasterPasswordRequest,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        let account = match verify_master_key(&state.db, &request.current_master_key_hash) {
+            Ok(account) => account,
+            Err(e) => {
+                audit::record(
+                    &state.db,
+                    AuditEvent::UnlockFailed,
+                    None,
+                    Some("password_change"),
+                );
+                return Err(e);
+            }
+        };
+        if account.offline_profile {
+            return Err(AppError::InvalidOperation(
+                "Offline profiles can't change their master password".to_string(),
+            ));
+        }
+        // The unfinished rotation holds the new vault key wrapped with the old master key
+        if rotation::is_pending(&state.db)? {
+            return Err(AppError::InvalidOperation(
+                "Finish the vault key rotation first".to_string(),
+            ));
+        }
+        strength::require_master_password(
+            &request.new_master_password,
+            &[&account.email],
+            state.db.get_settings()?.min_master_password_score,
+        )?;
+
+        let current_master_key = decode_master_key(&request.current_master_key_hash)?;
+        let new_master_key = decode_master_key(&request.new_master_key_hash)?;
+        let vault_key = state.encryption_key().await?;
+
+        let current_wrapped = state.sync_engine.wrapped_vault_key().await?;
+        // Without a keyring verifier this is what checks the current password
+        let unwrapped = crypto::decrypt_from_json(&current_wrapped, &current_master_key)
+            .map_err(|_| AppError::Auth(i18n::t("auth.invalid_master_password")))?;
+        // Another device may have rotated the key since this one unlocked
+        if !crypto::constant_time_eq(unwrapped.as_bytes(), BASE64.encode(&vault_key).as_bytes()) {
+            return Err(AppError::InvalidOperation(
+                "The vault key has changed on another device; unlock again first".to_string(),
+            ));
+        }
+
+        let new_wrapped = replace_master_key(
+            &state.sync_engine,
+            &account.user_id,
+            current_wrapped,
+            &vault_key,
+            &new_master_key,
+            &request.new_password_hash,
+        )
+        .await?;
+        deferred::remember_wrapped_key(&state.db, &new_wrapped);
+        // It wraps the old master key, so would only sign in with the old password
+        if let Err(e) = recovery::remove(&state.sync_engine).await {
+            log::warn!("Failed to remove the old recovery key: {}", e);
+        }
+
+        if let Ok(entry) = account_verifier_entry(&profiles::active_id(), &account.email) {
+            if let Ok(verifier) = crypto::create_verifier(&request.new_master_key_hash) {
+                let _ = entry.set_password(&verifier);
+            }
+        }
+        {
+            let mut key_hash = state.master_key_hash.write().await;
+            *key_hash = Some(request.new_master_key_hash);
+        }
+
+        pin::disable(&state.db)?;
+        resume::discard(&state.db)?;
+        audit::record(&state.db, AuditEvent::MasterPasswordChanged, None, None);
+        Ok(())
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Make a recovery key for the signed-in account, replacing any earlier one.
+/// Returned formatted for printing; it isn't kept anywhere.
+#[tauri::command]
+pub async fn create_recovery_key(
+    state: State<'_, AppState>,
+) -> std::result::Result<String, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<String> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let master_key_hash = state
+            .master_key_hash
+            .read()
+            .await
+            .clone()
+            .ok_or(AppError::VaultLocked)?;
+        recovery::create(&state.sync_engine, &decode_master_key(&master_key_hash)?).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Set a new master password for a forgotten one using the recovery key,
+/// then sign in with it. The used recovery key is replaced by the one
+/// returned.
+#[tauri::command]
+pub async fn recover_with_recovery_key(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    request: RecoveryRequest,
+) -> std::result::Result<RecoveryResponse, String> {
+    let result: Result<RecoveryResponse> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        if let Some(session) = state.db.get_session()? {
+            if !session.email.eq_ignore_ascii_case(request.email.trim()) {
+                return Err(AppError::InvalidOperation(i18n::t("auth.profile_exists")));
+            }
+        }
+
+        let recovered = recovery::unwrap(&state.sync_engine, &request.recovery_key).await;
+        let recovered = match recovered {
+            Ok(recovered) if recovered.email.eq_ignore_ascii_case(request.email.trim()) => {
+                recovered
+            }
+            Ok(_) | Err(AppError::Auth(_)) => {
+                audit::record(
+                    &state.db,
+                    AuditEvent::UnlockFailed,
+                    None,
+                    Some("recovery_key"),
+                );
+                return Err(AppError::Auth(
+                    "That recovery key doesn't match this account".to_string(),
+                ));
+            }
+            Err(e) => return Err(e),
+        };
+        let new_master_key = decode_master_key(&request.new_master_key_hash)?;
+
+        let session = state
+            .sync_engine
+            .authenticate(
+                &recovered.email,
+                &crypto::derive_auth_hash(&recovered.master_key)?,
+            )
+            .await?;
+        state.db.save_session(&session)?;
+
+        let current_wrapped = state.sync_engine.wrapped_vault_key().await?;
+        let vault_key = BASE64
+            .decode(crypto::decrypt_from_json(
+                &current_wrapped,
+                &recovered.master_key,
+            )?)
+            .map_err(|_| AppError::Encryption("Invalid vault key".to_string()))?;
+        let new_wrapped = replace_master_key(
+            &state.sync_engine,
+            &session.user_id,
+            current_wrapped,
+            &vault_key,
+            &new_master_key,
+            &request.new_password_hash,
+        )
+        .await?;
+        deferred::remember_wrapped_key(&state.db, &new_wrapped);
+        let recovery_key = recovery::create(&state.sync_engine, &new_master_key).await?;
+
+        // Anything sealing the old master key is no use now
+        pin::disable(&state.db)?;
+        resume::discard(&state.db)?;
+        audit::record(
+            &state.db,
+            AuditEvent::MasterPasswordChanged,
+            None,
+            Some("recovery_key"),
+        );
+
+        Ok(RecoveryResponse {
+            login: sign_in(&state, session, request.new_master_key_hash).await?,
+            recovery_key,
+        })
+    }
+    .await;
+
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
+
+/// A master key (base64) as the webview exports it
+fn decode_master_key(master_key_hash: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(master_key_hash)
+        .ok()
+        .filter(|k| k.len() == crypto::KEY_LENGTH)
+        .ok_or_else(|| AppError::Encryption("Invalid master key".to_string()))
+}
+
+/// Wrap the vault key with a new master key and switch the account to the
+/// password that goes with it. The wrapping changes first: if the password
+/// change then fails, `current_wrapped` goes back, so neither password is
+/// left unable to open the vault. Returns the new wrapping.
+async fn replace_master_key(
+    engine: &SyncEngine,
+    user_id: &str,
+    current_wrapped: String,
+    vault_key: &[u8],
+    new_master_key: &[u8],
+    new_password_hash: &str,
+) -> Result<String> {
+    let profile_path = format!("vault_profiles?id=eq.{}", user_id);
+    let profile_path = &profile_path;
+    let set_wrapped = |wrapped: String| async move {
+        let body = serde_json::json!({ "encrypted_symmetric_key": wrapped });
+        engine
+            .rest_write(Method::PATCH, profile_path, Some(&body), None)
+            .await
+    };
+
+    let new_wrapped = crypto::encrypt_to_json(&BASE64.encode(vault_key), new_master_key)?;
+    set_wrapped(new_wrapped.clone()).await?;
+    if let Err(e) = engine.update_password(new_password_hash).await {
+        if let Err(restore) = set_wrapped(current_wrapped).await {
+            log::error!("Failed to restore the wrapped vault key: {}", restore);
+        }
+        return Err(e);
+    }
+    Ok(new_wrapped)
+}
+
+/// Replace the vault key with a new one and re-encrypt the vault under it,
+/// here and on the server. `master_key` (base64) wraps the new key for the
+/// profile. Running it again after an interruption finishes the rotation.
+/// The webview takes the new key from the summary.
+#[tauri::command]
+pub async fn rotate_encryption_key(
+    state: State<'_, AppState>,
+    master_key: String,
+) -> std::result::Result<RotationSummary, String> {
+    // Held throughout, so the vault can't lock halfway
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<RotationSummary> = async {
+        let master_key = decode_master_key(&master_key)?;
+        let vault_key = state.encryption_key().await?;
+
+        let rotation =
+            rotation::rotate(&state.sync_engine, &state.db, &vault_key, &master_key).await?;
+
+        *state.encryption_key.write().await = Some(rotation.vault_key);
+        *state.search_index.write().await = None;
+        Ok(rotation.summary)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// The user did something in the window; pushes back the idle auto-lock
+#[tauri::command]
+pub async fn report_activity(state: State<'_, AppState>) -> std::result::Result<(), String> {
+    autolock::touch(&state);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    let result = state.lock().await;
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
+
+/// Stash the unlocked state so the next launch can resume it (e.g. before an update relaunch)
+#[tauri::command]
+pub async fn create_resume_token(
+    state: State<'_, AppState>,
+    grace_seconds: Option<i64>,
+) -> std::result::Result<String, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let master_key_hash = state
+        .master_key_hash
+        .read()
+        .await
+        .clone()
+        .ok_or_else(|| AppError::VaultLocked.to_string())?;
+
+    // Only the key this session actually unlocked with, never one handed in
+    let encryption_key = state
+        .encryption_key
+        .read()
+        .await
+        .as_ref()
+        .map(|k| BASE64.encode(k));
+
+    let payload = ResumePayload {
+        master_key_hash,
+        encryption_key,
+    };
+
+    resume::create(
+        &state.db,
+        &payload,
+        grace_seconds.unwrap_or(resume::DEFAULT_GRACE_SECONDS),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Unlock from a pending resume token. Returns None if there is none or it has expired.
+#[tauri::command]
+pub async fn resume_session(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<ResumeResponse>, String> {
+    let result: Result<Option<ResumeResponse>> = async {
+        let Some(payload) = resume::take(&state.db)? else {
+            return Ok(None);
+        };
+
+        let Some(session) = state.db.get_session()? else {
+            return Ok(None);
+        };
+
+        // Same check as unlock_vault, so a token can't outlive a password change
+        if !check_account_verifier(&session.email, &payload.master_key_hash) {
+            return Ok(None);
+        }
+
+        {
+            let mut key_hash = state.master_key_hash.write().await;
+            *key_hash = Some(payload.master_key_hash);
+        }
+
+        if let Some(key) = payload.encryption_key.as_deref() {
+            if let Ok(key) = BASE64.decode(key) {
+                let mut encryption_key = state.encryption_key.write().await;
+                *encryption_key = Some(key);
+            }
+        }
+
+        {
+            let mut locked = state.is_locked.write().await;
+            *locked = false;
+        }
+        audit::record(&state.db, AuditEvent::Unlock, None, Some("resume"));
+
+        Ok(Some(ResumeResponse {
+            user_id: session.user_id,
+            email: session.email,
+            access_token: session.access_token,
+            encryption_key: payload.encryption_key,
+        }))
+    }
+    .await;
+
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
+
+/// Hand the exported vault key (base64) to the backend after unlocking.
+/// Returns as soon as the key is stored; migrations and the search index
+/// are brought up to date in the background.
+#[tauri::command]
+pub async fn set_encryption_key(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    key: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let key = BASE64
+        .decode(key)
+        .ok()
+        .filter(|k| k.len() == crypto::KEY_LENGTH)
+        .ok_or_else(|| AppError::Encryption("Invalid vault key".to_string()).to_string())?;
+
+    {
+        let mut encryption_key = state.encryption_key.write().await;
+        *encryption_key = Some(key);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        warm_vault(&state).await;
+    });
+    Ok(())
+}
+
+/// Work that needs the vault key but not the UI: runs off the unlock path
+async fn warm_vault(state: &AppState) {
+    let Ok(key) = state.encryption_key().await else {
+        return;
     };
+    match rotation::finish_pending(&state.db, &key) {
+        Ok(false) => {}
+        Ok(true) => log::info!("Finished an interrupted vault key rotation"),
+        Err(e) => log::warn!("Failed to finish the vault key rotation: {}", e),
+    }
+    match fields::migrate_vault(&state.db, &key) {
+        Ok(0) => {}
+        Ok(migrated) => log::info!("Migrated custom fields on {} items", migrated),
+        Err(e) => log::warn!("Failed to migrate custom fields: {}", e),
+    }
+
+    // Warm the search index now rather than on the first keystroke
+    if let Err(e) = state.with_search_index(|_| ()).await {
+        log::warn!("Failed to build search index: {}", e);
+    }
+}
+
+/// Called by the frontend once the vault list has painted. Checks connectivity
+/// and syncs in the background, alongside the warm-up started by
+/// `set_encryption_key`, rather than holding up `unlock_vault`. A finished
+/// sync is reported as `sync-completed`.
+#[tauri::command]
+pub async fn vault_ready(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    if offline::is_active(&state.db) {
+        // Folder or WebDAV sync needs no sign-in
+        if filesync::configured(&state.db)
+            .map_err(|e| e.to_string())?
+            .is_some()
+        {
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                match state.sync_engine.sync("unlock").await {
+                    Ok(status) => {
+                        let _ = app_handle.emit("sync-completed", status);
+                    }
+                    Err(e) => log::warn!("Sync after unlock failed: {}", e),
+                }
+            });
+        }
+        return Ok(());
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        if !state.sync_engine.check_connectivity().await {
+            // Sync once the connectivity monitor reaches the server
+            let mut deferred = state.deferred_sign_in.write().await;
+            if let (None, Ok(Some(session))) = (deferred.as_ref(), state.db.get_session()) {
+                *deferred = Some(DeferredSignIn {
+                    email: session.email,
+                    password_hash: None,
+                });
+            }
+            return;
+        }
+        let synced = match deferred::complete(&state).await {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => state.sync_engine.sync("unlock").await,
+            Err(e) => Err(e),
+        };
+        match synced {
+            Ok(status) => {
+                let _ = app_handle.emit("sync-completed", status);
+            }
+            Err(e) => log::warn!("Sync after unlock failed: {}", e),
+        }
+
+        // Publish our public key so others can share with us, then bring
+        // the shared copies of our items up to date
+        let Ok(key) = state.encryption_key().await else {
+            return;
+        };
+        if let Err(e) = keypair::ensure(&state.sync_engine, &key).await {
+            log::warn!("Failed to set up account key pair: {}", e);
+            return;
+        }
+        match sharing::reconcile(&state.sync_engine, &state.db, &key).await {
+            Ok(0) => {}
+            Ok(_) => {
+                let _ = app_handle.emit("shared-items-updated", ());
+            }
+            Err(e) => log::warn!("Failed to reconcile shared items: {}", e),
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_lock_state(state: State<'_, AppState>) -> std::result::Result<LockState, String> {
+    Ok(state.lock_state().await)
+}
+
+#[tauri::command]
+pub async fn get_session(
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<UserSession>, String> {
+    state.db.get_session().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn has_stored_session(state: State<'_, AppState>) -> std::result::Result<bool, String> {
+    let session = state.db.get_session().map_err(|e| e.to_string())?;
+    Ok(session.is_some())
+}
+
+/// The profile's vault key wrapped with the master key, as kept from the
+/// last sign-in, for a degraded unlock that can't fetch it
+#[tauri::command]
+pub async fn get_wrapped_vault_key(
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<String>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    deferred::wrapped_key(&state.db).map_err(|e| e.to_string())
+}
+
+// ============================================
+// Profile Commands
+// ============================================
+
+fn app_data_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::InvalidOperation(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn list_profiles(
+    app_handle: tauri::AppHandle,
+) -> std::result::Result<profiles::Registry, String> {
+    let result: Result<profiles::Registry> = async {
+        let mut registry = profiles::load(&app_data_dir(&app_handle)?)?;
+        // The open database, which may differ from the saved choice after a failed save
+        registry.active = profiles::active_id();
+        Ok(registry)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    label: String,
+) -> std::result::Result<Profile, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Profile> =
+        async { profiles::create(&app_data_dir(&app_handle)?, &label) }.await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    profile_id: String,
+    label: String,
+) -> std::result::Result<Profile, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Profile> =
+        async { profiles::rename(&app_data_dir(&app_handle)?, &profile_id, &label) }.await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Lock the vault and reopen the app on another profile's database
+#[tauri::command]
+pub async fn switch_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    profile_id: String,
+) -> std::result::Result<(), String> {
+    let result: Result<()> = async {
+        let data_dir = app_data_dir(&app_handle)?;
+        let registry = profiles::load(&data_dir)?;
+        if registry.get(&profile_id).is_none() {
+            return Err(AppError::NotFound(format!("Profile {}", profile_id)));
+        }
+        if profile_id == profiles::active_id() {
+            return Ok(());
+        }
+
+        state.lock().await?;
+        *state.last_copied_item_id.write().await = None;
+
+        let db_path = profiles::database_path(&data_dir, &profile_id);
+        state
+            .sync_engine
+            .switch_database(|| state.db.switch_to(db_path, &profile_id))
+            .await?;
+        profiles::set_active(&profile_id);
+        profiles::save_active(&data_dir, &profile_id)?;
+
+        // Settings belong to the profile too, as does its server
+        if let Ok(settings) = state.db.get_settings() {
+            i18n::set_locale(&settings.locale);
+        }
+        state.sync_engine.set_config(server::load(&state.db)).await;
+        Ok(())
+    }
+    .await;
+
+    tray::refresh(&app_handle);
+    privacy::refresh(&app_handle);
+    result.map_err(|e| e.to_string())
+}
+
+/// Delete another profile's database and files, and forget its keyring
+/// entries. Can't be undone, so the active profile's master password is
+/// asked for again.
+#[tauri::command]
+pub async fn remove_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    profile_id: String,
+    master_key_hash: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        if let Err(e) = confirm_master_key(&state, &master_key_hash).await {
+            audit::record(
+                &state.db,
+                AuditEvent::UnlockFailed,
+                None,
+                Some("profile_removal"),
+            );
+            return Err(e);
+        }
+
+        let data_dir = app_data_dir(&app_handle)?;
+        let registry = profiles::load(&data_dir)?;
+        if registry.get(&profile_id).is_none()
+            || profile_id == profiles::active_id()
+            || profile_id == profiles::DEFAULT_PROFILE_ID
+        {
+            // profiles::remove says why
+            return profiles::remove(&data_dir, &profile_id);
+        }
+
+        // Only the profile's own database knows which entries it has
+        let db_path = profiles::database_path(&data_dir, &profile_id);
+        if db_path.exists() {
+            let db = Database::new(db_path, &profile_id)?;
+            if let Some(session) = db.get_session()? {
+                if let Ok(entry) = account_verifier_entry(&profile_id, &session.email) {
+                    let _ = entry.delete_password();
+                }
+            }
+            if let Some(offline_profile) = offline::get(&db)? {
+                offline::remove(&offline_profile);
+            }
+            devices::unpair_all(&db)?;
+        }
+
+        profiles::remove(&data_dir, &profile_id)?;
+        Database::forget_key(&profile_id);
+        Ok(())
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Vault Items Commands
+// ============================================
+
+fn check_locked(is_locked: bool) -> Result<()> {
+    if is_locked {
+        Err(AppError::VaultLocked)
+    } else {
+        Ok(())
+    }
+}
+
+/// An organization we've accepted membership of, with our role in it
+fn org_membership(db: &Database, org_id: &str) -> Result<(Organization, OrgRole)> {
+    let org = db
+        .get_organization(org_id)?
+        .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))?;
+    let role = organizations::role(&org).ok_or_else(|| {
+        AppError::InvalidOperation("Accept the invitation to this organization first".to_string())
+    })?;
+    Ok((org, role))
+}
+
+/// Members and collections are managed by owners and admins
+fn require_org_admin(db: &Database, org_id: &str) -> Result<Organization> {
+    let (org, role) = org_membership(db, org_id)?;
+    if !role.can_manage() {
+        return Err(AppError::InvalidOperation(
+            "Only organization owners and admins can do this".to_string(),
+        ));
+    }
+    Ok(org)
+}
+
+/// Putting items into (or taking them out of) collections needs write
+/// access to each one
+fn require_collection_write(
+    db: &Database,
+    org: &Organization,
+    collection_ids: &[String],
+) -> Result<()> {
+    let collections = db.get_collections(&org.id)?;
+    for id in collection_ids {
+        if !collections.iter().any(|c| &c.id == id) {
+            return Err(AppError::NotFound("Collection not found".to_string()));
+        }
+        if !organizations::can_write_collection(org, &collections, id) {
+            return Err(AppError::InvalidOperation(
+                "You have read-only access to this collection".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn require_item_write(db: &Database, item: &OrgItem) -> Result<Organization> {
+    let (org, _) = org_membership(db, &item.organization_id)?;
+    let collections = db.get_collections(&org.id)?;
+    if !organizations::can_write_item(&org, &collections, item) {
+        return Err(AppError::InvalidOperation(
+            "You have read-only access to this item".to_string(),
+        ));
+    }
+    Ok(org)
+}
+
+fn validate_org_item_data(
+    item_type: &str,
+    data: &serde_json::Value,
+    previous: Option<&serde_json::Value>,
+) -> Result<()> {
+    let item_type: ItemType = item_type.parse()?;
+    fields::validate(data, previous)?;
+    items::validate(item_type, data, previous)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemPage {
+    pub items: Vec<VaultItem>,
+    /// Live items across all pages
+    pub total: i64,
+}
+
+/// A page of live items, pinned first, for lists that load as they scroll
+#[tauri::command]
+pub async fn get_vault_items(
+    state: State<'_, AppState>,
+    sort: Option<ItemSort>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> std::result::Result<ItemPage, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let sort = sort.unwrap_or_default();
+    let limit = limit.unwrap_or(PAGE_DEFAULT).min(PAGE_MAX);
+    let offset = offset.unwrap_or(0);
+
+    let result: Result<ItemPage> = async {
+        if sort == ItemSort::Name {
+            state.load_sort_names().await?;
+        }
+        Ok(ItemPage {
+            items: state.db.get_sorted_vault_items(sort, Some(limit), offset)?,
+            total: state.db.count_vault_items()?,
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Pin an item to the top of the list, after those already pinned
+#[tauri::command]
+pub async fn pin_item(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.pin_item(&item_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unpin_item(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.unpin_item(&item_id).map_err(|e| e.to_string())
+}
+
+/// Put pinned items in the order given, e.g. after a drag and drop
+#[tauri::command]
+pub async fn reorder_pinned_items(
+    state: State<'_, AppState>,
+    item_ids: Vec<String>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .reorder_pinned_items(&item_ids)
+        .map_err(|e| e.to_string())
+}
+
+/// Lightweight listing without ciphertext; pair with `get_items_data` for visible rows
+#[tauri::command]
+pub async fn get_vault_item_summaries(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<VaultItemSummary>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_vault_item_summaries().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_items_data(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> std::result::Result<Vec<VaultItemData>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_items_data(&ids).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashQuery {
+    #[serde(flatten)]
+    pub filter: TrashFilter,
+    /// Matched against names, usernames and URLs via the search index
+    pub query: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashPage {
+    pub items: Vec<VaultItem>,
+    /// Matching items across all pages
+    pub total: i64,
+}
+
+/// Trashed item ids matching a text query, most recently deleted first
+async fn search_trash(state: &AppState, filter: &TrashFilter, query: &str) -> Result<Vec<String>> {
+    let mut hits = state
+        .with_search_index(|index| {
+            index.search(
+                query,
+                |entry| {
+                    entry
+                        .deleted_at
+                        .as_deref()
+                        .is_some_and(|deleted_at| filter.matches(&entry.item_type, deleted_at))
+                },
+                usize::MAX,
+            )
+        })
+        .await?;
+    hits.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(hits.into_iter().map(|hit| hit.id).collect())
+}
+
+/// A filtered page of the trash; text queries go through the search index
+#[tauri::command]
+pub async fn get_trashed_items(
+    state: State<'_, AppState>,
+    query: Option<TrashQuery>,
+) -> std::result::Result<TrashPage, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let query = query.unwrap_or_default();
+    let limit = query.limit.unwrap_or(PAGE_DEFAULT).min(PAGE_MAX);
+    let offset = query.offset.unwrap_or(0);
+
+    let result: Result<TrashPage> = async {
+        match query.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            Some(text) => {
+                let ids = search_trash(&state, &query.filter, text).await?;
+                let mut items = Vec::new();
+                for id in ids.iter().skip(offset as usize).take(limit as usize) {
+                    items.extend(state.db.get_vault_item(id)?);
+                }
+                Ok(TrashPage {
+                    items,
+                    total: ids.len() as i64,
+                })
+            }
+            None => Ok(TrashPage {
+                items: state
+                    .db
+                    .get_trashed_items_page(&query.filter, limit, offset)?,
+                total: state.db.count_trashed_items(&query.filter)?,
+            }),
+        }
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Just the number of trashed items matching `query` (its limit and offset are ignored)
+#[tauri::command]
+pub async fn count_trashed_items(
+    state: State<'_, AppState>,
+    query: TrashQuery,
+) -> std::result::Result<i64, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<i64> = async {
+        match query.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            Some(text) => Ok(search_trash(&state, &query.filter, text).await?.len() as i64),
+            None => state.db.count_trashed_items(&query.filter),
+        }
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_vault_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<Option<VaultItem>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_vault_item(&id).map_err(|e| e.to_string())
+}
+
+/// Check a payload against the typed item schema. Only possible once the
+/// webview has handed over the vault key; until then it is trusted as before.
+/// `previous_id` is the item being updated, whose stored version the
+/// payload is compared with.
+async fn validate_item_payload(
+    state: &AppState,
+    item_type: &str,
+    encrypted_data: &str,
+    previous_id: Option<&str>,
+) -> Result<()> {
+    let item_type: ItemType = item_type.parse()?;
+    let Some(key) = state.encryption_key.read().await.clone() else {
+        return Ok(());
+    };
+    let data: serde_json::Value =
+        serde_json::from_str(&crypto::decrypt_from_json(encrypted_data, &key)?)?;
+    let previous: Option<serde_json::Value> = match previous_id {
+        Some(id) => state
+            .db
+            .get_vault_item(id)?
+            .and_then(|item| crypto::decrypt_from_json(&item.encrypted_data, &key).ok())
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        None => None,
+    };
+    fields::validate(&data, previous.as_ref())?;
+    if let Some(sequence) = data.get("autoTypeSequence").and_then(|v| v.as_str()) {
+        autotype::parse(sequence)?;
+    }
+    items::validate(item_type, &data, previous.as_ref())
+}
+
+#[tauri::command]
+pub async fn create_vault_item(
+    state: State<'_, AppState>,
+    request: CreateVaultItemRequest,
+) -> std::result::Result<VaultItem, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    validate_item_payload(&state, &request.item_type, &request.encrypted_data, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let item = VaultItem {
+        id: Uuid::new_v4().to_string(),
+        encrypted_data: request.encrypted_data,
+        item_type: request.item_type,
+        folder_id: request.folder_id,
+        is_favorite: request.is_favorite,
+        deleted_at: None,
+        synced_at: None,
+        local_updated_at: now,
+        server_updated_at: None,
+        sort_index: None,
+    };
+
+    state.db.insert_vault_item(&item).map_err(|e| e.to_string())?;
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn update_vault_item(
+    state: State<'_, AppState>,
+    request: UpdateVaultItemRequest,
+) -> std::result::Result<VaultItem, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    validate_item_payload(
+        &state,
+        &request.item_type,
+        &request.encrypted_data,
+        Some(&request.id),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let item = VaultItem {
+        id: request.id.clone(),
+        encrypted_data: request.encrypted_data,
+        item_type: request.item_type,
+        folder_id: request.folder_id,
+        is_favorite: request.is_favorite,
+        deleted_at: None,
+        synced_at: None,
+        local_updated_at: now,
+        server_updated_at: None,
+        sort_index: None,
+    };
+
+    let result: Result<VaultItem> = async {
+        let keep = state.db.get_settings()?.item_revision_limit;
+        state.db.update_vault_item_with_revision(&item, keep)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Earlier versions of an item, newest first
+#[tauri::command]
+pub async fn list_item_revisions(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> std::result::Result<Vec<ItemRevision>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .get_item_revisions(&item_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Put an earlier version back. The version it replaces joins the history,
+/// so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_item_revision(
+    state: State<'_, AppState>,
+    revision_id: i64,
+) -> std::result::Result<VaultItem, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<VaultItem> = async {
+        let revision = state
+            .db
+            .get_item_revision(revision_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Revision {}", revision_id)))?;
+        let current = state
+            .db
+            .get_vault_item(&revision.item_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Vault item {}", revision.item_id)))?;
+
+        let item = VaultItem {
+            encrypted_data: revision.encrypted_data,
+            item_type: revision.item_type,
+            folder_id: revision.folder_id,
+            is_favorite: revision.is_favorite,
+            local_updated_at: Utc::now().to_rfc3339(),
+            ..current
+        };
+
+        let keep = state.db.get_settings()?.item_revision_limit;
+        state.db.update_vault_item_with_revision(&item, keep)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_vault_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .soft_delete_vault_item(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_vault_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .restore_vault_item(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn permanently_delete_vault_item(
+    state: State<'_, AppState>,
+    id: String,
+    force: Option<bool>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        // Items that never reached the server exist nowhere else, so make
+        // the caller confirm and keep a local copy for a grace period
+        if !state.db.has_unsynced_changes(&id)? {
+            return state.db.permanently_delete_vault_item(&id, None);
+        }
+
+        if !force.unwrap_or(false) {
+            return Err(AppError::InvalidOperation(i18n::t("delete.unsynced_requires_force")));
+        }
+
+        let recoverable_until =
+            (Utc::now() + chrono::Duration::hours(RECOVERABLE_DELETE_HOURS)).to_rfc3339();
+        state
+            .db
+            .permanently_delete_vault_item(&id, Some(&recoverable_until))
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_recoverable_deletions(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<RecoverableDeletion>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<RecoverableDeletion>> = async {
+        state.db.prune_recoverable_deletions()?;
+        state.db.get_recoverable_deletions()
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Bring back a force-deleted item as it was (including trash state)
+#[tauri::command]
+pub async fn recover_deleted_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<VaultItem, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .recover_deleted_vault_item(&id)
+        .and_then(|item| item.ok_or_else(|| AppError::NotFound(format!("Deleted item {}", id))))
+        .map_err(|e| e.to_string())
+}
+
+/// Whether the one-off favourite/type backfill still needs to run
+#[tauri::command]
+pub async fn needs_legacy_flag_migration(
+    state: State<'_, AppState>,
+) -> std::result::Result<bool, String> {
+    let migrated = state
+        .db
+        .get_meta(META_LEGACY_FLAGS_MIGRATED)
+        .map_err(|e| e.to_string())?;
+    Ok(migrated.is_none())
+}
+
+/// Backfill `is_favorite`/`item_type` columns from flags the frontend decrypted.
+/// Returns how many items changed; also marks the legacy migration as done.
+#[tauri::command]
+pub async fn reconcile_item_flags(
+    state: State<'_, AppState>,
+    flags: Vec<ItemFlags>,
+) -> std::result::Result<usize, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<usize> = async {
+        let flags: Vec<ItemFlags> = flags
+            .into_iter()
+            .filter(|f| f.item_type.parse::<ItemType>().is_ok())
+            .collect();
+
+        let updated = state.db.apply_item_flags(&flags)?;
+        state
+            .db
+            .set_meta(META_LEGACY_FLAGS_MIGRATED, &Utc::now().to_rfc3339())?;
+        Ok(updated)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Attachment Commands
+// ============================================
+
+/// Encrypt a file and attach it to an item, emitting `attachment-progress`
+#[tauri::command]
+pub async fn add_attachment(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item_id: String,
+    path: String,
+) -> std::result::Result<AttachmentInfo, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<AttachmentInfo> = async {
+        let key = state.encryption_key().await?;
+        attachments::add(&state.db, &item_id, Path::new(&path), &key, |progress| {
+            let _ = app_handle.emit("attachment-progress", progress);
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_attachments(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> std::result::Result<Vec<AttachmentInfo>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<AttachmentInfo>> = async {
+        let key = state.encryption_key().await?;
+        attachments::list(&state.db, &item_id, &key)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Decrypt an attachment to `destination`, fetching it from the server first if needed
+#[tauri::command]
+pub async fn download_attachment(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    attachment_id: String,
+    destination: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        let key = state.encryption_key().await?;
+        let attachment = state
+            .db
+            .get_attachment(&attachment_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Attachment {}", attachment_id)))?;
+
+        if !state.db.attachment_blob_path(&attachment.id).exists() {
+            offline::ensure_network_allowed(&state.db)?;
+            state
+                .sync_engine
+                .download_attachment(&attachment, |processed, total| {
+                    let _ = app_handle.emit(
+                        "attachment-progress",
+                        AttachmentProgress {
+                            attachment_id: attachment.id.clone(),
+                            phase: AttachmentPhase::Downloading,
+                            processed,
+                            total,
+                        },
+                    );
+                })
+                .await?;
+        }
+
+        attachments::save_to(&state.db, &attachment, Path::new(&destination), &key, |progress| {
+            let _ = app_handle.emit("attachment-progress", progress);
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_attachment(
+    state: State<'_, AppState>,
+    attachment_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    attachments::delete(&state.db, &attachment_id).map_err(|e| e.to_string())
+}
+
+// ============================================
+// Search Commands
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    /// Matches across all pages
+    pub total: i64,
+}
+
+/// Fuzzy search over live items' names, usernames and URL hosts, best match
+/// first, a page at a time
+#[tauri::command]
+pub async fn search_vault_items(
+    state: State<'_, AppState>,
+    query: String,
+    item_type: Option<String>,
+    folder_id: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> std::result::Result<SearchPage, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let limit = limit.unwrap_or(PAGE_DEFAULT).min(PAGE_MAX);
+    let offset = offset.unwrap_or(0);
+
+    state
+        .with_search_index(|index| {
+            let (results, total) = index.search_page(
+                &query,
+                live_entries(item_type.as_deref(), folder_id.as_deref()),
+                offset as usize,
+                limit as usize,
+            );
+            SearchPage {
+                results,
+                total: total as i64,
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Live entries, optionally only of one type or in one folder
+fn live_entries<'a>(
+    item_type: Option<&'a str>,
+    folder_id: Option<&'a str>,
+) -> impl Fn(&SearchEntry) -> bool + 'a {
+    move |entry| {
+        entry.deleted_at.is_none()
+            && item_type.is_none_or(|t| entry.item_type == t)
+            && folder_id.is_none_or(|f| entry.folder_id.as_deref() == Some(f))
+    }
+}
+
+/// Logins whose saved URIs match a page, for autofill and quick-search
+#[tauri::command]
+pub async fn match_items_for_url(
+    state: State<'_, AppState>,
+    url: String,
+    limit: Option<usize>,
+) -> std::result::Result<Vec<SearchResult>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let page = PageUrl::parse(&url).map_err(|e| e.to_string())?;
+    state
+        .with_search_index(|index| index.match_url(&page, limit.unwrap_or(search::DEFAULT_LIMIT)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// A login URL's favicon as a data URI, or `None` when there isn't one or
+/// site icons are turned off. Fetched here so the webview never contacts the
+/// sites in the vault.
+#[tauri::command]
+pub async fn fetch_site_icon(
+    state: State<'_, AppState>,
+    url: String,
+) -> std::result::Result<Option<String>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Option<String>> = async {
+        if !state.db.get_settings()?.show_site_icons {
+            return Ok(None);
+        }
+        let key = state.encryption_key().await?;
+        let allow_network = !offline::is_active(&state.db);
+        icons::site_icon(&state.db, &key, &url, allow_network).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Folders Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_folders(state: State<'_, AppState>) -> std::result::Result<Vec<Folder>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_all_folders().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_folder(
+    state: State<'_, AppState>,
+    request: CreateFolderRequest,
+) -> std::result::Result<Folder, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    retention::validate_retention_days(request.retention_days).map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let folder = Folder {
+        id: Uuid::new_v4().to_string(),
+        name: request.name,
+        synced_at: None,
+        local_updated_at: now,
+        retention_days: request.retention_days,
+    };
+
+    state.db.insert_folder(&folder).map_err(|e| e.to_string())?;
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn update_folder(
+    state: State<'_, AppState>,
+    request: UpdateFolderRequest,
+) -> std::result::Result<Folder, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    // Renaming keeps the folder's retention policy
+    let existing = state.db.get_folder(&request.id).map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let folder = Folder {
+        id: request.id.clone(),
+        name: request.name,
+        synced_at: None,
+        local_updated_at: now,
+        retention_days: existing.and_then(|f| f.retention_days),
+    };
+
+    state.db.update_folder(&folder).map_err(|e| e.to_string())?;
+    Ok(folder)
+}
+
+/// Set or clear (None) a folder's auto-trash policy
+#[tauri::command]
+pub async fn set_folder_retention(
+    state: State<'_, AppState>,
+    folder_id: String,
+    retention_days: Option<i64>,
+) -> std::result::Result<Folder, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Folder> = async {
+        retention::validate_retention_days(retention_days)?;
+        state.db.set_folder_retention(&folder_id, retention_days)?;
+        state
+            .db
+            .get_folder(&folder_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Folder {}", folder_id)))
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Items that retention policies will trash within the next few days
+#[tauri::command]
+pub async fn get_retention_warnings(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<RetentionWarning>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    retention::upcoming(&state.db).map_err(|e| e.to_string())
+}
+
+/// Run retention policies now instead of waiting for the background task
+#[tauri::command]
+pub async fn apply_retention_policies(
+    state: State<'_, AppState>,
+) -> std::result::Result<RetentionReport, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    retention::enforce(&state.db).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_folder(state: State<'_, AppState>, id: String) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.delete_folder(&id).map_err(|e| e.to_string())
+}
+
+// ============================================
+// Backup Commands
+// ============================================
+
+#[tauri::command]
+pub async fn export_vault(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    password: String,
+) -> std::result::Result<BackupSummary, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let app_version = app_handle.package_info().version.to_string();
+    let summary = backup::export(
+        &state.db,
+        std::path::Path::new(&path),
+        BackupKey::Password(&password),
+        &app_version,
+    )
+    .map_err(|e| e.to_string())?;
+    audit::record(&state.db, AuditEvent::Export, None, Some(&path));
+
+    Ok(summary)
+}
+
+/// Export logins and notes as a plaintext, Bitwarden-format CSV for moving
+/// to another password manager. The master password is asked for again and
+/// a warning shown before anything is written; `None` if the user backs out
+/// at the warning or the save dialog.
+#[tauri::command]
+pub async fn export_csv(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    master_key_hash: String,
+) -> std::result::Result<Option<CsvExportSummary>, String> {
+    // Not held across the dialogs, which would keep auto-lock waiting on them
+    check_locked(*state.is_locked.read().await).map_err(|e| e.to_string())?;
+
+    let result: Result<Option<CsvExportSummary>> = async {
+        if let Err(e) = confirm_master_key(&state, &master_key_hash).await {
+            audit::record(
+                &state.db,
+                AuditEvent::UnlockFailed,
+                None,
+                Some("csv_export"),
+            );
+            return Err(e);
+        }
+        if !export::confirm(&app_handle).await {
+            return Ok(None);
+        }
+
+        let key = state.encryption_key().await?;
+        let pending = export::write_csv(&state.db, &key)?;
+        let destination = match export::choose_destination(&app_handle).await {
+            Ok(Some(destination)) => destination,
+            Ok(None) => {
+                export::discard(&pending.path);
+                return Ok(None);
+            }
+            Err(e) => {
+                export::discard(&pending.path);
+                return Err(e);
+            }
+        };
+        export::save(&pending, &destination)?;
+
+        let path = destination.display().to_string();
+        audit::record(&state.db, AuditEvent::Export, None, Some(&path));
+        Ok(Some(CsvExportSummary {
+            path,
+            exported: pending.exported,
+            skipped: pending.skipped,
+        }))
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    password: Option<String>,
+) -> std::result::Result<BackupSummary, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    // Scheduled backups are sealed with the vault key rather than a password
+    let vault_key = state.encryption_key.read().await.clone();
+    // It rewrites records a sync would otherwise be pushing or pulling
+    let summary = state
+        .sync_engine
+        .while_paused(|| async {
+            backup::restore(
+                &state.db,
+                std::path::Path::new(&path),
+                password.as_deref(),
+                vault_key.as_deref(),
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Settings came from the backup too
+    if let Ok(settings) = state.db.get_settings() {
+        i18n::set_locale(&settings.locale);
+    }
+    privacy::refresh(&app_handle);
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<BackupFile>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<BackupFile>> = async {
+        let settings = state.db.get_settings()?;
+        backup::list(&backup::backup_dir(&app_handle, &settings)?)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_backup(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    file_name: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        let settings = state.db.get_settings()?;
+        backup::delete(&backup::backup_dir(&app_handle, &settings)?, &file_name)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Import Commands
+// ============================================
+
+/// Items encrypted between `import-progress` events
+const IMPORT_PROGRESS_STEP: usize = 50;
+
+#[tauri::command]
+pub async fn import_vault(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    format: Option<ImportFormat>,
+) -> std::result::Result<ImportSummary, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<ImportSummary> = async {
+        let key = state.encryption_key().await?;
+        let contents = std::fs::read(&path)?;
+        let format = match format {
+            Some(format) => format,
+            None => ImportFormat::detect(std::path::Path::new(&path), &contents)?,
+        };
+
+        let mut parsed = import::parse(format, &contents)?;
+        let existing = import::existing_login_keys(&state.db.get_all_vault_items()?, &key);
+        import::remove_duplicates(&mut parsed, existing);
+
+        let existing_folders = state.db.get_all_folders()?;
+        let records = import::build_records(&parsed, &existing_folders, &key, |processed, total| {
+            if processed % IMPORT_PROGRESS_STEP == 0 || processed == total {
+                let _ = app_handle.emit("import-progress", ImportProgress { processed, total });
+            }
+        })?;
+
+        state.db.import_records(&records.folders, &records.items)?;
+
+        Ok(ImportSummary {
+            imported: records.items.len(),
+            folders_created: records.folders.len(),
+            skipped: parsed.skipped,
+            duplicates: parsed.duplicates,
+            warnings: parsed.warnings,
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Sync Commands
+// ============================================
+
+#[tauri::command]
+pub async fn sync_vault(
+    state: State<'_, AppState>,
+    trigger: Option<String>,
+) -> std::result::Result<SyncStatus, String> {
+    // Not held across the sync, so locking doesn't wait for it
+    check_locked(*state.is_locked.read().await).map_err(|e| e.to_string())?;
+
+    state
+        .sync_engine
+        .sync(trigger.as_deref().unwrap_or("manual"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The offline profile's folder or WebDAV sync, without the WebDAV password
+#[tauri::command]
+pub async fn get_sync_backend(
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<BackendConfig>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let backend = filesync::configured(&state.db).map_err(|e| e.to_string())?;
+    Ok(backend.map(BackendConfig::redacted))
+}
+
+/// Sync the offline profile through a folder or WebDAV server, once it passes
+/// a connection test, or stop with `None`. Accounts sync with their server.
+#[tauri::command]
+pub async fn set_sync_backend(
+    state: State<'_, AppState>,
+    backend: Option<BackendConfig>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        if !offline::is_active(&state.db) {
+            return Err(AppError::InvalidOperation(
+                "Folder and WebDAV sync are for offline vaults".to_string(),
+            ));
+        }
+        filesync::set(&state.db, &reqwest::Client::new(), backend).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Recent sync attempts, newest first
+#[tauri::command]
+pub async fn get_sync_history(
+    state: State<'_, AppState>,
+    limit: Option<u32>,
+) -> std::result::Result<Vec<SyncHistoryEntry>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .get_sync_history(limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
+
+/// Queued changes the server has refused, for the sync errors view
+#[tauri::command]
+pub async fn get_sync_errors(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<SyncError>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_sync_errors().map_err(|e| e.to_string())
+}
+
+/// Put a parked change back in the queue (after fixing a policy server-side, say)
+#[tauri::command]
+pub async fn retry_sync_error(
+    state: State<'_, AppState>,
+    queue_id: i64,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    if !state
+        .db
+        .unblock_sync_item(queue_id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(AppError::NotFound(format!("Sync queue entry {}", queue_id)).to_string());
+    }
+    Ok(())
+}
+
+/// Drop a change that will never sync; the local record is kept
+#[tauri::command]
+pub async fn discard_sync_error(
+    state: State<'_, AppState>,
+    queue_id: i64,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .remove_from_sync_queue(queue_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Items changed both here and on the server, each with its conflicted copy
+#[tauri::command]
+pub async fn get_sync_conflicts(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<SyncConflict>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_sync_conflicts().map_err(|e| e.to_string())
+}
+
+/// Settle a conflict; waits for a running sync so the copy isn't mid-upload
+#[tauri::command]
+pub async fn resolve_conflict(
+    state: State<'_, AppState>,
+    id: String,
+    resolution: ConflictResolution,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .sync_engine
+        .while_idle(|| state.db.resolve_conflict(&id, resolution))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_sync_status(
+    state: State<'_, AppState>,
+) -> std::result::Result<SyncStatus, String> {
+    Ok(state.sync_engine.get_status().await)
+}
+
+#[tauri::command]
+pub async fn check_connectivity(state: State<'_, AppState>) -> std::result::Result<bool, String> {
+    Ok(state.sync_engine.check_connectivity().await)
+}
+
+/// Whether the server's schema matches this build, for the "update" / "migrate" banners
+#[tauri::command]
+pub async fn get_schema_status(
+    state: State<'_, AppState>,
+) -> std::result::Result<SchemaStatus, String> {
+    offline::ensure_network_allowed(&state.db).map_err(|e| e.to_string())?;
+
+    state
+        .sync_engine
+        .schema_status()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Build a redacted diagnostics bundle for bug reports. With `share` set it is
+/// also uploaded, and a link valid for `link_hours` (default 72, at most a
+/// week) is returned alongside the local file.
+#[tauri::command]
+pub async fn create_support_bundle(
+    state: State<'_, AppState>,
+    share: bool,
+    link_hours: Option<u32>,
+) -> std::result::Result<SupportBundle, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<SupportBundle> = async {
+        if share {
+            offline::ensure_network_allowed(&state.db)?;
+        }
+
+        let offline_profile = offline::is_active(&state.db);
+        let sync_status = state.sync_engine.get_status().await;
+        let schema = SchemaReport::new(if sync_status.is_online && !offline_profile {
+            Some(state.sync_engine.schema_status().await)
+        } else {
+            None
+        });
+        let (mut bundle, bytes) =
+            diagnostics::create_bundle(&state.db, &schema, &sync_status, offline_profile)?;
+
+        if share {
+            let hours = link_hours
+                .unwrap_or(diagnostics::DEFAULT_LINK_HOURS)
+                .clamp(1, diagnostics::MAX_LINK_HOURS);
+            let url = state
+                .sync_engine
+                .share_support_bundle(&bundle.file_name, bytes, hours as u64 * 3600)
+                .await?;
+            bundle.share_url = Some(url);
+            bundle.share_expires_at =
+                Some((Utc::now() + chrono::Duration::hours(hours as i64)).to_rfc3339());
+        }
+
+        Ok(bundle)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Emergency Access Commands
+// ============================================
+
+/// Emergency access arrangements on both sides, with pending invitations
+#[tauri::command]
+pub async fn list_emergency_access(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<EmergencyAccess>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<EmergencyAccess>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::list(&state.sync_engine).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Invite a trusted contact; `wait_days` defaults to a week
+#[tauri::command]
+pub async fn invite_emergency_contact(
+    state: State<'_, AppState>,
+    email: String,
+    wait_days: Option<u32>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::invite(
+            &state.sync_engine,
+            &email,
+            wait_days.unwrap_or(emergency::DEFAULT_WAIT_DAYS),
+        )
+        .await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Accept an invitation as the trusted contact
+#[tauri::command]
+pub async fn accept_emergency_invite(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        emergency::accept(&state.sync_engine, &id, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Escrow the vault key for a contact who has accepted
+#[tauri::command]
+pub async fn confirm_emergency_contact(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        emergency::confirm(&state.sync_engine, &id, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Ask for access as the trusted contact; starts the wait period
+#[tauri::command]
+pub async fn request_emergency_access(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::request(&state.sync_engine, &id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Grant a pending request before the wait period is over
+#[tauri::command]
+pub async fn approve_emergency_access(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::approve(&state.sync_engine, &id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Deny a pending request, or withdraw access already granted
+#[tauri::command]
+pub async fn deny_emergency_access(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::deny(&state.sync_engine, &id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// End an arrangement from either side
+#[tauri::command]
+pub async fn revoke_emergency_access(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        emergency::revoke(&state.sync_engine, &id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// The grantor's items, read-only, once access has been granted
+#[tauri::command]
+pub async fn view_emergency_vault(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<Vec<EmergencyVaultItem>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<EmergencyVaultItem>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        emergency::view_vault(&state.sync_engine, &id, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Organization Commands
+// ============================================
+
+/// Organizations we belong to or are invited to
+#[tauri::command]
+pub async fn list_organizations(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<Organization>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state.db.get_organizations().map_err(|e| e.to_string())
+}
+
+/// Collections in an organization, with our access to each
+#[tauri::command]
+pub async fn list_collections(
+    state: State<'_, AppState>,
+    organization_id: String,
+) -> std::result::Result<Vec<Collection>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .get_collections(&organization_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Create an organization with us as owner; returns its id
+#[tauri::command]
+pub async fn create_organization(
+    state: State<'_, AppState>,
+    name: String,
+) -> std::result::Result<String, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<String> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        organizations::create(&state.sync_engine, &key, &name).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn accept_organization_invite(
+    state: State<'_, AppState>,
+    organization_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        organizations::accept(&state.sync_engine, &organization_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Leave an organization, or decline an invitation; owners can't leave
+#[tauri::command]
+pub async fn leave_organization(
+    state: State<'_, AppState>,
+    organization_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let org = state
+            .db
+            .get_organization(&organization_id)?
+            .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))?;
+        if org.role == "owner" {
+            return Err(AppError::InvalidOperation(
+                "The owner can't leave their organization".to_string(),
+            ));
+        }
+        let session = state.sync_engine.active_session().await?;
+        organizations::remove_member(&state.sync_engine, &organization_id, &session.user_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_org_members(
+    state: State<'_, AppState>,
+    organization_id: String,
+) -> std::result::Result<Vec<OrgMember>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<OrgMember>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        org_membership(&state.db, &organization_id)?;
+        organizations::list_members(&state.sync_engine, &organization_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn invite_org_member(
+    state: State<'_, AppState>,
+    organization_id: String,
+    email: String,
+    role: OrgRole,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let org = require_org_admin(&state.db, &organization_id)?;
+        let key = state.encryption_key().await?;
+        organizations::invite(&state.sync_engine, &state.db, &key, &org, &email, role).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_org_member_role(
+    state: State<'_, AppState>,
+    organization_id: String,
+    user_id: String,
+    role: OrgRole,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        require_org_admin(&state.db, &organization_id)?;
+        organizations::set_member_role(&state.sync_engine, &organization_id, &user_id, role).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_org_member(
+    state: State<'_, AppState>,
+    organization_id: String,
+    user_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        require_org_admin(&state.db, &organization_id)?;
+        organizations::remove_member(&state.sync_engine, &organization_id, &user_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_collection(
+    state: State<'_, AppState>,
+    organization_id: String,
+    name: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        require_org_admin(&state.db, &organization_id)?;
+        organizations::create_collection(&state.sync_engine, &organization_id, &name).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_collection(
+    state: State<'_, AppState>,
+    organization_id: String,
+    collection_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        require_org_admin(&state.db, &organization_id)?;
+        if !state
+            .db
+            .get_collections(&organization_id)?
+            .iter()
+            .any(|c| c.id == collection_id)
+        {
+            return Err(AppError::NotFound("Collection not found".to_string()));
+        }
+        organizations::delete_collection(&state.sync_engine, &collection_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Grant a member read, write or admin access to a collection; `None` revokes it
+#[tauri::command]
+pub async fn set_collection_access(
+    state: State<'_, AppState>,
+    organization_id: String,
+    collection_id: String,
+    user_id: String,
+    permission: Option<CollectionPermission>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        require_org_admin(&state.db, &organization_id)?;
+        organizations::set_collection_access(
+            &state.sync_engine,
+            &collection_id,
+            &user_id,
+            permission,
+        )
+        .await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// An organization's items, decrypted, optionally limited to one collection
+#[tauri::command]
+pub async fn list_org_items(
+    state: State<'_, AppState>,
+    organization_id: String,
+    collection_id: Option<String>,
+) -> std::result::Result<Vec<OrgVaultItem>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<OrgVaultItem>> = async {
+        let (org, _) = org_membership(&state.db, &organization_id)?;
+        let key = state.encryption_key().await?;
+        let org_key = organizations::org_key(&state.sync_engine, &state.db, &key, &org).await?;
+        let collections = state.db.get_collections(&org.id)?;
+
+        let mut items = Vec::new();
+        for item in state.db.get_org_items(&org.id)? {
+            if collection_id
+                .as_ref()
+                .is_some_and(|id| !item.collection_ids.contains(id))
+            {
+                continue;
+            }
+            let id = item.id.clone();
+            match organizations::decrypt_item(item, &org_key, &org, &collections) {
+                Ok(item) => items.push(item),
+                Err(e) => log::warn!("Skipping organization item {}: {}", id, e),
+            }
+        }
+        Ok(items)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Add an item to an organization. Members must put it in at least one
+/// collection they can write to.
+#[tauri::command]
+pub async fn create_org_item(
+    state: State<'_, AppState>,
+    organization_id: String,
+    collection_ids: Vec<String>,
+    item_type: String,
+    data: serde_json::Value,
+) -> std::result::Result<OrgVaultItem, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<OrgVaultItem> = async {
+        let (org, role) = org_membership(&state.db, &organization_id)?;
+        if collection_ids.is_empty() && !role.can_manage() {
+            return Err(AppError::InvalidOperation(
+                "Choose a collection for the item".to_string(),
+            ));
+        }
+        require_collection_write(&state.db, &org, &collection_ids)?;
+        validate_org_item_data(&item_type, &data, None)?;
+
+        let key = state.encryption_key().await?;
+        let org_key = organizations::org_key(&state.sync_engine, &state.db, &key, &org).await?;
+        let session = state.db.get_session()?;
+        let item = OrgItem {
+            id: Uuid::new_v4().to_string(),
+            organization_id: org.id.clone(),
+            encrypted_data: organizations::encrypt_data(&data, &org_key)?,
+            item_type,
+            created_by: session.map(|s| s.user_id),
+            deleted_at: None,
+            synced_at: None,
+            local_updated_at: Utc::now().to_rfc3339(),
+            server_updated_at: None,
+            collection_ids,
+        };
+        state.db.create_org_item(&item)?;
+
+        let collections = state.db.get_collections(&org.id)?;
+        organizations::decrypt_item(item, &org_key, &org, &collections)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_org_item(
+    state: State<'_, AppState>,
+    id: String,
+    item_type: String,
+    data: serde_json::Value,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        let mut item = state
+            .db
+            .get_org_item(&id)?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+        let org = require_item_write(&state.db, &item)?;
+
+        let key = state.encryption_key().await?;
+        let org_key = organizations::org_key(&state.sync_engine, &state.db, &key, &org).await?;
+        let previous = crypto::decrypt_from_json(&item.encrypted_data, &org_key)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        validate_org_item_data(&item_type, &data, previous.as_ref())?;
+        item.encrypted_data = organizations::encrypt_data(&data, &org_key)?;
+        item.item_type = item_type;
+        state.db.update_org_item(&item)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Move an org item to the trash, or back out with `restore`
+#[tauri::command]
+pub async fn trash_org_item(
+    state: State<'_, AppState>,
+    id: String,
+    restore: Option<bool>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        let mut item = state
+            .db
+            .get_org_item(&id)?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+        require_item_write(&state.db, &item)?;
+
+        item.deleted_at = if restore.unwrap_or(false) {
+            None
+        } else {
+            Some(Utc::now().to_rfc3339())
+        };
+        state.db.update_org_item(&item)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Change which collections an org item is in
+#[tauri::command]
+pub async fn set_org_item_collections(
+    state: State<'_, AppState>,
+    id: String,
+    collection_ids: Vec<String>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let item = state
+            .db
+            .get_org_item(&id)?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+        if item.server_updated_at.is_none() {
+            return Err(AppError::InvalidOperation(
+                "Sync the item before moving it".to_string(),
+            ));
+        }
+        let org = require_item_write(&state.db, &item)?;
+        if collection_ids.is_empty() && !organizations::role(&org).is_some_and(|r| r.can_manage())
+        {
+            return Err(AppError::InvalidOperation(
+                "The item must stay in at least one collection".to_string(),
+            ));
+        }
+
+        let changed: Vec<String> = collection_ids
+            .iter()
+            .filter(|id| !item.collection_ids.contains(id))
+            .chain(
+                item.collection_ids
+                    .iter()
+                    .filter(|id| !collection_ids.contains(id)),
+            )
+            .cloned()
+            .collect();
+        require_collection_write(&state.db, &org, &changed)?;
+
+        organizations::set_item_collections(&state.sync_engine, &state.db, &item, &collection_ids)
+            .await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Sharing Commands
+// ============================================
+
+/// Share an item with another account by email, read-only or editable
+#[tauri::command]
+pub async fn share_item(
+    state: State<'_, AppState>,
+    item_id: String,
+    email: String,
+    permission: SharePermission,
+    expires_at: Option<String>,
+) -> std::result::Result<ItemShare, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<ItemShare> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sharing::share(
+            &state.sync_engine,
+            &state.db,
+            &key,
+            &item_id,
+            &email,
+            permission,
+            expires_at,
+        )
+        .await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Who one of our items is shared with
+#[tauri::command]
+pub async fn list_item_shares(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> std::result::Result<Vec<ItemShare>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<ItemShare>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        sharing::list_item_shares(&state.sync_engine, &item_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Items shared with this account, with their origin and permission
+#[tauri::command]
+pub async fn list_shared_with_me(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<SharedItem>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<SharedItem>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sharing::list_shared_with_me(&state.sync_engine, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn accept_shared_item(
+    state: State<'_, AppState>,
+    share_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        sharing::accept(&state.sync_engine, &share_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Save an edit to an item shared with us; fails for read-only shares
+#[tauri::command]
+pub async fn update_shared_item(
+    state: State<'_, AppState>,
+    share_id: String,
+    data: serde_json::Value,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sharing::update_shared(&state.sync_engine, &key, &share_id, &data).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Revoke a share as the owner, or leave it as the recipient
+#[tauri::command]
+pub async fn revoke_item_share(
+    state: State<'_, AppState>,
+    share_id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sharing::revoke(&state.sync_engine, &key, &share_id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Send Link Commands
+// ============================================
+
+/// Encrypt a text or file and upload it, returning a link that opens it
+/// until it expires or runs out of views
+#[tauri::command]
+pub async fn create_send(
+    state: State<'_, AppState>,
+    request: NewSend,
+) -> std::result::Result<Send, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Send> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sends::create(&state.sync_engine, &key, request).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Sends that can still be opened, with their links
+#[tauri::command]
+pub async fn list_sends(state: State<'_, AppState>) -> std::result::Result<Vec<Send>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<Send>> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        let key = state.encryption_key().await?;
+        sends::list(&state.sync_engine, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revoke_send(
+    state: State<'_, AppState>,
+    id: String,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<()> = async {
+        offline::ensure_network_allowed(&state.db)?;
+        sends::revoke(&state.sync_engine, &id).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Onboarding Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_onboarding_state(
+    state: State<'_, AppState>,
+) -> std::result::Result<OnboardingState, String> {
+    onboarding::get(&state.db).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn complete_onboarding_step(
+    state: State<'_, AppState>,
+    step: OnboardingStep,
+    skipped: Option<bool>,
+) -> std::result::Result<OnboardingState, String> {
+    onboarding::complete(&state.db, step, skipped.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+// ============================================
+// Server Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_server_config(
+    state: State<'_, AppState>,
+) -> std::result::Result<ServerConfig, String> {
+    let result: Result<ServerConfig> = async {
+        Ok(ServerConfig {
+            config: state.sync_engine.config(),
+            is_default: server::saved(&state.db)?.is_none(),
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Point this profile at another server, or back at the default with
+/// `None`, once it passes the connection test. Only while signed out: the
+/// session and everything synced belong to the server in use.
+#[tauri::command]
+pub async fn set_server_config(
+    state: State<'_, AppState>,
+    config: Option<SupabaseConfig>,
+) -> std::result::Result<ServerConfig, String> {
+    let result: Result<ServerConfig> = async {
+        if offline::get(&state.db)?.is_some() {
+            return Err(AppError::InvalidOperation(
+                "An offline vault doesn't sync with a server".to_string(),
+            ));
+        }
+        if state.db.get_session()?.is_some() {
+            return Err(AppError::InvalidOperation(
+                "Sign out before changing the server".to_string(),
+            ));
+        }
+
+        let is_default = config.is_none();
+        let config = match config {
+            Some(config) => server::normalise(config)?,
+            None => server::default_config(),
+        };
+        state.sync_engine.test_config(&config).await?;
+
+        server::save(&state.db, (!is_default).then_some(&config))?;
+        state.sync_engine.set_config(config.clone()).await;
+        onboarding::record(&state.db, &[OnboardingStep::ServerConfigured], false);
+        Ok(ServerConfig { config, is_default })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Run the connection test on a server without switching to it
+#[tauri::command]
+pub async fn test_server_config(
+    state: State<'_, AppState>,
+    config: SupabaseConfig,
+) -> std::result::Result<(), String> {
+    let result: Result<()> = async {
+        let config = server::normalise(config)?;
+        state.sync_engine.test_config(&config).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Settings Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_settings(
+    state: State<'_, AppState>,
+) -> std::result::Result<AppSettings, String> {
+    state.db.get_settings().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_settings(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    settings: AppSettings,
+) -> std::result::Result<(), String> {
+    notify::validate_quiet_hours(&settings).map_err(|e| e.to_string())?;
+    backup::validate_schedule(&settings).map_err(|e| e.to_string())?;
+    state.db.save_settings(&settings).map_err(|e| e.to_string())?;
+    if settings.backup_schedule != "off" {
+        onboarding::record(&state.db, &[OnboardingStep::BackupConfigured], false);
+    }
+    if !settings.show_site_icons {
+        icons::clear_cache(&state.db).map_err(|e| e.to_string())?;
+    }
+    state
+        .db
+        .prune_item_revisions(settings.item_revision_limit)
+        .map_err(|e| e.to_string())?;
+    i18n::set_locale(&settings.locale);
+    privacy::refresh(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_screen_capture_protection(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> std::result::Result<(), String> {
+    let mut settings = state.db.get_settings().map_err(|e| e.to_string())?;
+    settings.screen_capture_protection = enabled;
+    state.db.save_settings(&settings).map_err(|e| e.to_string())?;
+    privacy::refresh(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_supported_locales() -> Vec<LocaleInfo> {
+    i18n::supported_locales()
+}
+
+// ============================================
+// UI State Commands
+// ============================================
+//
+// Window size, last selected folder, column widths and the like. Kept apart
+// from `AppSettings`: never synced, available while locked, and wiped on
+// logout. The database is encrypted at rest, so these are too.
+
+fn check_ui_state_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > UI_STATE_MAX_KEY_LENGTH {
+        return Err(AppError::InvalidOperation(format!(
+            "UI state keys must be 1 to {} characters",
+            UI_STATE_MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_ui_state(
+    state: State<'_, AppState>,
+    key: String,
+) -> std::result::Result<Option<serde_json::Value>, String> {
+    let result: Result<Option<serde_json::Value>> = async {
+        check_ui_state_key(&key)?;
+        match state.db.get_ui_state(&key)? {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Every stored UI state entry, for restoring the whole UI in one call
+#[tauri::command]
+pub async fn get_all_ui_state(
+    state: State<'_, AppState>,
+) -> std::result::Result<HashMap<String, serde_json::Value>, String> {
+    let result: Result<HashMap<String, serde_json::Value>> = async {
+        state
+            .db
+            .get_all_ui_state()?
+            .into_iter()
+            .map(|(key, json)| Ok((key, serde_json::from_str(&json)?)))
+            .collect()
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Store `value` under `key`; `null` removes the entry
+#[tauri::command]
+pub async fn set_ui_state(
+    state: State<'_, AppState>,
+    key: String,
+    value: serde_json::Value,
+) -> std::result::Result<(), String> {
+    let result: Result<()> = async {
+        check_ui_state_key(&key)?;
+        if value.is_null() {
+            return state.db.delete_ui_state(&key);
+        }
+
+        let json = serde_json::to_string(&value)?;
+        if json.len() > UI_STATE_MAX_VALUE_BYTES {
+            return Err(AppError::InvalidOperation(format!(
+                "UI state values must be under {} KB",
+                UI_STATE_MAX_VALUE_BYTES / 1024
+            )));
+        }
+        state.db.set_ui_state(&key, &json)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Audit Log Commands
+// ============================================
+
+const AUDIT_PAGE_DEFAULT: u32 = 100;
+const AUDIT_PAGE_MAX: u32 = 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    #[serde(flatten)]
+    pub filter: AuditFilter,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    /// Matching entries across all pages
+    pub total: i64,
+    /// The whole chain, not just this page
+    pub verification: AuditVerification,
+}
+
+/// A filtered page of the audit log, newest first, with the chain checked
+#[tauri::command]
+pub async fn get_audit_log(
+    state: State<'_, AppState>,
+    query: Option<AuditQuery>,
+) -> std::result::Result<AuditLogPage, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let query = query.unwrap_or_default();
+    let limit = query
+        .limit
+        .unwrap_or(AUDIT_PAGE_DEFAULT)
+        .min(AUDIT_PAGE_MAX);
+    let offset = query.offset.unwrap_or(0);
+
+    let result: Result<AuditLogPage> = async {
+        Ok(AuditLogPage {
+            entries: state.db.get_audit_log(&query.filter, limit, offset)?,
+            total: state.db.count_audit_log(&query.filter)?,
+            verification: state.db.verify_audit_log()?,
+        })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Called by the webview when it reveals a hidden field (password, card
+/// code, hidden custom field), which it decrypts itself
+#[tauri::command]
+pub async fn record_hidden_field_view(
+    state: State<'_, AppState>,
+    item_id: String,
+    field: Option<String>,
+) -> std::result::Result<(), String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    audit::record(
+        &state.db,
+        AuditEvent::HiddenFieldViewed,
+        Some(&item_id),
+        field.as_deref(),
+    );
+    Ok(())
+}
+
+// ============================================
+// Clipboard Commands
+// ============================================
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardClearInPayload {
+    pub seconds: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardClearedPayload {
+    /// "timer", "replaced" (something else was copied) or "manual"
+    pub reason: String,
+}
+
+#[tauri::command]
+pub async fn copy_to_clipboard(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    text: String,
+    clear_after_seconds: Option<u32>,
+    item_id: Option<String>,
+) -> std::result::Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    clipboard::write_secret(&app_handle, &text).map_err(|e| e.to_string())?;
+
+    if let Some(id) = &item_id {
+        if let Err(e) = state.db.record_item_use(id) {
+            log::warn!("Failed to record the use of item {}: {}", id, e);
+        }
+        *state.last_copied_item_id.write().await = item_id;
+        tray::refresh(&app_handle);
+    }
+
+    // A new copy supersedes any countdown that is still running
+    let generation = state.clipboard_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    // Schedule clipboard clear if requested, announcing the remaining time each second
+    if let Some(seconds) = clear_after_seconds {
+        let handle = app_handle.clone();
+        let current_generation = state.clipboard_generation.clone();
+        let original_text = text.clone();
+        tokio::spawn(async move {
+            let superseded = || current_generation.load(Ordering::SeqCst) != generation;
+            let still_ours = || {
+                handle
+                    .clipboard()
+                    .read_text()
+                    .map(|current| current == original_text)
+                    .unwrap_or(false)
+            };
 
-    state.db.insert_vault_item(&item).map_err(|e| e.to_string())?;
-    Ok(item)
+            for remaining in (1..=seconds).rev() {
+                if superseded() {
+                    return;
+                }
+                if !still_ours() {
+                    let _ = handle.emit(
+                        "clipboard-cleared",
+                        ClipboardClearedPayload {
+                            reason: "replaced".to_string(),
+                        },
+                    );
+                    return;
+                }
+                let _ = handle.emit(
+                    "clipboard-clear-in",
+                    ClipboardClearInPayload { seconds: remaining },
+                );
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            }
+
+            if superseded() {
+                return;
+            }
+
+            // Only clear if clipboard still contains our text
+            let reason = if still_ours() {
+                if let Err(e) = clipboard::clear(&handle) {
+                    log::warn!("Failed to clear the clipboard: {}", e);
+                }
+                notify::dispatch(
+                    &handle,
+                    NotificationCategory::ClipboardCleared,
+                    &i18n::t("notify.clipboard_cleared_title"),
+                    &i18n::t("notify.clipboard_cleared_body"),
+                );
+                "timer"
+            } else {
+                "replaced"
+            };
+            let _ = handle.emit(
+                "clipboard-cleared",
+                ClipboardClearedPayload {
+                    reason: reason.to_string(),
+                },
+            );
+        });
+    }
+
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn update_vault_item(
+pub async fn clear_clipboard(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    request: UpdateVaultItemRequest,
-) -> std::result::Result<VaultItem, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+) -> std::result::Result<(), String> {
+    // Cancel any pending countdown
+    state.clipboard_generation.fetch_add(1, Ordering::SeqCst);
 
-    let now = Utc::now().to_rfc3339();
-    let item = VaultItem {
-        id: request.id.clone(),
-        encrypted_data: request.encrypted_data,
-        item_type: request.item_type,
-        folder_id: request.folder_id,
-        is_favorite: request.is_favorite,
-        deleted_at: None,
-        synced_at: None,
-        local_updated_at: now,
-        server_updated_at: None,
-    };
+    clipboard::clear(&app_handle).map_err(|e| e.to_string())?;
 
-    state.db.update_vault_item(&item).map_err(|e| e.to_string())?;
-    Ok(item)
+    let _ = app_handle.emit(
+        "clipboard-cleared",
+        ClipboardClearedPayload {
+            reason: "manual".to_string(),
+        },
+    );
+
+    Ok(())
 }
 
+// ============================================
+// Auto-Type Commands
+// ============================================
+
+/// Type an item's credentials into the window that had focus before ours.
+/// `sequence` overrides the item's own for a one-off.
 #[tauri::command]
-pub async fn delete_vault_item(
+pub async fn auto_type(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    id: String,
+    item_id: String,
+    sequence: Option<String>,
 ) -> std::result::Result<(), String> {
+    use tauri::Manager;
+
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    state
-        .db
-        .soft_delete_vault_item(&id)
+    let result: Result<()> = async {
+        if !autotype::is_supported() {
+            return Err(AppError::InvalidOperation(
+                "Auto-type is not supported on this platform".to_string(),
+            ));
+        }
+
+        let key = state.encryption_key().await?;
+        let item = state
+            .db
+            .get_vault_item(&item_id)?
+            .filter(|item| item.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(format!("Vault item {}", item_id)))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&crypto::decrypt_from_json(&item.encrypted_data, &key)?)?;
+
+        let sequence = sequence
+            .as_deref()
+            .unwrap_or_else(|| autotype::item_sequence(&data));
+        let steps = autotype::resolve(autotype::parse(sequence)?, &data);
+        let delay_ms = state.db.get_settings()?.auto_type_delay_ms;
+
+        // Minimising hands focus back to whatever the user was filling in
+        if let Some(window) = app_handle.get_webview_window("main") {
+            if let Err(e) = window.minimize() {
+                log::warn!("Failed to minimise before auto-type: {}", e);
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(autotype::FOCUS_DELAY_MS)).await;
+
+        tokio::task::spawn_blocking(move || autotype::send(&steps, delay_ms))
+            .await
+            .map_err(|e| AppError::InvalidOperation(e.to_string()))??;
+        if let Err(e) = state.db.record_item_use(&item_id) {
+            log::warn!("Failed to record the use of item {}: {}", item_id, e);
+        }
+        Ok(())
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Check a per-item sequence while the user edits it
+#[tauri::command]
+pub fn validate_auto_type_sequence(sequence: String) -> std::result::Result<(), String> {
+    autotype::parse(&sequence)
+        .map(|_| ())
         .map_err(|e| e.to_string())
 }
 
+// ============================================
+// Send to Phone Commands
+// ============================================
+
 #[tauri::command]
-pub async fn restore_vault_item(
+pub async fn get_paired_devices(
     state: State<'_, AppState>,
-    id: String,
-) -> std::result::Result<(), String> {
+) -> std::result::Result<Vec<PairedDevice>, String> {
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    state
-        .db
-        .restore_vault_item(&id)
-        .map_err(|e| e.to_string())
+    state.db.get_paired_devices().map_err(|e| e.to_string())
 }
 
+/// Pair a new device; the returned secret is only shown once
 #[tauri::command]
-pub async fn permanently_delete_vault_item(
+pub async fn pair_device(
     state: State<'_, AppState>,
-    id: String,
-) -> std::result::Result<(), String> {
+    name: String,
+) -> std::result::Result<DevicePairing, String> {
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    state
-        .db
-        .permanently_delete_vault_item(&id)
-        .map_err(|e| e.to_string())
+    devices::pair(&state.db, &name).map_err(|e| e.to_string())
 }
 
-// ============================================
-// Folders Commands
-// ============================================
-
 #[tauri::command]
-pub async fn get_folders(state: State<'_, AppState>) -> std::result::Result<Vec<Folder>, String> {
+pub async fn unpair_device(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> std::result::Result<(), String> {
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    state.db.get_all_folders().map_err(|e| e.to_string())
+    devices::unpair(&state.db, &device_id).map_err(|e| e.to_string())
 }
 
+/// Push a secret to a paired device, end-to-end encrypted
 #[tauri::command]
-pub async fn create_folder(
+pub async fn send_to_device(
     state: State<'_, AppState>,
-    request: CreateFolderRequest,
-) -> std::result::Result<Folder, String> {
+    device_id: String,
+    value: String,
+    label: Option<String>,
+) -> std::result::Result<(), String> {
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    let now = Utc::now().to_rfc3339();
-    let folder = Folder {
-        id: Uuid::new_v4().to_string(),
-        name: request.name,
-        synced_at: None,
-        local_updated_at: now,
-    };
+    let result: Result<()> = async {
+        let (topic, payload) = devices::seal(&device_id, label, value)?;
+        state
+            .sync_engine
+            .broadcast(&topic, devices::SEND_EVENT, payload)
+            .await?;
+        state.db.touch_paired_device(&device_id)?;
+        Ok(())
+    }
+    .await;
 
-    state.db.insert_folder(&folder).map_err(|e| e.to_string())?;
-    Ok(folder)
+    result.map_err(|e| e.to_string())
+}
+
+// ============================================
+// Password Strength Commands
+// ============================================
+
+/// Strength, crack-time estimates and suggestions for item forms and the generator.
+/// `user_inputs` are personal strings (name, email, site) to penalise.
+#[tauri::command]
+pub fn analyze_password_strength(
+    password: String,
+    user_inputs: Option<Vec<String>>,
+) -> PasswordAnalysis {
+    let user_inputs = user_inputs.unwrap_or_default();
+    let user_inputs: Vec<&str> = user_inputs.iter().map(String::as_str).collect();
+    strength::analyze(&password, &user_inputs)
 }
 
+/// Gate for setting or changing the master password. `item_password_hashes`
+/// are SHA-256 hex digests of the vault's item passwords, computed by the frontend.
 #[tauri::command]
-pub async fn update_folder(
+pub async fn check_master_password(
     state: State<'_, AppState>,
-    request: UpdateFolderRequest,
-) -> std::result::Result<Folder, String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+    password: String,
+    email: Option<String>,
+    item_password_hashes: Option<Vec<String>>,
+) -> std::result::Result<MasterPasswordCheck, String> {
+    let settings = state.db.get_settings().map_err(|e| e.to_string())?;
+    let user_inputs: Vec<&str> = email.as_deref().into_iter().collect();
 
-    let now = Utc::now().to_rfc3339();
-    let folder = Folder {
-        id: request.id.clone(),
-        name: request.name,
-        synced_at: None,
-        local_updated_at: now,
-    };
+    Ok(strength::check_master_password(
+        &password,
+        &user_inputs,
+        settings.min_master_password_score,
+        &item_password_hashes.unwrap_or_default(),
+    ))
+}
 
-    state.db.update_folder(&folder).map_err(|e| e.to_string())?;
-    Ok(folder)
+// ============================================
+// Card and Identity Commands
+// ============================================
+
+/// Brand for a (possibly partial) card number, for live feedback while typing
+#[tauri::command]
+pub fn detect_card_brand(number: String) -> Option<CardBrand> {
+    items::detect_brand(&number)
 }
 
+/// Parse an "MM/YY"-style expiry typed into a single box
 #[tauri::command]
-pub async fn delete_folder(state: State<'_, AppState>, id: String) -> std::result::Result<(), String> {
-    let locked = state.is_locked.read().await;
-    check_locked(*locked).map_err(|e| e.to_string())?;
+pub fn parse_card_expiry(text: String) -> std::result::Result<CardExpiry, String> {
+    items::parse_expiry_text(&text).map_err(|e| e.to_string())
+}
 
-    state.db.delete_folder(&id).map_err(|e| e.to_string())
+/// Brand, masked number and expiry status for list views
+#[tauri::command]
+pub fn get_card_display(card: Card) -> CardDisplay {
+    items::card_display(&card)
+}
+
+/// Full name and masked identifiers for list views
+#[tauri::command]
+pub fn get_identity_display(identity: Identity) -> IdentityDisplay {
+    items::identity_display(&identity)
 }
 
 // ============================================
-// Sync Commands
+// Passkey Commands
 // ============================================
 
+/// Register a new passkey and store it as a vault item
 #[tauri::command]
-pub async fn sync_vault(state: State<'_, AppState>) -> std::result::Result<SyncStatus, String> {
+pub async fn create_passkey(
+    state: State<'_, AppState>,
+    request: CreatePasskeyRequest,
+) -> std::result::Result<PasskeyCreated, String> {
     let locked = state.is_locked.read().await;
     check_locked(*locked).map_err(|e| e.to_string())?;
 
-    state.sync_engine.sync().await.map_err(|e| e.to_string())
+    let result: Result<PasskeyCreated> = async {
+        let key = state.encryption_key().await?;
+        let (passkey, registration) = passkeys::create(&request.passkey)?;
+        let payload = passkeys::item_payload(&passkey).to_string();
+
+        let item = VaultItem {
+            id: Uuid::new_v4().to_string(),
+            encrypted_data: crypto::encrypt_to_json(&payload, &key)?,
+            item_type: "passkey".to_string(),
+            folder_id: request.folder_id,
+            is_favorite: false,
+            deleted_at: None,
+            synced_at: None,
+            local_updated_at: Utc::now().to_rfc3339(),
+            server_updated_at: None,
+            sort_index: None,
+        };
+        state.db.insert_vault_item(&item)?;
+
+        Ok(PasskeyCreated { item, registration })
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
 }
 
+/// Live passkeys, optionally only those for one relying party
 #[tauri::command]
-pub async fn get_sync_status(
+pub async fn list_passkeys(
     state: State<'_, AppState>,
-) -> std::result::Result<SyncStatus, String> {
-    Ok(state.sync_engine.get_status().await)
+    rp_id: Option<String>,
+) -> std::result::Result<Vec<PasskeySummary>, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<Vec<PasskeySummary>> = async {
+        let key = state.encryption_key().await?;
+        let rp_id = rp_id.map(|id| id.trim().to_lowercase());
+
+        let summaries = state
+            .db
+            .get_all_vault_items()?
+            .into_iter()
+            .filter(|item| item.item_type == "passkey" && item.deleted_at.is_none())
+            .filter_map(|item| {
+                let plaintext = crypto::decrypt_from_json(&item.encrypted_data, &key).ok()?;
+                let passkey = passkeys::from_payload(&serde_json::from_str(&plaintext).ok()?)?;
+                Some(PasskeySummary::new(item.id, &passkey))
+            })
+            .filter(|summary| rp_id.as_ref().is_none_or(|id| *id == summary.rp_id))
+            .collect();
+
+        Ok(summaries)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
 }
 
+/// Sign a WebAuthn assertion with a stored passkey
 #[tauri::command]
-pub async fn check_connectivity(state: State<'_, AppState>) -> std::result::Result<bool, String> {
-    Ok(state.sync_engine.check_connectivity().await)
+pub async fn get_passkey_assertion(
+    state: State<'_, AppState>,
+    item_id: String,
+    rp_id: String,
+    client_data_hash: String,
+) -> std::result::Result<PasskeyAssertion, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<PasskeyAssertion> = async {
+        let key = state.encryption_key().await?;
+        let item = state
+            .db
+            .get_vault_item(&item_id)?
+            .filter(|item| item.item_type == "passkey" && item.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound(format!("Passkey {}", item_id)))?;
+
+        let plaintext = crypto::decrypt_from_json(&item.encrypted_data, &key)?;
+        let passkey = passkeys::from_payload(&serde_json::from_str(&plaintext)?)
+            .ok_or_else(|| AppError::InvalidOperation("Passkey item has no credential".to_string()))?;
+
+        passkeys::assert(&passkey, &rp_id, &client_data_hash)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
 }
 
 // ============================================
-// Settings Commands
+// Breach Check Commands
 // ============================================
 
 #[tauri::command]
-pub async fn get_settings(
+pub async fn check_password_breach(
     state: State<'_, AppState>,
-) -> std::result::Result<AppSettings, String> {
-    state.db.get_settings().map_err(|e| e.to_string())
+    password: String,
+) -> std::result::Result<BreachCheck, String> {
+    offline::ensure_network_allowed(&state.db).map_err(|e| e.to_string())?;
+
+    breach::check_password(&state.db, &reqwest::Client::new(), &password)
+        .await
+        .map_err(|e| e.to_string())
 }
 
+/// Check every login in the vault, emitting `breach-scan-progress` as it goes
 #[tauri::command]
-pub async fn save_settings(
+pub async fn scan_vault_breaches(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-    settings: AppSettings,
-) -> std::result::Result<(), String> {
-    state.db.save_settings(&settings).map_err(|e| e.to_string())
+) -> std::result::Result<BreachScanReport, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<BreachScanReport> = async {
+        offline::ensure_network_allowed(&state.db)?;
+
+        let key = state.encryption_key().await?;
+        let report = breach::scan_vault(
+            &state.db,
+            &reqwest::Client::new(),
+            &key,
+            |processed, total| {
+                let _ = app_handle
+                    .emit("breach-scan-progress", BreachScanProgress { processed, total });
+            },
+        )
+        .await?;
+
+        if !report.breached.is_empty() {
+            notify::dispatch(
+                &app_handle,
+                NotificationCategory::SecurityAlert,
+                &i18n::t("notify.breach_title"),
+                &i18n::t_with(
+                    "notify.breach_body",
+                    &[("count", &i18n::format_number(report.breached.len() as i64))],
+                ),
+            );
+        }
+
+        Ok(report)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
 }
 
 // ============================================
-// Clipboard Commands
+// Plugin Commands
 // ============================================
 
 #[tauri::command]
-pub async fn copy_to_clipboard(
+pub async fn list_plugins(
     app_handle: tauri::AppHandle,
-    text: String,
-    clear_after_seconds: Option<u32>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<PluginInfo>, String> {
+    plugins::plugins_dir(&app_handle)
+        .and_then(|dir| plugins::list(&state.db, &dir))
+        .map_err(|e| e.to_string())
+}
+
+/// Trust a plugin as it is now; any later change to its executable revokes
+/// this. A trusted plugin can be given vault fields, so the master password
+/// is asked for again.
+#[tauri::command]
+pub async fn trust_plugin(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    plugin_id: String,
+    master_key_hash: String,
+) -> std::result::Result<PluginInfo, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<PluginInfo> = async {
+        if let Err(e) = confirm_master_key(&state, &master_key_hash).await {
+            audit::record(
+                &state.db,
+                AuditEvent::UnlockFailed,
+                None,
+                Some("plugin_trust"),
+            );
+            return Err(e);
+        }
+        plugins::trust(&state.db, &plugins::plugins_dir(&app_handle)?, &plugin_id)
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revoke_plugin_trust(
+    state: State<'_, AppState>,
+    plugin_id: String,
 ) -> std::result::Result<(), String> {
-    use tauri_plugin_clipboard_manager::ClipboardExt;
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
 
-    app_handle
-        .clipboard()
-        .write_text(&text)
-        .map_err(|e| e.to_string())?;
+    plugins::revoke(&state.db, &plugin_id).map_err(|e| e.to_string())
+}
 
-    // Schedule clipboard clear if requested
-    if let Some(seconds) = clear_after_seconds {
-        let handle = app_handle.clone();
-        let original_text = text.clone();
-        tokio::spawn(async move {
-            tokio::time::sleep(tokio::time::Duration::from_secs(seconds as u64)).await;
-            if let Ok(current) = handle.clipboard().read_text() {
-                // Only clear if clipboard still contains our text
-                if current == original_text {
-                    let _ = handle.clipboard().write_text("");
-                }
-            }
-        });
+/// Describe what an action will receive; the returned token is passed to
+/// `invoke_plugin_action`, which asks the user in a native dialog before
+/// anything is shared
+#[tauri::command]
+pub async fn request_plugin_action(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    plugin_id: String,
+    action_id: String,
+    item_id: String,
+) -> std::result::Result<PluginConsentRequest, String> {
+    let locked = state.is_locked.read().await;
+    check_locked(*locked).map_err(|e| e.to_string())?;
+
+    let result: Result<PluginConsentRequest> = async {
+        let (request, pending) = plugins::request_consent(
+            &state.db,
+            &plugins::plugins_dir(&app_handle)?,
+            &plugin_id,
+            &action_id,
+            &item_id,
+        )?;
+        state
+            .plugin_consents
+            .write()
+            .await
+            .insert(request.token.clone(), pending);
+        Ok(request)
     }
+    .await;
 
-    Ok(())
+    result.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn clear_clipboard(app_handle: tauri::AppHandle) -> std::result::Result<(), String> {
-    use tauri_plugin_clipboard_manager::ClipboardExt;
-    app_handle
-        .clipboard()
-        .write_text("")
-        .map_err(|e| e.to_string())
+pub async fn invoke_plugin_action(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    consent_token: String,
+) -> std::result::Result<PluginActionResult, String> {
+    // Not held across the dialog, which would keep auto-lock waiting on it
+    check_locked(*state.is_locked.read().await).map_err(|e| e.to_string())?;
+
+    let result: Result<PluginActionResult> = async {
+        let consent = state
+            .plugin_consents
+            .write()
+            .await
+            .remove(&consent_token)
+            .ok_or_else(|| AppError::InvalidOperation("Unknown or used consent".to_string()))?;
+        if !plugins::confirm(&app_handle, &consent).await {
+            return Err(AppError::InvalidOperation(i18n::t(
+                "plugins.consent_declined",
+            )));
+        }
+
+        let locked = state.is_locked.read().await;
+        check_locked(*locked)?;
+        let key = state.encryption_key().await?;
+        plugins::invoke(&state.db, &plugins::plugins_dir(&app_handle)?, consent, &key).await
+    }
+    .await;
+
+    result.map_err(|e| e.to_string())
 }
 
 // ============================================
@@ -0,0 +1,122 @@
+// ============================================
+// BirchVault Desktop - Connectivity Monitor
+// ============================================
+//
+// Probes Supabase in the background so `SyncStatus.is_online` stays current.
+// A change is only reported once it has held for consecutive probes, so a
+// flaky connection doesn't flap the UI or the sync engine. Reaching the
+// server also finishes a sign-in that an unlock while offline put off.
+
+use crate::commands::AppState;
+use crate::deferred;
+use crate::error::AppError;
+use crate::offline;
+use crate::session::ReauthRequired;
+use crate::sync::Connectivity;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+const PROBE_INTERVAL_SECS: u64 = 30;
+
+/// Consecutive matching probes needed before a transition is reported
+const DEBOUNCE_PROBES: u32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityChanged {
+    pub online: bool,
+    pub captive_portal: bool,
+}
+
+/// Emits `connectivity-changed` on each debounced transition. Going back
+/// online flushes any changes queued while offline.
+pub fn spawn_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(PROBE_INTERVAL_SECS));
+        let mut reported = Connectivity::Online;
+        let mut candidate = Connectivity::Online;
+        let mut streak = 0;
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppState>();
+            if offline::is_active(&state.db) {
+                continue;
+            }
+            let probed = state.sync_engine.probe().await;
+
+            if probed == Connectivity::Online && state.deferred_sign_in.read().await.is_some() {
+                complete_deferred(&app).await;
+            }
+
+            if probed == reported {
+                streak = 0;
+                continue;
+            }
+            if probed == candidate {
+                streak += 1;
+            } else {
+                candidate = probed;
+                streak = 1;
+            }
+            if streak < DEBOUNCE_PROBES {
+                continue;
+            }
+
+            let was_online = reported == Connectivity::Online;
+            reported = probed;
+            streak = 0;
+
+            let online = reported == Connectivity::Online;
+            state.sync_engine.set_online(online).await;
+            let _ = app.emit(
+                "connectivity-changed",
+                ConnectivityChanged {
+                    online,
+                    captive_portal: reported == Connectivity::CaptivePortal,
+                },
+            );
+
+            if online && !was_online && !*state.is_locked.read().await {
+                let has_pending = state
+                    .db
+                    .get_pending_sync_items()
+                    .map(|items| !items.is_empty())
+                    .unwrap_or(false);
+                if has_pending {
+                    match state.sync_engine.sync("reconnect").await {
+                        Ok(status) => {
+                            let _ = app.emit("sync-completed", status);
+                        }
+                        Err(e) => log::warn!("Sync after reconnecting failed: {}", e),
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Sign in and sync as a degraded unlock put off, emitting `sync-completed`,
+/// or `reauth-required` if the server no longer takes the credentials
+async fn complete_deferred(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let email = match state.deferred_sign_in.read().await.as_ref() {
+        Some(deferred) => deferred.email.clone(),
+        None => return,
+    };
+
+    match deferred::complete(&state).await {
+        Ok(Some(status)) => {
+            log::info!("Finished signing in after unlocking offline");
+            let _ = app.emit("sync-completed", status);
+        }
+        Ok(None) => {}
+        Err(e @ (AppError::Auth(_) | AppError::SessionExpired)) => {
+            log::warn!("Deferred sign-in was refused: {}", e);
+            let _ = app.emit("reauth-required", ReauthRequired { email });
+        }
+        Err(e) => log::warn!("Deferred sign-in failed: {}", e),
+    }
+}
@@ -0,0 +1,164 @@
+// ============================================
+// BirchVault Desktop - Crypto Helpers
+// ============================================
+
+use crate::error::{AppError, Result};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::RngCore;
+use ring::hkdf;
+use serde::{Deserialize, Serialize};
+
+pub const KEY_LENGTH: usize = 32;
+pub const IV_LENGTH: usize = 12;
+
+/// AES-256-GCM ciphertext in the same shape as `EncryptedData` in @birchvault/core
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedData {
+    pub iv: String,
+    pub data: String,
+}
+
+/// Generate a random 256-bit symmetric key
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+}
+
+pub fn encrypt(plaintext: &[u8], key: &[u8]) -> Result<EncryptedData> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+
+    let mut iv = [0u8; IV_LENGTH];
+    rand::thread_rng().fill_bytes(&mut iv);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&iv), plaintext)
+        .map_err(|_| AppError::Encryption("Encryption failed".to_string()))?;
+
+    Ok(EncryptedData {
+        iv: BASE64.encode(iv),
+        data: BASE64.encode(ciphertext),
+    })
+}
+
+pub fn decrypt(encrypted: &EncryptedData, key: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Encryption("Invalid key length".to_string()))?;
+
+    let iv = BASE64
+        .decode(&encrypted.iv)
+        .map_err(|_| AppError::Encryption("Invalid IV encoding".to_string()))?;
+    if iv.len() != IV_LENGTH {
+        return Err(AppError::Encryption("Invalid IV length".to_string()));
+    }
+    let ciphertext = BASE64
+        .decode(&encrypted.data)
+        .map_err(|_| AppError::Encryption("Invalid ciphertext encoding".to_string()))?;
+
+    cipher
+        .decrypt(Nonce::from_slice(&iv), ciphertext.as_ref())
+        .map_err(|_| AppError::Encryption("Decryption failed".to_string()))
+}
+
+/// Encrypt a string and serialise the result as JSON (the format stored in `encrypted_data`)
+pub fn encrypt_to_json(plaintext: &str, key: &[u8]) -> Result<String> {
+    let encrypted = encrypt(plaintext.as_bytes(), key)?;
+    Ok(serde_json::to_string(&encrypted)?)
+}
+
+/// Parse an `encrypted_data` JSON value and decrypt it to a string
+pub fn decrypt_from_json(json: &str, key: &[u8]) -> Result<String> {
+    let encrypted: EncryptedData = serde_json::from_str(json)?;
+    String::from_utf8(decrypt(&encrypted, key)?)
+        .map_err(|_| AppError::Encryption("Decrypted data is not valid UTF-8".to_string()))
+}
+
+/// The password Supabase knows the account by, derived from the master key
+/// as `deriveKeys` in @birchvault/core does (HKDF-SHA256), base64
+pub fn derive_auth_hash(master_key: &[u8]) -> Result<String> {
+    struct Length(usize);
+    impl hkdf::KeyType for Length {
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    let mut auth_hash = [0u8; KEY_LENGTH];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, b"birchvault-auth")
+        .extract(master_key)
+        .expand(&[b"auth"], Length(KEY_LENGTH))
+        .and_then(|okm| okm.fill(&mut auth_hash))
+        .map_err(|_| AppError::Encryption("Failed to derive the auth hash".to_string()))?;
+    Ok(BASE64.encode(auth_hash))
+}
+
+// ============================================
+// Verification
+// ============================================
+
+/// Salt length for verifiers, in bytes
+const VERIFIER_SALT_LENGTH: usize = 16;
+
+/// How a secret compared with a stored verifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Valid,
+    /// Matched a value stored before verifiers existed (the secret itself);
+    /// the caller should replace it with `create_verifier`
+    ValidLegacy,
+    Invalid,
+}
+
+/// Compare two secrets without revealing where they first differ. Lengths
+/// can still show, which is fine for hashes and keys of fixed size.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Derive what to store for checking a master key hash later: an Argon2id
+/// PHC string, so the stored value can't be replayed as the hash itself
+pub fn create_verifier(secret: &str) -> Result<String> {
+    let mut salt = [0u8; VERIFIER_SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).map_err(verifier_error)?;
+
+    let hash = Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map_err(verifier_error)?;
+    Ok(hash.to_string())
+}
+
+/// Check a secret against a stored verifier, in constant time either way
+pub fn verify_secret(stored: &str, secret: &str) -> Verification {
+    if stored.starts_with('$') {
+        let matches = PasswordHash::new(stored).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(secret.as_bytes(), &hash)
+                .is_ok()
+        });
+        return if matches {
+            Verification::Valid
+        } else {
+            Verification::Invalid
+        };
+    }
+
+    if constant_time_eq(stored.as_bytes(), secret.as_bytes()) {
+        Verification::ValidLegacy
+    } else {
+        Verification::Invalid
+    }
+}
+
+fn verifier_error(e: argon2::password_hash::Error) -> AppError {
+    AppError::Encryption(format!("Verifier error: {}", e))
+}
@@ -2,14 +2,50 @@
 // BirchVault Desktop - Database Layer
 // ============================================
 
+use crate::crypto;
 use crate::error::{AppError, Result};
+use crate::profiles;
 use chrono::{DateTime, Utc};
+use keyring::Entry;
+use rusqlite::types::Value as SqlValue;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Keychain entry holding the SQLCipher key for vault.db, per profile
+const DB_KEYRING_SERVICE: &str = "birchvault-db";
+const DB_KEYRING_USER: &str = "vault.db";
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// How long a write waits for another connection's lock before giving up
+const BUSY_TIMEOUT_MS: u64 = 5000;
+
+/// Prepared statements kept for reuse; listing and lookups run on every unlock
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Set once legacy `server_updated_at` values have been cleared
+const META_SERVER_VERSIONS_RESET: &str = "server_versions_reset";
+
+/// Every column holding a value encrypted with the vault key
+const VAULT_KEY_COLUMNS: [(&str, &str); 6] = [
+    ("vault_items", "encrypted_data"),
+    ("item_revisions", "encrypted_data"),
+    ("recoverable_deletions", "encrypted_data"),
+    ("attachments", "encrypted_name"),
+    ("attachments", "encrypted_key"),
+    ("organizations", "encrypted_key"),
+];
+
+/// `prev_hash` of the first audit log entry
+const AUDIT_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
 // ============================================
 // Data Types
 // ============================================
@@ -26,6 +62,31 @@ pub struct VaultItem {
     pub synced_at: Option<String>,
     pub local_updated_at: String,
     pub server_updated_at: Option<String>,
+    /// Position among pinned items; `None` when not pinned
+    #[serde(default)]
+    pub sort_index: Option<i64>,
+}
+
+/// Listing row without `encrypted_data`, for virtualised lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultItemSummary {
+    pub id: String,
+    pub item_type: String,
+    pub folder_id: Option<String>,
+    pub is_favorite: bool,
+    pub deleted_at: Option<String>,
+    pub synced_at: Option<String>,
+    pub local_updated_at: String,
+    pub server_updated_at: Option<String>,
+    pub sort_index: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultItemData {
+    pub id: String,
+    pub encrypted_data: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +96,208 @@ pub struct Folder {
     pub name: String,
     pub synced_at: Option<String>,
     pub local_updated_at: String,
+    /// Items in this folder are moved to the trash this many days after their last change
+    #[serde(default)]
+    pub retention_days: Option<i64>,
+}
+
+/// A live item in a folder with a retention policy
+#[derive(Debug, Clone)]
+pub struct RetentionCandidate {
+    pub item_id: String,
+    pub folder_id: String,
+    pub local_updated_at: String,
+    pub retention_days: i64,
+}
+
+/// File attached to a vault item. The name and file key are encrypted with
+/// the vault key; the blob itself is encrypted with the file key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub item_id: String,
+    pub encrypted_name: String,
+    pub encrypted_key: String,
+    /// Plaintext size in bytes
+    pub size: i64,
+    pub created_at: String,
+    pub synced_at: Option<String>,
+}
+
+/// A previous version of an item, kept when it was overwritten
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemRevision {
+    pub id: i64,
+    pub item_id: String,
+    pub encrypted_data: String,
+    pub item_type: String,
+    pub folder_id: Option<String>,
+    pub is_favorite: bool,
+    /// When this version was last edited
+    pub local_updated_at: String,
+    /// When it was replaced by a newer version
+    pub replaced_at: String,
+}
+
+/// An organization the signed-in account belongs to or is invited to. The
+/// org key arrives wrapped with our public key; once unwrapped it is kept
+/// here encrypted with the vault key (see organizations.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    /// "owner", "admin" or "member"
+    pub role: String,
+    /// "invited" or "accepted"
+    pub status: String,
+    #[serde(skip)]
+    pub wrapped_key: Option<String>,
+    #[serde(skip)]
+    pub encrypted_key: Option<String>,
+    pub synced_at: Option<String>,
+}
+
+/// A collection in an organization, with our own access to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    pub id: String,
+    pub organization_id: String,
+    pub name: String,
+    /// "read", "write" or "admin"; `None` without an explicit grant
+    pub permission: Option<String>,
+}
+
+/// An organization's item, encrypted with the org key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgItem {
+    pub id: String,
+    pub organization_id: String,
+    pub encrypted_data: String,
+    pub item_type: String,
+    pub created_by: Option<String>,
+    pub deleted_at: Option<String>,
+    pub synced_at: Option<String>,
+    pub local_updated_at: String,
+    pub server_updated_at: Option<String>,
+    pub collection_ids: Vec<String>,
+}
+
+/// Trash view filters; dates are RFC 3339 and both bounds are inclusive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashFilter {
+    pub item_type: Option<String>,
+    pub deleted_after: Option<String>,
+    pub deleted_before: Option<String>,
+}
+
+impl TrashFilter {
+    pub fn matches(&self, item_type: &str, deleted_at: &str) -> bool {
+        self.item_type.as_deref().is_none_or(|t| t == item_type)
+            && self.deleted_after.as_deref().is_none_or(|d| deleted_at >= d)
+            && self.deleted_before.as_deref().is_none_or(|d| deleted_at <= d)
+    }
+
+    /// SQL conditions (without `WHERE`) and their parameters
+    fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        let mut conditions = vec!["deleted_at IS NOT NULL".to_string()];
+        let mut params = Vec::new();
+        for (column, op, value) in [
+            ("item_type", "=", &self.item_type),
+            ("deleted_at", ">=", &self.deleted_after),
+            ("deleted_at", "<=", &self.deleted_before),
+        ] {
+            if let Some(value) = value {
+                params.push(SqlValue::Text(value.clone()));
+                conditions.push(format!("{} {} ?{}", column, op, params.len()));
+            }
+        }
+        (conditions.join(" AND "), params)
+    }
+}
+
+/// Order of the item list after pinned items, which always come first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemSort {
+    /// Case-insensitive by name; needs `load_sort_names` first
+    Name,
+    /// Last copied or auto-typed first, then last edited
+    #[default]
+    Recent,
+    /// Most copied or auto-typed first
+    MostUsed,
+}
+
+impl ItemSort {
+    /// `ORDER BY` terms over `vault_items v`, `item_usage u` and `item_names n`
+    fn order_by(self) -> &'static str {
+        match self {
+            ItemSort::Name => {
+                "n.name IS NULL, n.name COLLATE NOCASE, v.local_updated_at DESC, v.id"
+            }
+            ItemSort::Recent => {
+                "u.last_used_at IS NULL, u.last_used_at DESC, v.local_updated_at DESC, v.id"
+            }
+            ItemSort::MostUsed => {
+                "COALESCE(u.use_count, 0) DESC, u.last_used_at DESC, v.local_updated_at DESC, v.id"
+            }
+        }
+    }
+}
+
+/// Flags decrypted by the frontend, used to backfill the plaintext columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemFlags {
+    pub id: String,
+    pub is_favorite: bool,
+    pub item_type: String,
+}
+
+/// One sync attempt, for the sync health timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncHistoryEntry {
+    pub id: i64,
+    /// What started the sync ("manual", "tray", "login", ...)
+    pub trigger: String,
+    pub started_at: String,
+    pub duration_ms: i64,
+    pub pushed: i64,
+    pub pulled: i64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Local tombstone for an unsynced item that was force-deleted, kept for a grace period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableDeletion {
+    pub id: String,
+    pub encrypted_data: String,
+    pub item_type: String,
+    pub folder_id: Option<String>,
+    pub is_favorite: bool,
+    /// When the item was moved to the trash, if it was
+    pub trashed_at: Option<String>,
+    pub deleted_at: String,
+    pub expires_at: String,
+}
+
+/// A mobile device paired for "send to phone"; its secret lives in the keyring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedDevice {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub last_sent_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +311,121 @@ pub struct SyncQueueItem {
     pub created_at: String,
 }
 
+/// A queued change whose last push failed, for the sync errors view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncError {
+    pub queue_id: i64,
+    pub operation: String,
+    pub table_name: String,
+    pub record_id: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub last_attempt_at: Option<String>,
+    /// Set when retrying won't help (see `sync::AccessDenial`); the change is
+    /// held back until the user retries or discards it
+    pub blocked_reason: Option<String>,
+}
+
+/// A vault item changed both here and on the server since the last sync. The
+/// server's version keeps the item's id; ours lives on as `copy_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub id: String,
+    pub item_id: String,
+    pub copy_id: String,
+    pub local_updated_at: String,
+    pub server_updated_at: String,
+    pub detected_at: String,
+}
+
+/// How far a paged pull of one server table has got: the last row stored,
+/// in `(updated_at, id)` order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCursor {
+    pub updated_at: String,
+    pub last_id: String,
+}
+
+/// A record permanently deleted on the server, by its server table name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tombstone {
+    pub table_name: String,
+    pub record_id: String,
+}
+
+/// One security-relevant event in the local audit log. `hash` covers the
+/// entry and the previous entry's hash, so an edited or removed entry breaks
+/// the chain from there on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    /// "unlock", "unlock_failed", "export" or "hidden_field_viewed"
+    pub event: String,
+    pub occurred_at: String,
+    pub item_id: Option<String>,
+    /// How the event came about, e.g. the unlock method or export path
+    pub detail: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// Audit log filters; dates are RFC 3339 and both bounds are inclusive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub event: Option<String>,
+    pub item_id: Option<String>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+impl AuditFilter {
+    /// SQL conditions (without `WHERE`) and their parameters
+    fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        let mut conditions = vec!["1 = 1".to_string()];
+        let mut params = Vec::new();
+        for (column, op, value) in [
+            ("event", "=", &self.event),
+            ("item_id", "=", &self.item_id),
+            ("occurred_at", ">=", &self.after),
+            ("occurred_at", "<=", &self.before),
+        ] {
+            if let Some(value) = value {
+                params.push(SqlValue::Text(value.clone()));
+                conditions.push(format!("{} {} ?{}", column, op, params.len()));
+            }
+        }
+        (conditions.join(" AND "), params)
+    }
+}
+
+/// Result of checking the audit log's hash chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub intact: bool,
+    pub entries_checked: i64,
+    /// The first entry that doesn't match its hash or follow its predecessor
+    pub first_broken_id: Option<i64>,
+}
+
+/// Which version of a conflicted item to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Drop the conflicted copy
+    Server,
+    /// Put the conflicted copy's contents back on the original and drop the copy
+    Local,
+    /// Keep the copy as an item in its own right
+    Both,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserSession {
@@ -68,6 +446,86 @@ pub struct AppSettings {
     pub start_on_boot: bool,
     pub theme: String,
     pub color_theme: String,
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Minimum strength score (0-4) accepted for a new master password
+    #[serde(default = "default_min_master_password_score")]
+    pub min_master_password_score: u8,
+    /// Exclude the window from screenshots and screen sharing while unlocked
+    #[serde(default = "default_screen_capture_protection")]
+    pub screen_capture_protection: bool,
+    /// Per-category notification toggles (see notify.rs)
+    #[serde(default = "default_true")]
+    pub notify_sync_failures: bool,
+    #[serde(default = "default_true")]
+    pub notify_security_alerts: bool,
+    #[serde(default = "default_true")]
+    pub notify_expiry_reminders: bool,
+    #[serde(default)]
+    pub notify_clipboard_cleared: bool,
+    /// Local "HH:MM" window in which only security alerts are shown
+    #[serde(default)]
+    pub quiet_hours_start: Option<String>,
+    #[serde(default)]
+    pub quiet_hours_end: Option<String>,
+    /// Scheduled local backups: "off", "daily" or "weekly" (see backup.rs)
+    #[serde(default = "default_backup_schedule")]
+    pub backup_schedule: String,
+    /// Defaults to a "backups" folder in the app data directory
+    #[serde(default)]
+    pub backup_directory: Option<String>,
+    /// Scheduled backups kept before the oldest are deleted
+    #[serde(default = "default_backup_retention_count")]
+    pub backup_retention_count: u32,
+    /// Previous versions kept per item; 0 turns version history off
+    #[serde(default = "default_item_revision_limit")]
+    pub item_revision_limit: u32,
+    /// Pause between keystrokes when auto-typing, for apps that drop fast input
+    #[serde(default = "default_auto_type_delay_ms")]
+    pub auto_type_delay_ms: u32,
+    /// Fetch and show website icons for logins (each fetch contacts the site)
+    #[serde(default = "default_show_site_icons")]
+    pub show_site_icons: bool,
+    /// Let a sync that is running when the vault locks finish; it only moves
+    /// ciphertext. Off stops it at the next record instead.
+    #[serde(default = "default_true")]
+    pub sync_after_lock: bool,
+}
+
+fn default_locale() -> String {
+    crate::i18n::DEFAULT_LOCALE.to_string()
+}
+
+fn default_min_master_password_score() -> u8 {
+    3
+}
+
+fn default_screen_capture_protection() -> bool {
+    true
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_backup_schedule() -> String {
+    "off".to_string()
+}
+
+fn default_backup_retention_count() -> u32 {
+    7
+}
+
+fn default_item_revision_limit() -> u32 {
+    20
+}
+
+fn default_auto_type_delay_ms() -> u32 {
+    25
+}
+
+fn default_show_site_icons() -> bool {
+    true
 }
 
 impl Default for AppSettings {
@@ -79,6 +537,22 @@ impl Default for AppSettings {
             start_on_boot: false,
             theme: "dark".to_string(),
             color_theme: "birch".to_string(),
+            locale: default_locale(),
+            min_master_password_score: default_min_master_password_score(),
+            screen_capture_protection: default_screen_capture_protection(),
+            notify_sync_failures: true,
+            notify_security_alerts: true,
+            notify_expiry_reminders: true,
+            notify_clipboard_cleared: false,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            backup_schedule: default_backup_schedule(),
+            backup_directory: None,
+            backup_retention_count: default_backup_retention_count(),
+            item_revision_limit: default_item_revision_limit(),
+            auto_type_delay_ms: default_auto_type_delay_ms(),
+            show_site_icons: default_show_site_icons(),
+            sync_after_lock: true,
         }
     }
 }
@@ -89,24 +563,155 @@ impl Default for AppSettings {
 
 pub struct Database {
     conn: Mutex<Connection>,
+    /// Directory holding the database, alongside which attachment blobs are kept
+    data_dir: RwLock<PathBuf>,
 }
 
 impl Database {
-    /// Initialize database with the given path
-    pub fn new(db_path: PathBuf) -> Result<Self> {
+    /// Initialize database with the given path, keyed from `profile_id`'s keychain entry
+    pub fn new(db_path: PathBuf, profile_id: &str) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
+        let key = Self::database_key(&db_path, profile_id)?;
+        if Self::is_plaintext(&db_path)? {
+            Self::encrypt_existing(&db_path, &key)?;
+        }
+
         let conn = Connection::open(&db_path)?;
+        Self::apply_key(&conn, &key)?;
+        Self::configure_concurrency(&conn)?;
+        // Keeps the decrypted names in `item_names` out of temp files
+        conn.pragma_update(None, "temp_store", "MEMORY")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         let db = Self {
             conn: Mutex::new(conn),
+            data_dir: RwLock::new(
+                db_path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+            ),
         };
         db.initialize_schema()?;
         Ok(db)
     }
 
+    /// Close this database and open another profile's in its place. Everything
+    /// holding this `Database` sees the new one from its next query.
+    pub fn switch_to(&self, db_path: PathBuf, profile_id: &str) -> Result<()> {
+        let opened = Self::new(db_path, profile_id)?;
+        let conn = opened.conn.into_inner().unwrap();
+        let data_dir = opened.data_dir.into_inner().unwrap();
+
+        *self.conn.lock().unwrap() = conn;
+        *self.data_dir.write().unwrap() = data_dir;
+        Ok(())
+    }
+
+    /// Delete a removed profile's database key
+    pub fn forget_key(profile_id: &str) {
+        let user = profiles::keyring_user_for(profile_id, DB_KEYRING_USER);
+        if let Ok(entry) = Entry::new(DB_KEYRING_SERVICE, &user) {
+            let _ = entry.delete_password();
+        }
+    }
+
+    // ============================================
+    // Encryption at Rest (SQLCipher)
+    // ============================================
+    //
+    // Settings, the session and the locked-screen state are read before the
+    // vault is unlocked, so the page key can't come from the master key.
+    // It is a random key held in the OS keychain instead, which keeps folder
+    // names, item types and timestamps off the disk in plaintext.
+
+    /// The SQLCipher raw key (`x'..'`) for this database, created on first run
+    fn database_key(db_path: &Path, profile_id: &str) -> Result<String> {
+        let user = profiles::keyring_user_for(profile_id, DB_KEYRING_USER);
+        let entry = Entry::new(DB_KEYRING_SERVICE, &user)?;
+        match entry.get_password() {
+            Ok(key) => return Ok(key),
+            Err(keyring::Error::NoEntry) => {}
+            // A locked keychain or a denied prompt: the key may well be there
+            Err(e) => return Err(e.into()),
+        }
+
+        // Without its key an encrypted file is unreadable, but it may hold
+        // what was never synced (offline and folder-synced vaults, the queue,
+        // the audit log), so it is left for the user to deal with
+        if db_path.exists() && !Self::is_plaintext(db_path)? {
+            return Err(AppError::InvalidOperation(format!(
+                "The key for {} is missing from the keychain",
+                db_path.display()
+            )));
+        }
+
+        let key: String = crypto::generate_key()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let key = format!("x'{}'", key);
+        entry.set_password(&key)?;
+        Ok(key)
+    }
+
+    fn apply_key(conn: &Connection, key: &str) -> Result<()> {
+        conn.pragma_update(None, "key", key)?;
+        // Fails here, rather than on first use, if the key is wrong
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Other processes (a second window, the CLI) may open the same file.
+    /// WAL lets readers carry on during a write, and the busy timeout makes
+    /// a writer wait its turn instead of failing with SQLITE_BUSY.
+    fn configure_concurrency(conn: &Connection) -> Result<()> {
+        let mode: String =
+            conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        if !mode.eq_ignore_ascii_case("wal") {
+            log::warn!("Database is in {} journal mode, not WAL", mode);
+        }
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.busy_timeout(Duration::from_millis(BUSY_TIMEOUT_MS))?;
+        Ok(())
+    }
+
+    /// An unencrypted SQLite file starts with a fixed header; a SQLCipher one doesn't
+    fn is_plaintext(db_path: &Path) -> Result<bool> {
+        let mut header = [0u8; 16];
+        match std::fs::File::open(db_path) {
+            Ok(mut file) => Ok(file.read_exact(&mut header).is_ok() && &header == SQLITE_HEADER),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Migrate a database from before encryption at rest, replacing the plaintext file
+    fn encrypt_existing(db_path: &Path, key: &str) -> Result<()> {
+        let encrypted_path = db_path.with_extension("db.encrypting");
+        let _ = std::fs::remove_file(&encrypted_path);
+
+        {
+            let conn = Connection::open(db_path)?;
+            conn.execute(
+                "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+                params![encrypted_path.to_string_lossy(), key],
+            )?;
+            conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+            conn.execute("DETACH DATABASE encrypted", [])?;
+        }
+
+        std::fs::rename(&encrypted_path, db_path)?;
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+        }
+        log::info!("Encrypted existing database at {:?}", db_path);
+        Ok(())
+    }
+
     /// Initialize database schema
     fn initialize_schema(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -124,6 +729,7 @@ impl Database {
                 synced_at TEXT,
                 local_updated_at TEXT NOT NULL,
                 server_updated_at TEXT,
+                sort_index INTEGER,
                 FOREIGN KEY (folder_id) REFERENCES folders(id) ON DELETE SET NULL
             );
 
@@ -132,7 +738,8 @@ impl Database {
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
                 synced_at TEXT,
-                local_updated_at TEXT NOT NULL
+                local_updated_at TEXT NOT NULL,
+                retention_days INTEGER
             );
 
             -- Sync queue for offline changes
@@ -142,7 +749,11 @@ impl Database {
                 table_name TEXT NOT NULL,
                 record_id TEXT NOT NULL,
                 payload TEXT,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                attempts INTEGER DEFAULT 0,
+                last_error TEXT,
+                last_attempt_at TEXT,
+                blocked_reason TEXT
             );
 
             -- User session
@@ -164,42 +775,396 @@ impl Database {
                 start_minimized INTEGER DEFAULT 0,
                 start_on_boot INTEGER DEFAULT 0,
                 theme TEXT DEFAULT 'dark',
-                color_theme TEXT DEFAULT 'birch'
+                color_theme TEXT DEFAULT 'birch',
+                locale TEXT DEFAULT 'en-GB',
+                min_master_password_score INTEGER DEFAULT 3,
+                screen_capture_protection INTEGER DEFAULT 1,
+                notify_sync_failures INTEGER DEFAULT 1,
+                notify_security_alerts INTEGER DEFAULT 1,
+                notify_expiry_reminders INTEGER DEFAULT 1,
+                notify_clipboard_cleared INTEGER DEFAULT 0,
+                quiet_hours_start TEXT DEFAULT NULL,
+                quiet_hours_end TEXT DEFAULT NULL,
+                backup_schedule TEXT DEFAULT 'off',
+                backup_directory TEXT DEFAULT NULL,
+                backup_retention_count INTEGER DEFAULT 7,
+                item_revision_limit INTEGER DEFAULT 20,
+                auto_type_delay_ms INTEGER DEFAULT 25,
+                show_site_icons INTEGER DEFAULT 1,
+                sync_after_lock INTEGER DEFAULT 1
             );
 
-            -- Indexes for performance
-            CREATE INDEX IF NOT EXISTS idx_vault_items_folder ON vault_items(folder_id);
-            CREATE INDEX IF NOT EXISTS idx_vault_items_type ON vault_items(item_type);
-            CREATE INDEX IF NOT EXISTS idx_vault_items_deleted ON vault_items(deleted_at);
-            CREATE INDEX IF NOT EXISTS idx_vault_items_synced ON vault_items(synced_at);
-            CREATE INDEX IF NOT EXISTS idx_sync_queue_created ON sync_queue(created_at);
+            -- Short-lived resume token for restarts (see resume.rs)
+            CREATE TABLE IF NOT EXISTS session_resume (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                payload TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
 
-            -- Insert default settings if not exists
-            INSERT OR IGNORE INTO app_settings (id) VALUES (1);
-            "#,
-        )?;
+            -- Key/value store for one-off migrations and local bookkeeping
+            CREATE TABLE IF NOT EXISTS app_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
 
-        Ok(())
-    }
+            -- Sync health timeline
+            CREATE TABLE IF NOT EXISTS sync_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trigger TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                pushed INTEGER NOT NULL DEFAULT 0,
+                pulled INTEGER NOT NULL DEFAULT 0,
+                success INTEGER NOT NULL,
+                error TEXT
+            );
 
-    // ============================================
-    // Vault Items CRUD
-    // ============================================
+            -- Force-deleted unsynced items, recoverable until expires_at
+            CREATE TABLE IF NOT EXISTS recoverable_deletions (
+                id TEXT PRIMARY KEY,
+                encrypted_data TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                folder_id TEXT,
+                is_favorite INTEGER DEFAULT 0,
+                trashed_at TEXT,
+                deleted_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
 
-    pub fn get_all_vault_items(&self) -> Result<Vec<VaultItem>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, encrypted_data, item_type, folder_id, is_favorite, 
-                   deleted_at, synced_at, local_updated_at, server_updated_at
-            FROM vault_items
-            WHERE deleted_at IS NULL
-            ORDER BY local_updated_at DESC
-            "#,
-        )?;
+            -- Devices paired for "send to phone" (see devices.rs)
+            CREATE TABLE IF NOT EXISTS paired_devices (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_sent_at TEXT
+            );
 
-        let items = stmt
-            .query_map([], |row| {
+            -- HIBP range responses by SHA-1 prefix (see breach.rs)
+            CREATE TABLE IF NOT EXISTS breach_cache (
+                prefix TEXT PRIMARY KEY,
+                response TEXT NOT NULL,
+                fetched_at TEXT NOT NULL
+            );
+
+            -- SHA-256 of each trusted plugin executable (see plugins.rs)
+            CREATE TABLE IF NOT EXISTS plugin_trust (
+                plugin_id TEXT PRIMARY KEY,
+                executable_hash TEXT NOT NULL,
+                trusted_at TEXT NOT NULL
+            );
+
+            -- Encrypted file attachments; blobs live in <data dir>/attachments
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                item_id TEXT NOT NULL,
+                encrypted_name TEXT NOT NULL,
+                encrypted_key TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                synced_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_attachments_item ON attachments(item_id);
+
+            -- Earlier versions of vault items, newest kept up to item_revision_limit
+            CREATE TABLE IF NOT EXISTS item_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id TEXT NOT NULL,
+                encrypted_data TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                folder_id TEXT,
+                is_favorite INTEGER DEFAULT 0,
+                local_updated_at TEXT NOT NULL,
+                replaced_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_item_revisions_item ON item_revisions(item_id);
+
+            -- How often and when each item was last copied or auto-typed; never synced
+            CREATE TABLE IF NOT EXISTS item_usage (
+                item_id TEXT PRIMARY KEY,
+                use_count INTEGER NOT NULL DEFAULT 0,
+                last_used_at TEXT
+            );
+
+            -- Decrypted item names for sorting by name (see load_sort_names). TEMP
+            -- tables belong to this connection and, with temp_store = MEMORY,
+            -- never reach the disk.
+            CREATE TEMP TABLE IF NOT EXISTS item_names (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            );
+            CREATE TEMP TABLE IF NOT EXISTS item_names_revision (
+                revision INTEGER NOT NULL
+            );
+
+            -- Device-local UI state (window geometry, column widths, ...); never synced
+            CREATE TABLE IF NOT EXISTS ui_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Organizations, their collections and items (see organizations.rs)
+            CREATE TABLE IF NOT EXISTS organizations (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                role TEXT NOT NULL,
+                status TEXT NOT NULL,
+                wrapped_key TEXT,
+                encrypted_key TEXT,
+                synced_at TEXT
+            );
+            CREATE TABLE IF NOT EXISTS collections (
+                id TEXT PRIMARY KEY,
+                organization_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                permission TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_collections_org ON collections(organization_id);
+            CREATE TABLE IF NOT EXISTS collection_items (
+                collection_id TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                PRIMARY KEY (collection_id, item_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_collection_items_item ON collection_items(item_id);
+            CREATE TABLE IF NOT EXISTS org_items (
+                id TEXT PRIMARY KEY,
+                organization_id TEXT NOT NULL,
+                encrypted_data TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                created_by TEXT,
+                deleted_at TEXT,
+                synced_at TEXT,
+                local_updated_at TEXT NOT NULL,
+                server_updated_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_org_items_org ON org_items(organization_id);
+
+            -- Last row stored by each table's paged pull (see SyncEngine::pull_paged)
+            CREATE TABLE IF NOT EXISTS sync_cursors (
+                table_name TEXT PRIMARY KEY,
+                updated_at TEXT NOT NULL,
+                last_id TEXT NOT NULL
+            );
+
+            -- Items changed on both sides, awaiting the user's choice (see bulk_upsert_vault_items)
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                id TEXT PRIMARY KEY,
+                item_id TEXT NOT NULL,
+                copy_id TEXT NOT NULL,
+                local_updated_at TEXT NOT NULL,
+                server_updated_at TEXT NOT NULL,
+                detected_at TEXT NOT NULL
+            );
+
+            -- Hash-chained log of security-relevant events (see audit.rs); rows
+            -- can be added but never changed or removed
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                event TEXT NOT NULL,
+                occurred_at TEXT NOT NULL,
+                item_id TEXT,
+                detail TEXT,
+                prev_hash TEXT NOT NULL,
+                hash TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_occurred ON audit_log(occurred_at);
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'The audit log is append-only');
+            END;
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'The audit log is append-only');
+            END;
+
+            -- Bumped on every vault item write so in-memory indexes know when they're stale
+            CREATE TABLE IF NOT EXISTS vault_revision (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                revision INTEGER NOT NULL DEFAULT 0
+            );
+            INSERT OR IGNORE INTO vault_revision (id) VALUES (1);
+
+            CREATE TRIGGER IF NOT EXISTS vault_items_revision_insert AFTER INSERT ON vault_items
+            BEGIN
+                UPDATE vault_revision SET revision = revision + 1 WHERE id = 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS vault_items_revision_update AFTER UPDATE ON vault_items
+            BEGIN
+                UPDATE vault_revision SET revision = revision + 1 WHERE id = 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS vault_items_revision_delete AFTER DELETE ON vault_items
+            BEGIN
+                UPDATE vault_revision SET revision = revision + 1 WHERE id = 1;
+            END;
+
+            -- Indexes for performance
+            CREATE INDEX IF NOT EXISTS idx_vault_items_folder ON vault_items(folder_id);
+            CREATE INDEX IF NOT EXISTS idx_vault_items_type ON vault_items(item_type);
+            CREATE INDEX IF NOT EXISTS idx_vault_items_deleted ON vault_items(deleted_at);
+            CREATE INDEX IF NOT EXISTS idx_vault_items_synced ON vault_items(synced_at);
+            -- Covers the default listing (summaries), so it never reads encrypted_data
+            CREATE INDEX IF NOT EXISTS idx_vault_items_listing ON vault_items(
+                deleted_at, local_updated_at DESC, id, item_type, folder_id, is_favorite,
+                synced_at, server_updated_at
+            );
+            CREATE INDEX IF NOT EXISTS idx_sync_queue_created ON sync_queue(created_at);
+            CREATE INDEX IF NOT EXISTS idx_sync_history_started ON sync_history(started_at);
+
+            -- Insert default settings if not exists
+            INSERT OR IGNORE INTO app_settings (id) VALUES (1);
+            "#,
+        )?;
+
+        // Columns added after the initial release
+        Self::add_column_if_missing(&conn, "app_settings", "locale", "TEXT DEFAULT 'en-GB'")?;
+        Self::add_column_if_missing(&conn, "folders", "retention_days", "INTEGER")?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "min_master_password_score",
+            "INTEGER DEFAULT 3",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "screen_capture_protection",
+            "INTEGER DEFAULT 1",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "notify_sync_failures",
+            "INTEGER DEFAULT 1",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "notify_security_alerts",
+            "INTEGER DEFAULT 1",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "notify_expiry_reminders",
+            "INTEGER DEFAULT 1",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "notify_clipboard_cleared",
+            "INTEGER DEFAULT 0",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "quiet_hours_start",
+            "TEXT DEFAULT NULL",
+        )?;
+        Self::add_column_if_missing(&conn, "app_settings", "quiet_hours_end", "TEXT DEFAULT NULL")?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "backup_schedule",
+            "TEXT DEFAULT 'off'",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "backup_directory",
+            "TEXT DEFAULT NULL",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "backup_retention_count",
+            "INTEGER DEFAULT 7",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "item_revision_limit",
+            "INTEGER DEFAULT 20",
+        )?;
+        Self::add_column_if_missing(
+            &conn,
+            "app_settings",
+            "auto_type_delay_ms",
+            "INTEGER DEFAULT 25",
+        )?;
+        Self::add_column_if_missing(&conn, "sync_queue", "attempts", "INTEGER DEFAULT 0")?;
+        Self::add_column_if_missing(&conn, "sync_queue", "last_error", "TEXT")?;
+        Self::add_column_if_missing(&conn, "sync_queue", "last_attempt_at", "TEXT")?;
+        Self::add_column_if_missing(&conn, "sync_queue", "blocked_reason", "TEXT")?;
+        Self::add_column_if_missing(&conn, "app_settings", "show_site_icons", "INTEGER DEFAULT 1")?;
+        Self::add_column_if_missing(&conn, "app_settings", "sync_after_lock", "INTEGER DEFAULT 1")?;
+        Self::add_column_if_missing(&conn, "vault_items", "sort_index", "INTEGER")?;
+
+        // Older versions stored the local time an item was marked synced as its
+        // server version. No server row matches that, so every guarded push
+        // of those items would come back as a conflict; without a version they
+        // are pushed unconditionally once and take the server's from then on.
+        let versions_reset = conn
+            .query_row(
+                "SELECT 1 FROM app_meta WHERE key = ?1",
+                [META_SERVER_VERSIONS_RESET],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !versions_reset {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute("UPDATE vault_items SET server_updated_at = NULL", [])?;
+            tx.execute(
+                "INSERT INTO app_meta (key, value) VALUES (?1, ?2)",
+                params![META_SERVER_VERSIONS_RESET, Utc::now().to_rfc3339()],
+            )?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a column to an existing table when upgrading an older database
+    fn add_column_if_missing(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // ============================================
+    // Vault Items CRUD
+    // ============================================
+
+    pub fn get_all_vault_items(&self) -> Result<Vec<VaultItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            r#"
+            SELECT id, encrypted_data, item_type, folder_id, is_favorite, 
+                   deleted_at, synced_at, local_updated_at, server_updated_at, sort_index
+            FROM vault_items
+            WHERE deleted_at IS NULL
+            ORDER BY local_updated_at DESC
+            "#,
+        )?;
+
+        let items = stmt
+            .query_map([], |row| {
                 Ok(VaultItem {
                     id: row.get(0)?,
                     encrypted_data: row.get(1)?,
@@ -210,6 +1175,7 @@ impl Database {
                     synced_at: row.get(6)?,
                     local_updated_at: row.get(7)?,
                     server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -219,10 +1185,10 @@ impl Database {
 
     pub fn get_trashed_items(&self) -> Result<Vec<VaultItem>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             r#"
             SELECT id, encrypted_data, item_type, folder_id, is_favorite, 
-                   deleted_at, synced_at, local_updated_at, server_updated_at
+                   deleted_at, synced_at, local_updated_at, server_updated_at, sort_index
             FROM vault_items
             WHERE deleted_at IS NOT NULL
             ORDER BY deleted_at DESC
@@ -241,6 +1207,52 @@ impl Database {
                     synced_at: row.get(6)?,
                     local_updated_at: row.get(7)?,
                     server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    /// One page of the trash, most recently deleted first
+    pub fn get_trashed_items_page(
+        &self,
+        filter: &TrashFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<VaultItem>> {
+        let conn = self.conn.lock().unwrap();
+        let (conditions, mut values) = filter.to_sql();
+        values.push(SqlValue::Integer(limit.into()));
+        values.push(SqlValue::Integer(offset.into()));
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, encrypted_data, item_type, folder_id, is_favorite,
+                   deleted_at, synced_at, local_updated_at, server_updated_at, sort_index
+            FROM vault_items
+            WHERE {}
+            ORDER BY deleted_at DESC
+            LIMIT ?{} OFFSET ?{}
+            "#,
+            conditions,
+            values.len() - 1,
+            values.len()
+        ))?;
+
+        let items = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+                Ok(VaultItem {
+                    id: row.get(0)?,
+                    encrypted_data: row.get(1)?,
+                    item_type: row.get(2)?,
+                    folder_id: row.get(3)?,
+                    is_favorite: row.get::<_, i32>(4)? == 1,
+                    deleted_at: row.get(5)?,
+                    synced_at: row.get(6)?,
+                    local_updated_at: row.get(7)?,
+                    server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -248,12 +1260,23 @@ impl Database {
         Ok(items)
     }
 
+    pub fn count_trashed_items(&self, filter: &TrashFilter) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let (conditions, values) = filter.to_sql();
+        let count = conn.query_row(
+            &format!("SELECT COUNT(*) FROM vault_items WHERE {}", conditions),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
     pub fn get_vault_item(&self, id: &str) -> Result<Option<VaultItem>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             r#"
             SELECT id, encrypted_data, item_type, folder_id, is_favorite, 
-                   deleted_at, synced_at, local_updated_at, server_updated_at
+                   deleted_at, synced_at, local_updated_at, server_updated_at, sort_index
             FROM vault_items
             WHERE id = ?1
             "#,
@@ -271,6 +1294,7 @@ impl Database {
                     synced_at: row.get(6)?,
                     local_updated_at: row.get(7)?,
                     server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
                 })
             })
             .optional()?;
@@ -278,6 +1302,64 @@ impl Database {
         Ok(item)
     }
 
+    pub fn get_vault_item_summaries(&self) -> Result<Vec<VaultItemSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            r#"
+            SELECT id, item_type, folder_id, is_favorite, deleted_at,
+                   synced_at, local_updated_at, server_updated_at, sort_index
+            FROM vault_items
+            WHERE deleted_at IS NULL
+            ORDER BY local_updated_at DESC
+            "#,
+        )?;
+
+        let items = stmt
+            .query_map([], |row| {
+                Ok(VaultItemSummary {
+                    id: row.get(0)?,
+                    item_type: row.get(1)?,
+                    folder_id: row.get(2)?,
+                    is_favorite: row.get::<_, i32>(3)? == 1,
+                    deleted_at: row.get(4)?,
+                    synced_at: row.get(5)?,
+                    local_updated_at: row.get(6)?,
+                    server_updated_at: row.get(7)?,
+                    sort_index: row.get(8)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    /// Fetch the encrypted payloads for a batch of items (unknown ids are skipped)
+    pub fn get_items_data(&self, ids: &[String]) -> Result<Vec<VaultItemData>> {
+        let conn = self.conn.lock().unwrap();
+        let mut data = Vec::with_capacity(ids.len());
+
+        // Stay well below SQLite's bound parameter limit
+        for chunk in ids.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT id, encrypted_data FROM vault_items WHERE id IN ({})",
+                placeholders
+            ))?;
+
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(chunk.iter()), |row| {
+                    Ok(VaultItemData {
+                        id: row.get(0)?,
+                        encrypted_data: row.get(1)?,
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            data.extend(rows);
+        }
+
+        Ok(data)
+    }
+
     pub fn insert_vault_item(&self, item: &VaultItem) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -307,6 +1389,29 @@ impl Database {
 
     pub fn update_vault_item(&self, item: &VaultItem) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        self.update_vault_item_internal(&conn, item)
+    }
+
+    /// Save an edit, keeping the version it replaces as a revision (the newest
+    /// `keep`), in one transaction. Returns the item as stored.
+    pub fn update_vault_item_with_revision(
+        &self,
+        item: &VaultItem,
+        keep: u32,
+    ) -> Result<VaultItem> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            Self::record_item_revision_internal(&tx, &item.id, keep)?;
+            self.update_vault_item_internal(&tx, item)?;
+            tx.commit()?;
+        }
+
+        self.get_vault_item(&item.id)?
+            .ok_or_else(|| AppError::NotFound(format!("Vault item {}", item.id)))
+    }
+
+    fn update_vault_item_internal(&self, conn: &Connection, item: &VaultItem) -> Result<()> {
         let now = Utc::now().to_rfc3339();
 
         conn.execute(
@@ -328,70 +1433,704 @@ impl Database {
         )?;
 
         // Add to sync queue
-        self.add_to_sync_queue_internal(&conn, "update", "vault_items", &item.id, Some(item))?;
+        self.add_to_sync_queue_internal(conn, "update", "vault_items", &item.id, Some(item))?;
 
         Ok(())
     }
 
-    pub fn soft_delete_vault_item(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let now = Utc::now().to_rfc3339();
+    // ============================================
+    // Sorting and Pinning
+    // ============================================
+    //
+    // Pinned items have a `sort_index` and list first in its order. Like
+    // `is_favorite` it is device-local, so sync leaves it alone. Names are
+    // only in the encrypted data; sorting by them uses `item_names`, filled
+    // from the search index while the vault is unlocked.
 
-        conn.execute(
+    /// Live items, pinned first, then in `sort` order; `limit` of them from
+    /// `offset` on, or all when `None`
+    pub fn get_sorted_vault_items(
+        &self,
+        sort: ItemSort,
+        limit: Option<u32>,
+        offset: u32,
+    ) -> Result<Vec<VaultItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(&format!(
             r#"
-            UPDATE vault_items 
-            SET deleted_at = ?2, local_updated_at = ?2
-            WHERE id = ?1
+            SELECT v.id, v.encrypted_data, v.item_type, v.folder_id, v.is_favorite,
+                   v.deleted_at, v.synced_at, v.local_updated_at, v.server_updated_at,
+                   v.sort_index
+            FROM vault_items v
+            LEFT JOIN item_usage u ON u.item_id = v.id
+            LEFT JOIN temp.item_names n ON n.id = v.id
+            WHERE v.deleted_at IS NULL
+            ORDER BY v.sort_index IS NULL, v.sort_index, {}
+            LIMIT ?1 OFFSET ?2
             "#,
-            params![id, now],
-        )?;
+            sort.order_by()
+        ))?;
 
-        // Add to sync queue
-        self.add_to_sync_queue_internal(&conn, "update", "vault_items", id, None::<&VaultItem>)?;
+        // A negative limit is none at all
+        let limit = limit.map_or(-1, i64::from);
+        let items = stmt
+            .query_map(params![limit, offset], |row| {
+                Ok(VaultItem {
+                    id: row.get(0)?,
+                    encrypted_data: row.get(1)?,
+                    item_type: row.get(2)?,
+                    folder_id: row.get(3)?,
+                    is_favorite: row.get::<_, i32>(4)? == 1,
+                    deleted_at: row.get(5)?,
+                    synced_at: row.get(6)?,
+                    local_updated_at: row.get(7)?,
+                    server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    pub fn count_vault_items(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let count = conn
+            .prepare_cached("SELECT COUNT(*) FROM vault_items WHERE deleted_at IS NULL")?
+            .query_row([], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Vault revision `item_names` was last filled at
+    pub fn sort_names_revision(&self) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let revision = conn
+            .query_row("SELECT revision FROM temp.item_names_revision", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(revision)
+    }
 
+    /// Replace the names used for sorting with those of vault `revision`
+    pub fn load_sort_names<'a>(
+        &self,
+        revision: i64,
+        names: impl Iterator<Item = (&'a str, &'a str)>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute_batch("DELETE FROM temp.item_names; DELETE FROM temp.item_names_revision;")?;
+        {
+            let mut insert =
+                tx.prepare("INSERT OR REPLACE INTO temp.item_names (id, name) VALUES (?1, ?2)")?;
+            for (id, name) in names {
+                insert.execute(params![id, name])?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO temp.item_names_revision (revision) VALUES (?1)",
+            [revision],
+        )?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn restore_vault_item(&self, id: &str) -> Result<()> {
+    /// Forget the decrypted names, on lock
+    pub fn clear_sort_names(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let now = Utc::now().to_rfc3339();
+        conn.execute_batch("DELETE FROM temp.item_names; DELETE FROM temp.item_names_revision;")?;
+        Ok(())
+    }
 
-        conn.execute(
+    /// Pin a live item after those already pinned; pinning it again leaves it in place
+    pub fn pin_item(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
             r#"
-            UPDATE vault_items 
-            SET deleted_at = NULL, local_updated_at = ?2
-            WHERE id = ?1
+            UPDATE vault_items
+            SET sort_index = COALESCE(
+                sort_index,
+                (SELECT COALESCE(MAX(sort_index) + 1, 0) FROM vault_items)
+            )
+            WHERE id = ?1 AND deleted_at IS NULL
             "#,
-            params![id, now],
+            [id],
         )?;
-
-        // Add to sync queue
-        self.add_to_sync_queue_internal(&conn, "update", "vault_items", id, None::<&VaultItem>)?;
-
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Vault item {}", id)));
+        }
         Ok(())
     }
 
-    pub fn permanently_delete_vault_item(&self, id: &str) -> Result<()> {
+    pub fn unpin_item(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE vault_items SET sort_index = NULL WHERE id = ?1",
+            [id],
+        )?;
+        Ok(())
+    }
 
-        conn.execute("DELETE FROM vault_items WHERE id = ?1", [id])?;
+    /// Put pinned items in the order of `ids`. Pinned items left out keep
+    /// their relative order after those given; unpinned ones are ignored.
+    pub fn reorder_pinned_items(&self, ids: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        // Add to sync queue
-        self.add_to_sync_queue_internal(&conn, "delete", "vault_items", id, None::<&VaultItem>)?;
+        let mut pinned: Vec<String> = tx
+            .prepare(
+                "SELECT id FROM vault_items WHERE sort_index IS NOT NULL ORDER BY sort_index, id",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        let given: Vec<String> = ids
+            .iter()
+            .filter(|id| pinned.contains(id))
+            .cloned()
+            .collect();
+        pinned.retain(|id| !given.contains(id));
 
+        for (index, id) in given.iter().chain(pinned.iter()).enumerate() {
+            tx.execute(
+                "UPDATE vault_items SET sort_index = ?2 WHERE id = ?1",
+                params![id, index as i64],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    // ============================================
-    // Folders CRUD
-    // ============================================
-
-    pub fn get_all_folders(&self) -> Result<Vec<Folder>> {
+    /// Count a copy or auto-type of a vault item, for the recent and
+    /// most-used sorts; other ids (org items) are ignored
+    pub fn record_item_use(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        conn.execute(
             r#"
-            SELECT id, name, synced_at, local_updated_at
-            FROM folders
+            INSERT INTO item_usage (item_id, use_count, last_used_at)
+            SELECT ?1, 1, ?2 WHERE EXISTS (SELECT 1 FROM vault_items WHERE id = ?1)
+            ON CONFLICT(item_id) DO UPDATE SET
+                use_count = use_count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // ============================================
+    // Attachments
+    // ============================================
+
+    /// Advisory lock file held by whichever process is running a sync
+    pub fn sync_lock_path(&self) -> PathBuf {
+        self.data_dir.read().unwrap().join("sync.lock")
+    }
+
+    /// Encrypted website icon cache (see icons.rs)
+    pub fn icons_dir(&self) -> PathBuf {
+        self.data_dir.read().unwrap().join("icons")
+    }
+
+    /// Redacted diagnostic bundles (see diagnostics.rs)
+    pub fn support_dir(&self) -> PathBuf {
+        self.data_dir.read().unwrap().join("support")
+    }
+
+    /// Plaintext exports waiting to be saved (see export.rs)
+    pub fn export_dir(&self) -> PathBuf {
+        self.data_dir.read().unwrap().join("export")
+    }
+
+    pub fn attachments_dir(&self) -> PathBuf {
+        self.data_dir.read().unwrap().join("attachments")
+    }
+
+    /// Where an attachment's encrypted blob is kept on disk
+    pub fn attachment_blob_path(&self, id: &str) -> PathBuf {
+        self.attachments_dir().join(format!("{}.bvat", id))
+    }
+
+    pub fn insert_attachment(&self, attachment: &Attachment) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO attachments (id, item_id, encrypted_name, encrypted_key, size, created_at, synced_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                attachment.id,
+                attachment.item_id,
+                attachment.encrypted_name,
+                attachment.encrypted_key,
+                attachment.size,
+                attachment.created_at,
+                attachment.synced_at,
+            ],
+        )?;
+
+        self.add_to_sync_queue_internal(
+            &conn,
+            "create",
+            "attachments",
+            &attachment.id,
+            None::<&Attachment>,
+        )?;
+
+        Ok(())
+    }
+
+    /// Oldest first
+    pub fn get_attachments(&self, item_id: &str) -> Result<Vec<Attachment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, item_id, encrypted_name, encrypted_key, size, created_at, synced_at
+            FROM attachments
+            WHERE item_id = ?1
+            ORDER BY created_at ASC
+            "#,
+        )?;
+
+        let attachments = stmt
+            .query_map([item_id], Self::attachment_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(attachments)
+    }
+
+    pub fn get_attachment(&self, id: &str) -> Result<Option<Attachment>> {
+        let conn = self.conn.lock().unwrap();
+        let attachment = conn
+            .query_row(
+                r#"
+                SELECT id, item_id, encrypted_name, encrypted_key, size, created_at, synced_at
+                FROM attachments
+                WHERE id = ?1
+                "#,
+                [id],
+                Self::attachment_from_row,
+            )
+            .optional()?;
+        Ok(attachment)
+    }
+
+    pub fn delete_attachment(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM attachments WHERE id = ?1", [id])?;
+
+        self.add_to_sync_queue_internal(&conn, "delete", "attachments", id, None::<&Attachment>)?;
+
+        Ok(())
+    }
+
+    /// Store attachment records pulled from the server; blobs are fetched on demand
+    pub fn bulk_upsert_attachments(&self, attachments: &[Attachment]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for attachment in attachments {
+            tx.execute(
+                r#"
+                INSERT OR REPLACE INTO attachments
+                    (id, item_id, encrypted_name, encrypted_key, size, created_at, synced_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+                params![
+                    attachment.id,
+                    attachment.item_id,
+                    attachment.encrypted_name,
+                    attachment.encrypted_key,
+                    attachment.size,
+                    attachment.created_at,
+                    attachment.synced_at,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn attachment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+        Ok(Attachment {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            encrypted_name: row.get(2)?,
+            encrypted_key: row.get(3)?,
+            size: row.get(4)?,
+            created_at: row.get(5)?,
+            synced_at: row.get(6)?,
+        })
+    }
+
+    // ============================================
+    // Item Revisions
+    // ============================================
+
+    /// Copy the item's current version into its history, keeping the newest `keep`
+    fn record_item_revision_internal(conn: &Connection, item_id: &str, keep: u32) -> Result<()> {
+        if keep == 0 {
+            return Ok(());
+        }
+
+        conn.execute(
+            r#"
+            INSERT INTO item_revisions
+                (item_id, encrypted_data, item_type, folder_id, is_favorite, local_updated_at, replaced_at)
+            SELECT id, encrypted_data, item_type, folder_id, is_favorite, local_updated_at, ?2
+            FROM vault_items
+            WHERE id = ?1
+            "#,
+            params![item_id, Utc::now().to_rfc3339()],
+        )?;
+        Self::prune_item_revisions_internal(conn, Some(item_id), keep)?;
+        Ok(())
+    }
+
+    /// Newest first
+    pub fn get_item_revisions(&self, item_id: &str) -> Result<Vec<ItemRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, item_id, encrypted_data, item_type, folder_id, is_favorite,
+                   local_updated_at, replaced_at
+            FROM item_revisions
+            WHERE item_id = ?1
+            ORDER BY id DESC
+            "#,
+        )?;
+
+        let revisions = stmt
+            .query_map([item_id], Self::item_revision_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(revisions)
+    }
+
+    pub fn get_item_revision(&self, id: i64) -> Result<Option<ItemRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let revision = conn
+            .query_row(
+                r#"
+                SELECT id, item_id, encrypted_data, item_type, folder_id, is_favorite,
+                       local_updated_at, replaced_at
+                FROM item_revisions
+                WHERE id = ?1
+                "#,
+                [id],
+                Self::item_revision_from_row,
+            )
+            .optional()?;
+        Ok(revision)
+    }
+
+    /// Apply a lowered limit to every item's history
+    pub fn prune_item_revisions(&self, keep: u32) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        Self::prune_item_revisions_internal(&conn, None, keep)
+    }
+
+    fn prune_item_revisions_internal(
+        conn: &Connection,
+        item_id: Option<&str>,
+        keep: u32,
+    ) -> Result<()> {
+        conn.execute(
+            r#"
+            DELETE FROM item_revisions
+            WHERE (?1 IS NULL OR item_id = ?1)
+              AND id NOT IN (
+                  SELECT id FROM (
+                      SELECT id, ROW_NUMBER() OVER (PARTITION BY item_id ORDER BY id DESC) AS n
+                      FROM item_revisions
+                  )
+                  WHERE n <= ?2
+              )
+            "#,
+            params![item_id, keep],
+        )?;
+        Ok(())
+    }
+
+    fn item_revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<ItemRevision> {
+        Ok(ItemRevision {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            encrypted_data: row.get(2)?,
+            item_type: row.get(3)?,
+            folder_id: row.get(4)?,
+            is_favorite: row.get::<_, i32>(5)? == 1,
+            local_updated_at: row.get(6)?,
+            replaced_at: row.get(7)?,
+        })
+    }
+
+    pub fn soft_delete_vault_item(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        conn.execute(
+            r#"
+            UPDATE vault_items 
+            SET deleted_at = ?2, local_updated_at = ?2
+            WHERE id = ?1
+            "#,
+            params![id, now],
+        )?;
+
+        // Add to sync queue
+        self.add_to_sync_queue_internal(&conn, "update", "vault_items", id, None::<&VaultItem>)?;
+
+        Ok(())
+    }
+
+    pub fn restore_vault_item(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        conn.execute(
+            r#"
+            UPDATE vault_items 
+            SET deleted_at = NULL, local_updated_at = ?2
+            WHERE id = ?1
+            "#,
+            params![id, now],
+        )?;
+
+        // Add to sync queue
+        self.add_to_sync_queue_internal(&conn, "update", "vault_items", id, None::<&VaultItem>)?;
+
+        Ok(())
+    }
+
+    /// Backfill `is_favorite`/`item_type` from decrypted data. Items whose type
+    /// changed are queued so the server's `type` column is corrected too.
+    pub fn apply_item_flags(&self, flags: &[ItemFlags]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut updated = 0;
+
+        for flag in flags {
+            let current: Option<(bool, String)> = tx
+                .query_row(
+                    "SELECT is_favorite, item_type FROM vault_items WHERE id = ?1",
+                    [&flag.id],
+                    |row| Ok((row.get::<_, i32>(0)? == 1, row.get(1)?)),
+                )
+                .optional()?;
+
+            let Some((is_favorite, item_type)) = current else {
+                continue;
+            };
+            if is_favorite == flag.is_favorite && item_type == flag.item_type {
+                continue;
+            }
+
+            tx.execute(
+                "UPDATE vault_items SET is_favorite = ?2, item_type = ?3 WHERE id = ?1",
+                params![flag.id, flag.is_favorite as i32, flag.item_type],
+            )?;
+
+            if item_type != flag.item_type {
+                self.add_to_sync_queue_internal(
+                    &tx,
+                    "update",
+                    "vault_items",
+                    &flag.id,
+                    None::<&VaultItem>,
+                )?;
+            }
+            updated += 1;
+        }
+
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// True if the item was never pushed or has local changes still queued
+    pub fn has_unsynced_changes(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let unsynced = conn.query_row(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM vault_items WHERE id = ?1 AND synced_at IS NULL
+            ) OR EXISTS (
+                SELECT 1 FROM sync_queue WHERE table_name = 'vault_items' AND record_id = ?1
+            )
+            "#,
+            [id],
+            |row| row.get::<_, bool>(0),
+        )?;
+        Ok(unsynced)
+    }
+
+    /// Delete an item for good. With `recoverable_until`, a copy is kept in
+    /// `recoverable_deletions` until that time.
+    pub fn permanently_delete_vault_item(
+        &self,
+        id: &str,
+        recoverable_until: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        if let Some(expires_at) = recoverable_until {
+            let now = Utc::now().to_rfc3339();
+            tx.execute(
+                r#"
+                INSERT OR REPLACE INTO recoverable_deletions
+                    (id, encrypted_data, item_type, folder_id, is_favorite, trashed_at, deleted_at, expires_at)
+                SELECT id, encrypted_data, item_type, folder_id, is_favorite, deleted_at, ?2, ?3
+                FROM vault_items
+                WHERE id = ?1
+                "#,
+                params![id, now, expires_at],
+            )?;
+        }
+
+        // Attachments go with the item, server-side blobs included
+        let attachment_ids = {
+            let mut stmt = tx.prepare("SELECT id FROM attachments WHERE item_id = ?1")?;
+            let ids = stmt
+                .query_map([id], |row| row.get::<_, String>(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            ids
+        };
+        for attachment_id in &attachment_ids {
+            tx.execute("DELETE FROM attachments WHERE id = ?1", [attachment_id])?;
+            self.add_to_sync_queue_internal(
+                &tx,
+                "delete",
+                "attachments",
+                attachment_id,
+                None::<&Attachment>,
+            )?;
+        }
+
+        tx.execute("DELETE FROM vault_items WHERE id = ?1", [id])?;
+        tx.execute("DELETE FROM item_revisions WHERE item_id = ?1", [id])?;
+        tx.execute("DELETE FROM item_usage WHERE item_id = ?1", [id])?;
+
+        // Add to sync queue
+        self.add_to_sync_queue_internal(&tx, "delete", "vault_items", id, None::<&VaultItem>)?;
+        tx.commit()?;
+
+        // Blobs only go once nothing can roll back to needing them
+        for attachment_id in &attachment_ids {
+            let _ = std::fs::remove_file(self.attachment_blob_path(attachment_id));
+        }
+        Ok(())
+    }
+
+    pub fn get_recoverable_deletions(&self) -> Result<Vec<RecoverableDeletion>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, encrypted_data, item_type, folder_id, is_favorite,
+                   trashed_at, deleted_at, expires_at
+            FROM recoverable_deletions
+            ORDER BY deleted_at DESC
+            "#,
+        )?;
+
+        let deletions = stmt
+            .query_map([], |row| {
+                Ok(RecoverableDeletion {
+                    id: row.get(0)?,
+                    encrypted_data: row.get(1)?,
+                    item_type: row.get(2)?,
+                    folder_id: row.get(3)?,
+                    is_favorite: row.get::<_, i32>(4)? == 1,
+                    trashed_at: row.get(5)?,
+                    deleted_at: row.get(6)?,
+                    expires_at: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(deletions)
+    }
+
+    /// Put a recoverable deletion back as an item, as it was (trash state
+    /// included), queued for upload. `None` if there's no such deletion or it
+    /// has expired; the tombstone only goes once the item is back.
+    pub fn recover_deleted_vault_item(&self, id: &str) -> Result<Option<VaultItem>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now().to_rfc3339();
+
+        let item = tx
+            .query_row(
+                r#"
+                DELETE FROM recoverable_deletions
+                WHERE id = ?1 AND expires_at > ?2
+                RETURNING id, encrypted_data, item_type, folder_id, is_favorite, trashed_at
+                "#,
+                params![id, now],
+                |row| {
+                    Ok(VaultItem {
+                        id: row.get(0)?,
+                        encrypted_data: row.get(1)?,
+                        item_type: row.get(2)?,
+                        folder_id: row.get(3)?,
+                        is_favorite: row.get::<_, i32>(4)? == 1,
+                        deleted_at: row.get(5)?,
+                        synced_at: None,
+                        local_updated_at: now.clone(),
+                        server_updated_at: None,
+                        sort_index: None,
+                    })
+                },
+            )
+            .optional()?;
+        let Some(item) = item else {
+            return Ok(None);
+        };
+
+        tx.execute(
+            r#"
+            INSERT INTO vault_items (id, encrypted_data, item_type, folder_id, is_favorite,
+                                     deleted_at, synced_at, local_updated_at, server_updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, ?7, NULL)
+            "#,
+            params![
+                item.id,
+                item.encrypted_data,
+                item.item_type,
+                item.folder_id,
+                item.is_favorite as i32,
+                item.deleted_at,
+                item.local_updated_at,
+            ],
+        )?;
+        self.add_to_sync_queue_internal(&tx, "create", "vault_items", &item.id, Some(&item))?;
+
+        tx.commit()?;
+        Ok(Some(item))
+    }
+
+    /// Drop tombstones whose grace period has passed
+    pub fn prune_recoverable_deletions(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+        let pruned = conn.execute(
+            "DELETE FROM recoverable_deletions WHERE expires_at <= ?1",
+            [now],
+        )?;
+        Ok(pruned)
+    }
+
+    // ============================================
+    // Folders CRUD
+    // ============================================
+
+    pub fn get_all_folders(&self) -> Result<Vec<Folder>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            r#"
+            SELECT id, name, synced_at, local_updated_at, retention_days
+            FROM folders
             ORDER BY name ASC
             "#,
         )?;
@@ -403,6 +2142,7 @@ impl Database {
                     name: row.get(1)?,
                     synced_at: row.get(2)?,
                     local_updated_at: row.get(3)?,
+                    retention_days: row.get(4)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -410,58 +2150,472 @@ impl Database {
         Ok(folders)
     }
 
+    pub fn get_folder(&self, id: &str) -> Result<Option<Folder>> {
+        let conn = self.conn.lock().unwrap();
+        let folder = conn
+            .query_row(
+                r#"
+                SELECT id, name, synced_at, local_updated_at, retention_days
+                FROM folders
+                WHERE id = ?1
+                "#,
+                [id],
+                |row| {
+                    Ok(Folder {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        synced_at: row.get(2)?,
+                        local_updated_at: row.get(3)?,
+                        retention_days: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(folder)
+    }
+
     pub fn insert_folder(&self, folder: &Folder) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             r#"
-            INSERT INTO folders (id, name, synced_at, local_updated_at)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO folders (id, name, synced_at, local_updated_at, retention_days)
+            VALUES (?1, ?2, ?3, ?4, ?5)
             "#,
             params![
                 folder.id,
                 folder.name,
                 folder.synced_at,
                 folder.local_updated_at,
+                folder.retention_days,
+            ],
+        )?;
+
+        self.add_to_sync_queue_internal(&conn, "create", "folders", &folder.id, Some(folder))?;
+
+        Ok(())
+    }
+
+    pub fn update_folder(&self, folder: &Folder) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        conn.execute(
+            r#"
+            UPDATE folders 
+            SET name = ?2, local_updated_at = ?3
+            WHERE id = ?1
+            "#,
+            params![folder.id, folder.name, now],
+        )?;
+
+        self.add_to_sync_queue_internal(&conn, "update", "folders", &folder.id, Some(folder))?;
+
+        Ok(())
+    }
+
+    pub fn set_folder_retention(&self, id: &str, retention_days: Option<i64>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        let updated = conn.execute(
+            r#"
+            UPDATE folders
+            SET retention_days = ?2, local_updated_at = ?3
+            WHERE id = ?1
+            "#,
+            params![id, retention_days, now],
+        )?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Folder {}", id)));
+        }
+
+        self.add_to_sync_queue_internal(&conn, "update", "folders", id, None::<&Folder>)?;
+
+        Ok(())
+    }
+
+    /// Live items that sit in a folder with a retention policy
+    pub fn get_retention_candidates(&self) -> Result<Vec<RetentionCandidate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT v.id, v.folder_id, v.local_updated_at, f.retention_days
+            FROM vault_items v
+            JOIN folders f ON f.id = v.folder_id
+            WHERE v.deleted_at IS NULL AND f.retention_days IS NOT NULL
+            "#,
+        )?;
+
+        let candidates = stmt
+            .query_map([], |row| {
+                Ok(RetentionCandidate {
+                    item_id: row.get(0)?,
+                    folder_id: row.get(1)?,
+                    local_updated_at: row.get(2)?,
+                    retention_days: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(candidates)
+    }
+
+    pub fn delete_folder(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // Remove folder_id from items in this folder
+        conn.execute(
+            "UPDATE vault_items SET folder_id = NULL WHERE folder_id = ?1",
+            [id],
+        )?;
+
+        // Delete the folder
+        conn.execute("DELETE FROM folders WHERE id = ?1", [id])?;
+
+        self.add_to_sync_queue_internal(&conn, "delete", "folders", id, None::<&Folder>)?;
+
+        Ok(())
+    }
+
+    // ============================================
+    // Organizations
+    // ============================================
+
+    pub fn get_organizations(&self) -> Result<Vec<Organization>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, name, role, status, wrapped_key, encrypted_key, synced_at
+            FROM organizations
+            ORDER BY name COLLATE NOCASE
+            "#,
+        )?;
+
+        let orgs = stmt
+            .query_map([], Self::organization_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(orgs)
+    }
+
+    pub fn get_organization(&self, id: &str) -> Result<Option<Organization>> {
+        let conn = self.conn.lock().unwrap();
+        let org = conn
+            .query_row(
+                r#"
+                SELECT id, name, role, status, wrapped_key, encrypted_key, synced_at
+                FROM organizations
+                WHERE id = ?1
+                "#,
+                [id],
+                Self::organization_from_row,
+            )
+            .optional()?;
+        Ok(org)
+    }
+
+    fn organization_from_row(row: &rusqlite::Row) -> rusqlite::Result<Organization> {
+        Ok(Organization {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            role: row.get(2)?,
+            status: row.get(3)?,
+            wrapped_key: row.get(4)?,
+            encrypted_key: row.get(5)?,
+            synced_at: row.get(6)?,
+        })
+    }
+
+    /// Replace our memberships with the server's. Organizations we no longer
+    /// belong to are removed along with their collections and items. Returns
+    /// the organizations that became accessible since the last call, whose
+    /// items need a full pull.
+    pub fn replace_organizations(&self, orgs: &[Organization]) -> Result<Vec<String>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let accepted: HashSet<String> = {
+            let mut stmt = tx.prepare("SELECT id FROM organizations WHERE status = 'accepted'")?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            ids
+        };
+
+        let keep: Vec<&str> = orgs.iter().map(|o| o.id.as_str()).collect();
+        let gone: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT id FROM organizations")?;
+            let ids = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .filter_map(|id| id.ok())
+                .filter(|id| !keep.contains(&id.as_str()))
+                .collect();
+            ids
+        };
+        for id in &gone {
+            tx.execute(
+                r#"
+                DELETE FROM sync_queue WHERE table_name = 'org_items'
+                    AND record_id IN (SELECT id FROM org_items WHERE organization_id = ?1)
+                "#,
+                [id],
+            )?;
+            tx.execute(
+                r#"
+                DELETE FROM collection_items
+                WHERE collection_id IN (SELECT id FROM collections WHERE organization_id = ?1)
+                "#,
+                [id],
+            )?;
+            tx.execute("DELETE FROM collections WHERE organization_id = ?1", [id])?;
+            tx.execute("DELETE FROM org_items WHERE organization_id = ?1", [id])?;
+            tx.execute("DELETE FROM organizations WHERE id = ?1", [id])?;
+        }
+
+        for org in orgs {
+            tx.execute(
+                r#"
+                INSERT INTO organizations (id, name, role, status, wrapped_key, synced_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name,
+                    role = excluded.role,
+                    status = excluded.status,
+                    synced_at = excluded.synced_at,
+                    -- A different wrapped key means the org key changed
+                    encrypted_key = CASE WHEN wrapped_key IS excluded.wrapped_key
+                        THEN encrypted_key ELSE NULL END,
+                    wrapped_key = excluded.wrapped_key
+                "#,
+                params![
+                    org.id,
+                    org.name,
+                    org.role,
+                    org.status,
+                    org.wrapped_key,
+                    org.synced_at,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(orgs
+            .iter()
+            .filter(|o| o.status == "accepted" && !accepted.contains(&o.id))
+            .map(|o| o.id.clone())
+            .collect())
+    }
+
+    /// Cache the unwrapped org key, encrypted with the vault key
+    pub fn set_organization_key(&self, id: &str, encrypted_key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE organizations SET encrypted_key = ?2 WHERE id = ?1",
+            params![id, encrypted_key],
+        )?;
+        Ok(())
+    }
+
+    /// Replace collections and item membership with the server's, keeping
+    /// links for org items that haven't been pushed yet
+    pub fn replace_collections(
+        &self,
+        collections: &[Collection],
+        links: &[(String, String)],
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM collections", [])?;
+        for collection in collections {
+            tx.execute(
+                r#"
+                INSERT INTO collections (id, organization_id, name, permission)
+                VALUES (?1, ?2, ?3, ?4)
+                "#,
+                params![
+                    collection.id,
+                    collection.organization_id,
+                    collection.name,
+                    collection.permission,
+                ],
+            )?;
+        }
+
+        tx.execute(
+            r#"
+            DELETE FROM collection_items
+            WHERE item_id NOT IN (SELECT id FROM org_items WHERE server_updated_at IS NULL)
+            "#,
+            [],
+        )?;
+        for (collection_id, item_id) in links {
+            tx.execute(
+                "INSERT OR IGNORE INTO collection_items (collection_id, item_id) VALUES (?1, ?2)",
+                params![collection_id, item_id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_collections(&self, organization_id: &str) -> Result<Vec<Collection>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, organization_id, name, permission
+            FROM collections
+            WHERE organization_id = ?1
+            ORDER BY name COLLATE NOCASE
+            "#,
+        )?;
+
+        let collections = stmt
+            .query_map([organization_id], |row| {
+                Ok(Collection {
+                    id: row.get(0)?,
+                    organization_id: row.get(1)?,
+                    name: row.get(2)?,
+                    permission: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(collections)
+    }
+
+    pub fn get_org_items(&self, organization_id: &str) -> Result<Vec<OrgItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, organization_id, encrypted_data, item_type, created_by, deleted_at,
+                   synced_at, local_updated_at, server_updated_at,
+                   (SELECT GROUP_CONCAT(collection_id) FROM collection_items WHERE item_id = org_items.id)
+            FROM org_items
+            WHERE organization_id = ?1
+            ORDER BY local_updated_at DESC
+            "#,
+        )?;
+
+        let items = stmt
+            .query_map([organization_id], Self::org_item_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    pub fn get_org_item(&self, id: &str) -> Result<Option<OrgItem>> {
+        let conn = self.conn.lock().unwrap();
+        let item = conn
+            .query_row(
+                r#"
+                SELECT id, organization_id, encrypted_data, item_type, created_by, deleted_at,
+                       synced_at, local_updated_at, server_updated_at,
+                       (SELECT GROUP_CONCAT(collection_id) FROM collection_items WHERE item_id = org_items.id)
+                FROM org_items
+                WHERE id = ?1
+                "#,
+                [id],
+                Self::org_item_from_row,
+            )
+            .optional()?;
+        Ok(item)
+    }
+
+    fn org_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<OrgItem> {
+        let collection_ids: Option<String> = row.get(9)?;
+        Ok(OrgItem {
+            id: row.get(0)?,
+            organization_id: row.get(1)?,
+            encrypted_data: row.get(2)?,
+            item_type: row.get(3)?,
+            created_by: row.get(4)?,
+            deleted_at: row.get(5)?,
+            synced_at: row.get(6)?,
+            local_updated_at: row.get(7)?,
+            server_updated_at: row.get(8)?,
+            collection_ids: collection_ids
+                .map(|ids| ids.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Add an org item with its collections and queue it for sync
+    pub fn create_org_item(&self, item: &OrgItem) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            r#"
+            INSERT INTO org_items (id, organization_id, encrypted_data, item_type, created_by,
+                                   deleted_at, synced_at, local_updated_at, server_updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                item.id,
+                item.organization_id,
+                item.encrypted_data,
+                item.item_type,
+                item.created_by,
+                item.deleted_at,
+                item.synced_at,
+                item.local_updated_at,
+                item.server_updated_at,
             ],
         )?;
+        for collection_id in &item.collection_ids {
+            conn.execute(
+                "INSERT OR IGNORE INTO collection_items (collection_id, item_id) VALUES (?1, ?2)",
+                params![collection_id, item.id],
+            )?;
+        }
 
-        self.add_to_sync_queue_internal(&conn, "create", "folders", &folder.id, Some(folder))?;
+        self.add_to_sync_queue_internal(&conn, "create", "org_items", &item.id, None::<&OrgItem>)?;
 
         Ok(())
     }
 
-    pub fn update_folder(&self, folder: &Folder) -> Result<()> {
+    pub fn update_org_item(&self, item: &OrgItem) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let now = Utc::now().to_rfc3339();
 
         conn.execute(
             r#"
-            UPDATE folders 
-            SET name = ?2, local_updated_at = ?3
+            UPDATE org_items
+            SET encrypted_data = ?2, item_type = ?3, deleted_at = ?4, local_updated_at = ?5
             WHERE id = ?1
             "#,
-            params![folder.id, folder.name, now],
+            params![
+                item.id,
+                item.encrypted_data,
+                item.item_type,
+                item.deleted_at,
+                now
+            ],
         )?;
 
-        self.add_to_sync_queue_internal(&conn, "update", "folders", &folder.id, Some(folder))?;
+        self.add_to_sync_queue_internal(&conn, "update", "org_items", &item.id, None::<&OrgItem>)?;
 
         Ok(())
     }
 
-    pub fn delete_folder(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-
-        // Remove folder_id from items in this folder
-        conn.execute(
-            "UPDATE vault_items SET folder_id = NULL WHERE folder_id = ?1",
-            [id],
-        )?;
-
-        // Delete the folder
-        conn.execute("DELETE FROM folders WHERE id = ?1", [id])?;
+    /// Record an item's collections after the server accepted the change
+    pub fn set_org_item_collections(&self, item_id: &str, collection_ids: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        self.add_to_sync_queue_internal(&conn, "delete", "folders", id, None::<&Folder>)?;
+        tx.execute("DELETE FROM collection_items WHERE item_id = ?1", [item_id])?;
+        for collection_id in collection_ids {
+            tx.execute(
+                "INSERT INTO collection_items (collection_id, item_id) VALUES (?1, ?2)",
+                params![collection_id, item_id],
+            )?;
+        }
 
+        tx.commit()?;
         Ok(())
     }
 
@@ -497,6 +2651,7 @@ impl Database {
             r#"
             SELECT id, operation, table_name, record_id, payload, created_at
             FROM sync_queue
+            WHERE blocked_reason IS NULL
             ORDER BY created_at ASC
             "#,
         )?;
@@ -523,6 +2678,565 @@ impl Database {
         Ok(())
     }
 
+    /// Queue every item and folder not already queued, for a sync location
+    /// that has none of them yet. Server versions are dropped, since they
+    /// belong to the old location.
+    pub fn queue_all_for_upload(&self) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now().to_rfc3339();
+
+        tx.execute("UPDATE vault_items SET server_updated_at = NULL", [])?;
+        let mut queued = 0;
+        for table in ["vault_items", "folders"] {
+            queued += tx.execute(
+                &format!(
+                    r#"
+                    INSERT INTO sync_queue (operation, table_name, record_id, created_at)
+                    SELECT 'update', ?1, id, ?2 FROM {0}
+                    WHERE id NOT IN (SELECT record_id FROM sync_queue WHERE table_name = ?1)
+                    "#,
+                    table
+                ),
+                params![table, now],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(queued)
+    }
+
+    /// Note a failed push; `blocked_reason` parks the entry so later syncs skip it
+    pub fn record_sync_failure(
+        &self,
+        id: i64,
+        error: &str,
+        blocked_reason: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            UPDATE sync_queue
+            SET attempts = COALESCE(attempts, 0) + 1,
+                last_error = ?2,
+                last_attempt_at = ?3,
+                blocked_reason = ?4
+            WHERE id = ?1
+            "#,
+            params![id, error, Utc::now().to_rfc3339(), blocked_reason],
+        )?;
+        Ok(())
+    }
+
+    /// Queued changes that failed at least once, parked ones first
+    pub fn get_sync_errors(&self) -> Result<Vec<SyncError>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, operation, table_name, record_id, COALESCE(attempts, 0), last_error,
+                   last_attempt_at, blocked_reason
+            FROM sync_queue
+            WHERE last_error IS NOT NULL
+            ORDER BY blocked_reason IS NULL, last_attempt_at DESC
+            "#,
+        )?;
+
+        let errors = stmt
+            .query_map([], |row| {
+                Ok(SyncError {
+                    queue_id: row.get(0)?,
+                    operation: row.get(1)?,
+                    table_name: row.get(2)?,
+                    record_id: row.get(3)?,
+                    attempts: row.get(4)?,
+                    last_error: row.get(5)?,
+                    last_attempt_at: row.get(6)?,
+                    blocked_reason: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(errors)
+    }
+
+    /// Put a parked entry back in line for the next sync
+    pub fn unblock_sync_item(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE sync_queue SET blocked_reason = NULL WHERE id = ?1",
+            [id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// The server's `updated_at` for a pushed item, which the next push of it
+    /// must still match
+    pub fn set_vault_item_server_version(&self, id: &str, server_updated_at: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE vault_items SET server_updated_at = ?2 WHERE id = ?1",
+            params![id, server_updated_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_sync_cursor(&self, table_name: &str) -> Result<Option<SyncCursor>> {
+        let conn = self.conn.lock().unwrap();
+        let cursor = conn
+            .query_row(
+                "SELECT updated_at, last_id FROM sync_cursors WHERE table_name = ?1",
+                [table_name],
+                |row| {
+                    Ok(SyncCursor {
+                        updated_at: row.get(0)?,
+                        last_id: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor)
+    }
+
+    pub fn set_sync_cursor(&self, table_name: &str, cursor: &SyncCursor) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO sync_cursors (table_name, updated_at, last_id)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(table_name) DO UPDATE SET
+                updated_at = excluded.updated_at,
+                last_id = excluded.last_id
+            "#,
+            params![table_name, cursor.updated_at, cursor.last_id],
+        )?;
+        Ok(())
+    }
+
+    /// Start the next pull of every table from the beginning
+    pub fn clear_sync_cursors(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM sync_cursors", [])?;
+        Ok(())
+    }
+
+    /// Remove records that were permanently deleted on another device,
+    /// returning how many were removed. Records with local changes still
+    /// queued are kept; the push recreates them, so an edit here wins over a
+    /// delete made elsewhere.
+    pub fn apply_tombstones(&self, tombstones: &[Tombstone]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        let mut blob_ids = Vec::new();
+
+        for tombstone in tombstones {
+            let table = match tombstone.table_name.as_str() {
+                "vault_items" => "vault_items",
+                "vault_folders" => "folders",
+                _ => continue,
+            };
+            let id = &tombstone.record_id;
+
+            let queued: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sync_queue WHERE table_name = ?1 AND record_id = ?2)",
+                params![table, id],
+                |row| row.get(0),
+            )?;
+            if queued {
+                continue;
+            }
+
+            if table == "vault_items" {
+                let mut stmt = tx.prepare("SELECT id FROM attachments WHERE item_id = ?1")?;
+                let attachment_ids = stmt
+                    .query_map([id], |row| row.get::<_, String>(0))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                drop(stmt);
+                blob_ids.extend(attachment_ids);
+
+                tx.execute("DELETE FROM attachments WHERE item_id = ?1", [id])?;
+                tx.execute("DELETE FROM item_revisions WHERE item_id = ?1", [id])?;
+                tx.execute("DELETE FROM item_usage WHERE item_id = ?1", [id])?;
+                removed += tx.execute("DELETE FROM vault_items WHERE id = ?1", [id])?;
+            } else {
+                // The server cleared folder_id too; those items come down with the next pull
+                tx.execute(
+                    "UPDATE vault_items SET folder_id = NULL WHERE folder_id = ?1",
+                    [id],
+                )?;
+                removed += tx.execute("DELETE FROM folders WHERE id = ?1", [id])?;
+            }
+        }
+
+        tx.commit()?;
+        drop(conn);
+        for id in &blob_ids {
+            let _ = std::fs::remove_file(self.attachment_blob_path(id));
+        }
+        Ok(removed)
+    }
+
+    // ============================================
+    // Sync Conflicts
+    // ============================================
+
+    /// Newest first
+    pub fn get_sync_conflicts(&self) -> Result<Vec<SyncConflict>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, item_id, copy_id, local_updated_at, server_updated_at, detected_at
+            FROM sync_conflicts
+            ORDER BY detected_at DESC
+            "#,
+        )?;
+
+        let conflicts = stmt
+            .query_map([], |row| {
+                Ok(SyncConflict {
+                    id: row.get(0)?,
+                    item_id: row.get(1)?,
+                    copy_id: row.get(2)?,
+                    local_updated_at: row.get(3)?,
+                    server_updated_at: row.get(4)?,
+                    detected_at: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(conflicts)
+    }
+
+    pub fn resolve_conflict(&self, id: &str, resolution: ConflictResolution) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let (item_id, copy_id): (String, String) = tx
+            .query_row(
+                "SELECT item_id, copy_id FROM sync_conflicts WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Sync conflict {}", id)))?;
+
+        if resolution == ConflictResolution::Local {
+            let updated = tx.execute(
+                r#"
+                UPDATE vault_items
+                SET (encrypted_data, item_type, folder_id, is_favorite, deleted_at) = (
+                        SELECT encrypted_data, item_type, folder_id, is_favorite, deleted_at
+                        FROM vault_items WHERE id = ?2
+                    ),
+                    local_updated_at = ?3
+                WHERE id = ?1 AND EXISTS (SELECT 1 FROM vault_items WHERE id = ?2)
+                "#,
+                params![item_id, copy_id, Utc::now().to_rfc3339()],
+            )?;
+            if updated == 0 {
+                return Err(AppError::NotFound(
+                    "The item or its conflicted copy no longer exists".to_string(),
+                ));
+            }
+            self.add_to_sync_queue_internal(
+                &tx,
+                "update",
+                "vault_items",
+                &item_id,
+                None::<&VaultItem>,
+            )?;
+        }
+
+        if resolution != ConflictResolution::Both {
+            // A copy that never reached the server only needs its queued create dropped
+            let uploaded: bool = tx
+                .query_row(
+                    "SELECT synced_at IS NOT NULL FROM vault_items WHERE id = ?1",
+                    [&copy_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or(false);
+            tx.execute(
+                "DELETE FROM sync_queue WHERE table_name = 'vault_items' AND record_id = ?1",
+                [&copy_id],
+            )?;
+            tx.execute("DELETE FROM vault_items WHERE id = ?1", [&copy_id])?;
+            tx.execute("DELETE FROM item_revisions WHERE item_id = ?1", [&copy_id])?;
+            if uploaded {
+                self.add_to_sync_queue_internal(
+                    &tx,
+                    "delete",
+                    "vault_items",
+                    &copy_id,
+                    None::<&VaultItem>,
+                )?;
+            }
+        }
+
+        tx.execute("DELETE FROM sync_conflicts WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // ============================================
+    // Key Rotation
+    // ============================================
+
+    /// Pass every value encrypted with the vault key through `reencrypt`, in
+    /// one transaction that also removes the `meta_key` record, so a rotation
+    /// is only marked finished once nothing is left under the old key (see
+    /// rotation.rs). Items take the server versions the rotation produced;
+    /// without them their server version is forgotten, and the next pull
+    /// brings it back. Returns how many values were rewritten.
+    pub fn reencrypt_vault(
+        &self,
+        reencrypt: impl Fn(&str) -> String,
+        server_versions: Option<&HashMap<String, String>>,
+        meta_key: &str,
+    ) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut rewritten = 0;
+
+        for (table, column) in VAULT_KEY_COLUMNS {
+            let values: Vec<(i64, String)> = tx
+                .prepare(&format!(
+                    "SELECT rowid, {0} FROM {1} WHERE {0} IS NOT NULL",
+                    column, table
+                ))?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            let mut update = tx.prepare(&format!(
+                "UPDATE {} SET {} = ?1 WHERE rowid = ?2",
+                table, column
+            ))?;
+            for (rowid, value) in values {
+                update.execute(params![reencrypt(&value), rowid])?;
+                rewritten += 1;
+            }
+        }
+
+        match server_versions {
+            Some(versions) => {
+                let mut update =
+                    tx.prepare("UPDATE vault_items SET server_updated_at = ?2 WHERE id = ?1")?;
+                for (id, server_updated_at) in versions {
+                    update.execute(params![id, server_updated_at])?;
+                }
+            }
+            None => {
+                tx.execute("UPDATE vault_items SET server_updated_at = NULL", [])?;
+            }
+        }
+
+        tx.execute("DELETE FROM app_meta WHERE key = ?1", [meta_key])?;
+        tx.commit()?;
+        Ok(rewritten)
+    }
+
+    // ============================================
+    // Audit Log
+    // ============================================
+
+    /// Append an event, chained to the newest entry
+    pub fn append_audit_entry(
+        &self,
+        event: &str,
+        item_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<AuditEntry> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let prev_hash: String = tx
+            .query_row(
+                "SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or_else(|| AUDIT_GENESIS_HASH.to_string());
+        let occurred_at = Utc::now().to_rfc3339();
+        let hash = audit_entry_hash(&prev_hash, event, &occurred_at, item_id, detail);
+
+        tx.execute(
+            r#"
+            INSERT INTO audit_log (event, occurred_at, item_id, detail, prev_hash, hash)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![event, occurred_at, item_id, detail, prev_hash, hash],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+
+        Ok(AuditEntry {
+            id,
+            event: event.to_string(),
+            occurred_at,
+            item_id: item_id.map(str::to_string),
+            detail: detail.map(str::to_string),
+            prev_hash,
+            hash,
+        })
+    }
+
+    /// One page of the audit log, newest first
+    pub fn get_audit_log(
+        &self,
+        filter: &AuditFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AuditEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let (conditions, mut values) = filter.to_sql();
+        values.push(SqlValue::Integer(limit.into()));
+        values.push(SqlValue::Integer(offset.into()));
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, event, occurred_at, item_id, detail, prev_hash, hash
+            FROM audit_log
+            WHERE {}
+            ORDER BY id DESC
+            LIMIT ?{} OFFSET ?{}
+            "#,
+            conditions,
+            values.len() - 1,
+            values.len()
+        ))?;
+
+        let entries = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter()),
+                audit_entry_from_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn count_audit_log(&self, filter: &AuditFilter) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let (conditions, values) = filter.to_sql();
+        let count = conn.query_row(
+            &format!("SELECT COUNT(*) FROM audit_log WHERE {}", conditions),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Walk the whole log, checking each entry's hash and link to the one before
+    pub fn verify_audit_log(&self) -> Result<AuditVerification> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, event, occurred_at, item_id, detail, prev_hash, hash
+            FROM audit_log
+            ORDER BY id
+            "#,
+        )?;
+        let mut rows = stmt.query([])?;
+
+        let mut expected_prev = AUDIT_GENESIS_HASH.to_string();
+        let mut entries_checked = 0;
+        while let Some(row) = rows.next()? {
+            let entry = audit_entry_from_row(row)?;
+            entries_checked += 1;
+
+            let hash = audit_entry_hash(
+                &entry.prev_hash,
+                &entry.event,
+                &entry.occurred_at,
+                entry.item_id.as_deref(),
+                entry.detail.as_deref(),
+            );
+            if entry.prev_hash != expected_prev || entry.hash != hash {
+                return Ok(AuditVerification {
+                    intact: false,
+                    entries_checked,
+                    first_broken_id: Some(entry.id),
+                });
+            }
+            expected_prev = entry.hash;
+        }
+
+        Ok(AuditVerification {
+            intact: true,
+            entries_checked,
+            first_broken_id: None,
+        })
+    }
+
+    // ============================================
+    // Sync History
+    // ============================================
+
+    pub fn insert_sync_history(&self, entry: &SyncHistoryEntry) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO sync_history (trigger, started_at, duration_ms, pushed, pulled, success, error)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                entry.trigger,
+                entry.started_at,
+                entry.duration_ms,
+                entry.pushed,
+                entry.pulled,
+                entry.success as i32,
+                entry.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent attempts first
+    pub fn get_sync_history(&self, limit: u32) -> Result<Vec<SyncHistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, trigger, started_at, duration_ms, pushed, pulled, success, error
+            FROM sync_history
+            ORDER BY id DESC
+            LIMIT ?1
+            "#,
+        )?;
+
+        let entries = stmt
+            .query_map([limit], |row| {
+                Ok(SyncHistoryEntry {
+                    id: row.get(0)?,
+                    trigger: row.get(1)?,
+                    started_at: row.get(2)?,
+                    duration_ms: row.get(3)?,
+                    pushed: row.get(4)?,
+                    pulled: row.get(5)?,
+                    success: row.get::<_, i32>(6)? == 1,
+                    error: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// Keep at most `max_entries` rows, none older than `older_than`
+    pub fn prune_sync_history(&self, max_entries: u32, older_than: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            DELETE FROM sync_history
+            WHERE started_at < ?2
+               OR id NOT IN (SELECT id FROM sync_history ORDER BY id DESC LIMIT ?1)
+            "#,
+            params![max_entries, older_than],
+        )?;
+        Ok(())
+    }
+
     pub fn clear_sync_queue(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM sync_queue", [])?;
@@ -534,15 +3248,28 @@ impl Database {
         let now = Utc::now().to_rfc3339();
 
         match table_name {
+            // server_updated_at comes from the push response (set_vault_item_server_version)
             "vault_items" => {
                 conn.execute(
-                    "UPDATE vault_items SET synced_at = ?2, server_updated_at = ?2 WHERE id = ?1",
+                    "UPDATE vault_items SET synced_at = ?2 WHERE id = ?1",
+                    params![record_id, now],
+                )?;
+            }
+            "folders" => {
+                conn.execute(
+                    "UPDATE folders SET synced_at = ?2 WHERE id = ?1",
+                    params![record_id, now],
+                )?;
+            }
+            "attachments" => {
+                conn.execute(
+                    "UPDATE attachments SET synced_at = ?2 WHERE id = ?1",
                     params![record_id, now],
                 )?;
             }
-            "folders" => {
+            "org_items" => {
                 conn.execute(
-                    "UPDATE folders SET synced_at = ?2 WHERE id = ?1",
+                    "UPDATE org_items SET synced_at = ?2, server_updated_at = ?2 WHERE id = ?1",
                     params![record_id, now],
                 )?;
             }
@@ -558,7 +3285,7 @@ impl Database {
 
     pub fn get_session(&self) -> Result<Option<UserSession>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             r#"
             SELECT user_id, email, access_token, refresh_token, expires_at, last_sync_at
             FROM user_session
@@ -615,6 +3342,221 @@ impl Database {
         Ok(())
     }
 
+    pub fn save_resume_token(&self, payload: &str, expires_at: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO session_resume (id, payload, expires_at) VALUES (1, ?1, ?2)",
+            params![payload, expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// Read and delete the resume token in one step
+    pub fn take_resume_token(&self) -> Result<Option<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let token = conn
+            .query_row(
+                "SELECT payload, expires_at FROM session_resume WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        conn.execute("DELETE FROM session_resume", [])?;
+        Ok(token)
+    }
+
+    // ============================================
+    // App Meta
+    // ============================================
+
+    pub fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let value = conn
+            .query_row("SELECT value FROM app_meta WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO app_meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_meta(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM app_meta WHERE key = ?1", [key])?;
+        Ok(())
+    }
+
+    /// Counter bumped by triggers on every `vault_items` insert, update or delete
+    pub fn vault_revision(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let revision = conn
+            .prepare_cached("SELECT revision FROM vault_revision WHERE id = 1")?
+            .query_row([], |row| row.get(0))?;
+        Ok(revision)
+    }
+
+    // ============================================
+    // UI State
+    // ============================================
+
+    /// JSON value stored under `key`
+    pub fn get_ui_state(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let value = conn
+            .query_row("SELECT value FROM ui_state WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn get_all_ui_state(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM ui_state")?;
+        let entries = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    pub fn set_ui_state(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO ui_state (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![key, value, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_ui_state(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM ui_state WHERE key = ?1", [key])?;
+        Ok(())
+    }
+
+    // ============================================
+    // Breach Cache
+    // ============================================
+
+    /// A cached HIBP range response, if fetched after `fetched_after`
+    pub fn get_breach_range(&self, prefix: &str, fetched_after: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let response = conn
+            .query_row(
+                "SELECT response FROM breach_cache WHERE prefix = ?1 AND fetched_at > ?2",
+                params![prefix, fetched_after],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(response)
+    }
+
+    pub fn save_breach_range(&self, prefix: &str, response: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO breach_cache (prefix, response, fetched_at) VALUES (?1, ?2, ?3)",
+            params![prefix, response, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // ============================================
+    // Plugin Trust
+    // ============================================
+
+    /// The executable hash pinned when the plugin was trusted
+    pub fn get_plugin_trust(&self, plugin_id: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let hash = conn
+            .query_row(
+                "SELECT executable_hash FROM plugin_trust WHERE plugin_id = ?1",
+                [plugin_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(hash)
+    }
+
+    pub fn set_plugin_trust(&self, plugin_id: &str, executable_hash: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO plugin_trust (plugin_id, executable_hash, trusted_at) VALUES (?1, ?2, ?3)",
+            params![plugin_id, executable_hash, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_plugin_trust(&self, plugin_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM plugin_trust WHERE plugin_id = ?1", [plugin_id])?;
+        Ok(())
+    }
+
+    // ============================================
+    // Paired Devices
+    // ============================================
+
+    pub fn get_paired_devices(&self) -> Result<Vec<PairedDevice>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, name, created_at, last_sent_at
+            FROM paired_devices
+            ORDER BY created_at ASC
+            "#,
+        )?;
+
+        let devices = stmt
+            .query_map([], |row| {
+                Ok(PairedDevice {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    created_at: row.get(2)?,
+                    last_sent_at: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(devices)
+    }
+
+    pub fn insert_paired_device(&self, device: &PairedDevice) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO paired_devices (id, name, created_at, last_sent_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+            params![
+                device.id,
+                device.name,
+                device.created_at,
+                device.last_sent_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn touch_paired_device(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE paired_devices SET last_sent_at = ?2 WHERE id = ?1",
+            params![id, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_paired_device(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM paired_devices WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
     // ============================================
     // App Settings
     // ============================================
@@ -624,7 +3566,11 @@ impl Database {
         let mut stmt = conn.prepare(
             r#"
             SELECT auto_lock_minutes, clipboard_clear_seconds, start_minimized, 
-                   start_on_boot, theme, color_theme
+                   start_on_boot, theme, color_theme, locale, min_master_password_score,
+                   screen_capture_protection, notify_sync_failures, notify_security_alerts,
+                   notify_expiry_reminders, notify_clipboard_cleared, quiet_hours_start,
+                   quiet_hours_end, backup_schedule, backup_directory, backup_retention_count,
+                   item_revision_limit, auto_type_delay_ms, show_site_icons, sync_after_lock
             FROM app_settings
             WHERE id = 1
             "#,
@@ -639,6 +3585,32 @@ impl Database {
                     start_on_boot: row.get::<_, i32>(3)? == 1,
                     theme: row.get(4)?,
                     color_theme: row.get::<_, Option<String>>(5)?.unwrap_or_else(|| "birch".to_string()),
+                    locale: row.get::<_, Option<String>>(6)?.unwrap_or_else(default_locale),
+                    min_master_password_score: row
+                        .get::<_, Option<u8>>(7)?
+                        .unwrap_or_else(default_min_master_password_score),
+                    screen_capture_protection: row.get::<_, Option<i32>>(8)?.unwrap_or(1) == 1,
+                    notify_sync_failures: row.get::<_, Option<i32>>(9)?.unwrap_or(1) == 1,
+                    notify_security_alerts: row.get::<_, Option<i32>>(10)?.unwrap_or(1) == 1,
+                    notify_expiry_reminders: row.get::<_, Option<i32>>(11)?.unwrap_or(1) == 1,
+                    notify_clipboard_cleared: row.get::<_, Option<i32>>(12)?.unwrap_or(0) == 1,
+                    quiet_hours_start: row.get(13)?,
+                    quiet_hours_end: row.get(14)?,
+                    backup_schedule: row
+                        .get::<_, Option<String>>(15)?
+                        .unwrap_or_else(default_backup_schedule),
+                    backup_directory: row.get(16)?,
+                    backup_retention_count: row
+                        .get::<_, Option<u32>>(17)?
+                        .unwrap_or_else(default_backup_retention_count),
+                    item_revision_limit: row
+                        .get::<_, Option<u32>>(18)?
+                        .unwrap_or_else(default_item_revision_limit),
+                    auto_type_delay_ms: row
+                        .get::<_, Option<u32>>(19)?
+                        .unwrap_or_else(default_auto_type_delay_ms),
+                    show_site_icons: row.get::<_, Option<i32>>(20)?.unwrap_or(1) == 1,
+                    sync_after_lock: row.get::<_, Option<i32>>(21)?.unwrap_or(1) == 1,
                 })
             })
             .unwrap_or_default();
@@ -652,7 +3624,22 @@ impl Database {
             r#"
             UPDATE app_settings 
             SET auto_lock_minutes = ?1, clipboard_clear_seconds = ?2, 
-                start_minimized = ?3, start_on_boot = ?4, theme = ?5, color_theme = ?6
+                start_minimized = ?3, start_on_boot = ?4, theme = ?5, color_theme = ?6,
+                locale = ?7, min_master_password_score = ?8,
+                screen_capture_protection = ?9,
+                notify_sync_failures = ?10,
+                notify_security_alerts = ?11,
+                notify_expiry_reminders = ?12,
+                notify_clipboard_cleared = ?13,
+                quiet_hours_start = ?14,
+                quiet_hours_end = ?15,
+                backup_schedule = ?16,
+                backup_directory = ?17,
+                backup_retention_count = ?18,
+                item_revision_limit = ?19,
+                auto_type_delay_ms = ?20,
+                show_site_icons = ?21,
+                sync_after_lock = ?22
             WHERE id = 1
             "#,
             params![
@@ -662,26 +3649,213 @@ impl Database {
                 settings.start_on_boot as i32,
                 settings.theme,
                 settings.color_theme,
+                settings.locale,
+                settings.min_master_password_score,
+                settings.screen_capture_protection as i32,
+                settings.notify_sync_failures as i32,
+                settings.notify_security_alerts as i32,
+                settings.notify_expiry_reminders as i32,
+                settings.notify_clipboard_cleared as i32,
+                settings.quiet_hours_start,
+                settings.quiet_hours_end,
+                settings.backup_schedule,
+                settings.backup_directory,
+                settings.backup_retention_count,
+                settings.item_revision_limit,
+                settings.auto_type_delay_ms,
+                settings.show_site_icons as i32,
+                settings.sync_after_lock as i32,
             ],
         )?;
         Ok(())
     }
 
+    // ============================================
+    // Import
+    // ============================================
+
+    /// Insert imported folders and items in one transaction, queueing each for sync
+    pub fn import_records(&self, folders: &[Folder], items: &[VaultItem]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for folder in folders {
+            tx.execute(
+                r#"
+                INSERT INTO folders (id, name, synced_at, local_updated_at, retention_days)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                params![
+                    folder.id,
+                    folder.name,
+                    folder.synced_at,
+                    folder.local_updated_at,
+                    folder.retention_days,
+                ],
+            )?;
+            self.add_to_sync_queue_internal(&tx, "create", "folders", &folder.id, Some(folder))?;
+        }
+
+        for item in items {
+            tx.execute(
+                r#"
+                INSERT INTO vault_items (id, encrypted_data, item_type, folder_id, is_favorite,
+                                         deleted_at, synced_at, local_updated_at, server_updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+                params![
+                    item.id,
+                    item.encrypted_data,
+                    item.item_type,
+                    item.folder_id,
+                    item.is_favorite as i32,
+                    item.deleted_at,
+                    item.synced_at,
+                    item.local_updated_at,
+                    item.server_updated_at,
+                ],
+            )?;
+            self.add_to_sync_queue_internal(&tx, "create", "vault_items", &item.id, Some(item))?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Upsert folders and items from a backup, queueing each as a create or update
+    pub fn restore_records(&self, folders: &[Folder], items: &[VaultItem]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now().to_rfc3339();
+
+        for folder in folders {
+            let exists = tx
+                .query_row("SELECT 1 FROM folders WHERE id = ?1", [&folder.id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            tx.execute(
+                r#"
+                INSERT INTO folders (id, name, synced_at, local_updated_at, retention_days)
+                VALUES (?1, ?2, NULL, ?3, ?4)
+                ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name,
+                    synced_at = NULL,
+                    local_updated_at = excluded.local_updated_at,
+                    retention_days = excluded.retention_days
+                "#,
+                params![folder.id, folder.name, now, folder.retention_days],
+            )?;
+            let operation = if exists { "update" } else { "create" };
+            self.add_to_sync_queue_internal(&tx, operation, "folders", &folder.id, Some(folder))?;
+        }
+
+        for item in items {
+            let exists = tx
+                .query_row("SELECT 1 FROM vault_items WHERE id = ?1", [&item.id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            tx.execute(
+                r#"
+                INSERT INTO vault_items (id, encrypted_data, item_type, folder_id, is_favorite,
+                                         deleted_at, synced_at, local_updated_at, server_updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, ?7, ?8)
+                ON CONFLICT(id) DO UPDATE SET
+                    encrypted_data = excluded.encrypted_data,
+                    item_type = excluded.item_type,
+                    folder_id = excluded.folder_id,
+                    is_favorite = excluded.is_favorite,
+                    deleted_at = excluded.deleted_at,
+                    synced_at = NULL,
+                    local_updated_at = excluded.local_updated_at
+                "#,
+                params![
+                    item.id,
+                    item.encrypted_data,
+                    item.item_type,
+                    item.folder_id,
+                    item.is_favorite as i32,
+                    item.deleted_at,
+                    now,
+                    item.server_updated_at,
+                ],
+            )?;
+            let operation = if exists { "update" } else { "create" };
+            self.add_to_sync_queue_internal(&tx, operation, "vault_items", &item.id, Some(item))?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
     // ============================================
     // Bulk Operations for Sync
     // ============================================
 
-    pub fn bulk_upsert_vault_items(&self, items: &[VaultItem]) -> Result<()> {
+    /// Store vault items pulled from the server. An item with local changes
+    /// still queued whose server `updated_at` has moved on from the
+    /// `server_updated_at` those changes were based on was changed on both
+    /// sides: the local version is kept as a new "conflicted copy" item, queued
+    /// for upload, and the server's version takes the original id. Otherwise
+    /// an item with queued changes keeps its local version.
+    pub fn bulk_upsert_vault_items(&self, items: &[VaultItem]) -> Result<Vec<SyncConflict>> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
+        let mut conflicts = Vec::new();
 
         for item in items {
+            let local: Option<(String, String, Option<String>)> = tx
+                .query_row(
+                    r#"
+                    SELECT encrypted_data, local_updated_at, server_updated_at
+                    FROM vault_items
+                    WHERE id = ?1 AND EXISTS (
+                        SELECT 1 FROM sync_queue WHERE table_name = 'vault_items' AND record_id = ?1
+                    )
+                    "#,
+                    [&item.id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            if let Some((encrypted_data, local_updated_at, base)) = local {
+                let server_changed = base != item.server_updated_at;
+                if server_changed && encrypted_data != item.encrypted_data {
+                    let server_updated_at = item
+                        .server_updated_at
+                        .as_deref()
+                        .unwrap_or(&item.local_updated_at);
+                    conflicts.push(self.fork_conflicted_copy(
+                        &tx,
+                        &item.id,
+                        &local_updated_at,
+                        server_updated_at,
+                    )?);
+                } else {
+                    // The queued edit is still to be pushed: keep it, only
+                    // moving its base on to the server's current version
+                    tx.execute(
+                        "UPDATE vault_items SET server_updated_at = ?2 WHERE id = ?1",
+                        params![item.id, item.server_updated_at],
+                    )?;
+                    continue;
+                }
+            }
+
             tx.execute(
                 r#"
-                INSERT OR REPLACE INTO vault_items 
+                INSERT INTO vault_items 
                 (id, encrypted_data, item_type, folder_id, is_favorite, deleted_at, 
                  synced_at, local_updated_at, server_updated_at)
                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(id) DO UPDATE SET
+                    encrypted_data = excluded.encrypted_data,
+                    item_type = excluded.item_type,
+                    folder_id = excluded.folder_id,
+                    deleted_at = excluded.deleted_at,
+                    synced_at = excluded.synced_at,
+                    local_updated_at = excluded.local_updated_at,
+                    server_updated_at = excluded.server_updated_at
+                -- is_favorite isn't stored server-side, so keep the local value
                 "#,
                 params![
                     item.id,
@@ -697,6 +3871,105 @@ impl Database {
             )?;
         }
 
+        tx.commit()?;
+        Ok(conflicts)
+    }
+
+    /// Copy an item's local version to a new id queued for upload, and drop
+    /// the original's queued changes so they don't overwrite the server's
+    fn fork_conflicted_copy(
+        &self,
+        conn: &Connection,
+        item_id: &str,
+        local_updated_at: &str,
+        server_updated_at: &str,
+    ) -> Result<SyncConflict> {
+        let copy_id = Uuid::new_v4().to_string();
+        conn.execute(
+            r#"
+            INSERT INTO vault_items
+                (id, encrypted_data, item_type, folder_id, is_favorite, deleted_at,
+                 synced_at, local_updated_at, server_updated_at)
+            SELECT ?2, encrypted_data, item_type, folder_id, is_favorite, deleted_at,
+                   NULL, local_updated_at, NULL
+            FROM vault_items
+            WHERE id = ?1
+            "#,
+            params![item_id, copy_id],
+        )?;
+        conn.execute(
+            "DELETE FROM sync_queue WHERE table_name = 'vault_items' AND record_id = ?1",
+            [item_id],
+        )?;
+        self.add_to_sync_queue_internal(
+            conn,
+            "create",
+            "vault_items",
+            &copy_id,
+            None::<&VaultItem>,
+        )?;
+
+        let conflict = SyncConflict {
+            id: Uuid::new_v4().to_string(),
+            item_id: item_id.to_string(),
+            copy_id,
+            local_updated_at: local_updated_at.to_string(),
+            server_updated_at: server_updated_at.to_string(),
+            detected_at: Utc::now().to_rfc3339(),
+        };
+        conn.execute(
+            r#"
+            INSERT INTO sync_conflicts
+                (id, item_id, copy_id, local_updated_at, server_updated_at, detected_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                conflict.id,
+                conflict.item_id,
+                conflict.copy_id,
+                conflict.local_updated_at,
+                conflict.server_updated_at,
+                conflict.detected_at,
+            ],
+        )?;
+        Ok(conflict)
+    }
+
+    pub fn bulk_upsert_org_items(&self, items: &[OrgItem]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for item in items {
+            tx.execute(
+                r#"
+                INSERT INTO org_items
+                (id, organization_id, encrypted_data, item_type, created_by, deleted_at,
+                 synced_at, local_updated_at, server_updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(id) DO UPDATE SET
+                    organization_id = excluded.organization_id,
+                    encrypted_data = excluded.encrypted_data,
+                    item_type = excluded.item_type,
+                    created_by = excluded.created_by,
+                    deleted_at = excluded.deleted_at,
+                    synced_at = excluded.synced_at,
+                    local_updated_at = excluded.local_updated_at,
+                    server_updated_at = excluded.server_updated_at
+                "#,
+                params![
+                    item.id,
+                    item.organization_id,
+                    item.encrypted_data,
+                    item.item_type,
+                    item.created_by,
+                    item.deleted_at,
+                    item.synced_at,
+                    item.local_updated_at,
+                    item.server_updated_at,
+                ],
+            )?;
+        }
+
         tx.commit()?;
         Ok(())
     }
@@ -708,14 +3981,15 @@ impl Database {
         for folder in folders {
             tx.execute(
                 r#"
-                INSERT OR REPLACE INTO folders (id, name, synced_at, local_updated_at)
-                VALUES (?1, ?2, ?3, ?4)
+                INSERT OR REPLACE INTO folders (id, name, synced_at, local_updated_at, retention_days)
+                VALUES (?1, ?2, ?3, ?4, ?5)
                 "#,
                 params![
                     folder.id,
                     folder.name,
                     folder.synced_at,
                     folder.local_updated_at,
+                    folder.retention_days,
                 ],
             )?;
         }
@@ -724,7 +3998,7 @@ impl Database {
         Ok(())
     }
 
-    /// Clear all data (used when logging out)
+    /// Clear all data (used when logging out), except the audit log
     pub fn clear_all_data(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute_batch(
@@ -733,6 +4007,23 @@ impl Database {
             DELETE FROM folders;
             DELETE FROM sync_queue;
             DELETE FROM user_session;
+            DELETE FROM session_resume;
+            DELETE FROM paired_devices;
+            DELETE FROM recoverable_deletions;
+            DELETE FROM sync_history;
+            DELETE FROM app_meta;
+            DELETE FROM breach_cache;
+            DELETE FROM ui_state;
+            DELETE FROM item_revisions;
+            DELETE FROM item_usage;
+            DELETE FROM attachments;
+            DELETE FROM organizations;
+            DELETE FROM collections;
+            DELETE FROM collection_items;
+            DELETE FROM org_items;
+            DELETE FROM sync_conflicts;
+            DELETE FROM sync_cursors;
+            -- audit_log is kept: it refuses deletes (see audit.rs)
             "#,
         )?;
         Ok(())
@@ -744,7 +4035,7 @@ impl Database {
         let mut stmt = conn.prepare(
             r#"
             SELECT id, encrypted_data, item_type, folder_id, is_favorite, 
-                   deleted_at, synced_at, local_updated_at, server_updated_at
+                   deleted_at, synced_at, local_updated_at, server_updated_at, sort_index
             FROM vault_items
             WHERE synced_at IS NULL 
                OR local_updated_at > COALESCE(synced_at, '1970-01-01')
@@ -763,6 +4054,7 @@ impl Database {
                     synced_at: row.get(6)?,
                     local_updated_at: row.get(7)?,
                     server_updated_at: row.get(8)?,
+                    sort_index: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -771,6 +4063,37 @@ impl Database {
     }
 }
 
+/// SHA-256 over the previous hash and the entry's fields, hex encoded. The
+/// fields go through JSON so no two entries can hash the same input.
+fn audit_entry_hash(
+    prev_hash: &str,
+    event: &str,
+    occurred_at: &str,
+    item_id: Option<&str>,
+    detail: Option<&str>,
+) -> String {
+    let fields = serde_json::json!([event, occurred_at, item_id, detail]).to_string();
+    Sha256::new()
+        .chain_update(prev_hash.as_bytes())
+        .chain_update(fields.as_bytes())
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn audit_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        event: row.get(1)?,
+        occurred_at: row.get(2)?,
+        item_id: row.get(3)?,
+        detail: row.get(4)?,
+        prev_hash: row.get(5)?,
+        hash: row.get(6)?,
+    })
+}
+
 
 
 
@@ -0,0 +1,93 @@
+// ============================================
+// BirchVault Desktop - Deferred Sign-in
+// ============================================
+//
+// Unlocking needs nothing from Supabase: the master key is checked against
+// the keyring verifier, and the vault key is unwrapped from the copy of the
+// profile's wrapped key kept from the last sign-in. When the server can't be
+// reached, `login` and `unlock_vault` therefore still open the local vault,
+// and the session is marked degraded. Whatever needed the server (signing in,
+// the first sync) waits here until the connectivity monitor reaches it again.
+
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::onboarding::{self, OnboardingStep};
+use crate::registration;
+use crate::sync::{SyncEngine, SyncStatus};
+
+const META_WRAPPED_VAULT_KEY: &str = "wrapped_vault_key";
+
+/// Server work put off by a degraded unlock
+#[derive(Clone)]
+pub struct DeferredSignIn {
+    pub email: String,
+    /// From `login`: the account still has to be signed in to. `None` when
+    /// an existing session was unlocked and only needs to sync.
+    pub password_hash: Option<String>,
+}
+
+/// Whether `error` means the server couldn't be reached, rather than that it
+/// answered with a refusal
+pub fn is_unreachable(error: &AppError) -> bool {
+    match error {
+        AppError::NetworkUnavailable => true,
+        AppError::Http(e) => e.is_connect() || e.is_timeout(),
+        _ => false,
+    }
+}
+
+/// The profile's wrapped vault key as last seen, for unlocking offline
+pub fn wrapped_key(db: &Database) -> Result<Option<String>> {
+    db.get_meta(META_WRAPPED_VAULT_KEY)
+}
+
+pub fn remember_wrapped_key(db: &Database, wrapped: &str) {
+    if let Err(e) = db.set_meta(META_WRAPPED_VAULT_KEY, wrapped) {
+        log::warn!("Failed to keep the wrapped vault key: {}", e);
+    }
+}
+
+/// Refresh the kept copy of the wrapped vault key from the server
+pub async fn refresh_wrapped_key(engine: &SyncEngine, db: &Database) {
+    match engine.wrapped_vault_key().await {
+        Ok(wrapped) => remember_wrapped_key(db, &wrapped),
+        Err(e) => log::warn!("Failed to fetch the wrapped vault key: {}", e),
+    }
+}
+
+/// Do the server work a degraded unlock put off, if any. Still unreachable,
+/// it stays deferred; any other failure drops it (e.g. a changed password
+/// can only be fixed by signing in again).
+pub async fn complete(state: &AppState) -> Result<Option<SyncStatus>> {
+    let Some(deferred) = state.deferred_sign_in.write().await.take() else {
+        return Ok(None);
+    };
+
+    let engine = &state.sync_engine;
+    let result = async {
+        let Some(password_hash) = &deferred.password_hash else {
+            return engine.sync("reconnect").await;
+        };
+
+        let session = engine.authenticate(&deferred.email, password_hash).await?;
+        state.db.save_session(&session)?;
+        registration::complete(engine, &state.db, &session.email).await?;
+        refresh_wrapped_key(engine, &state.db).await;
+
+        engine.initial_sync(&session).await?;
+        onboarding::record(&state.db, &[OnboardingStep::FirstSyncDone], false);
+        Ok(engine.get_status().await)
+    }
+    .await;
+
+    match result {
+        Ok(status) => Ok(Some(status)),
+        Err(e) => {
+            if is_unreachable(&e) {
+                *state.deferred_sign_in.write().await = Some(deferred);
+            }
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// ============================================
+// BirchVault Desktop - Paired Devices (Send to Phone)
+// ============================================
+//
+// Secrets are sealed with a per-device key shared at pairing time and pushed
+// over a Realtime broadcast topic derived from that key, so the server only
+// ever relays ciphertext.
+
+use crate::crypto;
+use crate::db::{Database, PairedDevice};
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{Duration, Utc};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+const KEYRING_SERVICE: &str = "birchvault-pairing";
+const TOPIC_PREFIX: &str = "birchvault-send";
+
+pub const SEND_EVENT: &str = "secret";
+
+/// Receivers should discard anything older than this
+pub const SEND_TTL_SECONDS: i64 = 120;
+
+/// Shown once (as a QR code) so the phone can derive the same key and topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePairing {
+    pub device: PairedDevice,
+    pub secret: String,
+    pub topic: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SentSecret {
+    label: Option<String>,
+    value: String,
+    sent_at: String,
+    expires_at: String,
+}
+
+/// Broadcast topic for a pairing secret; unguessable without the secret
+fn topic_for(secret: &[u8]) -> String {
+    let digest = Sha256::new()
+        .chain_update(TOPIC_PREFIX.as_bytes())
+        .chain_update(secret)
+        .finalize();
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", TOPIC_PREFIX, hex)
+}
+
+fn secret_for(device_id: &str) -> Result<Vec<u8>> {
+    let encoded = Entry::new(KEYRING_SERVICE, device_id)?
+        .get_password()
+        .map_err(|_| AppError::NotFound(format!("Paired device {}", device_id)))?;
+    BASE64
+        .decode(encoded)
+        .map_err(|_| AppError::Encryption("Invalid pairing secret".to_string()))
+}
+
+pub fn pair(db: &Database, name: &str) -> Result<DevicePairing> {
+    let secret = crypto::generate_key();
+    let device = PairedDevice {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        last_sent_at: None,
+    };
+
+    Entry::new(KEYRING_SERVICE, &device.id)?.set_password(&BASE64.encode(secret))?;
+    db.insert_paired_device(&device)?;
+
+    Ok(DevicePairing {
+        device,
+        secret: BASE64.encode(secret),
+        topic: topic_for(&secret),
+    })
+}
+
+pub fn unpair(db: &Database, device_id: &str) -> Result<()> {
+    if let Ok(entry) = Entry::new(KEYRING_SERVICE, device_id) {
+        let _ = entry.delete_password();
+    }
+    db.delete_paired_device(device_id)
+}
+
+/// Forget every paired device (on logout)
+pub fn unpair_all(db: &Database) -> Result<()> {
+    for device in db.get_paired_devices()? {
+        unpair(db, &device.id)?;
+    }
+    Ok(())
+}
+
+/// Encrypt a secret for a device, returning the topic and broadcast payload
+pub fn seal(
+    device_id: &str,
+    label: Option<String>,
+    value: String,
+) -> Result<(String, serde_json::Value)> {
+    let secret = secret_for(device_id)?;
+    let now = Utc::now();
+    let message = SentSecret {
+        label,
+        value,
+        sent_at: now.to_rfc3339(),
+        expires_at: (now + Duration::seconds(SEND_TTL_SECONDS)).to_rfc3339(),
+    };
+
+    let encrypted = crypto::encrypt(&serde_json::to_vec(&message)?, &secret)?;
+    Ok((topic_for(&secret), serde_json::to_value(encrypted)?))
+}
@@ -0,0 +1,286 @@
+// ============================================
+// BirchVault Desktop - Diagnostics
+// ============================================
+//
+// Support bundles: a zip of recent logs, sync history, queued sync errors,
+// schema version and settings that users can attach to bug reports. Nothing
+// from the vault goes in, and every file passes through `redact` first, so
+// tokens, email addresses, URLs and home directory paths are masked.
+//
+// Logs only ever go to stderr, so the logger installed here also keeps the
+// most recent lines in memory for the bundle.
+
+use crate::db::{AppSettings, Database, SyncError, SyncHistoryEntry};
+use crate::error::{AppError, Result};
+use crate::sync::{SchemaStatus, SyncStatus, EXPECTED_SCHEMA_VERSION};
+use chrono::Utc;
+use log::{Level, Log, Metadata, Record};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use zip::write::SimpleFileOptions;
+
+/// Captured whatever RUST_LOG says, so bundles are useful without it
+const CAPTURE_LEVEL: Level = Level::Info;
+const MAX_LOG_LINES: usize = 2000;
+
+const SYNC_HISTORY_LIMIT: u32 = 100;
+/// Older bundles in the support directory are removed past this many
+const KEEP_BUNDLES: usize = 5;
+
+const FILE_PREFIX: &str = "birchvault-support-";
+
+/// Signed share links last this long unless the user picks otherwise
+pub const DEFAULT_LINK_HOURS: u32 = 72;
+pub const MAX_LINK_HOURS: u32 = 7 * 24;
+
+/// Settings that can name the user (paths) rather than describe the app
+const PRIVATE_SETTINGS: &[&str] = &["backupDirectory"];
+
+static LOG_BUFFER: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportBundle {
+    pub file_name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+    /// Time-limited download link, when the bundle was uploaded
+    pub share_url: Option<String>,
+    pub share_expires_at: Option<String>,
+}
+
+/// What the bundle says about the server schema; unreachable is worth knowing too
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaReport {
+    pub expected_version: i64,
+    pub server: Option<SchemaStatus>,
+    pub error: Option<String>,
+}
+
+impl SchemaReport {
+    pub fn new(server: Option<Result<SchemaStatus>>) -> Self {
+        let (server, error) = match server {
+            Some(Ok(status)) => (Some(status), None),
+            Some(Err(e)) => (None, Some(e.to_string())),
+            None => (None, Some("Not checked (offline)".to_string())),
+        };
+        SchemaReport {
+            expected_version: EXPECTED_SCHEMA_VERSION,
+            server,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest<'a> {
+    app_version: &'a str,
+    os: &'a str,
+    arch: &'a str,
+    created_at: &'a str,
+    offline_profile: bool,
+    schema: &'a SchemaReport,
+    sync_status: &'a SyncStatus,
+}
+
+// ============================================
+// Log capture
+// ============================================
+
+struct CaptureLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= CAPTURE_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= CAPTURE_LEVEL {
+            let line = format!(
+                "{} {:<5} {}: {}",
+                Utc::now().to_rfc3339(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            if let Ok(mut buffer) = LOG_BUFFER.lock() {
+                if buffer.len() == MAX_LOG_LINES {
+                    buffer.pop_front();
+                }
+                buffer.push_back(line);
+            }
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install the logger: env_logger to stderr as before, plus the in-memory capture
+pub fn init_logging() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter().max(CAPTURE_LEVEL.to_level_filter());
+
+    if log::set_boxed_logger(Box::new(CaptureLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+fn captured_logs() -> String {
+    let buffer = LOG_BUFFER.lock().map(|b| b.clone()).unwrap_or_default();
+    let mut text = buffer.into_iter().collect::<Vec<_>>().join("\n");
+    text.push('\n');
+    text
+}
+
+// ============================================
+// Redaction
+// ============================================
+
+fn redactions() -> &'static [(Regex, &'static str)] {
+    static REDACTIONS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    REDACTIONS.get_or_init(|| {
+        [
+            // JWTs (Supabase access tokens) wherever they appear
+            (r"eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*", "<token>"),
+            (r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]+", "Bearer <token>"),
+            (
+                r#"(?i)\b(access_token|refresh_token|password|secret|api_?key)(["']?\s*[:=]\s*["']?)[^\s"',}&]+"#,
+                "$1$2<redacted>",
+            ),
+            (r#"\b[a-z][a-z0-9+.-]*://[^\s"'<>]+"#, "<url>"),
+            (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "<email>"),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+        .collect()
+    })
+}
+
+/// Mask anything in diagnostic text that identifies the user or grants access
+pub fn redact(text: &str) -> String {
+    let mut text = redactions()
+        .iter()
+        .fold(text.to_string(), |text, (re, replacement)| {
+            re.replace_all(&text, *replacement).into_owned()
+        });
+
+    for var in ["HOME", "USERPROFILE"] {
+        if let Ok(home) = std::env::var(var) {
+            if home.len() > 1 {
+                text = text.replace(&home, "~");
+            }
+        }
+    }
+    text
+}
+
+fn settings_snapshot(settings: &AppSettings) -> Result<Value> {
+    let mut value = serde_json::to_value(settings)?;
+    if let Some(map) = value.as_object_mut() {
+        for key in PRIVATE_SETTINGS {
+            if let Some(field) = map.get_mut(*key) {
+                if !field.is_null() {
+                    *field = Value::String("<redacted>".to_string());
+                }
+            }
+        }
+    }
+    Ok(value)
+}
+
+// ============================================
+// Bundles
+// ============================================
+
+/// Write a redacted bundle to the support directory. Returns its bytes too,
+/// for uploading.
+pub fn create_bundle(
+    db: &Database,
+    schema: &SchemaReport,
+    sync_status: &SyncStatus,
+    offline_profile: bool,
+) -> Result<(SupportBundle, Vec<u8>)> {
+    let now = Utc::now();
+    let created_at = now.to_rfc3339();
+
+    let manifest = Manifest {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        created_at: &created_at,
+        offline_profile,
+        schema,
+        sync_status,
+    };
+    let history: Vec<SyncHistoryEntry> = db.get_sync_history(SYNC_HISTORY_LIMIT)?;
+    let errors: Vec<SyncError> = db.get_sync_errors()?;
+    let settings = settings_snapshot(&db.get_settings()?)?;
+
+    let files = [
+        ("manifest.json", serde_json::to_string_pretty(&manifest)?),
+        ("logs.txt", captured_logs()),
+        ("sync-history.json", serde_json::to_string_pretty(&history)?),
+        ("sync-errors.json", serde_json::to_string_pretty(&errors)?),
+        ("settings.json", serde_json::to_string_pretty(&settings)?),
+    ];
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, contents) in files {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(redact(&contents).as_bytes())?;
+    }
+    let bytes = zip.finish().map_err(zip_error)?.into_inner();
+
+    let dir = db.support_dir();
+    std::fs::create_dir_all(&dir)?;
+    let file_name = format!("{}{}.zip", FILE_PREFIX, now.format("%Y%m%d-%H%M%S"));
+    let path = dir.join(&file_name);
+    std::fs::write(&path, &bytes)?;
+    prune_bundles(&dir)?;
+
+    let bundle = SupportBundle {
+        file_name,
+        path: path.to_string_lossy().to_string(),
+        created_at,
+        size_bytes: bytes.len() as u64,
+        share_url: None,
+        share_expires_at: None,
+    };
+    Ok((bundle, bytes))
+}
+
+fn zip_error(e: zip::result::ZipError) -> AppError {
+    AppError::InvalidOperation(format!("Failed to write support bundle: {}", e))
+}
+
+/// Bundle names sort by creation time, so keep the newest few
+fn prune_bundles(dir: &Path) -> Result<()> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(FILE_PREFIX) && name.ends_with(".zip"))
+        .collect();
+    names.sort();
+
+    let excess = names.len().saturating_sub(KEEP_BUNDLES);
+    for name in &names[..excess] {
+        std::fs::remove_file(dir.join(name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+// ============================================
+// BirchVault Desktop - Emergency Access
+// ============================================
+//
+// A grantor names a trusted contact who can read their vault in an
+// emergency. The flow, with each step enforced server-side:
+//
+//   invited -> accepted          the contact accepts the invitation
+//   accepted -> confirmed        the grantor escrows their vault key, wrapped
+//                                with the contact's public key
+//   confirmed -> recovery_initiated
+//                                the contact asks for access; the wait
+//                                period starts
+//   recovery_initiated -> recovery_approved
+//                                the grantor approves early
+//   recovery_* -> confirmed      the grantor denies (or later withdraws) access
+//
+// Row-level security releases the escrowed key and the grantor's items to
+// the contact only once access is approved or the wait period has passed
+// without a denial. Either side can end the arrangement by revoking it.
+
+use crate::crypto;
+use crate::error::{AppError, Result};
+use crate::keypair;
+use crate::sync::SyncEngine;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_WAIT_DAYS: u32 = 7;
+const MAX_WAIT_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyStatus {
+    Invited,
+    Accepted,
+    Confirmed,
+    RecoveryInitiated,
+    RecoveryApproved,
+}
+
+/// Which side of an arrangement the signed-in account is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EmergencyRole {
+    Grantor,
+    Grantee,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmergencyAccessRow {
+    id: String,
+    grantor_id: String,
+    grantor_email: String,
+    grantee_id: Option<String>,
+    grantee_email: String,
+    status: EmergencyStatus,
+    wait_days: u32,
+    recovery_initiated_at: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmergencyAccess {
+    pub id: String,
+    pub role: EmergencyRole,
+    pub grantor_email: String,
+    pub grantee_email: String,
+    pub status: EmergencyStatus,
+    pub wait_days: u32,
+    pub recovery_initiated_at: Option<String>,
+    /// When a pending request is granted automatically unless denied first
+    pub access_available_at: Option<String>,
+    /// The grantee can open the grantor's vault right now
+    pub access_granted: bool,
+    pub created_at: String,
+}
+
+impl EmergencyAccess {
+    fn from_row(row: EmergencyAccessRow, user_id: &str) -> Self {
+        let available_at = row
+            .recovery_initiated_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc) + Duration::days(row.wait_days as i64));
+        let access_granted = match row.status {
+            EmergencyStatus::RecoveryApproved => true,
+            EmergencyStatus::RecoveryInitiated => available_at.is_some_and(|at| at <= Utc::now()),
+            _ => false,
+        };
+
+        EmergencyAccess {
+            id: row.id,
+            role: if row.grantor_id == user_id {
+                EmergencyRole::Grantor
+            } else {
+                EmergencyRole::Grantee
+            },
+            grantor_email: row.grantor_email,
+            grantee_email: row.grantee_email,
+            status: row.status,
+            wait_days: row.wait_days,
+            recovery_initiated_at: row.recovery_initiated_at,
+            access_available_at: available_at.map(|at| at.to_rfc3339()),
+            access_granted,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// One of the grantor's items, decrypted for read-only viewing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmergencyVaultItem {
+    pub id: String,
+    pub item_type: String,
+    pub data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct EscrowedKeyRow {
+    encrypted_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GrantorItemRow {
+    id: String,
+    item_type: String,
+    encrypted_data: String,
+}
+
+const ROW_COLUMNS: &str = "id,grantor_id,grantor_email,grantee_id,grantee_email,status,\
+                           wait_days,recovery_initiated_at,created_at";
+
+/// Arrangements on both sides, including invitations not yet accepted
+pub async fn list(engine: &SyncEngine) -> Result<Vec<EmergencyAccess>> {
+    let session = engine.active_session().await?;
+    let rows: Vec<EmergencyAccessRow> = engine
+        .rest_get(&format!(
+            "vault_emergency_access?select={}&order=created_at.desc",
+            ROW_COLUMNS
+        ))
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| EmergencyAccess::from_row(row, &session.user_id))
+        .collect())
+}
+
+async fn get(engine: &SyncEngine, id: &str) -> Result<EmergencyAccessRow> {
+    let rows: Vec<EmergencyAccessRow> = engine
+        .rest_get(&format!(
+            "vault_emergency_access?id=eq.{}&select={}",
+            id, ROW_COLUMNS
+        ))
+        .await?;
+    rows.into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("Emergency access not found".to_string()))
+}
+
+pub fn validate_wait_days(wait_days: u32) -> Result<()> {
+    if !(1..=MAX_WAIT_DAYS).contains(&wait_days) {
+        return Err(AppError::InvalidOperation(format!(
+            "The wait period must be between 1 and {} days",
+            MAX_WAIT_DAYS
+        )));
+    }
+    Ok(())
+}
+
+/// Invite a trusted contact by email
+pub async fn invite(engine: &SyncEngine, email: &str, wait_days: u32) -> Result<()> {
+    validate_wait_days(wait_days)?;
+    let session = engine.active_session().await?;
+    let email = email.trim().to_lowercase();
+
+    if !email.contains('@') {
+        return Err(AppError::InvalidOperation(
+            "Enter the email address of a BirchVault account".to_string(),
+        ));
+    }
+    if email == session.email.to_lowercase() {
+        return Err(AppError::InvalidOperation(
+            "You can't be your own emergency contact".to_string(),
+        ));
+    }
+
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_emergency_access",
+            Some(&serde_json::json!({
+                "grantor_id": session.user_id,
+                "grantor_email": session.email.to_lowercase(),
+                "grantee_email": email,
+                "wait_days": wait_days,
+            })),
+            None,
+        )
+        .await
+}
+
+/// Accept an invitation addressed to this account. Sets up our key pair if
+/// needed, since the grantor wraps their key to it when confirming.
+pub async fn accept(engine: &SyncEngine, id: &str, vault_key: &[u8]) -> Result<()> {
+    keypair::ensure(engine, vault_key).await?;
+    engine
+        .rpc(
+            "emergency_access_accept",
+            serde_json::json!({ "access_id": id }),
+        )
+        .await
+}
+
+/// Escrow the vault key for a contact who has accepted
+pub async fn confirm(engine: &SyncEngine, id: &str, vault_key: &[u8]) -> Result<()> {
+    let access = get(engine, id).await?;
+    if access.status != EmergencyStatus::Accepted {
+        return Err(AppError::InvalidOperation(
+            "The contact has not accepted the invitation yet".to_string(),
+        ));
+    }
+    let grantee_id = access
+        .grantee_id
+        .ok_or_else(|| AppError::InvalidOperation("The contact has no account".to_string()))?;
+    let public_key = keypair::public_key_for(engine, &grantee_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::InvalidOperation(
+                "The contact has no key pair yet; ask them to accept the invitation again"
+                    .to_string(),
+            )
+        })?;
+
+    let encrypted_key = keypair::wrap(&public_key, vault_key)?;
+    engine
+        .rpc(
+            "emergency_access_confirm",
+            serde_json::json!({ "access_id": id, "encrypted_key": encrypted_key }),
+        )
+        .await
+}
+
+/// Ask for access as the contact; starts the wait period
+pub async fn request(engine: &SyncEngine, id: &str) -> Result<()> {
+    engine
+        .rpc(
+            "emergency_access_initiate",
+            serde_json::json!({ "access_id": id }),
+        )
+        .await
+}
+
+/// Grant a pending request without waiting out the period
+pub async fn approve(engine: &SyncEngine, id: &str) -> Result<()> {
+    engine
+        .rpc(
+            "emergency_access_approve",
+            serde_json::json!({ "access_id": id }),
+        )
+        .await
+}
+
+/// Deny a pending request, or withdraw access already granted
+pub async fn deny(engine: &SyncEngine, id: &str) -> Result<()> {
+    engine
+        .rpc(
+            "emergency_access_reject",
+            serde_json::json!({ "access_id": id }),
+        )
+        .await
+}
+
+/// End the arrangement from either side; the escrowed key goes with it
+pub async fn revoke(engine: &SyncEngine, id: &str) -> Result<()> {
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_emergency_access?id=eq.{}", id),
+            None,
+            None,
+        )
+        .await
+}
+
+/// The grantor's items, once access has been granted. Nothing is stored
+/// locally; the recovered key is dropped when this returns.
+pub async fn view_vault(
+    engine: &SyncEngine,
+    id: &str,
+    vault_key: &[u8],
+) -> Result<Vec<EmergencyVaultItem>> {
+    let session = engine.active_session().await?;
+    let row = get(engine, id).await?;
+    let grantor_id = row.grantor_id.clone();
+    let access = EmergencyAccess::from_row(row, &session.user_id);
+    if access.role != EmergencyRole::Grantee || !access.access_granted {
+        return Err(AppError::InvalidOperation(
+            "Emergency access has not been granted".to_string(),
+        ));
+    }
+
+    let escrowed: Vec<EscrowedKeyRow> = engine
+        .rest_get(&format!(
+            "vault_emergency_access_keys?access_id=eq.{}&select=encrypted_key",
+            id
+        ))
+        .await?;
+    let escrowed = escrowed
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("Emergency access key not found".to_string()))?;
+
+    let private_key = keypair::ensure(engine, vault_key).await?;
+    let grantor_key = keypair::unwrap(&private_key, &escrowed.encrypted_key)?;
+
+    let rows: Vec<GrantorItemRow> = engine
+        .rest_get(&format!(
+            "vault_items?user_id=eq.{}&deleted_at=is.null&select=id,item_type,encrypted_data",
+            grantor_id
+        ))
+        .await?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        match crypto::decrypt_from_json(&row.encrypted_data, &grantor_key) {
+            Ok(json) => items.push(EmergencyVaultItem {
+                id: row.id,
+                item_type: row.item_type,
+                data: serde_json::from_str(&json)?,
+            }),
+            Err(e) => log::warn!("Skipping emergency item {}: {}", row.id, e),
+        }
+    }
+    Ok(items)
+}
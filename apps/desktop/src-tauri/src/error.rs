@@ -2,45 +2,50 @@
 // BirchVault Desktop - Error Types
 // ============================================
 
+use crate::i18n;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum AppError {
-    #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
-
-    #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
-
-    #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
-
-    #[error("Keyring error: {0}")]
     Keyring(#[from] keyring::Error),
-
-    #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
-
-    #[error("Authentication failed: {0}")]
     Auth(String),
-
-    #[error("Sync error: {0}")]
     Sync(String),
-
-    #[error("Encryption error: {0}")]
     Encryption(String),
-
-    #[error("Not found: {0}")]
     NotFound(String),
-
-    #[error("Invalid operation: {0}")]
     InvalidOperation(String),
-
-    #[error("Vault is locked")]
+    /// Input refused, with the reason in the current locale
+    Validation(String),
     VaultLocked,
-
-    #[error("Network unavailable")]
     NetworkUnavailable,
+    /// The server refused the refresh token; only signing in again helps
+    SessionExpired,
+}
+
+// Messages are resolved through the i18n tables so they follow the locale setting
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (key, detail) = match self {
+            AppError::Database(e) => ("error.database", e.to_string()),
+            AppError::Serialization(e) => ("error.serialization", e.to_string()),
+            AppError::Http(e) => ("error.http", e.to_string()),
+            AppError::Keyring(e) => ("error.keyring", e.to_string()),
+            AppError::Io(e) => ("error.io", e.to_string()),
+            AppError::Auth(msg) => ("error.auth", msg.clone()),
+            AppError::Sync(msg) => ("error.sync", msg.clone()),
+            AppError::Encryption(msg) => ("error.encryption", msg.clone()),
+            AppError::NotFound(msg) => ("error.not_found", msg.clone()),
+            AppError::InvalidOperation(msg) => ("error.invalid_operation", msg.clone()),
+            AppError::Validation(msg) => ("error.validation", msg.clone()),
+            AppError::VaultLocked => ("error.vault_locked", String::new()),
+            AppError::NetworkUnavailable => ("error.network_unavailable", String::new()),
+            AppError::SessionExpired => ("error.session_expired", String::new()),
+        };
+        f.write_str(&i18n::t_with(key, &[("detail", &detail)]))
+    }
 }
 
 // Convert AppError to a serializable format for Tauri
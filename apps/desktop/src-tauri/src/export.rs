@@ -0,0 +1,281 @@
+// ============================================
+// BirchVault Desktop - Plaintext CSV Export
+// ============================================
+//
+// Logins and secure notes as a Bitwarden-format CSV, for moving to another
+// password manager. Everything in it is in the clear, so `export_csv` asks
+// for the master password again and warns before writing anything. The file
+// is written to the profile's data directory first and only moved to where
+// the user saves it; cancelling the save dialog overwrites it before it is
+// removed, as does finding one left behind by an export that never finished.
+// Bitwarden's CSV has no columns for cards, identities and the other types,
+// so those are left out and counted.
+
+use crate::crypto;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::i18n;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+const HEADERS: [&str; 11] = [
+    "folder",
+    "favorite",
+    "type",
+    "name",
+    "notes",
+    "fields",
+    "reprompt",
+    "login_uri",
+    "login_username",
+    "login_password",
+    "login_totp",
+];
+
+const DEFAULT_FILE_NAME: &str = "birchvault_export.csv";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportSummary {
+    pub path: String,
+    pub exported: usize,
+    /// Items of types the CSV has no columns for
+    pub skipped: usize,
+}
+
+/// A CSV written and waiting for the user to pick where it goes
+pub struct PendingExport {
+    pub path: PathBuf,
+    pub exported: usize,
+    pub skipped: usize,
+}
+
+/// Warn that the export won't be encrypted; whether the user went ahead
+pub async fn confirm(app: &tauri::AppHandle) -> bool {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .message(i18n::t("export.warning_body"))
+        .title(i18n::t("export.warning_title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t("export.confirm"),
+            i18n::t("export.cancel"),
+        ))
+        .show(move |confirmed| {
+            let _ = tx.send(confirmed);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Ask where to save the export; `None` if the dialog was cancelled
+pub async fn choose_destination(app: &tauri::AppHandle) -> Result<Option<PathBuf>> {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title(i18n::t("export.save_title"))
+        .add_filter(i18n::t("export.csv_filter"), &["csv"])
+        .set_file_name(DEFAULT_FILE_NAME)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    match rx.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| AppError::InvalidOperation(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Write the vault's logins and notes to a new file in the export directory.
+/// Items that fail to decrypt stop the export rather than go missing from it.
+pub fn write_csv(db: &Database, key: &[u8]) -> Result<PendingExport> {
+    let dir = db.export_dir();
+    discard_leftovers(&dir);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.csv", Uuid::new_v4()));
+
+    let folders: HashMap<String, String> = db
+        .get_all_folders()?
+        .into_iter()
+        .map(|f| (f.id, f.name))
+        .collect();
+
+    let result = (|| {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let mut writer = csv::Writer::from_writer(file);
+        writer.write_record(HEADERS).map_err(csv_error)?;
+
+        let (mut exported, mut skipped) = (0, 0);
+        for item in db.get_all_vault_items()? {
+            let json = crypto::decrypt_from_json(&item.encrypted_data, key)?;
+            let data: Value = serde_json::from_str(&json)?;
+            let folder = item
+                .folder_id
+                .as_ref()
+                .and_then(|id| folders.get(id))
+                .map(String::as_str)
+                .unwrap_or_default();
+
+            let favorite = data
+                .get("favorite")
+                .and_then(Value::as_bool)
+                .unwrap_or(item.is_favorite);
+            match row(&item.item_type, favorite, folder, &data) {
+                Some(row) => {
+                    writer.write_record(&row).map_err(csv_error)?;
+                    exported += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+
+        let file = writer
+            .into_inner()
+            .map_err(|e| AppError::Io(e.into_error()))?;
+        file.sync_all()?;
+        Ok((exported, skipped))
+    })();
+
+    match result {
+        Ok((exported, skipped)) => Ok(PendingExport {
+            path,
+            exported,
+            skipped,
+        }),
+        Err(e) => {
+            discard(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Move a written export to where the user saved it
+pub fn save(pending: &PendingExport, destination: &Path) -> Result<()> {
+    if fs::rename(&pending.path, destination).is_ok() {
+        return Ok(());
+    }
+
+    // Another volume: copy, then shred the original
+    let copied = fs::copy(&pending.path, destination);
+    discard(&pending.path);
+    copied?;
+    Ok(())
+}
+
+/// Overwrite an export with zeros before removing it, so its contents don't
+/// linger in free space. Best effort: on SSDs and copy-on-write file systems
+/// the old blocks may survive anyway.
+pub fn discard(path: &Path) {
+    let overwritten = (|| -> std::io::Result<()> {
+        let length = fs::metadata(path)?.len();
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        let zeros = [0u8; 8192];
+        let mut remaining = length;
+        while remaining > 0 {
+            let chunk = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()
+    })();
+    if let Err(e) = overwritten {
+        log::warn!("Failed to overwrite export {}: {}", path.display(), e);
+    }
+    if let Err(e) = fs::remove_file(path) {
+        log::warn!("Failed to remove export {}: {}", path.display(), e);
+    }
+}
+
+/// Exports from a run that crashed or was killed before the save
+fn discard_leftovers(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        discard(&entry.path());
+    }
+}
+
+fn csv_error(e: csv::Error) -> AppError {
+    AppError::InvalidOperation(format!("Failed to write CSV: {}", e))
+}
+
+/// The CSV row for an item, or `None` for a type Bitwarden's CSV can't hold
+fn row(item_type: &str, favorite: bool, folder: &str, data: &Value) -> Option<Vec<String>> {
+    let text = |pointer: &str| {
+        data.pointer(pointer)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let csv_type = match item_type {
+        "login" => "login",
+        "securenote" => "note",
+        _ => return None,
+    };
+
+    // "name: value" per line, as Bitwarden writes them
+    let fields = data
+        .get("fields")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|field| {
+            let name = field
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let value = match field.get("value") {
+                Some(Value::String(value)) => value.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let uris = data
+        .pointer("/login/uris")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|uri| uri.get("uri").and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let is_login = csv_type == "login";
+    let login = |pointer: &str| {
+        if is_login {
+            text(pointer)
+        } else {
+            String::new()
+        }
+    };
+    Some(vec![
+        folder.to_string(),
+        if favorite { "1" } else { "" }.to_string(),
+        csv_type.to_string(),
+        text("/name"),
+        text("/notes"),
+        fields,
+        "0".to_string(),
+        if is_login { uris } else { String::new() },
+        login("/login/username"),
+        login("/login/password"),
+        login("/login/totp"),
+    ])
+}
@@ -0,0 +1,192 @@
+// ============================================
+// BirchVault Desktop - Custom Fields
+// ============================================
+//
+// Typed custom fields in an item's decrypted `fields` array:
+//
+//   { "name": "PIN", "type": "hidden", "value": "1234" }
+//   { "name": "Renewed", "type": "date", "value": "2025-03-01" }
+//   { "name": "MFA enabled", "type": "boolean", "value": true }
+//
+// Older items (and other managers' exports) use numeric types (0 text,
+// 1 hidden, 2 boolean, 3 linked), omit the type, or store booleans as
+// strings. `normalise` turns those into the typed form.
+
+use crate::crypto;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `app_meta` key set once legacy fields have been rewritten in the typed form
+const META_FIELDS_MIGRATED: &str = "custom_fields_migrated";
+
+const MAX_FIELDS: usize = 100;
+const MAX_NAME_LENGTH: usize = 200;
+const MAX_VALUE_LENGTH: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum FieldValue {
+    Text(String),
+    Hidden(String),
+    Boolean(bool),
+    Date(NaiveDate),
+    Url(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    #[serde(flatten)]
+    pub value: FieldValue,
+}
+
+impl CustomField {
+    fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            AppError::InvalidOperation(format!("Custom field \"{}\": {}", self.name, reason))
+        };
+
+        // Names may be empty: imports and older items have unnamed fields
+        if self.name.chars().count() > MAX_NAME_LENGTH {
+            return Err(invalid("name is too long"));
+        }
+
+        match &self.value {
+            FieldValue::Text(text) | FieldValue::Hidden(text) => {
+                if text.chars().count() > MAX_VALUE_LENGTH {
+                    return Err(invalid("value is too long"));
+                }
+            }
+            FieldValue::Url(url) => {
+                if !url.is_empty() && reqwest::Url::parse(url).is_err() {
+                    return Err(invalid("not a valid URL"));
+                }
+            }
+            FieldValue::Boolean(_) | FieldValue::Date(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Check an item's `fields` array against the typed schema. Fields carried
+/// over unchanged from the `previous` version of the item aren't checked, so
+/// imported or legacy ones that don't fit the schema never block an edit.
+pub fn validate(data: &Value, previous: Option<&Value>) -> Result<()> {
+    let Some(fields) = data.get("fields") else {
+        return Ok(());
+    };
+    let fields = fields
+        .as_array()
+        .ok_or_else(|| AppError::InvalidOperation("Custom fields must be a list".to_string()))?;
+    if fields.len() > MAX_FIELDS {
+        return Err(AppError::InvalidOperation(format!(
+            "Items can have at most {} custom fields",
+            MAX_FIELDS
+        )));
+    }
+
+    let unchanged = previous
+        .and_then(|previous| previous.get("fields"))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for field in fields {
+        if unchanged.contains(field) {
+            continue;
+        }
+        let field: CustomField = serde_json::from_value(field.clone())
+            .map_err(|e| AppError::InvalidOperation(format!("Invalid custom field: {}", e)))?;
+        field.validate()?;
+    }
+    Ok(())
+}
+
+/// Read a field in any legacy shape, falling back to text when the value
+/// doesn't fit its declared type
+pub fn normalise(field: &Value) -> CustomField {
+    if let Ok(typed) = serde_json::from_value::<CustomField>(field.clone()) {
+        return typed;
+    }
+
+    let name = field
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let text = match field.get("value") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    let kind = match field.get("type") {
+        Some(Value::Number(n)) => match n.as_u64() {
+            Some(1) => "hidden",
+            Some(2) => "boolean",
+            _ => "text",
+        },
+        Some(Value::String(s)) => s.as_str(),
+        _ => "text",
+    };
+
+    let value = match kind {
+        "hidden" => FieldValue::Hidden(text),
+        "boolean" => match text.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => FieldValue::Boolean(true),
+            "false" | "0" | "no" | "" => FieldValue::Boolean(false),
+            _ => FieldValue::Text(text),
+        },
+        "date" => match NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d") {
+            Ok(date) => FieldValue::Date(date),
+            Err(_) => FieldValue::Text(text),
+        },
+        "url" => FieldValue::Url(text),
+        _ => FieldValue::Text(text),
+    };
+    CustomField { name, value }
+}
+
+/// Rewrite legacy fields in place; true if anything changed
+pub fn migrate(data: &mut Value) -> bool {
+    let Some(fields) = data.get_mut("fields").and_then(Value::as_array_mut) else {
+        return false;
+    };
+
+    let mut changed = false;
+    for field in fields.iter_mut() {
+        let typed = serde_json::to_value(normalise(field)).unwrap_or(Value::Null);
+        if *field != typed {
+            *field = typed;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// One-off rewrite of every item holding legacy fields, re-encrypted and
+/// queued for sync so other devices get the typed form too. Returns how many
+/// items changed.
+pub fn migrate_vault(db: &Database, key: &[u8]) -> Result<usize> {
+    if db.get_meta(META_FIELDS_MIGRATED)?.is_some() {
+        return Ok(0);
+    }
+
+    let mut migrated = 0;
+    let items = db.get_all_vault_items()?.into_iter().chain(db.get_trashed_items()?);
+    for mut item in items {
+        let Ok(json) = crypto::decrypt_from_json(&item.encrypted_data, key) else {
+            continue;
+        };
+        let mut data: Value = serde_json::from_str(&json)?;
+        if migrate(&mut data) {
+            item.encrypted_data = crypto::encrypt_to_json(&data.to_string(), key)?;
+            db.update_vault_item(&item)?;
+            migrated += 1;
+        }
+    }
+
+    db.set_meta(META_FIELDS_MIGRATED, &chrono::Utc::now().to_rfc3339())?;
+    Ok(migrated)
+}
@@ -0,0 +1,580 @@
+// ============================================
+// BirchVault Desktop - Folder and WebDAV Sync
+// ============================================
+//
+// An offline profile can sync through a folder kept in step by another tool
+// (Dropbox, OneDrive, a network share) or through a WebDAV server, instead of
+// a Supabase account. Either is only somewhere to keep files, behind
+// `SyncBackend`; what goes in them is the same encrypted records Supabase
+// would hold, one JSON file each:
+//
+//   birchvault/vault_items/<id>.json
+//   birchvault/folders/<id>.json
+//   birchvault/tombstones/<id>.json
+//
+// A file per record keeps devices' writes apart, so the sync tool never has
+// a whole vault to conflict over. Changes go through the usual sync queue. An
+// item file is only overwritten while it is still the version our change was
+// made to; otherwise the stored version is pulled in through the usual
+// conflict handling and ours is kept as a conflicted copy. A pull reads only
+// the files whose version (etag, or size and modification time) has changed
+// since the last one. Files can't be compared-and-swapped, so two devices
+// writing the same item in the same moment still leave the last write.
+//
+// Every device syncing the same files has to unlock the same vault key.
+
+use crate::db::{Database, Folder, SyncQueueItem, Tombstone, VaultItem};
+use crate::error::{AppError, Result};
+use crate::webdav::WebDavBackend;
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+const META_SYNC_BACKEND: &str = "sync_backend";
+/// Version of each file as last pulled, by path
+const META_FILE_VERSIONS: &str = "file_sync_versions";
+
+const ROOT_DIR: &str = "birchvault";
+const ITEMS_DIR: &str = "birchvault/vault_items";
+const FOLDERS_DIR: &str = "birchvault/folders";
+const TOMBSTONES_DIR: &str = "birchvault/tombstones";
+
+/// Written and removed again by the connection test
+const PROBE_FILE: &str = "birchvault/.probe";
+
+/// Where an offline profile syncs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackendConfig {
+    Folder {
+        path: String,
+    },
+    #[serde(rename = "webdav")]
+    WebDav {
+        url: String,
+        username: String,
+        /// Never sent back to the webview; left empty, the saved one is kept
+        #[serde(default)]
+        password: String,
+    },
+}
+
+impl BackendConfig {
+    /// For the settings screen
+    pub fn redacted(mut self) -> Self {
+        if let BackendConfig::WebDav { password, .. } = &mut self {
+            password.clear();
+        }
+        self
+    }
+}
+
+/// A file in a backend directory, with a tag that changes whenever it is rewritten
+pub struct RemoteFile {
+    pub name: String,
+    pub version: String,
+}
+
+/// Somewhere to keep the vault's record files. Paths are relative to the
+/// configured root and use `/`.
+pub trait SyncBackend {
+    /// Create `dir` and any parents missing
+    async fn create_dir(&self, dir: &str) -> Result<()>;
+
+    /// The files in `dir`; none if it doesn't exist yet
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteFile>>;
+
+    /// A file's contents, or `None` if it isn't there
+    async fn read(&self, path: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Replace a file's contents in one go, so no one reads it half-written
+    async fn write(&self, path: &str, contents: &[u8]) -> Result<()>;
+
+    /// Remove a file; one already gone is fine
+    async fn remove(&self, path: &str) -> Result<()>;
+}
+
+// ============================================
+// Records
+// ============================================
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemRecord {
+    id: String,
+    encrypted_data: String,
+    #[serde(rename = "type")]
+    item_type: String,
+    folder_id: Option<String>,
+    deleted_at: Option<String>,
+    updated_at: String,
+}
+
+impl ItemRecord {
+    fn into_local(self, synced_at: &str) -> VaultItem {
+        VaultItem {
+            id: self.id,
+            encrypted_data: self.encrypted_data,
+            item_type: self.item_type,
+            folder_id: self.folder_id,
+            is_favorite: false, // Favorite flag is stored in encrypted_data
+            deleted_at: self.deleted_at,
+            synced_at: Some(synced_at.to_string()),
+            local_updated_at: self.updated_at.clone(),
+            server_updated_at: Some(self.updated_at),
+            sort_index: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FolderRecord {
+    id: String,
+    name: String,
+    #[serde(default)]
+    retention_days: Option<i64>,
+    updated_at: String,
+}
+
+/// Table names as Supabase's `vault_tombstones` has them, for `apply_tombstones`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TombstoneRecord {
+    table_name: String,
+    record_id: String,
+    deleted_at: String,
+}
+
+fn record_path(dir: &str, id: &str) -> String {
+    format!("{}/{}.json", dir, id)
+}
+
+/// A record file, or `None` if it's missing or isn't one
+async fn read_record<T: DeserializeOwned>(
+    backend: &impl SyncBackend,
+    path: &str,
+) -> Result<Option<T>> {
+    let Some(contents) = backend.read(path).await? else {
+        return Ok(None);
+    };
+    // One bad file (a sync tool's half-copy, say) mustn't hold up the rest
+    match serde_json::from_slice(&contents) {
+        Ok(record) => Ok(Some(record)),
+        Err(e) => {
+            log::warn!("Skipping unreadable sync file {}: {}", path, e);
+            Ok(None)
+        }
+    }
+}
+
+async fn write_record<T: Serialize>(
+    backend: &impl SyncBackend,
+    path: &str,
+    record: &T,
+) -> Result<()> {
+    backend.write(path, &serde_json::to_vec(record)?).await
+}
+
+// ============================================
+// Configuration
+// ============================================
+
+/// The profile's folder or WebDAV sync, if set up
+pub fn configured(db: &Database) -> Result<Option<BackendConfig>> {
+    db.get_meta(META_SYNC_BACKEND)?
+        .map(|json| serde_json::from_str(&json).map_err(AppError::from))
+        .transpose()
+}
+
+/// Switch to `config` once it passes the connection test, or stop syncing
+/// with `None`. Everything local is queued for upload to the new location;
+/// what's already there is merged as any pull would be.
+pub async fn set(
+    db: &Database,
+    client: &reqwest::Client,
+    config: Option<BackendConfig>,
+) -> Result<()> {
+    let Some(mut config) = config else {
+        db.delete_meta(META_SYNC_BACKEND)?;
+        return db.delete_meta(META_FILE_VERSIONS);
+    };
+
+    if let (
+        BackendConfig::WebDav { url, password, .. },
+        Some(BackendConfig::WebDav {
+            url: saved_url,
+            password: saved_password,
+            ..
+        }),
+    ) = (&mut config, configured(db)?)
+    {
+        if password.is_empty() && *url == saved_url {
+            *password = saved_password;
+        }
+    }
+    test(&config, client).await?;
+
+    db.set_meta(META_SYNC_BACKEND, &serde_json::to_string(&config)?)?;
+    db.delete_meta(META_FILE_VERSIONS)?;
+    db.queue_all_for_upload()?;
+    Ok(())
+}
+
+/// Check the location can be written to, read back and cleaned up
+pub async fn test(config: &BackendConfig, client: &reqwest::Client) -> Result<()> {
+    match config {
+        BackendConfig::Folder { path } => probe(&FolderBackend::open(path)?).await,
+        BackendConfig::WebDav {
+            url,
+            username,
+            password,
+        } => {
+            probe(&WebDavBackend::open(
+                client.clone(),
+                url,
+                username,
+                password,
+            )?)
+            .await
+        }
+    }
+}
+
+async fn probe(backend: &impl SyncBackend) -> Result<()> {
+    let contents = Utc::now().to_rfc3339().into_bytes();
+    backend.create_dir(ROOT_DIR).await?;
+    backend.write(PROBE_FILE, &contents).await?;
+    let read = backend.read(PROBE_FILE).await?;
+    backend.remove(PROBE_FILE).await?;
+
+    if read.as_deref() != Some(&contents[..]) {
+        return Err(AppError::Sync(
+            "The sync location didn't return what was written to it".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ============================================
+// Sync
+// ============================================
+
+/// Push the queue and pull what changed, returning the number of records
+/// pushed and pulled. `check_aborted` is asked between records.
+pub async fn sync(
+    config: &BackendConfig,
+    client: &reqwest::Client,
+    db: &Database,
+    check_aborted: impl Fn() -> Result<()>,
+) -> Result<(usize, usize)> {
+    match config {
+        BackendConfig::Folder { path } => run(&FolderBackend::open(path)?, db, check_aborted).await,
+        BackendConfig::WebDav {
+            url,
+            username,
+            password,
+        } => {
+            let backend = WebDavBackend::open(client.clone(), url, username, password)?;
+            run(&backend, db, check_aborted).await
+        }
+    }
+}
+
+async fn run(
+    backend: &impl SyncBackend,
+    db: &Database,
+    check_aborted: impl Fn() -> Result<()>,
+) -> Result<(usize, usize)> {
+    for dir in [ITEMS_DIR, FOLDERS_DIR, TOMBSTONES_DIR] {
+        backend.create_dir(dir).await?;
+    }
+
+    let mut pushed = 0;
+    for item in db.get_pending_sync_items()? {
+        check_aborted()?;
+        match push(backend, db, &item).await {
+            Ok(Pushed::Stored) => {
+                db.remove_from_sync_queue(item.id)?;
+                db.mark_item_synced(&item.table_name, &item.record_id)?;
+                pushed += 1;
+            }
+            Ok(Pushed::Conflict) => {
+                log::info!(
+                    "Vault item {} changed in the sync location; kept local changes as a conflicted copy",
+                    item.record_id
+                );
+            }
+            Err(e) => {
+                log::warn!("Failed to sync item {}: {}", item.record_id, e);
+                // Continue with other items, don't fail the whole sync
+                db.record_sync_failure(item.id, &e.to_string(), None)?;
+            }
+        }
+    }
+
+    check_aborted()?;
+    let pulled = pull(backend, db, &check_aborted).await?;
+    Ok((pushed, pulled))
+}
+
+enum Pushed {
+    Stored,
+    /// The stored version moved on; ours is now a conflicted copy
+    Conflict,
+}
+
+async fn push(backend: &impl SyncBackend, db: &Database, item: &SyncQueueItem) -> Result<Pushed> {
+    let id = &item.record_id;
+    match (item.operation.as_str(), item.table_name.as_str()) {
+        ("create" | "update", "vault_items") => {
+            if let Some(item) = db.get_vault_item(id)? {
+                return push_vault_item(backend, db, &item).await;
+            }
+        }
+        ("create" | "update", "folders") => {
+            let folders = db.get_all_folders()?;
+            if let Some(folder) = folders.iter().find(|f| f.id == *id) {
+                let record = FolderRecord {
+                    id: folder.id.clone(),
+                    name: folder.name.clone(),
+                    retention_days: folder.retention_days,
+                    updated_at: Utc::now().to_rfc3339(),
+                };
+                write_record(backend, &record_path(FOLDERS_DIR, id), &record).await?;
+                backend.remove(&record_path(TOMBSTONES_DIR, id)).await?;
+            }
+        }
+        ("delete", table @ ("vault_items" | "folders")) => {
+            let (dir, table_name) = match table {
+                "vault_items" => (ITEMS_DIR, "vault_items"),
+                _ => (FOLDERS_DIR, "vault_folders"),
+            };
+            let tombstone = TombstoneRecord {
+                table_name: table_name.to_string(),
+                record_id: id.clone(),
+                deleted_at: Utc::now().to_rfc3339(),
+            };
+            write_record(backend, &record_path(TOMBSTONES_DIR, id), &tombstone).await?;
+            backend.remove(&record_path(dir, id)).await?;
+        }
+        // Attachments and organizations need a Supabase account
+        _ => {}
+    }
+    Ok(Pushed::Stored)
+}
+
+async fn push_vault_item(
+    backend: &impl SyncBackend,
+    db: &Database,
+    item: &VaultItem,
+) -> Result<Pushed> {
+    let path = record_path(ITEMS_DIR, &item.id);
+
+    // Also taken when we have no version at all: a device joining a location
+    // merges with what's there rather than overwriting it
+    if let Some(stored) = read_record::<ItemRecord>(backend, &path).await? {
+        if item.server_updated_at.as_deref() != Some(stored.updated_at.as_str()) {
+            let now = Utc::now().to_rfc3339();
+            let conflicts = db.bulk_upsert_vault_items(&[stored.into_local(&now)])?;
+            if !conflicts.is_empty() {
+                return Ok(Pushed::Conflict);
+            }
+            // Same contents either way; nothing left to push
+            return Ok(Pushed::Stored);
+        }
+    }
+
+    let record = ItemRecord {
+        id: item.id.clone(),
+        encrypted_data: item.encrypted_data.clone(),
+        item_type: item.item_type.clone(),
+        folder_id: item.folder_id.clone(),
+        deleted_at: item.deleted_at.clone(),
+        updated_at: Utc::now().to_rfc3339(),
+    };
+    write_record(backend, &path, &record).await?;
+    backend
+        .remove(&record_path(TOMBSTONES_DIR, &item.id))
+        .await?;
+    db.set_vault_item_server_version(&item.id, &record.updated_at)?;
+    Ok(Pushed::Stored)
+}
+
+/// Read the files changed since the last pull; folders first, so pulled items
+/// never point at a folder we don't have yet, and deletions last
+async fn pull(
+    backend: &impl SyncBackend,
+    db: &Database,
+    check_aborted: &impl Fn() -> Result<()>,
+) -> Result<usize> {
+    let known: HashMap<String, String> = match db.get_meta(META_FILE_VERSIONS)? {
+        Some(json) => serde_json::from_str(&json).map_err(AppError::from)?,
+        None => HashMap::new(),
+    };
+    let mut seen = HashMap::new();
+    let mut pulled = 0;
+    let mut conflicts = 0;
+    let mut removed = 0;
+
+    for dir in [FOLDERS_DIR, ITEMS_DIR, TOMBSTONES_DIR] {
+        for file in backend.list(dir).await? {
+            let path = format!("{}/{}", dir, file.name);
+            if known.get(&path) == Some(&file.version) {
+                seen.insert(path, file.version);
+                continue;
+            }
+            check_aborted()?;
+
+            let now = Utc::now().to_rfc3339();
+            let stored = match dir {
+                FOLDERS_DIR => match read_record::<FolderRecord>(backend, &path).await? {
+                    Some(f) => {
+                        db.bulk_upsert_folders(&[Folder {
+                            id: f.id,
+                            name: f.name,
+                            synced_at: Some(now),
+                            local_updated_at: f.updated_at,
+                            retention_days: f.retention_days,
+                        }])?;
+                        true
+                    }
+                    None => false,
+                },
+                ITEMS_DIR => match read_record::<ItemRecord>(backend, &path).await? {
+                    Some(item) => {
+                        conflicts += db.bulk_upsert_vault_items(&[item.into_local(&now)])?.len();
+                        true
+                    }
+                    None => false,
+                },
+                _ => match read_record::<TombstoneRecord>(backend, &path).await? {
+                    Some(t) => {
+                        removed += db.apply_tombstones(&[Tombstone {
+                            table_name: t.table_name,
+                            record_id: t.record_id,
+                        }])?;
+                        true
+                    }
+                    None => false,
+                },
+            };
+
+            // Gone since it was listed, or unreadable: nothing to remember
+            if stored {
+                pulled += 1;
+                seen.insert(path, file.version);
+            }
+        }
+    }
+
+    // Only what's there now; removed files drop out
+    db.set_meta(META_FILE_VERSIONS, &serde_json::to_string(&seen)?)?;
+    if conflicts > 0 {
+        log::info!(
+            "{} vault items changed on both sides; kept local versions as conflicted copies",
+            conflicts
+        );
+    }
+    if removed > 0 {
+        log::info!("Removed {} records deleted on other devices", removed);
+    }
+    Ok(pulled)
+}
+
+// ============================================
+// Folder Backend
+// ============================================
+
+/// A local folder, usually one a sync tool mirrors elsewhere
+pub struct FolderBackend {
+    root: PathBuf,
+}
+
+impl FolderBackend {
+    pub fn open(path: &str) -> Result<Self> {
+        let root = PathBuf::from(path.trim());
+        if !root.is_absolute() || !root.is_dir() {
+            return Err(AppError::InvalidOperation(format!(
+                "{} isn't a folder",
+                root.display()
+            )));
+        }
+        Ok(Self { root })
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        path.split('/')
+            .fold(self.root.clone(), |p, part| p.join(part))
+    }
+}
+
+impl SyncBackend for FolderBackend {
+    async fn create_dir(&self, dir: &str) -> Result<()> {
+        Ok(tokio::fs::create_dir_all(self.path(dir)).await?)
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteFile>> {
+        let mut entries = match tokio::fs::read_dir(self.path(dir)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Skips write_'s temporary files, and whatever else a sync tool leaves
+            if name.starts_with('.') || !name.ends_with(".json") {
+                continue;
+            }
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            files.push(RemoteFile {
+                name,
+                version: format!("{}-{}", metadata.len(), modified),
+            });
+        }
+        Ok(files)
+    }
+
+    async fn read(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(path)).await {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn write(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let target = self.path(path);
+        let file_name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let temp = target.with_file_name(format!(".{}.tmp", file_name));
+
+        tokio::fs::write(&temp, contents).await?;
+        tokio::fs::rename(&temp, &target).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, path: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(path)).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,566 @@
+// ============================================
+// BirchVault Desktop - Localisation
+// ============================================
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+// ============================================
+// Locales
+// ============================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    EnGb,
+    EnUs,
+    De,
+    Fr,
+    Es,
+}
+
+pub const DEFAULT_LOCALE: &str = "en-GB";
+
+const SUPPORTED_LOCALES: [Locale; 5] = [
+    Locale::EnGb,
+    Locale::EnUs,
+    Locale::De,
+    Locale::Fr,
+    Locale::Es,
+];
+
+impl Locale {
+    /// Parse a BCP 47 style tag ("de-DE", "en_us", "fr"), falling back to en-GB
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.trim().replace('_', "-").to_lowercase();
+        match tag.as_str() {
+            "en-us" => Locale::EnUs,
+            t if t.starts_with("de") => Locale::De,
+            t if t.starts_with("fr") => Locale::Fr,
+            t if t.starts_with("es") => Locale::Es,
+            _ => Locale::EnGb,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::EnGb => "en-GB",
+            Locale::EnUs => "en-US",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::Es => "es",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Locale::EnGb => "English (UK)",
+            Locale::EnUs => "English (US)",
+            Locale::De => "Deutsch",
+            Locale::Fr => "Français",
+            Locale::Es => "Español",
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::EnGb => EN_GB,
+            Locale::EnUs => EN_US,
+            Locale::De => DE,
+            Locale::Fr => FR,
+            Locale::Es => ES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    pub code: String,
+    pub name: String,
+}
+
+pub fn supported_locales() -> Vec<LocaleInfo> {
+    SUPPORTED_LOCALES
+        .iter()
+        .map(|l| LocaleInfo {
+            code: l.tag().to_string(),
+            name: l.display_name().to_string(),
+        })
+        .collect()
+}
+
+// ============================================
+// Current Locale
+// ============================================
+
+static CURRENT_LOCALE: RwLock<Locale> = RwLock::new(Locale::EnGb);
+
+/// Set the locale used for backend-generated strings (from `AppSettings.locale`)
+pub fn set_locale(tag: &str) {
+    if let Ok(mut current) = CURRENT_LOCALE.write() {
+        *current = Locale::from_tag(tag);
+    }
+}
+
+pub fn current_locale() -> Locale {
+    CURRENT_LOCALE
+        .read()
+        .map(|l| *l)
+        .unwrap_or(Locale::EnGb)
+}
+
+// ============================================
+// Translation
+// ============================================
+
+/// Look up a string for the given locale, falling back to en-GB and then the key itself
+pub fn translate(locale: Locale, key: &str) -> &str {
+    let lookup = |table: &'static [(&'static str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+
+    lookup(locale.table())
+        .or_else(|| lookup(EN_GB))
+        .unwrap_or(key)
+}
+
+/// Translate a key using the current locale
+pub fn t(key: &str) -> String {
+    translate(current_locale(), key).to_string()
+}
+
+/// Translate a key using the current locale, substituting `{name}` placeholders
+pub fn t_with(key: &str, args: &[(&str, &str)]) -> String {
+    let mut text = t(key);
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+// ============================================
+// Locale-aware Formatting
+// ============================================
+
+/// Format a timestamp's date in the user's local time zone
+pub fn format_date(value: &DateTime<Utc>) -> String {
+    let pattern = match current_locale() {
+        Locale::EnUs => "%m/%d/%Y",
+        Locale::De => "%d.%m.%Y",
+        Locale::EnGb | Locale::Fr | Locale::Es => "%d/%m/%Y",
+    };
+    value.with_timezone(&Local).format(pattern).to_string()
+}
+
+/// Format an integer with the locale's digit grouping (1,234 / 1.234 / 1 234)
+pub fn format_number(value: i64) -> String {
+    let separator = match current_locale() {
+        Locale::EnGb | Locale::EnUs => ',',
+        Locale::De | Locale::Es => '.',
+        Locale::Fr => '\u{202F}',
+    };
+
+    let digits = value.unsigned_abs().to_string();
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let grouped = groups.join(&separator.to_string());
+
+    if value < 0 {
+        format!("-{}", grouped)
+    } else {
+        grouped
+    }
+}
+
+// ============================================
+// Translation Tables
+// ============================================
+
+const EN_GB: &[(&str, &str)] = &[
+    ("error.database", "Database error: {detail}"),
+    ("error.serialization", "Serialisation error: {detail}"),
+    ("error.http", "HTTP error: {detail}"),
+    ("error.keyring", "Keyring error: {detail}"),
+    ("error.io", "IO error: {detail}"),
+    ("error.auth", "Authentication failed: {detail}"),
+    ("error.sync", "Sync error: {detail}"),
+    ("error.encryption", "Encryption error: {detail}"),
+    ("error.not_found", "Not found: {detail}"),
+    ("error.invalid_operation", "Invalid operation: {detail}"),
+    ("error.validation", "{detail}"),
+    ("error.vault_locked", "Vault is locked"),
+    ("error.network_unavailable", "Network unavailable"),
+    ("error.session_expired", "Your session has expired; sign in again"),
+    ("auth.no_session", "No session found"),
+    ("auth.not_logged_in", "Not logged in"),
+    ("auth.invalid_master_password", "Invalid master password"),
+    ("auth.refresh_failed", "Failed to refresh token"),
+    ("auth.invalid_token_expiry", "Invalid token expiry"),
+    ("auth.failed", "Authentication failed"),
+    ("tray.status_locked", "BirchVault is locked"),
+    ("tray.status_unlocked", "BirchVault is unlocked"),
+    ("tray.signed_out", "Not signed in"),
+    ("tray.show", "Show BirchVault"),
+    ("tray.copy_last_used", "Copy last used item"),
+    ("tray.sync_now", "Sync now"),
+    ("tray.lock", "Lock vault"),
+    ("tray.quit", "Quit BirchVault"),
+    ("delete.unsynced_requires_force", "This item hasn't been synced yet, so deleting it can't be undone on other devices. Confirm to delete it anyway."),
+    ("strength.sequence", "Avoid sequences like \"abcd\" or \"1234\""),
+    ("strength.repeated", "Avoid repeated characters"),
+    ("strength.common", "Avoid common passwords and words"),
+    ("strength.personal_info", "Don't include your name or email address"),
+    ("strength.too_short", "Use at least 12 characters"),
+    ("strength.add_variety", "Mix upper and lower case letters, numbers and symbols"),
+    ("strength.master_too_weak", "This master password is too weak"),
+    ("strength.master_reused", "Your master password shouldn't be used for anything else"),
+    ("notify.sync_failed_title", "Sync failed"),
+    ("notify.sync_failed_body", "Your vault couldn't be synced: {error}"),
+    ("notify.expiry_title", "Items expiring soon"),
+    (
+        "notify.expiry_body",
+        "{count} item(s) will be moved to the trash by their folder's retention policy, the first on {date}",
+    ),
+    ("notify.clipboard_cleared_title", "Clipboard cleared"),
+    ("notify.clipboard_cleared_body", "The copied secret was removed from the clipboard"),
+    ("strength.time_instant", "less than a second"),
+    ("strength.time_seconds", "{count} second(s)"),
+    ("strength.time_minutes", "{count} minute(s)"),
+    ("strength.time_hours", "{count} hour(s)"),
+    ("strength.time_days", "{count} day(s)"),
+    ("strength.time_months", "{count} month(s)"),
+    ("strength.time_years", "{count} year(s)"),
+    ("strength.time_centuries", "centuries"),
+    ("notify.breach_title", "Breached passwords found"),
+    ("notify.breach_body", "{count} item(s) use a password that has appeared in a data breach"),
+    ("auth.offline_profile_no_network", "Not available in an offline profile"),
+    ("auth.profile_exists", "This device already has an account or offline profile"),
+    (
+        "auth.offline_verifier_missing",
+        "The offline profile's unlock key is missing from the system keyring",
+    ),
+    ("auth.pin_not_set", "PIN unlock is not set up"),
+    ("auth.pin_invalid", "Incorrect PIN, {remaining} attempt(s) left"),
+    (
+        "auth.pin_wiped",
+        "Too many incorrect PINs. PIN unlock has been turned off; unlock with your master password.",
+    ),
+    (
+        "sync.schema_server_outdated",
+        "The server's database schema (version {server}) is older than this app needs (version {expected}). Ask your administrator to run the latest migrations.",
+    ),
+    (
+        "sync.schema_app_outdated",
+        "The server's database schema (version {server}) is newer than this app supports. Please update BirchVault.",
+    ),
+    ("export.warning_title", "Export unencrypted passwords?"),
+    (
+        "export.warning_body",
+        "The CSV file won't be encrypted: anyone who can open it can read every password in it. Delete it as soon as it has been imported into your new password manager.",
+    ),
+    ("export.confirm", "Export"),
+    ("export.cancel", "Cancel"),
+    ("export.save_title", "Save the unencrypted export"),
+    ("export.csv_filter", "CSV file"),
+    ("plugins.consent_title", "Share item fields with a plugin?"),
+    ("plugins.consent_body", "{plugin} will receive these fields for \"{action}\": {fields}"),
+    ("plugins.consent_allow", "Share"),
+    ("plugins.consent_deny", "Don't share"),
+    ("plugins.consent_declined", "The plugin action was cancelled"),
+];
+
+// Only strings that differ from en-GB
+const EN_US: &[(&str, &str)] = &[("error.serialization", "Serialization error: {detail}")];
+
+const DE: &[(&str, &str)] = &[
+    ("error.database", "Datenbankfehler: {detail}"),
+    ("error.serialization", "Serialisierungsfehler: {detail}"),
+    ("error.http", "HTTP-Fehler: {detail}"),
+    ("error.keyring", "Schlüsselbundfehler: {detail}"),
+    ("error.io", "E/A-Fehler: {detail}"),
+    ("error.auth", "Anmeldung fehlgeschlagen: {detail}"),
+    ("error.sync", "Synchronisierungsfehler: {detail}"),
+    ("error.encryption", "Verschlüsselungsfehler: {detail}"),
+    ("error.not_found", "Nicht gefunden: {detail}"),
+    ("error.invalid_operation", "Ungültiger Vorgang: {detail}"),
+    ("error.validation", "{detail}"),
+    ("error.vault_locked", "Der Tresor ist gesperrt"),
+    ("error.network_unavailable", "Netzwerk nicht verfügbar"),
+    ("error.session_expired", "Ihre Sitzung ist abgelaufen; bitte melden Sie sich erneut an"),
+    ("auth.no_session", "Keine Sitzung gefunden"),
+    ("auth.not_logged_in", "Nicht angemeldet"),
+    ("auth.invalid_master_password", "Ungültiges Master-Passwort"),
+    ("auth.refresh_failed", "Token konnte nicht erneuert werden"),
+    ("auth.invalid_token_expiry", "Ungültiger Token-Ablauf"),
+    ("auth.failed", "Anmeldung fehlgeschlagen"),
+    ("tray.status_locked", "BirchVault ist gesperrt"),
+    ("tray.status_unlocked", "BirchVault ist entsperrt"),
+    ("tray.signed_out", "Nicht angemeldet"),
+    ("tray.show", "BirchVault anzeigen"),
+    ("tray.copy_last_used", "Zuletzt verwendeten Eintrag kopieren"),
+    ("tray.sync_now", "Jetzt synchronisieren"),
+    ("tray.lock", "Tresor sperren"),
+    ("tray.quit", "BirchVault beenden"),
+    ("delete.unsynced_requires_force", "Dieser Eintrag wurde noch nicht synchronisiert und kann auf anderen Geräten nicht wiederhergestellt werden. Bestätigen Sie, um ihn trotzdem zu löschen."),
+    ("strength.sequence", "Vermeiden Sie Folgen wie \"abcd\" oder \"1234\""),
+    ("strength.repeated", "Vermeiden Sie wiederholte Zeichen"),
+    ("strength.common", "Vermeiden Sie gängige Passwörter und Wörter"),
+    ("strength.personal_info", "Verwenden Sie nicht Ihren Namen oder Ihre E-Mail-Adresse"),
+    ("strength.too_short", "Verwenden Sie mindestens 12 Zeichen"),
+    ("strength.add_variety", "Kombinieren Sie Groß- und Kleinbuchstaben, Zahlen und Sonderzeichen"),
+    ("strength.master_too_weak", "Dieses Master-Passwort ist zu schwach"),
+    ("strength.master_reused", "Ihr Master-Passwort sollte nirgendwo anders verwendet werden"),
+    ("notify.sync_failed_title", "Synchronisierung fehlgeschlagen"),
+    ("notify.sync_failed_body", "Ihr Tresor konnte nicht synchronisiert werden: {error}"),
+    ("notify.expiry_title", "Einträge laufen bald ab"),
+    (
+        "notify.expiry_body",
+        "{count} Eintrag/Einträge werden durch die Aufbewahrungsrichtlinie des Ordners in den Papierkorb verschoben, der erste am {date}",
+    ),
+    ("notify.clipboard_cleared_title", "Zwischenablage geleert"),
+    (
+        "notify.clipboard_cleared_body",
+        "Das kopierte Geheimnis wurde aus der Zwischenablage entfernt",
+    ),
+    ("strength.time_instant", "weniger als eine Sekunde"),
+    ("strength.time_seconds", "{count} Sekunde(n)"),
+    ("strength.time_minutes", "{count} Minute(n)"),
+    ("strength.time_hours", "{count} Stunde(n)"),
+    ("strength.time_days", "{count} Tag(e)"),
+    ("strength.time_months", "{count} Monat(e)"),
+    ("strength.time_years", "{count} Jahr(e)"),
+    ("strength.time_centuries", "Jahrhunderte"),
+    ("notify.breach_title", "Kompromittierte Passwörter gefunden"),
+    ("notify.breach_body", "{count} Eintrag/Einträge verwenden ein Passwort aus einem Datenleck"),
+    ("auth.offline_profile_no_network", "In einem Offline-Profil nicht verfügbar"),
+    ("auth.profile_exists", "Auf diesem Gerät gibt es bereits ein Konto oder Offline-Profil"),
+    (
+        "auth.offline_verifier_missing",
+        "Der Entsperrschlüssel des Offline-Profils fehlt im Systemschlüsselbund",
+    ),
+    ("auth.pin_not_set", "Die PIN-Entsperrung ist nicht eingerichtet"),
+    ("auth.pin_invalid", "Falsche PIN, noch {remaining} Versuch(e)"),
+    (
+        "auth.pin_wiped",
+        "Zu viele falsche PINs. Die PIN-Entsperrung wurde deaktiviert; entsperren Sie mit Ihrem Master-Passwort.",
+    ),
+    (
+        "sync.schema_server_outdated",
+        "Das Datenbankschema des Servers (Version {server}) ist älter, als diese App benötigt (Version {expected}). Bitten Sie Ihren Administrator, die neuesten Migrationen auszuführen.",
+    ),
+    (
+        "sync.schema_app_outdated",
+        "Das Datenbankschema des Servers (Version {server}) ist neuer, als diese App unterstützt. Bitte aktualisieren Sie BirchVault.",
+    ),
+    ("export.warning_title", "Unverschlüsselte Passwörter exportieren?"),
+    (
+        "export.warning_body",
+        "Die CSV-Datei wird nicht verschlüsselt: Wer sie öffnen kann, kann alle darin enthaltenen Passwörter lesen. Löschen Sie sie, sobald sie in Ihren neuen Passwort-Manager importiert wurde.",
+    ),
+    ("export.confirm", "Exportieren"),
+    ("export.cancel", "Abbrechen"),
+    ("export.save_title", "Unverschlüsselten Export speichern"),
+    ("export.csv_filter", "CSV-Datei"),
+    ("plugins.consent_title", "Eintragsfelder mit einem Plugin teilen?"),
+    ("plugins.consent_body", "{plugin} erhält diese Felder für „{action}“: {fields}"),
+    ("plugins.consent_allow", "Teilen"),
+    ("plugins.consent_deny", "Nicht teilen"),
+    ("plugins.consent_declined", "Die Plugin-Aktion wurde abgebrochen"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("error.database", "Erreur de base de données : {detail}"),
+    ("error.serialization", "Erreur de sérialisation : {detail}"),
+    ("error.http", "Erreur HTTP : {detail}"),
+    ("error.keyring", "Erreur du trousseau : {detail}"),
+    ("error.io", "Erreur d'E/S : {detail}"),
+    ("error.auth", "Échec de l'authentification : {detail}"),
+    ("error.sync", "Erreur de synchronisation : {detail}"),
+    ("error.encryption", "Erreur de chiffrement : {detail}"),
+    ("error.not_found", "Introuvable : {detail}"),
+    ("error.invalid_operation", "Opération non valide : {detail}"),
+    ("error.validation", "{detail}"),
+    ("error.vault_locked", "Le coffre est verrouillé"),
+    ("error.network_unavailable", "Réseau indisponible"),
+    ("error.session_expired", "Votre session a expiré ; reconnectez-vous"),
+    ("auth.no_session", "Aucune session trouvée"),
+    ("auth.not_logged_in", "Non connecté"),
+    ("auth.invalid_master_password", "Mot de passe maître incorrect"),
+    ("auth.refresh_failed", "Impossible de renouveler le jeton"),
+    ("auth.invalid_token_expiry", "Expiration du jeton non valide"),
+    ("auth.failed", "Échec de l'authentification"),
+    ("tray.status_locked", "BirchVault est verrouillé"),
+    ("tray.status_unlocked", "BirchVault est déverrouillé"),
+    ("tray.signed_out", "Non connecté"),
+    ("tray.show", "Afficher BirchVault"),
+    ("tray.copy_last_used", "Copier le dernier élément utilisé"),
+    ("tray.sync_now", "Synchroniser maintenant"),
+    ("tray.lock", "Verrouiller le coffre"),
+    ("tray.quit", "Quitter BirchVault"),
+    ("delete.unsynced_requires_force", "Cet élément n'a pas encore été synchronisé et ne pourra pas être récupéré sur vos autres appareils. Confirmez pour le supprimer quand même."),
+    ("strength.sequence", "Évitez les suites comme « abcd » ou « 1234 »"),
+    ("strength.repeated", "Évitez les caractères répétés"),
+    ("strength.common", "Évitez les mots et mots de passe courants"),
+    ("strength.personal_info", "N'incluez pas votre nom ni votre adresse e-mail"),
+    ("strength.too_short", "Utilisez au moins 12 caractères"),
+    ("strength.add_variety", "Mélangez majuscules, minuscules, chiffres et symboles"),
+    ("strength.master_too_weak", "Ce mot de passe maître est trop faible"),
+    ("strength.master_reused", "Votre mot de passe maître ne doit servir nulle part ailleurs"),
+    ("notify.sync_failed_title", "Échec de la synchronisation"),
+    ("notify.sync_failed_body", "Votre coffre n'a pas pu être synchronisé : {error}"),
+    ("notify.expiry_title", "Éléments bientôt expirés"),
+    (
+        "notify.expiry_body",
+        "{count} élément(s) seront placés dans la corbeille par la règle de conservation de leur dossier, le premier le {date}",
+    ),
+    ("notify.clipboard_cleared_title", "Presse-papiers effacé"),
+    ("notify.clipboard_cleared_body", "Le secret copié a été retiré du presse-papiers"),
+    ("strength.time_instant", "moins d'une seconde"),
+    ("strength.time_seconds", "{count} seconde(s)"),
+    ("strength.time_minutes", "{count} minute(s)"),
+    ("strength.time_hours", "{count} heure(s)"),
+    ("strength.time_days", "{count} jour(s)"),
+    ("strength.time_months", "{count} mois"),
+    ("strength.time_years", "{count} an(s)"),
+    ("strength.time_centuries", "des siècles"),
+    ("notify.breach_title", "Mots de passe compromis détectés"),
+    (
+        "notify.breach_body",
+        "{count} élément(s) utilisent un mot de passe apparu dans une fuite de données",
+    ),
+    ("auth.offline_profile_no_network", "Indisponible dans un profil hors ligne"),
+    ("auth.profile_exists", "Cet appareil possède déjà un compte ou un profil hors ligne"),
+    (
+        "auth.offline_verifier_missing",
+        "La clé de déverrouillage du profil hors ligne est absente du trousseau système",
+    ),
+    ("auth.pin_not_set", "Le déverrouillage par code PIN n'est pas configuré"),
+    ("auth.pin_invalid", "Code PIN incorrect, {remaining} tentative(s) restante(s)"),
+    (
+        "auth.pin_wiped",
+        "Trop de codes PIN incorrects. Le déverrouillage par code PIN a été désactivé ; déverrouillez avec votre mot de passe maître.",
+    ),
+    (
+        "sync.schema_server_outdated",
+        "Le schéma de base de données du serveur (version {server}) est plus ancien que celui requis par cette application (version {expected}). Demandez à votre administrateur d'exécuter les dernières migrations.",
+    ),
+    (
+        "sync.schema_app_outdated",
+        "Le schéma de base de données du serveur (version {server}) est plus récent que ce que cette application prend en charge. Veuillez mettre à jour BirchVault.",
+    ),
+    ("export.warning_title", "Exporter les mots de passe non chiffrés ?"),
+    (
+        "export.warning_body",
+        "Le fichier CSV ne sera pas chiffré : toute personne pouvant l'ouvrir pourra lire tous les mots de passe qu'il contient. Supprimez-le dès qu'il a été importé dans votre nouveau gestionnaire de mots de passe.",
+    ),
+    ("export.confirm", "Exporter"),
+    ("export.cancel", "Annuler"),
+    ("export.save_title", "Enregistrer l'export non chiffré"),
+    ("export.csv_filter", "Fichier CSV"),
+    ("plugins.consent_title", "Partager des champs avec un plugin ?"),
+    ("plugins.consent_body", "{plugin} recevra ces champs pour « {action} » : {fields}"),
+    ("plugins.consent_allow", "Partager"),
+    ("plugins.consent_deny", "Ne pas partager"),
+    ("plugins.consent_declined", "L'action du plugin a été annulée"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("error.database", "Error de base de datos: {detail}"),
+    ("error.serialization", "Error de serialización: {detail}"),
+    ("error.http", "Error HTTP: {detail}"),
+    ("error.keyring", "Error del llavero: {detail}"),
+    ("error.io", "Error de E/S: {detail}"),
+    ("error.auth", "Error de autenticación: {detail}"),
+    ("error.sync", "Error de sincronización: {detail}"),
+    ("error.encryption", "Error de cifrado: {detail}"),
+    ("error.not_found", "No encontrado: {detail}"),
+    ("error.invalid_operation", "Operación no válida: {detail}"),
+    ("error.validation", "{detail}"),
+    ("error.vault_locked", "La bóveda está bloqueada"),
+    ("error.network_unavailable", "Red no disponible"),
+    ("error.session_expired", "Tu sesión ha caducado; vuelve a iniciar sesión"),
+    ("auth.no_session", "No se encontró ninguna sesión"),
+    ("auth.not_logged_in", "No has iniciado sesión"),
+    ("auth.invalid_master_password", "Contraseña maestra no válida"),
+    ("auth.refresh_failed", "No se pudo renovar el token"),
+    ("auth.invalid_token_expiry", "Caducidad del token no válida"),
+    ("auth.failed", "Error de autenticación"),
+    ("tray.status_locked", "BirchVault está bloqueado"),
+    ("tray.status_unlocked", "BirchVault está desbloqueado"),
+    ("tray.signed_out", "Sin sesión iniciada"),
+    ("tray.show", "Mostrar BirchVault"),
+    ("tray.copy_last_used", "Copiar el último elemento usado"),
+    ("tray.sync_now", "Sincronizar ahora"),
+    ("tray.lock", "Bloquear bóveda"),
+    ("tray.quit", "Salir de BirchVault"),
+    ("delete.unsynced_requires_force", "Este elemento aún no se ha sincronizado y no se podrá recuperar en otros dispositivos. Confirma para eliminarlo de todos modos."),
+    ("strength.sequence", "Evita secuencias como «abcd» o «1234»"),
+    ("strength.repeated", "Evita los caracteres repetidos"),
+    ("strength.common", "Evita contraseñas y palabras comunes"),
+    ("strength.personal_info", "No incluyas tu nombre ni tu correo electrónico"),
+    ("strength.too_short", "Usa al menos 12 caracteres"),
+    ("strength.add_variety", "Combina mayúsculas, minúsculas, números y símbolos"),
+    ("strength.master_too_weak", "Esta contraseña maestra es demasiado débil"),
+    ("strength.master_reused", "Tu contraseña maestra no debería usarse para nada más"),
+    ("notify.sync_failed_title", "Error de sincronización"),
+    ("notify.sync_failed_body", "No se pudo sincronizar tu bóveda: {error}"),
+    ("notify.expiry_title", "Elementos a punto de caducar"),
+    (
+        "notify.expiry_body",
+        "{count} elemento(s) se moverán a la papelera por la política de retención de su carpeta, el primero el {date}",
+    ),
+    ("notify.clipboard_cleared_title", "Portapapeles borrado"),
+    ("notify.clipboard_cleared_body", "El secreto copiado se ha eliminado del portapapeles"),
+    ("strength.time_instant", "menos de un segundo"),
+    ("strength.time_seconds", "{count} segundo(s)"),
+    ("strength.time_minutes", "{count} minuto(s)"),
+    ("strength.time_hours", "{count} hora(s)"),
+    ("strength.time_days", "{count} día(s)"),
+    ("strength.time_months", "{count} mes(es)"),
+    ("strength.time_years", "{count} año(s)"),
+    ("strength.time_centuries", "siglos"),
+    ("notify.breach_title", "Contraseñas filtradas encontradas"),
+    (
+        "notify.breach_body",
+        "{count} elemento(s) usan una contraseña que ha aparecido en una filtración",
+    ),
+    ("auth.offline_profile_no_network", "No disponible en un perfil sin conexión"),
+    ("auth.profile_exists", "Este dispositivo ya tiene una cuenta o un perfil sin conexión"),
+    (
+        "auth.offline_verifier_missing",
+        "Falta la clave de desbloqueo del perfil sin conexión en el llavero del sistema",
+    ),
+    ("auth.pin_not_set", "El desbloqueo con PIN no está configurado"),
+    ("auth.pin_invalid", "PIN incorrecto, quedan {remaining} intento(s)"),
+    (
+        "auth.pin_wiped",
+        "Demasiados PIN incorrectos. Se ha desactivado el desbloqueo con PIN; desbloquea con tu contraseña maestra.",
+    ),
+    (
+        "sync.schema_server_outdated",
+        "El esquema de base de datos del servidor (versión {server}) es anterior al que necesita esta aplicación (versión {expected}). Pide a tu administrador que ejecute las últimas migraciones.",
+    ),
+    (
+        "sync.schema_app_outdated",
+        "El esquema de base de datos del servidor (versión {server}) es más reciente de lo que admite esta aplicación. Actualiza BirchVault.",
+    ),
+    ("export.warning_title", "¿Exportar contraseñas sin cifrar?"),
+    (
+        "export.warning_body",
+        "El archivo CSV no estará cifrado: cualquiera que pueda abrirlo podrá leer todas las contraseñas que contiene. Elimínalo en cuanto lo hayas importado en tu nuevo gestor de contraseñas.",
+    ),
+    ("export.confirm", "Exportar"),
+    ("export.cancel", "Cancelar"),
+    ("export.save_title", "Guardar la exportación sin cifrar"),
+    ("export.csv_filter", "Archivo CSV"),
+    ("plugins.consent_title", "¿Compartir campos con un plugin?"),
+    ("plugins.consent_body", "{plugin} recibirá estos campos para «{action}»: {fields}"),
+    ("plugins.consent_allow", "Compartir"),
+    ("plugins.consent_deny", "No compartir"),
+    ("plugins.consent_declined", "Se canceló la acción del plugin"),
+];
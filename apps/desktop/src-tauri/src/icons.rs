@@ -0,0 +1,278 @@
+// ============================================
+// BirchVault Desktop - Website Icons
+// ============================================
+//
+// Fetches favicons for login URLs on the webview's behalf. Loaded from the
+// webview, every icon would be a cross-origin request (or a request to a
+// third-party icon service) announcing which sites are in the vault. Here
+// each site only ever sees a request for its own icon.
+//
+// Icons are cached in app data, encrypted with the vault key, under a keyed
+// hash of the host, so the cache reveals neither the icons nor the sites.
+// Failed lookups are cached too, for a shorter time.
+
+use crate::crypto::{self, EncryptedData};
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::import::url_host;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use reqwest::{redirect, Client, Response, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const ICON_TTL_DAYS: i64 = 7;
+const MISS_TTL_HOURS: i64 = 24;
+
+const MAX_ICON_BYTES: usize = 100 * 1024;
+/// Icon links are in the <head>; no need to read a whole page
+const MAX_PAGE_BYTES: usize = 256 * 1024;
+const MAX_CACHE_BYTES: u64 = 20 * 1024 * 1024;
+
+const FETCH_TIMEOUT_SECS: u64 = 5;
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedIcon {
+    /// `None` records a lookup that found nothing
+    mime: Option<String>,
+    /// base64
+    data: Option<String>,
+    fetched_at: String,
+}
+
+impl CachedIcon {
+    fn is_fresh(&self) -> bool {
+        let ttl = if self.data.is_some() {
+            Duration::days(ICON_TTL_DAYS)
+        } else {
+            Duration::hours(MISS_TTL_HOURS)
+        };
+        DateTime::parse_from_rfc3339(&self.fetched_at)
+            .is_ok_and(|fetched| fetched.with_timezone(&Utc) + ttl > Utc::now())
+    }
+
+    fn data_uri(&self) -> Option<String> {
+        Some(format!(
+            "data:{};base64,{}",
+            self.mime.as_deref()?,
+            self.data.as_deref()?
+        ))
+    }
+}
+
+/// The icon for a login URL as a data URI, from the cache when fresh.
+/// With `allow_network` off only the cache is consulted, stale or not.
+pub async fn site_icon(
+    db: &Database,
+    key: &[u8],
+    url: &str,
+    allow_network: bool,
+) -> Result<Option<String>> {
+    let Some(host) = url_host(url) else {
+        return Ok(None);
+    };
+    let dir = db.icons_dir();
+    let path = cache_path(&dir, &host, key);
+
+    if let Some(cached) = read_cache(&path, key) {
+        if cached.is_fresh() || !allow_network {
+            return Ok(cached.data_uri());
+        }
+    }
+    if !allow_network {
+        return Ok(None);
+    }
+
+    let icon = fetch_icon(&client()?, &host).await;
+    let entry = CachedIcon {
+        mime: icon.as_ref().map(|(mime, _)| mime.clone()),
+        data: icon.map(|(_, bytes)| BASE64.encode(bytes)),
+        fetched_at: Utc::now().to_rfc3339(),
+    };
+
+    std::fs::create_dir_all(&dir)?;
+    let encrypted = crypto::encrypt(&serde_json::to_vec(&entry)?, key)?;
+    std::fs::write(&path, serde_json::to_vec(&encrypted)?)?;
+    prune_cache(&dir)?;
+
+    Ok(entry.data_uri())
+}
+
+/// Drop every cached icon (on logout, or when icons are turned off)
+pub fn clear_cache(db: &Database) -> Result<()> {
+    let dir = db.icons_dir();
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+// ============================================
+// Cache
+// ============================================
+
+/// SHA-256 over the vault key and host, so file names can't be matched to sites
+fn cache_path(dir: &Path, host: &str, key: &[u8]) -> PathBuf {
+    let digest = Sha256::new()
+        .chain_update(key)
+        .chain_update(host.as_bytes())
+        .finalize();
+    let name: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.bvic", name))
+}
+
+/// A missing, unreadable or foreign-key entry is just a cache miss
+fn read_cache(path: &Path, key: &[u8]) -> Option<CachedIcon> {
+    let encrypted: EncryptedData = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+    serde_json::from_slice(&crypto::decrypt(&encrypted, key).ok()?).ok()
+}
+
+/// Evict the least recently written icons once the cache outgrows its cap
+fn prune_cache(dir: &Path) -> Result<()> {
+    let mut files: Vec<(PathBuf, u64, std::time::SystemTime)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let meta = entry.metadata().ok()?;
+            Some((entry.path(), meta.len(), meta.modified().ok()?))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    for (path, size, _) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= size;
+    }
+    Ok(())
+}
+
+// ============================================
+// Fetching
+// ============================================
+
+fn client() -> Result<Client> {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .redirect(redirect::Policy::limited(MAX_REDIRECTS))
+        .build()
+        .map_err(AppError::from)
+}
+
+/// Icons declared by the home page, then `/favicon.ico`
+async fn fetch_icon(client: &Client, host: &str) -> Option<(String, Vec<u8>)> {
+    let home = format!("https://{}/", host);
+    let mut candidates = declared_icons(client, &home).await;
+    candidates.push(format!("https://{}/favicon.ico", host));
+
+    for candidate in candidates {
+        if let Some(icon) = fetch_image(client, &candidate).await {
+            return Some(icon);
+        }
+    }
+    None
+}
+
+fn link_tag() -> &'static Regex {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    LINK.get_or_init(|| Regex::new(r"(?is)<link\b[^>]*>").unwrap())
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(r#"(?is)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#, name);
+    let captures = Regex::new(&pattern).ok()?.captures(tag)?;
+    (1..=3)
+        .find_map(|i| captures.get(i))
+        .map(|m| m.as_str().trim().to_string())
+}
+
+/// `<link rel="icon">` targets, resolved against wherever the page redirected to
+async fn declared_icons(client: &Client, page: &str) -> Vec<String> {
+    let Ok(response) = client.get(page).send().await else {
+        return Vec::new();
+    };
+    let base = response.url().clone();
+    let Some(html) = read_limited(response, MAX_PAGE_BYTES).await else {
+        return Vec::new();
+    };
+    let html = String::from_utf8_lossy(&html);
+
+    link_tag()
+        .find_iter(&html)
+        .filter(|tag| {
+            attribute(tag.as_str(), "rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("icon"))
+            })
+        })
+        .filter_map(|tag| attribute(tag.as_str(), "href"))
+        .filter_map(|href| base.join(&href).ok())
+        .filter(|url: &Url| matches!(url.scheme(), "https" | "http"))
+        .map(String::from)
+        .collect()
+}
+
+async fn fetch_image(client: &Client, url: &str) -> Option<(String, Vec<u8>)> {
+    let response = client.get(url).send().await.ok()?;
+    let declared = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase()
+        });
+    let bytes = read_limited(response, MAX_ICON_BYTES).await?;
+
+    // Plenty of servers send favicon.ico as text/plain or octet-stream
+    let mime = declared
+        .filter(|mime| mime.starts_with("image/"))
+        .or_else(|| sniff_image(&bytes))?;
+    (!bytes.is_empty()).then_some((mime, bytes))
+}
+
+fn sniff_image(bytes: &[u8]) -> Option<String> {
+    let mime = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(&[0, 0, 1, 0]) {
+        "image/x-icon"
+    } else if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        "image/jpeg"
+    } else {
+        return None;
+    };
+    Some(mime.to_string())
+}
+
+/// The body of a successful response, or `None` if it fails or runs past `limit`
+async fn read_limited(mut response: Response, limit: usize) -> Option<Vec<u8>> {
+    if !response.status().is_success()
+        || response
+            .content_length()
+            .is_some_and(|len| len > limit as u64)
+    {
+        return None;
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        if body.len() + chunk.len() > limit {
+            return None;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Some(body)
+}
@@ -0,0 +1,998 @@
+// ============================================
+// BirchVault Desktop - Vault Import
+// ============================================
+//
+// Parses exports from other password managers into the item JSON the
+// frontend stores, then encrypts it with the session key. Anything without
+// a BirchVault equivalent is kept as a custom field rather than dropped.
+
+use crate::crypto;
+use crate::db::{Folder, VaultItem};
+use crate::error::{AppError, Result};
+use crate::fields;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use uuid::Uuid;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportFormat {
+    BitwardenJson,
+    BitwardenCsv,
+    OnePasswordPux,
+    BrowserCsv,
+}
+
+impl ImportFormat {
+    /// Guess the format from the file extension, falling back to the contents
+    pub fn detect(path: &Path, contents: &[u8]) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        match extension.as_deref() {
+            Some("json") => Ok(ImportFormat::BitwardenJson),
+            Some("csv") => Ok(detect_csv(contents)),
+            Some("1pux") => Ok(ImportFormat::OnePasswordPux),
+            _ if contents.starts_with(ZIP_MAGIC) => Ok(ImportFormat::OnePasswordPux),
+            _ if contents.trim_ascii_start().starts_with(b"{") => Ok(ImportFormat::BitwardenJson),
+            _ => Err(AppError::InvalidOperation(
+                "Unrecognised import file format".to_string(),
+            )),
+        }
+    }
+}
+
+/// An item in BirchVault's shape, before it is given an id and encrypted
+#[derive(Debug, Clone, Default)]
+pub struct ImportedItem {
+    pub item_type: String,
+    pub name: String,
+    /// Folder name; folders are matched or created by name
+    pub folder: Option<String>,
+    pub favorite: bool,
+    /// Type-specific keys (`login`, `card`, `notes`, `fields`, ...)
+    pub data: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedImport {
+    pub items: Vec<ImportedItem>,
+    /// Entries that couldn't be mapped
+    pub skipped: usize,
+    /// Logins already in the vault (see `remove_duplicates`)
+    pub duplicates: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub folders_created: usize,
+    pub skipped: usize,
+    pub duplicates: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+pub fn parse(format: ImportFormat, contents: &[u8]) -> Result<ParsedImport> {
+    match format {
+        ImportFormat::BitwardenJson => parse_bitwarden_json(as_text(contents)?),
+        ImportFormat::BitwardenCsv => parse_bitwarden_csv(as_text(contents)?),
+        ImportFormat::OnePasswordPux => parse_1pux(contents),
+        ImportFormat::BrowserCsv => parse_browser_csv(as_text(contents)?),
+    }
+}
+
+// ============================================
+// Shared Helpers
+// ============================================
+
+/// A custom field in the typed form; e.g. "true" for a boolean becomes `true`
+pub(crate) fn custom_field(name: &str, value: &str, field_type: &str) -> Value {
+    let field = fields::normalise(&json!({ "name": name, "value": value, "type": field_type }));
+    serde_json::to_value(field).unwrap_or(Value::Null)
+}
+
+fn as_text(contents: &[u8]) -> Result<&str> {
+    std::str::from_utf8(contents)
+        .map_err(|_| AppError::InvalidOperation("Import file is not valid UTF-8".to_string()))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// Read a CSV export into rows keyed by lower-cased header
+pub(crate) fn read_csv(contents: &str) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::Headers)
+        .from_reader(contents.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| AppError::InvalidOperation(format!("Invalid CSV: {}", e)))?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record =
+                record.map_err(|e| AppError::InvalidOperation(format!("Invalid CSV: {}", e)))?;
+            Ok(headers
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect()
+}
+
+fn login_value(
+    username: Option<String>,
+    password: Option<String>,
+    uris: Vec<String>,
+    totp: Option<String>,
+) -> Value {
+    let uris: Vec<Value> = uris
+        .into_iter()
+        .filter(|uri| !uri.trim().is_empty())
+        .map(|uri| json!({ "uri": uri.trim(), "match": Value::Null }))
+        .collect();
+
+    json!({
+        "username": non_empty(username),
+        "password": non_empty(password),
+        "uris": uris,
+        "totp": non_empty(totp),
+    })
+}
+
+// ============================================
+// Bitwarden JSON
+// ============================================
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    item_type: u8,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    folder_id: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default)]
+    fields: Option<Vec<BitwardenField>>,
+    #[serde(default)]
+    login: Option<BitwardenLogin>,
+    #[serde(default)]
+    card: Option<Map<String, Value>>,
+    #[serde(default)]
+    identity: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenField {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    field_type: u8,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BitwardenLogin {
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Option<Vec<BitwardenUri>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitwardenUri {
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+const CARD_KEYS: &[&str] = &[
+    "cardholderName",
+    "brand",
+    "number",
+    "expMonth",
+    "expYear",
+    "code",
+];
+
+const IDENTITY_KEYS: &[&str] = &[
+    "title",
+    "firstName",
+    "middleName",
+    "lastName",
+    "email",
+    "phone",
+    "address1",
+    "address2",
+    "city",
+    "state",
+    "postalCode",
+    "country",
+    "company",
+    "ssn",
+    "passportNumber",
+    "licenseNumber",
+];
+
+/// Copy the known keys across; anything else non-empty becomes a custom field
+fn split_known(source: Map<String, Value>, known: &[&str], fields: &mut Vec<Value>) -> Value {
+    let mut mapped = Map::new();
+    for (key, value) in source {
+        if known.contains(&key.as_str()) {
+            mapped.insert(key, value);
+        } else if let Some(text) = value.as_str().filter(|v| !v.is_empty()) {
+            fields.push(custom_field(&key, text, "text"));
+        }
+    }
+    Value::Object(mapped)
+}
+
+fn parse_bitwarden_json(contents: &str) -> Result<ParsedImport> {
+    let export: BitwardenExport = serde_json::from_str(contents)?;
+    if export.encrypted {
+        return Err(AppError::InvalidOperation(
+            "Encrypted Bitwarden exports can't be imported; export as unencrypted JSON".to_string(),
+        ));
+    }
+
+    let folder_names: HashMap<String, String> =
+        export.folders.into_iter().map(|f| (f.id, f.name)).collect();
+
+    let mut parsed = ParsedImport::default();
+
+    for item in export.items {
+        let name = non_empty(item.name).unwrap_or_else(|| "Untitled".to_string());
+        let mut data = Map::new();
+        let mut fields = Vec::new();
+
+        for field in item.fields.unwrap_or_default() {
+            let field_type = match field.field_type {
+                1 => "hidden",
+                2 => "boolean",
+                _ => "text",
+            };
+            fields.push(custom_field(
+                field.name.as_deref().unwrap_or_default(),
+                field.value.as_deref().unwrap_or_default(),
+                field_type,
+            ));
+        }
+
+        let item_type = match item.item_type {
+            1 => {
+                let login = item.login.unwrap_or_default();
+                let uris = login
+                    .uris
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|u| u.uri)
+                    .collect();
+                data.insert(
+                    "login".to_string(),
+                    login_value(login.username, login.password, uris, login.totp),
+                );
+                "login"
+            }
+            2 => {
+                data.insert("secureNote".to_string(), json!({ "type": 0 }));
+                "securenote"
+            }
+            3 => {
+                let card = split_known(item.card.unwrap_or_default(), CARD_KEYS, &mut fields);
+                data.insert("card".to_string(), card);
+                "card"
+            }
+            4 => {
+                let identity = split_known(
+                    item.identity.unwrap_or_default(),
+                    IDENTITY_KEYS,
+                    &mut fields,
+                );
+                data.insert("identity".to_string(), identity);
+                "identity"
+            }
+            other => {
+                parsed.skipped += 1;
+                parsed.warnings.push(format!(
+                    "Skipped \"{}\": unsupported item type {}",
+                    name, other
+                ));
+                continue;
+            }
+        };
+
+        data.insert("notes".to_string(), json!(non_empty(item.notes)));
+        if !fields.is_empty() {
+            data.insert("fields".to_string(), Value::Array(fields));
+        }
+
+        parsed.items.push(ImportedItem {
+            item_type: item_type.to_string(),
+            name,
+            folder: item.folder_id.and_then(|id| folder_names.get(&id).cloned()),
+            favorite: item.favorite,
+            data,
+        });
+    }
+
+    Ok(parsed)
+}
+
+// ============================================
+// Bitwarden CSV
+// ============================================
+
+fn parse_bitwarden_csv(contents: &str) -> Result<ParsedImport> {
+    let mut parsed = ParsedImport::default();
+
+    for (index, row) in read_csv(contents)?.into_iter().enumerate() {
+        let get = |key: &str| row.get(key).cloned();
+        let name = non_empty(get("name")).unwrap_or_else(|| "Untitled".to_string());
+        let mut data = Map::new();
+
+        // "name: value" per line
+        let fields: Vec<Value> = get("fields")
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match line.split_once(": ") {
+                Some((name, value)) => custom_field(name, value, "text"),
+                None => custom_field(line, "", "text"),
+            })
+            .collect();
+
+        let item_type = match get("type").unwrap_or_default().trim() {
+            "login" | "" => {
+                let uris = get("login_uri")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::to_string)
+                    .collect();
+                data.insert(
+                    "login".to_string(),
+                    login_value(
+                        get("login_username"),
+                        get("login_password"),
+                        uris,
+                        get("login_totp"),
+                    ),
+                );
+                "login"
+            }
+            "note" => {
+                data.insert("secureNote".to_string(), json!({ "type": 0 }));
+                "securenote"
+            }
+            other => {
+                parsed.skipped += 1;
+                parsed.warnings.push(format!(
+                    "Skipped row {} (\"{}\"): unsupported item type \"{}\"",
+                    index + 2,
+                    name,
+                    other
+                ));
+                continue;
+            }
+        };
+
+        data.insert("notes".to_string(), json!(non_empty(get("notes"))));
+        if !fields.is_empty() {
+            data.insert("fields".to_string(), Value::Array(fields));
+        }
+
+        parsed.items.push(ImportedItem {
+            item_type: item_type.to_string(),
+            name,
+            folder: non_empty(get("folder")),
+            favorite: get("favorite").as_deref().map(str::trim) == Some("1"),
+            data,
+        });
+    }
+
+    Ok(parsed)
+}
+
+// ============================================
+// Browser CSV (Chrome, Edge, Firefox, Safari)
+// ============================================
+
+/// Bitwarden's CSV has its own columns; anything with url/username/password is a browser export
+fn detect_csv(contents: &[u8]) -> ImportFormat {
+    let header = as_text(contents)
+        .ok()
+        .and_then(|text| text.trim_start_matches('\u{feff}').lines().next())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if header.split(',').any(|column| column.trim() == "login_uri") {
+        ImportFormat::BitwardenCsv
+    } else {
+        ImportFormat::BrowserCsv
+    }
+}
+
+/// Host without `www.`, for naming and duplicate detection
+pub(crate) fn url_host(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .or_else(|_| reqwest::Url::parse(&format!("https://{}", url.trim())))
+        .ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+fn parse_browser_csv(contents: &str) -> Result<ParsedImport> {
+    let mut parsed = ParsedImport::default();
+
+    for (index, row) in read_csv(contents)?.into_iter().enumerate() {
+        let get = |key: &str| non_empty(row.get(key).cloned());
+        let url = get("url");
+        let username = get("username");
+        let password = get("password");
+
+        if url.is_none() && username.is_none() && password.is_none() {
+            parsed.skipped += 1;
+            parsed.warnings.push(format!(
+                "Skipped row {}: no URL, username or password",
+                index + 2
+            ));
+            continue;
+        }
+
+        // Firefox has no name column, so fall back to the site
+        let name = get("name")
+            .or_else(|| get("title"))
+            .or_else(|| url.as_deref().and_then(url_host))
+            .unwrap_or_else(|| "Untitled".to_string());
+
+        let mut data = Map::new();
+        data.insert(
+            "login".to_string(),
+            login_value(
+                username,
+                password,
+                url.into_iter().collect(),
+                get("otpauth"),
+            ),
+        );
+        data.insert(
+            "notes".to_string(),
+            json!(get("note").or_else(|| get("notes"))),
+        );
+
+        parsed.items.push(ImportedItem {
+            item_type: "login".to_string(),
+            name,
+            folder: None,
+            favorite: false,
+            data,
+        });
+    }
+
+    Ok(parsed)
+}
+
+// ============================================
+// Duplicate Detection
+// ============================================
+
+/// Identity of a login for duplicate checks: site, username and password
+fn login_key(item: &Map<String, Value>) -> Option<String> {
+    let login = item.get("login")?;
+    let text = |key: &str| login.get(key).and_then(Value::as_str).unwrap_or_default();
+    let host = login
+        .get("uris")
+        .and_then(Value::as_array)
+        .and_then(|uris| uris.first())
+        .and_then(|uri| uri.get("uri"))
+        .and_then(Value::as_str)
+        .and_then(url_host)
+        .unwrap_or_default();
+
+    Some(format!(
+        "{}\n{}\n{}",
+        host,
+        text("username"),
+        text("password")
+    ))
+}
+
+/// Login keys of the existing (non-deleted) items; items that fail to decrypt are ignored
+pub fn existing_login_keys(items: &[VaultItem], key: &[u8]) -> HashSet<String> {
+    items
+        .iter()
+        .filter(|item| item.item_type == "login")
+        .filter_map(|item| crypto::decrypt_from_json(&item.encrypted_data, key).ok())
+        .filter_map(|plaintext| serde_json::from_str::<Map<String, Value>>(&plaintext).ok())
+        .filter_map(|data| login_key(&data))
+        .collect()
+}
+
+/// Drop logins already in the vault or repeated within the import itself
+pub fn remove_duplicates(parsed: &mut ParsedImport, mut existing: HashSet<String>) {
+    let before = parsed.items.len();
+    parsed.items.retain(|item| match login_key(&item.data) {
+        Some(key) => existing.insert(key),
+        None => true,
+    });
+    parsed.duplicates = before - parsed.items.len();
+}
+
+// ============================================
+// 1Password 1PUX
+// ============================================
+
+/// The archive entry holding every account, vault and item
+const PUX_DATA_ENTRY: &str = "export.data";
+
+#[derive(Debug, Deserialize)]
+struct PuxExport {
+    #[serde(default)]
+    accounts: Vec<PuxAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxAccount {
+    #[serde(default)]
+    vaults: Vec<PuxVault>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxVault {
+    attrs: PuxVaultAttrs,
+    #[serde(default)]
+    items: Vec<PuxItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxVaultAttrs {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PuxItem {
+    category_uuid: String,
+    #[serde(default)]
+    fav_index: i64,
+    #[serde(default)]
+    state: Option<String>,
+    overview: PuxOverview,
+    #[serde(default)]
+    details: PuxDetails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PuxOverview {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    urls: Vec<PuxUrl>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxUrl {
+    url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PuxDetails {
+    #[serde(default)]
+    login_fields: Vec<PuxLoginField>,
+    #[serde(default)]
+    notes_plain: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    sections: Vec<PuxSection>,
+    #[serde(default)]
+    document_attributes: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxLoginField {
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    designation: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxSection {
+    #[serde(default)]
+    fields: Vec<PuxSectionField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PuxSectionField {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    value: Map<String, Value>,
+}
+
+/// A section field flattened to text, keyed by 1Password's field id
+#[derive(Debug)]
+struct PuxField {
+    id: String,
+    title: String,
+    kind: String,
+    value: String,
+}
+
+/// Flatten a typed 1PUX value (`{"concealed": "..."}`, `{"monthYear": 202512}`, ...)
+fn pux_value_text(kind: &str, value: &Value) -> String {
+    match (kind, value) {
+        ("date", Value::Number(n)) => n
+            .as_i64()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        ("monthYear", Value::Number(n)) => n
+            .as_i64()
+            .map(|my| format!("{:02}/{}", my % 100, my / 100))
+            .unwrap_or_default(),
+        ("email", Value::Object(email)) => email
+            .get("email_address")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        ("address", Value::Object(address)) => ["street", "city", "state", "zip", "country"]
+            .iter()
+            .filter_map(|key| address.get(*key).and_then(Value::as_str))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        (_, Value::Object(parts)) => parts
+            .values()
+            .filter_map(Value::as_str)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        (_, Value::String(text)) => text.clone(),
+        (_, Value::Null) => String::new(),
+        (_, other) => other.to_string(),
+    }
+}
+
+fn pux_fields(sections: &[PuxSection]) -> Vec<PuxField> {
+    sections
+        .iter()
+        .flat_map(|section| &section.fields)
+        .filter_map(|field| {
+            let (kind, value) = field.value.iter().next()?;
+            Some(PuxField {
+                id: field.id.clone(),
+                title: field.title.clone(),
+                kind: kind.clone(),
+                value: pux_value_text(kind, value),
+            })
+        })
+        .filter(|field| !field.value.is_empty())
+        .collect()
+}
+
+/// Remove and return the first field with one of the given ids or kinds
+fn take_pux_field(fields: &mut Vec<PuxField>, keys: &[&str]) -> Option<String> {
+    let index = fields
+        .iter()
+        .position(|f| keys.contains(&f.id.as_str()) || keys.contains(&f.kind.as_str()))?;
+    Some(fields.remove(index).value)
+}
+
+fn parse_1pux(contents: &[u8]) -> Result<ParsedImport> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(contents))
+        .map_err(|e| AppError::InvalidOperation(format!("Invalid 1PUX archive: {}", e)))?;
+    let mut data = String::new();
+    archive
+        .by_name(PUX_DATA_ENTRY)
+        .map_err(|e| AppError::InvalidOperation(format!("Invalid 1PUX archive: {}", e)))?
+        .read_to_string(&mut data)?;
+
+    let export: PuxExport = serde_json::from_str(&data)?;
+    let mut parsed = ParsedImport::default();
+
+    for vault in export.accounts.into_iter().flat_map(|a| a.vaults) {
+        for item in vault.items {
+            if item.state.as_deref() == Some("deleted") {
+                continue;
+            }
+            parsed
+                .items
+                .push(map_pux_item(item, &vault.attrs.name, &mut parsed.warnings));
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn map_pux_item(item: PuxItem, vault_name: &str, warnings: &mut Vec<String>) -> ImportedItem {
+    let name = non_empty(item.overview.title).unwrap_or_else(|| "Untitled".to_string());
+    let mut fields = pux_fields(&item.details.sections);
+    let mut data = Map::new();
+
+    let item_type = match item.category_uuid.as_str() {
+        // Login, Password
+        "001" | "005" => {
+            let mut username = None;
+            let mut password = item.details.password;
+            for field in item.details.login_fields {
+                match field.designation.as_deref() {
+                    Some("username") => username = Some(field.value),
+                    Some("password") => password = Some(field.value),
+                    _ if !field.value.is_empty() => {
+                        fields.push(PuxField {
+                            id: field.name.clone(),
+                            title: field.name,
+                            kind: "string".to_string(),
+                            value: field.value,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            let mut uris: Vec<String> = item.overview.urls.into_iter().map(|u| u.url).collect();
+            if let Some(url) = item.overview.url.filter(|u| !uris.contains(u)) {
+                uris.insert(0, url);
+            }
+            let totp = take_pux_field(&mut fields, &["totp"]);
+            data.insert(
+                "login".to_string(),
+                login_value(username, password, uris, totp),
+            );
+            "login"
+        }
+        // Credit Card
+        "002" => {
+            let expiry = take_pux_field(&mut fields, &["expiry"]);
+            let (exp_month, exp_year) = expiry
+                .as_deref()
+                .and_then(|e| e.split_once('/'))
+                .map(|(m, y)| (Some(m.to_string()), Some(y.to_string())))
+                .unwrap_or_default();
+            data.insert(
+                "card".to_string(),
+                json!({
+                    "cardholderName": take_pux_field(&mut fields, &["cardholder"]),
+                    "brand": take_pux_field(&mut fields, &["type", "creditCardType"]),
+                    "number": take_pux_field(&mut fields, &["ccnum", "creditCardNumber"]),
+                    "expMonth": exp_month,
+                    "expYear": exp_year,
+                    "code": take_pux_field(&mut fields, &["cvv"]),
+                }),
+            );
+            "card"
+        }
+        // Identity, Driver Licence, Passport, Social Security Number
+        "004" | "103" | "106" | "108" => {
+            let mut identity = Map::new();
+            let number = match item.category_uuid.as_str() {
+                "103" => Some("licenseNumber"),
+                "106" => Some("passportNumber"),
+                "108" => Some("ssn"),
+                _ => None,
+            };
+            if let Some(key) = number {
+                identity.insert(
+                    key.to_string(),
+                    json!(take_pux_field(&mut fields, &["number"])),
+                );
+            }
+            for (key, ids) in [
+                ("firstName", &["firstname"][..]),
+                ("middleName", &["initial"]),
+                ("lastName", &["lastname"]),
+                ("email", &["email"]),
+                ("phone", &["defphone", "cell", "homephone", "busphone"]),
+                ("company", &["company"]),
+                ("address1", &["address"]),
+            ] {
+                if let Some(value) = take_pux_field(&mut fields, ids) {
+                    identity.insert(key.to_string(), json!(value));
+                }
+            }
+            data.insert("identity".to_string(), Value::Object(identity));
+            "identity"
+        }
+        // Wireless Router
+        "109" => {
+            data.insert(
+                "wifi".to_string(),
+                json!({
+                    "ssid": take_pux_field(&mut fields, &["network_name"]).unwrap_or_default(),
+                    "password": take_pux_field(&mut fields, &["wireless_password"]),
+                }),
+            );
+            "wifi"
+        }
+        // API Credential
+        "112" => {
+            data.insert(
+                "apiKey".to_string(),
+                json!({
+                    "key": take_pux_field(&mut fields, &["credential"]).unwrap_or_default(),
+                    "endpoint": take_pux_field(&mut fields, &["hostname"]),
+                }),
+            );
+            "apikey"
+        }
+        // Secure Note, Document and everything without a BirchVault equivalent
+        category => {
+            if category == "006" || item.details.document_attributes.is_some() {
+                warnings.push(format!("\"{}\": attached file was not imported", name));
+            }
+            data.insert("secureNote".to_string(), json!({ "type": 0 }));
+            "securenote"
+        }
+    };
+
+    let mut custom_fields: Vec<Value> = fields
+        .into_iter()
+        .map(|f| {
+            let field_type = if f.kind == "concealed" {
+                "hidden"
+            } else {
+                "text"
+            };
+            let label = if f.title.is_empty() { &f.id } else { &f.title };
+            custom_field(label, &f.value, field_type)
+        })
+        .collect();
+    if !item.overview.tags.is_empty() {
+        custom_fields.push(custom_field("Tags", &item.overview.tags.join(", "), "text"));
+    }
+
+    data.insert(
+        "notes".to_string(),
+        json!(non_empty(item.details.notes_plain)),
+    );
+    if !custom_fields.is_empty() {
+        data.insert("fields".to_string(), Value::Array(custom_fields));
+    }
+
+    ImportedItem {
+        item_type: item_type.to_string(),
+        name,
+        // 1Password has vaults rather than folders
+        folder: Some(vault_name.to_string()),
+        favorite: item.fav_index > 0,
+        data,
+    }
+}
+
+// ============================================
+// Building Records
+// ============================================
+
+/// Folders and items ready to insert, with every item encrypted
+#[derive(Debug, Default)]
+pub struct ImportRecords {
+    pub folders: Vec<Folder>,
+    pub items: Vec<VaultItem>,
+}
+
+/// Assign ids, resolve folders by name (case-insensitively, creating missing
+/// ones) and encrypt each item. `on_progress` is called as items are encrypted.
+pub fn build_records(
+    parsed: &ParsedImport,
+    existing_folders: &[Folder],
+    key: &[u8],
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<ImportRecords> {
+    let now = Utc::now().to_rfc3339();
+    let mut records = ImportRecords::default();
+    let mut folder_ids: HashMap<String, String> = existing_folders
+        .iter()
+        .map(|f| (f.name.to_lowercase(), f.id.clone()))
+        .collect();
+
+    let total = parsed.items.len();
+    for (index, imported) in parsed.items.iter().enumerate() {
+        let folder_id = imported.folder.as_ref().map(|name| {
+            folder_ids
+                .entry(name.to_lowercase())
+                .or_insert_with(|| {
+                    let folder = Folder {
+                        id: Uuid::new_v4().to_string(),
+                        name: name.clone(),
+                        synced_at: None,
+                        local_updated_at: now.clone(),
+                        retention_days: None,
+                    };
+                    let id = folder.id.clone();
+                    records.folders.push(folder);
+                    id
+                })
+                .clone()
+        });
+
+        let id = Uuid::new_v4().to_string();
+        let mut item = imported.data.clone();
+        item.insert("id".to_string(), json!(id));
+        item.insert("type".to_string(), json!(imported.item_type));
+        item.insert("name".to_string(), json!(imported.name));
+        item.insert("folderId".to_string(), json!(folder_id));
+        item.insert("favorite".to_string(), json!(imported.favorite));
+        item.insert("createdAt".to_string(), json!(now));
+        item.insert("updatedAt".to_string(), json!(now));
+
+        let encrypted_data = crypto::encrypt_to_json(&Value::Object(item).to_string(), key)?;
+
+        records.items.push(VaultItem {
+            id,
+            encrypted_data,
+            item_type: imported.item_type.clone(),
+            folder_id,
+            is_favorite: imported.favorite,
+            deleted_at: None,
+            synced_at: None,
+            local_updated_at: now.clone(),
+            server_updated_at: None,
+            sort_index: None,
+        });
+
+        on_progress(index + 1, total);
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,416 @@
+// ============================================
+// BirchVault Desktop - Item Types
+// ============================================
+//
+// Typed views of the decrypted item payload (see @birchvault/core's
+// `VaultItem`) for the types the backend needs to understand: schema
+// validation on save, card brand detection, expiry parsing and masked
+// display values that never expose the full number.
+
+use crate::error::{AppError, Result};
+use crate::passkeys::{self, Passkey};
+use crate::urimatch::{self, LoginUri, UriMatch};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+const MASK: char = '•';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemType {
+    Login,
+    Card,
+    Identity,
+    SecureNote,
+    ApiKey,
+    Wifi,
+    Document,
+    Passkey,
+}
+
+impl FromStr for ItemType {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "login" => Ok(ItemType::Login),
+            "card" => Ok(ItemType::Card),
+            "identity" => Ok(ItemType::Identity),
+            "securenote" => Ok(ItemType::SecureNote),
+            "apikey" => Ok(ItemType::ApiKey),
+            "wifi" => Ok(ItemType::Wifi),
+            "document" => Ok(ItemType::Document),
+            "passkey" => Ok(ItemType::Passkey),
+            other => Err(AppError::InvalidOperation(format!(
+                "Unknown item type: {}",
+                other
+            ))),
+        }
+    }
+}
+
+// ============================================
+// Cards
+// ============================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Card {
+    pub cardholder_name: Option<String>,
+    pub brand: Option<String>,
+    pub number: Option<String>,
+    pub exp_month: Option<String>,
+    pub exp_year: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    Diners,
+    Jcb,
+    UnionPay,
+    Maestro,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardExpiry {
+    pub month: u32,
+    pub year: i32,
+}
+
+impl CardExpiry {
+    /// Cards are valid to the end of their expiry month
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        (self.year, self.month) < (today.year(), today.month())
+    }
+
+    /// "MM/YY"
+    pub fn display(&self) -> String {
+        format!("{:02}/{:02}", self.month, self.year % 100)
+    }
+}
+
+/// What the UI shows for a card without decrypting the number on screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardDisplay {
+    pub brand: Option<CardBrand>,
+    pub masked_number: Option<String>,
+    pub expiry: Option<String>,
+    pub expired: bool,
+}
+
+fn digits_only(number: &str) -> String {
+    number
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect()
+}
+
+/// Brand from the issuer prefix; works on partial numbers as the user types
+pub fn detect_brand(number: &str) -> Option<CardBrand> {
+    let digits = digits_only(number);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let prefix = |len: usize| digits.get(..len).and_then(|p| p.parse::<u32>().ok());
+    let in_range =
+        |len: usize, low: u32, high: u32| prefix(len).is_some_and(|p| (low..=high).contains(&p));
+
+    if digits.starts_with('4') {
+        Some(CardBrand::Visa)
+    } else if in_range(2, 34, 34) || in_range(2, 37, 37) {
+        Some(CardBrand::Amex)
+    } else if in_range(2, 51, 55) || in_range(4, 2221, 2720) {
+        Some(CardBrand::Mastercard)
+    } else if in_range(4, 6011, 6011) || in_range(3, 644, 649) || in_range(2, 65, 65) {
+        Some(CardBrand::Discover)
+    } else if in_range(3, 300, 305) || in_range(2, 36, 36) || in_range(2, 38, 39) {
+        Some(CardBrand::Diners)
+    } else if in_range(4, 3528, 3589) {
+        Some(CardBrand::Jcb)
+    } else if in_range(2, 62, 62) {
+        Some(CardBrand::UnionPay)
+    } else if in_range(2, 50, 50) || in_range(2, 56, 69) {
+        Some(CardBrand::Maestro)
+    } else {
+        None
+    }
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    // Every second digit from the right is doubled
+    let digits: Vec<u32> = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .collect();
+    let kept: u32 = digits.iter().step_by(2).sum();
+    let doubled: u32 = digits
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .map(|d| if d * 2 > 9 { d * 2 - 9 } else { d * 2 })
+        .sum();
+    (kept + doubled).is_multiple_of(10)
+}
+
+/// "•••• 4242"
+pub fn mask_card_number(number: &str) -> String {
+    let digits = digits_only(number);
+    let last4: String = digits
+        .chars()
+        .skip(digits.chars().count().saturating_sub(4))
+        .collect();
+    format!("{} {}", MASK.to_string().repeat(4), last4)
+}
+
+/// Month "1"–"12" (leading zero optional) and a two- or four-digit year
+pub fn parse_expiry(month: &str, year: &str) -> Result<CardExpiry> {
+    let invalid = || AppError::InvalidOperation("Invalid card expiry date".to_string());
+
+    let month: u32 = month.trim().parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) {
+        return Err(invalid());
+    }
+
+    let year_text = year.trim();
+    let year: i32 = year_text.parse().map_err(|_| invalid())?;
+    let year = match year_text.len() {
+        2 => 2000 + year,
+        4 => year,
+        _ => return Err(invalid()),
+    };
+
+    Ok(CardExpiry { month, year })
+}
+
+/// "MM/YY", "MM/YYYY" or "MM-YY", as typed into a single expiry box
+pub fn parse_expiry_text(text: &str) -> Result<CardExpiry> {
+    let (month, year) = text
+        .split_once(['/', '-'])
+        .ok_or_else(|| AppError::InvalidOperation("Invalid card expiry date".to_string()))?;
+    parse_expiry(month, year)
+}
+
+fn card_expiry(card: &Card) -> Result<Option<CardExpiry>> {
+    match (non_empty(&card.exp_month), non_empty(&card.exp_year)) {
+        (Some(month), Some(year)) => parse_expiry(month, year).map(Some),
+        (None, None) => Ok(None),
+        _ => Err(AppError::InvalidOperation(
+            "Card expiry needs both a month and a year".to_string(),
+        )),
+    }
+}
+
+/// Check a card's details. On an update only what differs from `previous`
+/// is checked, so a saved card that fails the checks can still be edited.
+pub fn validate_card(card: &Card, previous: Option<&Card>) -> Result<()> {
+    let changed = |field: fn(&Card) -> &Option<String>| {
+        previous.is_none_or(|previous| field(previous) != field(card))
+    };
+
+    let digits = non_empty(&card.number).map(digits_only);
+    if let Some(digits) = digits.as_deref().filter(|_| changed(|c| &c.number)) {
+        if !digits.chars().all(|c| c.is_ascii_digit())
+            || !(12..=19).contains(&digits.len())
+            || !luhn_valid(digits)
+        {
+            return Err(AppError::InvalidOperation(
+                "Invalid card number".to_string(),
+            ));
+        }
+    }
+    let brand = digits.as_deref().and_then(detect_brand);
+
+    if let Some(code) = non_empty(&card.code).filter(|_| changed(|c| &c.code)) {
+        let lengths = match brand {
+            Some(CardBrand::Amex) => 4..=4,
+            Some(_) => 3..=3,
+            None => 3..=4,
+        };
+        if !code.chars().all(|c| c.is_ascii_digit()) || !lengths.contains(&code.len()) {
+            return Err(AppError::InvalidOperation(
+                "Invalid card security code".to_string(),
+            ));
+        }
+    }
+
+    if changed(|c| &c.exp_month) || changed(|c| &c.exp_year) {
+        card_expiry(card)?;
+    }
+    Ok(())
+}
+
+pub fn card_display(card: &Card) -> CardDisplay {
+    let expiry = card_expiry(card).ok().flatten();
+    CardDisplay {
+        brand: non_empty(&card.number).and_then(detect_brand),
+        masked_number: non_empty(&card.number).map(mask_card_number),
+        expired: expiry.is_some_and(|e| e.is_expired(Utc::now().date_naive())),
+        expiry: expiry.map(|e| e.display()),
+    }
+}
+
+// ============================================
+// Identities
+// ============================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub title: Option<String>,
+    pub first_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address1: Option<String>,
+    pub address2: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    pub company: Option<String>,
+    pub ssn: Option<String>,
+    pub passport_number: Option<String>,
+    pub license_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityDisplay {
+    pub full_name: Option<String>,
+    pub masked_ssn: Option<String>,
+    pub masked_passport_number: Option<String>,
+    pub masked_license_number: Option<String>,
+}
+
+/// All but the last four characters masked, keeping separators
+pub fn mask_identifier(value: &str) -> String {
+    let significant = value.chars().filter(|c| c.is_alphanumeric()).count();
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen <= significant.saturating_sub(4) {
+                MASK
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Check an identity's email address and phone number, as `validate_card`
+/// only where they differ from `previous`
+pub fn validate_identity(identity: &Identity, previous: Option<&Identity>) -> Result<()> {
+    let changed = |field: fn(&Identity) -> &Option<String>| {
+        previous.is_none_or(|previous| field(previous) != field(identity))
+    };
+
+    if let Some(email) = non_empty(&identity.email).filter(|_| changed(|i| &i.email)) {
+        let valid = email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+        if !valid || email.contains(char::is_whitespace) {
+            return Err(AppError::InvalidOperation(
+                "Invalid email address".to_string(),
+            ));
+        }
+    }
+
+    if let Some(phone) = non_empty(&identity.phone).filter(|_| changed(|i| &i.phone)) {
+        let valid = phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+            && phone.chars().any(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(AppError::InvalidOperation(
+                "Invalid phone number".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn identity_display(identity: &Identity) -> IdentityDisplay {
+    let name: Vec<&str> = [
+        &identity.title,
+        &identity.first_name,
+        &identity.middle_name,
+        &identity.last_name,
+    ]
+    .into_iter()
+    .filter_map(non_empty)
+    .collect();
+
+    IdentityDisplay {
+        full_name: (!name.is_empty()).then(|| name.join(" ")),
+        masked_ssn: non_empty(&identity.ssn).map(mask_identifier),
+        masked_passport_number: non_empty(&identity.passport_number).map(mask_identifier),
+        masked_license_number: non_empty(&identity.license_number).map(mask_identifier),
+    }
+}
+
+// ============================================
+// Validation
+// ============================================
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// The payload's `key` section, if present
+fn section<T: serde::de::DeserializeOwned + Default>(data: &Value, key: &str) -> Result<T> {
+    match data.get(key) {
+        None | Some(Value::Null) => Ok(T::default()),
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| AppError::InvalidOperation(format!("Invalid {}: {}", key, e))),
+    }
+}
+
+fn validate_login_uris(data: &Value) -> Result<()> {
+    let uris: Vec<LoginUri> = match data.pointer("/login/uris") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(uris) => serde_json::from_value(uris.clone())
+            .map_err(|e| AppError::InvalidOperation(format!("Invalid login URIs: {}", e)))?,
+    };
+    uris.iter()
+        .filter(|uri| uri.match_type(UriMatch::Domain) == UriMatch::Regex)
+        .try_for_each(|uri| urimatch::validate_pattern(&uri.uri))
+}
+
+/// Check the type-specific part of a decrypted payload. `previous` is the
+/// stored version when updating an item.
+pub fn validate(item_type: ItemType, data: &Value, previous: Option<&Value>) -> Result<()> {
+    match item_type {
+        ItemType::Login => validate_login_uris(data),
+        ItemType::Card => {
+            let previous = previous.and_then(|previous| section(previous, "card").ok());
+            validate_card(&section(data, "card")?, previous.as_ref())
+        }
+        ItemType::Identity => {
+            let previous = previous.and_then(|previous| section(previous, "identity").ok());
+            validate_identity(&section(data, "identity")?, previous.as_ref())
+        }
+        ItemType::Passkey => match section::<Option<Passkey>>(data, "passkey")? {
+            Some(passkey) => passkeys::validate(&passkey),
+            None => Err(AppError::InvalidOperation(
+                "Passkey item has no credential".to_string(),
+            )),
+        },
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,127 @@
+// ============================================
+// BirchVault Desktop - Account Key Pair
+// ============================================
+//
+// Each account has an RSA-OAEP (SHA-256) key pair so other accounts can hand
+// it keys without a shared secret. The public key (SPKI, base64) lives in
+// `vault_user_public_keys`, readable by anyone; the private key (PKCS#8) is
+// encrypted with the vault key and kept on the account's own profile row.
+// Both formats import directly into WebCrypto, so web clients can share
+// the pair.
+
+use crate::crypto::{self, EncryptedData};
+use crate::error::{AppError, Result};
+use crate::sync::SyncEngine;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::Method;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use serde::Deserialize;
+
+const KEY_BITS: usize = 2048;
+const KEY_TYPE: &str = "RSA-OAEP";
+
+#[derive(Debug, Deserialize)]
+struct ProfileKeyRow {
+    encrypted_private_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PublicKeyRow {
+    public_key: String,
+}
+
+fn key_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Encryption(format!("Account key error: {}", e))
+}
+
+fn oaep() -> Oaep {
+    Oaep::new::<sha2::Sha256>()
+}
+
+/// Encrypt a small secret (a vault or item key) to someone's public key
+pub fn wrap(public_key: &str, secret: &[u8]) -> Result<String> {
+    let der = BASE64.decode(public_key).map_err(key_error)?;
+    let public_key = RsaPublicKey::from_public_key_der(&der).map_err(key_error)?;
+    let wrapped = public_key
+        .encrypt(&mut rand::thread_rng(), oaep(), secret)
+        .map_err(key_error)?;
+    Ok(BASE64.encode(wrapped))
+}
+
+/// Decrypt something `wrap`ped to our public key
+pub fn unwrap(private_key: &RsaPrivateKey, wrapped: &str) -> Result<Vec<u8>> {
+    let wrapped = BASE64.decode(wrapped).map_err(key_error)?;
+    private_key.decrypt(oaep(), &wrapped).map_err(key_error)
+}
+
+/// Another account's public key, if it has set one up
+pub async fn public_key_for(engine: &SyncEngine, user_id: &str) -> Result<Option<String>> {
+    let rows: Vec<PublicKeyRow> = engine
+        .rest_get(&format!(
+            "vault_user_public_keys?user_id=eq.{}&select=public_key",
+            user_id
+        ))
+        .await?;
+    Ok(rows.into_iter().next().map(|row| row.public_key))
+}
+
+/// Our private key, creating and publishing the pair on first use
+pub async fn ensure(engine: &SyncEngine, vault_key: &[u8]) -> Result<RsaPrivateKey> {
+    let session = engine.active_session().await?;
+    let rows: Vec<ProfileKeyRow> = engine
+        .rest_get(&format!(
+            "vault_profiles?id=eq.{}&select=encrypted_private_key",
+            session.user_id
+        ))
+        .await?;
+
+    if let Some(encrypted) = rows
+        .into_iter()
+        .next()
+        .and_then(|r| r.encrypted_private_key)
+    {
+        let encrypted: EncryptedData = serde_json::from_str(&encrypted)?;
+        let der = crypto::decrypt(&encrypted, vault_key)?;
+        return RsaPrivateKey::from_pkcs8_der(&der).map_err(key_error);
+    }
+
+    // RSA generation takes a moment; keep it off the async workers
+    let private_key = tokio::task::spawn_blocking(|| {
+        RsaPrivateKey::new(&mut rand::thread_rng(), KEY_BITS).map_err(key_error)
+    })
+    .await
+    .map_err(key_error)??;
+
+    let public_der = private_key
+        .to_public_key()
+        .to_public_key_der()
+        .map_err(key_error)?;
+    let private_der = private_key.to_pkcs8_der().map_err(key_error)?;
+    let encrypted_private_key =
+        serde_json::to_string(&crypto::encrypt(private_der.as_bytes(), vault_key)?)?;
+
+    // Private half first: a published public key without it would be unusable
+    engine
+        .rest_write(
+            Method::PATCH,
+            &format!("vault_profiles?id=eq.{}", session.user_id),
+            Some(&serde_json::json!({ "encrypted_private_key": encrypted_private_key })),
+            None,
+        )
+        .await?;
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_user_public_keys?on_conflict=user_id",
+            Some(&serde_json::json!({
+                "user_id": session.user_id,
+                "public_key": BASE64.encode(public_der.as_bytes()),
+                "key_type": KEY_TYPE,
+            })),
+            Some("resolution=merge-duplicates"),
+        )
+        .await?;
+
+    Ok(private_key)
+}
@@ -0,0 +1,93 @@
+// ============================================
+// BirchVault Desktop - Notifications
+// ============================================
+//
+// Every notification raised by the backend goes through `dispatch`, which
+// honours the per-category toggles and quiet hours in `AppSettings`.
+
+use crate::commands::AppState;
+use crate::db::AppSettings;
+use crate::error::{AppError, Result};
+use chrono::{Local, NaiveTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    SyncFailure,
+    /// Always shown during quiet hours
+    SecurityAlert,
+    ExpiryReminder,
+    ClipboardCleared,
+}
+
+impl NotificationCategory {
+    fn enabled(self, settings: &AppSettings) -> bool {
+        match self {
+            NotificationCategory::SyncFailure => settings.notify_sync_failures,
+            NotificationCategory::SecurityAlert => settings.notify_security_alerts,
+            NotificationCategory::ExpiryReminder => settings.notify_expiry_reminders,
+            NotificationCategory::ClipboardCleared => settings.notify_clipboard_cleared,
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value, TIME_FORMAT).map_err(|_| {
+        AppError::InvalidOperation(format!("Quiet hours must be HH:MM, got \"{}\"", value))
+    })
+}
+
+/// Quiet hours need both ends, each as "HH:MM"
+pub fn validate_quiet_hours(settings: &AppSettings) -> Result<()> {
+    match (&settings.quiet_hours_start, &settings.quiet_hours_end) {
+        (Some(start), Some(end)) => {
+            parse_time(start)?;
+            parse_time(end)?;
+            Ok(())
+        }
+        (None, None) => Ok(()),
+        _ => Err(AppError::InvalidOperation(
+            "Quiet hours need both a start and an end time".to_string(),
+        )),
+    }
+}
+
+/// True if `now` falls in the quiet hours window, which may wrap past midnight
+fn in_quiet_hours(settings: &AppSettings, now: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (&settings.quiet_hours_start, &settings.quiet_hours_end) else {
+        return false;
+    };
+    let (Ok(start), Ok(end)) = (parse_time(start), parse_time(end)) else {
+        return false;
+    };
+
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// Show a system notification if the user's preferences allow it
+pub fn dispatch(app: &AppHandle, category: NotificationCategory, title: &str, body: &str) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let settings = state.db.get_settings().unwrap_or_default();
+
+    if !category.enabled(&settings) {
+        return;
+    }
+    if category != NotificationCategory::SecurityAlert
+        && in_quiet_hours(&settings, Local::now().time())
+    {
+        return;
+    }
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,97 @@
+// ============================================
+// BirchVault Desktop - Offline Profile
+// ============================================
+//
+// A local-only vault with no Supabase account. The profile record lives in
+// `app_meta`; its unlock verifier (see crypto::create_verifier) lives in the
+// OS keyring and must be present, since there is no server to fall back on.
+// Nothing in an offline profile touches the network, unless it is set up to
+// sync through a WebDAV server (see filesync.rs).
+
+use crate::crypto::{self, Verification};
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::i18n;
+use chrono::Utc;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const KEYRING_SERVICE: &str = "birchvault-offline";
+const META_OFFLINE_PROFILE: &str = "offline_profile";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineProfile {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+pub fn get(db: &Database) -> Result<Option<OfflineProfile>> {
+    db.get_meta(META_OFFLINE_PROFILE)?
+        .map(|json| serde_json::from_str(&json).map_err(AppError::from))
+        .transpose()
+}
+
+pub fn is_active(db: &Database) -> bool {
+    matches!(get(db), Ok(Some(_)))
+}
+
+/// Refuse anything that would reach the network from an offline profile
+pub fn ensure_network_allowed(db: &Database) -> Result<()> {
+    if is_active(db) {
+        return Err(AppError::InvalidOperation(i18n::t(
+            "auth.offline_profile_no_network",
+        )));
+    }
+    Ok(())
+}
+
+/// Set up a new offline profile; only possible on a device with no account
+pub fn create(db: &Database, name: &str, master_key_hash: &str) -> Result<OfflineProfile> {
+    if db.get_session()?.is_some() || get(db)?.is_some() {
+        return Err(AppError::InvalidOperation(i18n::t("auth.profile_exists")));
+    }
+
+    let profile = OfflineProfile {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    Entry::new(KEYRING_SERVICE, &profile.id)?
+        .set_password(&crypto::create_verifier(master_key_hash)?)?;
+    db.set_meta(META_OFFLINE_PROFILE, &serde_json::to_string(&profile)?)?;
+
+    Ok(profile)
+}
+
+/// Check `master_key_hash` against the keyring verifier; a missing verifier
+/// fails. A hash stored by older versions is replaced with a verifier on
+/// success.
+pub fn verify(profile: &OfflineProfile, master_key_hash: &str) -> Result<()> {
+    let entry = Entry::new(KEYRING_SERVICE, &profile.id)?;
+    let stored = entry
+        .get_password()
+        .map_err(|_| AppError::Auth(i18n::t("auth.offline_verifier_missing")))?;
+
+    match crypto::verify_secret(&stored, master_key_hash) {
+        Verification::Valid => Ok(()),
+        Verification::ValidLegacy => {
+            if let Err(e) = crypto::create_verifier(master_key_hash)
+                .and_then(|verifier| Ok(entry.set_password(&verifier)?))
+            {
+                log::warn!("Failed to upgrade offline profile verifier: {}", e);
+            }
+            Ok(())
+        }
+        Verification::Invalid => Err(AppError::Auth(i18n::t("auth.invalid_master_password"))),
+    }
+}
+
+/// Forget the verifier; the profile record goes with the rest of `app_meta`
+pub fn remove(profile: &OfflineProfile) {
+    if let Ok(entry) = Entry::new(KEYRING_SERVICE, &profile.id) {
+        let _ = entry.delete_password();
+    }
+}
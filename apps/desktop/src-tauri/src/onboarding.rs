@@ -0,0 +1,95 @@
+// ============================================
+// BirchVault Desktop - Onboarding
+// ============================================
+//
+// First-run setup progress, kept in `app_meta` so a half-finished setup picks
+// up where it left off after a restart. Steps are recorded explicitly, by the
+// frontend or by the backend where it does the work itself (sign-in, the
+// first sync), rather than inferred from whatever state happens to exist.
+
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+const META_ONBOARDING: &str = "onboarding";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    ServerConfigured,
+    AccountCreated,
+    MasterPasswordSet,
+    FirstSyncDone,
+    BackupConfigured,
+}
+
+impl OnboardingStep {
+    /// In the order setup walks through them
+    pub const ALL: [OnboardingStep; 5] = [
+        OnboardingStep::ServerConfigured,
+        OnboardingStep::AccountCreated,
+        OnboardingStep::MasterPasswordSet,
+        OnboardingStep::FirstSyncDone,
+        OnboardingStep::BackupConfigured,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepRecord {
+    pub step: OnboardingStep,
+    pub completed_at: String,
+    /// Passed over rather than done, e.g. the server step for an offline profile
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    pub steps: Vec<StepRecord>,
+    /// First step not yet done or skipped; `None` once setup is finished
+    pub next_step: Option<OnboardingStep>,
+    pub finished: bool,
+}
+
+pub fn get(db: &Database) -> Result<OnboardingState> {
+    let steps: Vec<StepRecord> = match db.get_meta(META_ONBOARDING)? {
+        Some(json) => serde_json::from_str(&json).map_err(AppError::from)?,
+        None => Vec::new(),
+    };
+
+    let next_step = OnboardingStep::ALL
+        .into_iter()
+        .find(|step| !steps.iter().any(|r| r.step == *step));
+    Ok(OnboardingState {
+        steps,
+        next_step,
+        finished: next_step.is_none(),
+    })
+}
+
+/// Record a step; completing one again keeps its first timestamp
+pub fn complete(db: &Database, step: OnboardingStep, skipped: bool) -> Result<OnboardingState> {
+    let mut state = get(db)?;
+    if !state.steps.iter().any(|r| r.step == step) {
+        state.steps.push(StepRecord {
+            step,
+            completed_at: Utc::now().to_rfc3339(),
+            skipped,
+        });
+        db.set_meta(META_ONBOARDING, &serde_json::to_string(&state.steps)?)?;
+    }
+    get(db)
+}
+
+/// Record steps the backend has just done itself; failures only cost the
+/// user a prompt they've already answered, so they are logged, not returned
+pub fn record(db: &Database, steps: &[OnboardingStep], skipped: bool) {
+    for step in steps {
+        if let Err(e) = complete(db, *step, skipped) {
+            log::warn!("Failed to record onboarding step {:?}: {}", step, e);
+        }
+    }
+}
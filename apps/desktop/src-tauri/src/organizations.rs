@@ -0,0 +1,451 @@
+// ============================================
+// BirchVault Desktop - Organizations
+// ============================================
+//
+// Teams share items through organizations. Each organization has a random
+// org key; its items are ordinary server vault items with `organization_id`
+// set, encrypted with the org key instead of anyone's vault key. Members
+// receive the org key wrapped with their public key (see keypair.rs), and
+// this device keeps it encrypted with the vault key once unwrapped.
+//
+// Roles decide who can change what:
+//
+//   owner, admin   manage members and collections, edit any org item
+//   member         read every org item; edit items in collections where
+//                  they have write access
+//
+// The sync engine pulls memberships, collections and org items alongside
+// the personal vault and pushes org item edits through the sync queue.
+// Membership and collection changes are made online and followed by a
+// refresh. Removing a member revokes their server access but does not
+// rotate the org key.
+
+use crate::crypto;
+use crate::db::{Collection, Database, OrgItem, Organization};
+use crate::error::{AppError, Result};
+use crate::keypair;
+use crate::sync::SyncEngine;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrgRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl OrgRole {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "owner" => Some(OrgRole::Owner),
+            "admin" => Some(OrgRole::Admin),
+            "member" => Some(OrgRole::Member),
+            _ => None,
+        }
+    }
+
+    pub fn can_manage(self) -> bool {
+        matches!(self, OrgRole::Owner | OrgRole::Admin)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionPermission {
+    Read,
+    Write,
+    Admin,
+}
+
+/// A member of an organization, as listed for its admins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgMember {
+    pub user_id: String,
+    pub email: Option<String>,
+    pub role: OrgRole,
+    pub status: String,
+}
+
+/// An org item, decrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgVaultItem {
+    pub id: String,
+    pub organization_id: String,
+    pub item_type: String,
+    pub data: Value,
+    pub collection_ids: Vec<String>,
+    /// Whether our role and collection access let us change it
+    pub editable: bool,
+    pub deleted_at: Option<String>,
+    pub updated_at: String,
+    pub synced_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recipient {
+    user_id: String,
+    public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberRow {
+    user_id: String,
+    email: Option<String>,
+    role: String,
+    status: String,
+}
+
+/// Our role in an accepted membership; invitations grant nothing yet
+pub fn role(org: &Organization) -> Option<OrgRole> {
+    (org.status == "accepted")
+        .then(|| OrgRole::parse(&org.role))
+        .flatten()
+}
+
+pub fn can_write_collection(org: &Organization, collections: &[Collection], id: &str) -> bool {
+    match role(org) {
+        Some(role) if role.can_manage() => true,
+        Some(_) => collections.iter().any(|c| {
+            c.id == id && matches!(c.permission.as_deref(), Some("write") | Some("admin"))
+        }),
+        None => false,
+    }
+}
+
+/// Admins can change any item; members one in a collection they can write to
+pub fn can_write_item(org: &Organization, collections: &[Collection], item: &OrgItem) -> bool {
+    role(org).is_some_and(|role| role.can_manage())
+        || item
+            .collection_ids
+            .iter()
+            .any(|id| can_write_collection(org, collections, id))
+}
+
+// ============================================
+// Keys and items
+// ============================================
+
+/// The org key, unwrapping it with our private key the first time
+pub async fn org_key(
+    engine: &SyncEngine,
+    db: &Database,
+    vault_key: &[u8],
+    org: &Organization,
+) -> Result<Vec<u8>> {
+    if let Some(encrypted) = &org.encrypted_key {
+        return crypto::decrypt(&serde_json::from_str(encrypted)?, vault_key);
+    }
+
+    let wrapped = org.wrapped_key.as_deref().ok_or_else(|| {
+        AppError::InvalidOperation("This organization hasn't shared its key with you".to_string())
+    })?;
+    let private_key = keypair::ensure(engine, vault_key).await?;
+    let key = keypair::unwrap(&private_key, wrapped)?;
+
+    let encrypted = serde_json::to_string(&crypto::encrypt(&key, vault_key)?)?;
+    db.set_organization_key(&org.id, &encrypted)?;
+    Ok(key)
+}
+
+pub fn encrypt_data(data: &Value, org_key: &[u8]) -> Result<String> {
+    crypto::encrypt_to_json(&data.to_string(), org_key)
+}
+
+pub fn decrypt_item(
+    item: OrgItem,
+    org_key: &[u8],
+    org: &Organization,
+    collections: &[Collection],
+) -> Result<OrgVaultItem> {
+    let data = serde_json::from_str(&crypto::decrypt_from_json(&item.encrypted_data, org_key)?)?;
+    Ok(OrgVaultItem {
+        editable: can_write_item(org, collections, &item),
+        id: item.id,
+        organization_id: item.organization_id,
+        item_type: item.item_type,
+        data,
+        collection_ids: item.collection_ids,
+        deleted_at: item.deleted_at,
+        updated_at: item.local_updated_at,
+        synced_at: item.synced_at,
+    })
+}
+
+// ============================================
+// Membership
+// ============================================
+
+/// Create an organization owned by us; returns its id
+pub async fn create(engine: &SyncEngine, vault_key: &[u8], name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidOperation(
+            "Organization name is required".to_string(),
+        ));
+    }
+
+    let session = engine.active_session().await?;
+    keypair::ensure(engine, vault_key).await?;
+    let public_key = keypair::public_key_for(engine, &session.user_id)
+        .await?
+        .ok_or_else(|| AppError::Encryption("Account public key is missing".to_string()))?;
+
+    let org_key = crypto::generate_key();
+    let id: String = engine
+        .rpc_query(
+            "create_vault_organization",
+            serde_json::json!({
+                "p_name": name,
+                "p_email": session.email,
+                "p_encrypted_org_key": keypair::wrap(&public_key, &org_key)?,
+            }),
+        )
+        .await?;
+
+    engine.refresh_organizations().await?;
+    Ok(id)
+}
+
+/// Invite an account by email. It must have a key pair, since the org key
+/// is wrapped for it now rather than on acceptance.
+pub async fn invite(
+    engine: &SyncEngine,
+    db: &Database,
+    vault_key: &[u8],
+    org: &Organization,
+    email: &str,
+    role: OrgRole,
+) -> Result<()> {
+    if role == OrgRole::Owner {
+        return Err(AppError::InvalidOperation(
+            "An organization has a single owner".to_string(),
+        ));
+    }
+    let email = email.trim().to_lowercase();
+
+    let recipients: Vec<Recipient> = engine
+        .rpc_query(
+            "lookup_sharing_recipient",
+            serde_json::json!({ "p_email": email }),
+        )
+        .await?;
+    let recipient = recipients.into_iter().next().ok_or_else(|| {
+        AppError::NotFound(
+            "No BirchVault account with sharing set up uses that email address".to_string(),
+        )
+    })?;
+
+    let key = org_key(engine, db, vault_key, org).await?;
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_org_members",
+            Some(&serde_json::json!({
+                "organization_id": org.id,
+                "user_id": recipient.user_id,
+                "email": email,
+                "role": role,
+                "status": "invited",
+                "encrypted_org_key": keypair::wrap(&recipient.public_key, &key)?,
+            })),
+            None,
+        )
+        .await
+}
+
+/// Accept an invitation; the organization's items arrive with the refresh
+pub async fn accept(engine: &SyncEngine, org_id: &str) -> Result<()> {
+    engine
+        .rpc("vault_org_accept", serde_json::json!({ "p_org": org_id }))
+        .await?;
+    engine.refresh_organizations().await
+}
+
+pub async fn list_members(engine: &SyncEngine, org_id: &str) -> Result<Vec<OrgMember>> {
+    let rows: Vec<MemberRow> = engine
+        .rest_get(&format!(
+            "vault_org_members?organization_id=eq.{}&select=user_id,email,role,status\
+             &order=created_at.asc",
+            org_id
+        ))
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(OrgMember {
+                role: OrgRole::parse(&row.role)?,
+                user_id: row.user_id,
+                email: row.email,
+                status: row.status,
+            })
+        })
+        .collect())
+}
+
+pub async fn set_member_role(
+    engine: &SyncEngine,
+    org_id: &str,
+    user_id: &str,
+    role: OrgRole,
+) -> Result<()> {
+    if role == OrgRole::Owner {
+        return Err(AppError::InvalidOperation(
+            "Ownership can't be transferred here".to_string(),
+        ));
+    }
+    engine
+        .rest_write(
+            Method::PATCH,
+            &format!(
+                "vault_org_members?organization_id=eq.{}&user_id=eq.{}",
+                org_id, user_id
+            ),
+            Some(&serde_json::json!({ "role": role })),
+            None,
+        )
+        .await
+}
+
+/// Remove a member, or leave when `user_id` is our own
+pub async fn remove_member(engine: &SyncEngine, org_id: &str, user_id: &str) -> Result<()> {
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!(
+                "vault_org_members?organization_id=eq.{}&user_id=eq.{}",
+                org_id, user_id
+            ),
+            None,
+            None,
+        )
+        .await?;
+    engine.refresh_organizations().await
+}
+
+// ============================================
+// Collections
+// ============================================
+
+pub async fn create_collection(engine: &SyncEngine, org_id: &str, name: &str) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidOperation(
+            "Collection name is required".to_string(),
+        ));
+    }
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_org_collections",
+            Some(&serde_json::json!({ "organization_id": org_id, "name": name })),
+            None,
+        )
+        .await?;
+    engine.refresh_organizations().await
+}
+
+/// Items stay in the organization; they just leave the collection
+pub async fn delete_collection(engine: &SyncEngine, collection_id: &str) -> Result<()> {
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_org_collections?id=eq.{}", collection_id),
+            None,
+            None,
+        )
+        .await?;
+    engine.refresh_organizations().await
+}
+
+/// Grant a member access to a collection, or take it away with `None`
+pub async fn set_collection_access(
+    engine: &SyncEngine,
+    collection_id: &str,
+    user_id: &str,
+    permission: Option<CollectionPermission>,
+) -> Result<()> {
+    match permission {
+        Some(permission) => {
+            engine
+                .rest_write(
+                    Method::POST,
+                    "vault_collection_access?on_conflict=collection_id,user_id",
+                    Some(&serde_json::json!({
+                        "collection_id": collection_id,
+                        "user_id": user_id,
+                        "permission": permission,
+                    })),
+                    Some("resolution=merge-duplicates"),
+                )
+                .await?
+        }
+        None => {
+            engine
+                .rest_write(
+                    Method::DELETE,
+                    &format!(
+                        "vault_collection_access?collection_id=eq.{}&user_id=eq.{}",
+                        collection_id, user_id
+                    ),
+                    None,
+                    None,
+                )
+                .await?
+        }
+    }
+    engine.refresh_organizations().await
+}
+
+/// Move a synced org item between collections
+pub async fn set_item_collections(
+    engine: &SyncEngine,
+    db: &Database,
+    item: &OrgItem,
+    collection_ids: &[String],
+) -> Result<()> {
+    let added: Vec<Value> = collection_ids
+        .iter()
+        .filter(|id| !item.collection_ids.contains(id))
+        .map(|id| serde_json::json!({ "collection_id": id, "vault_item_id": item.id }))
+        .collect();
+    let removed: Vec<&str> = item
+        .collection_ids
+        .iter()
+        .filter(|id| !collection_ids.contains(id))
+        .map(String::as_str)
+        .collect();
+
+    // Add first, so a member never drops out of write access midway
+    if !added.is_empty() {
+        engine
+            .rest_write(
+                Method::POST,
+                "vault_collection_items",
+                Some(&Value::Array(added)),
+                Some("resolution=ignore-duplicates"),
+            )
+            .await?;
+    }
+    if !removed.is_empty() {
+        engine
+            .rest_write(
+                Method::DELETE,
+                &format!(
+                    "vault_collection_items?vault_item_id=eq.{}&collection_id=in.({})",
+                    item.id,
+                    removed.join(",")
+                ),
+                None,
+                None,
+            )
+            .await?;
+    }
+
+    db.set_org_item_collections(&item.id, collection_ids)
+}
@@ -0,0 +1,330 @@
+// ============================================
+// BirchVault Desktop - Passkeys (WebAuthn)
+// ============================================
+//
+// Passkeys are stored as ordinary encrypted vault items of type "passkey",
+// so they sync like everything else. The private key never leaves the
+// backend: registration and assertion signing happen here and only the
+// public WebAuthn structures are handed back to the caller (the browser
+// extension, eventually).
+//
+// Credentials are ES256 (ECDSA P-256 with SHA-256), the algorithm every
+// relying party accepts. As synced credentials they report a signature
+// counter of zero and set the backup-eligible / backed-up flags.
+
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use chrono::Utc;
+use rand::RngCore;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// COSE algorithm identifier for ES256
+pub const ES256: i64 = -7;
+
+const CREDENTIAL_ID_LENGTH: usize = 32;
+
+/// We don't attest, so the authenticator model is reported as all zeroes
+const AAGUID: [u8; 16] = [0; 16];
+
+// Authenticator data flags
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_BACKUP_ELIGIBLE: u8 = 0x08;
+const FLAG_BACKED_UP: u8 = 0x10;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// DER prefix turning an uncompressed P-256 point into a SubjectPublicKeyInfo
+const P256_SPKI_PREFIX: [u8; 26] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+
+/// The `passkey` section of a decrypted item payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Passkey {
+    /// base64url, as the relying party sees it
+    pub credential_id: String,
+    /// base64url PKCS#8
+    pub private_key: String,
+    pub algorithm: i64,
+    pub rp_id: String,
+    pub rp_name: Option<String>,
+    /// base64url
+    pub user_handle: String,
+    pub user_name: Option<String>,
+    pub user_display_name: Option<String>,
+    pub created_at: String,
+}
+
+/// Listing view of a passkey; never carries the private key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeySummary {
+    pub item_id: String,
+    pub credential_id: String,
+    pub rp_id: String,
+    pub rp_name: Option<String>,
+    pub user_handle: String,
+    pub user_name: Option<String>,
+    pub user_display_name: Option<String>,
+}
+
+impl PasskeySummary {
+    pub fn new(item_id: String, passkey: &Passkey) -> Self {
+        PasskeySummary {
+            item_id,
+            credential_id: passkey.credential_id.clone(),
+            rp_id: passkey.rp_id.clone(),
+            rp_name: passkey.rp_name.clone(),
+            user_handle: passkey.user_handle.clone(),
+            user_name: passkey.user_name.clone(),
+            user_display_name: passkey.user_display_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewPasskey {
+    pub rp_id: String,
+    pub rp_name: Option<String>,
+    /// base64url
+    pub user_handle: String,
+    pub user_name: Option<String>,
+    pub user_display_name: Option<String>,
+}
+
+/// What `navigator.credentials.create()` needs back; all binary fields base64url
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRegistration {
+    pub credential_id: String,
+    /// DER SubjectPublicKeyInfo, for `getPublicKey()`
+    pub public_key: String,
+    pub public_key_algorithm: i64,
+    pub authenticator_data: String,
+    /// "none" attestation
+    pub attestation_object: String,
+}
+
+/// What `navigator.credentials.get()` needs back; all binary fields base64url
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAssertion {
+    pub credential_id: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: String,
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>> {
+    BASE64URL
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| AppError::InvalidOperation(format!("Invalid passkey {}", field)))
+}
+
+fn key_pair(passkey: &Passkey) -> Result<EcdsaKeyPair> {
+    let pkcs8 = decode("private key", &passkey.private_key)?;
+    EcdsaKeyPair::from_pkcs8(
+        &ECDSA_P256_SHA256_ASN1_SIGNING,
+        &pkcs8,
+        &SystemRandom::new(),
+    )
+    .map_err(|_| AppError::InvalidOperation("Invalid passkey private key".to_string()))
+}
+
+/// Relying party IDs are bare, lowercase domains
+fn normalise_rp_id(rp_id: &str) -> Result<String> {
+    let rp_id = rp_id.trim().trim_end_matches('.').to_lowercase();
+    let valid = !rp_id.is_empty()
+        && rp_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    if !valid {
+        return Err(AppError::InvalidOperation(
+            "Invalid relying party ID".to_string(),
+        ));
+    }
+    Ok(rp_id)
+}
+
+// ============================================
+// CBOR
+// ============================================
+//
+// Just enough of RFC 8949 for the COSE key and attestation object.
+
+fn cbor_header(out: &mut Vec<u8>, major: u8, length: usize) {
+    let major = major << 5;
+    match length {
+        0..=23 => out.push(major | length as u8),
+        24..=0xff => out.extend([major | 24, length as u8]),
+        _ => {
+            out.push(major | 25);
+            out.extend((length as u16).to_be_bytes());
+        }
+    }
+}
+
+fn cbor_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    cbor_header(out, 2, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn cbor_text(out: &mut Vec<u8>, text: &str) {
+    cbor_header(out, 3, text.len());
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// EC2 COSE_Key for an uncompressed P-256 point (0x04 ‖ x ‖ y)
+fn cose_public_key(point: &[u8]) -> Vec<u8> {
+    let (x, y) = point[1..].split_at(32);
+    let mut out = Vec::with_capacity(77);
+    cbor_header(&mut out, 5, 5);
+    out.extend([0x01, 0x02]); // kty: EC2
+    out.extend([0x03, 0x26]); // alg: ES256
+    out.extend([0x20, 0x01]); // crv: P-256
+    out.push(0x21); // x
+    cbor_bytes(&mut out, x);
+    out.push(0x22); // y
+    cbor_bytes(&mut out, y);
+    out
+}
+
+fn attestation_object(auth_data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(auth_data.len() + 32);
+    cbor_header(&mut out, 5, 3);
+    cbor_text(&mut out, "fmt");
+    cbor_text(&mut out, "none");
+    cbor_text(&mut out, "attStmt");
+    cbor_header(&mut out, 5, 0);
+    cbor_text(&mut out, "authData");
+    cbor_bytes(&mut out, auth_data);
+    out
+}
+
+// ============================================
+// Authenticator
+// ============================================
+
+/// rpIdHash ‖ flags ‖ signCount
+fn authenticator_data(rp_id: &str, flags: u8) -> Vec<u8> {
+    let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+    data.push(
+        FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_BACKUP_ELIGIBLE | FLAG_BACKED_UP | flags,
+    );
+    data.extend(0u32.to_be_bytes());
+    data
+}
+
+/// Generate a new credential; the caller stores the `Passkey` in the vault
+pub fn create(request: &NewPasskey) -> Result<(Passkey, PasskeyRegistration)> {
+    let rp_id = normalise_rp_id(&request.rp_id)?;
+    let user_handle = decode("user handle", &request.user_handle)?;
+    if user_handle.is_empty() || user_handle.len() > 64 {
+        return Err(AppError::InvalidOperation(
+            "Invalid passkey user handle".to_string(),
+        ));
+    }
+
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
+        .map_err(|_| AppError::Encryption("Failed to generate passkey".to_string()))?;
+
+    let mut credential_id = [0u8; CREDENTIAL_ID_LENGTH];
+    rand::thread_rng().fill_bytes(&mut credential_id);
+
+    let passkey = Passkey {
+        credential_id: BASE64URL.encode(credential_id),
+        private_key: BASE64URL.encode(pkcs8.as_ref()),
+        algorithm: ES256,
+        rp_id: rp_id.clone(),
+        rp_name: request.rp_name.clone(),
+        user_handle: BASE64URL.encode(&user_handle),
+        user_name: request.user_name.clone(),
+        user_display_name: request.user_display_name.clone(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let point = key_pair(&passkey)?.public_key().as_ref().to_vec();
+
+    let mut auth_data = authenticator_data(&rp_id, FLAG_ATTESTED_CREDENTIAL);
+    auth_data.extend(AAGUID);
+    auth_data.extend((CREDENTIAL_ID_LENGTH as u16).to_be_bytes());
+    auth_data.extend(credential_id);
+    auth_data.extend(cose_public_key(&point));
+
+    let registration = PasskeyRegistration {
+        credential_id: passkey.credential_id.clone(),
+        public_key: BASE64URL.encode([&P256_SPKI_PREFIX[..], &point].concat()),
+        public_key_algorithm: ES256,
+        authenticator_data: BASE64URL.encode(&auth_data),
+        attestation_object: BASE64URL.encode(attestation_object(&auth_data)),
+    };
+
+    Ok((passkey, registration))
+}
+
+/// Sign an assertion for `rp_id` over the SHA-256 of the clientDataJSON
+pub fn assert(passkey: &Passkey, rp_id: &str, client_data_hash: &str) -> Result<PasskeyAssertion> {
+    if normalise_rp_id(rp_id)? != passkey.rp_id {
+        return Err(AppError::InvalidOperation(
+            "Passkey does not belong to this site".to_string(),
+        ));
+    }
+    let client_data_hash = decode("client data hash", client_data_hash)?;
+    if client_data_hash.len() != 32 {
+        return Err(AppError::InvalidOperation(
+            "Invalid passkey client data hash".to_string(),
+        ));
+    }
+
+    let auth_data = authenticator_data(&passkey.rp_id, 0);
+    let signature = key_pair(passkey)?
+        .sign(
+            &SystemRandom::new(),
+            &[auth_data.as_slice(), &client_data_hash].concat(),
+        )
+        .map_err(|_| AppError::Encryption("Failed to sign passkey assertion".to_string()))?;
+
+    Ok(PasskeyAssertion {
+        credential_id: passkey.credential_id.clone(),
+        authenticator_data: BASE64URL.encode(&auth_data),
+        signature: BASE64URL.encode(signature.as_ref()),
+        user_handle: passkey.user_handle.clone(),
+    })
+}
+
+/// Full item payload for a freshly created passkey
+pub fn item_payload(passkey: &Passkey) -> Value {
+    json!({
+        "name": passkey.rp_name.as_deref().unwrap_or(&passkey.rp_id),
+        "passkey": passkey,
+    })
+}
+
+/// The passkey section of a decrypted payload, if it has one
+pub fn from_payload(data: &Value) -> Option<Passkey> {
+    serde_json::from_value(data.get("passkey")?.clone()).ok()
+}
+
+pub fn validate(passkey: &Passkey) -> Result<()> {
+    if passkey.algorithm != ES256 {
+        return Err(AppError::InvalidOperation(
+            "Unsupported passkey algorithm".to_string(),
+        ));
+    }
+    if normalise_rp_id(&passkey.rp_id)? != passkey.rp_id {
+        return Err(AppError::InvalidOperation(
+            "Invalid relying party ID".to_string(),
+        ));
+    }
+    decode("credential ID", &passkey.credential_id)?;
+    decode("user handle", &passkey.user_handle)?;
+    key_pair(passkey)?;
+    Ok(())
+}
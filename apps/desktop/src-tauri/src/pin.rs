@@ -0,0 +1,204 @@
+// ============================================
+// BirchVault Desktop - PIN Unlock
+// ============================================
+//
+// A short PIN stands in for the master password on this device. The master
+// key hash and vault key are sealed with a key derived from the PIN (Argon2id)
+// and kept in `app_meta`. A PIN has little entropy, so wrong guesses are
+// counted and the sealed keys are wiped once the limit is reached.
+
+use crate::crypto::{self, EncryptedData, KEY_LENGTH};
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::i18n;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+const META_PIN_UNLOCK: &str = "pin_unlock";
+
+const MIN_PIN_LENGTH: usize = 4;
+const MAX_PIN_LENGTH: usize = 12;
+const SALT_LENGTH: usize = 16;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const MAX_ATTEMPTS_LIMIT: u32 = 10;
+
+// Argon2id cost; higher than the verifier's since a PIN is easy to enumerate
+const ARGON2_M_COST_KIB: u32 = 64 * 1024;
+const ARGON2_T_COST: u32 = 3;
+const ARGON2_P_COST: u32 = 1;
+
+/// One attempt at a time, so parallel guesses can't all see the same count
+static ATTEMPT: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PinRecord {
+    salt: String,
+    sealed: EncryptedData,
+    failed_attempts: u32,
+    max_attempts: u32,
+    created_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SealedKeys {
+    master_key_hash: String,
+    encryption_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinStatus {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    pub remaining_attempts: u32,
+}
+
+/// What a correct PIN gives back
+pub struct UnlockedKeys {
+    pub master_key_hash: String,
+    pub vault_key: Vec<u8>,
+}
+
+fn load(db: &Database) -> Result<Option<PinRecord>> {
+    db.get_meta(META_PIN_UNLOCK)?
+        .map(|json| serde_json::from_str(&json).map_err(AppError::from))
+        .transpose()
+}
+
+fn save(db: &Database, record: &PinRecord) -> Result<()> {
+    db.set_meta(META_PIN_UNLOCK, &serde_json::to_string(record)?)
+}
+
+fn derive_key(pin: &str, salt: &[u8]) -> Result<Vec<u8>> {
+    let params = Params::new(
+        ARGON2_M_COST_KIB,
+        ARGON2_T_COST,
+        ARGON2_P_COST,
+        Some(KEY_LENGTH),
+    )
+    .map_err(|e| AppError::Encryption(format!("Invalid PIN parameters: {}", e)))?;
+
+    let mut key = vec![0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(pin.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::Encryption(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn validate_pin(pin: &str) -> Result<()> {
+    let length = pin.chars().count();
+    if !(MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&length)
+        || !pin.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(AppError::InvalidOperation(format!(
+            "A PIN must be {} to {} digits",
+            MIN_PIN_LENGTH, MAX_PIN_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn status(db: &Database) -> Result<PinStatus> {
+    Ok(match load(db)? {
+        Some(record) => PinStatus {
+            enabled: true,
+            max_attempts: record.max_attempts,
+            remaining_attempts: record.max_attempts.saturating_sub(record.failed_attempts),
+        },
+        None => PinStatus {
+            enabled: false,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            remaining_attempts: 0,
+        },
+    })
+}
+
+/// Seal the unlocked vault's keys under `pin`, replacing any previous PIN
+pub fn enable(
+    db: &Database,
+    pin: &str,
+    max_attempts: u32,
+    master_key_hash: &str,
+    vault_key: &[u8],
+) -> Result<PinStatus> {
+    validate_pin(pin)?;
+    if !(1..=MAX_ATTEMPTS_LIMIT).contains(&max_attempts) {
+        return Err(AppError::InvalidOperation(format!(
+            "Allowed attempts must be between 1 and {}",
+            MAX_ATTEMPTS_LIMIT
+        )));
+    }
+
+    let mut salt = [0u8; SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let key = derive_key(pin, &salt)?;
+
+    let keys = SealedKeys {
+        master_key_hash: master_key_hash.to_string(),
+        encryption_key: BASE64.encode(vault_key),
+    };
+    let record = PinRecord {
+        salt: BASE64.encode(salt),
+        sealed: crypto::encrypt(&serde_json::to_vec(&keys)?, &key)?,
+        failed_attempts: 0,
+        max_attempts,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    save(db, &record)?;
+    status(db)
+}
+
+pub fn disable(db: &Database) -> Result<()> {
+    db.delete_meta(META_PIN_UNLOCK)
+}
+
+/// Open the sealed keys with `pin`. A wrong PIN uses up an attempt; the last
+/// one wipes the sealed keys, leaving the master password as the way back in.
+pub fn unlock(db: &Database, pin: &str) -> Result<UnlockedKeys> {
+    let _attempt = ATTEMPT.lock().unwrap_or_else(|e| e.into_inner());
+    let mut record = load(db)?.ok_or_else(|| AppError::Auth(i18n::t("auth.pin_not_set")))?;
+
+    // Counted before checking, so quitting mid-check doesn't get a free guess
+    record.failed_attempts += 1;
+    save(db, &record)?;
+
+    let salt = BASE64
+        .decode(&record.salt)
+        .map_err(|e| AppError::Encryption(format!("Invalid PIN salt: {}", e)))?;
+    let key = derive_key(pin, &salt)?;
+
+    let plaintext = match crypto::decrypt(&record.sealed, &key) {
+        Ok(plaintext) => plaintext,
+        Err(_) => {
+            if record.failed_attempts >= record.max_attempts {
+                disable(db)?;
+                return Err(AppError::Auth(i18n::t("auth.pin_wiped")));
+            }
+            let remaining =
+                i18n::format_number((record.max_attempts - record.failed_attempts) as i64);
+            return Err(AppError::Auth(i18n::t_with(
+                "auth.pin_invalid",
+                &[("remaining", &remaining)],
+            )));
+        }
+    };
+
+    record.failed_attempts = 0;
+    save(db, &record)?;
+
+    let keys: SealedKeys = serde_json::from_slice(&plaintext)?;
+    let vault_key = BASE64
+        .decode(&keys.encryption_key)
+        .map_err(|e| AppError::Encryption(format!("Invalid sealed key: {}", e)))?;
+    Ok(UnlockedKeys {
+        master_key_hash: keys.master_key_hash,
+        vault_key,
+    })
+}
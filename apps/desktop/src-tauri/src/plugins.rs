@@ -0,0 +1,380 @@
+// ============================================
+// BirchVault Desktop - Item Action Plugins
+// ============================================
+//
+// A plugin is an executable in `<app data>/plugins/<id>/` with a
+// `plugin.json` manifest declaring item actions and the fields each needs.
+// Plugins must be trusted before use, which pins the executable's SHA-256;
+// a changed binary has to be trusted again. Every invocation needs its own
+// consent: the frontend asks for a consent token and redeems it once, and
+// the backend then shows a native dialog naming exactly which fields will be
+// shared, so a script in the webview can't consent for the user. The plugin
+// gets an empty environment and a timeout, and receives only the requested
+// fields, as JSON on stdin (never as arguments, which other processes can see).
+//
+// Deliberately less than signed, sandboxed plugins: trust is on first use,
+// so whatever binary is there when the user trusts it is what gets pinned,
+// and no publisher signature is checked. Nor is a plugin sandboxed; it runs
+// with the user's own access to files and the network.
+
+use crate::crypto;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::i18n;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+const MANIFEST_FILE: &str = "plugin.json";
+const CONSENT_TTL_SECONDS: i64 = 60;
+const RUN_TIMEOUT_SECS: u64 = 30;
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginAction {
+    pub id: String,
+    pub label: String,
+    /// Item types the action applies to; empty means all
+    #[serde(default)]
+    pub item_types: Vec<String>,
+    /// Dotted paths into the item, e.g. "login.username"
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// Path relative to the plugin directory
+    pub executable: String,
+    pub actions: Vec<PluginAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub trusted: bool,
+    /// Trusted once, but the executable has changed since
+    pub modified: bool,
+}
+
+/// Shown to the user before any field leaves the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginConsentRequest {
+    pub token: String,
+    pub plugin_name: String,
+    pub action_label: String,
+    pub item_id: String,
+    pub fields: Vec<String>,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingConsent {
+    plugin_id: String,
+    plugin_name: String,
+    action_id: String,
+    action_label: String,
+    item_id: String,
+    fields: Vec<String>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginActionResult {
+    pub success: bool,
+    /// The plugin's stdout, trimmed
+    pub output: String,
+}
+
+// ============================================
+// Discovery and Trust
+// ============================================
+
+/// `<app data>/plugins`
+pub fn plugins_dir(app: &AppHandle) -> Result<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("plugins"))
+        .map_err(|e| AppError::InvalidOperation(e.to_string()))
+}
+
+fn load_manifest(dir: &Path) -> Result<PluginManifest> {
+    let manifest: PluginManifest =
+        serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE))?)?;
+    // The executable must stay inside the plugin's own directory, symlinks
+    // resolved
+    let invalid = || {
+        AppError::InvalidOperation(format!(
+            "Plugin {} has an invalid executable path",
+            manifest.id
+        ))
+    };
+    if manifest.executable.contains("..") || Path::new(&manifest.executable).is_absolute() {
+        return Err(invalid());
+    }
+    let executable = dir
+        .join(&manifest.executable)
+        .canonicalize()
+        .map_err(|_| invalid())?;
+    if !executable.starts_with(dir.canonicalize()?) {
+        return Err(invalid());
+    }
+    Ok(manifest)
+}
+
+fn executable_hash(dir: &Path, manifest: &PluginManifest) -> Result<String> {
+    let bytes = std::fs::read(dir.join(&manifest.executable))?;
+    Ok(Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Plugin directories with a readable manifest, keyed by plugin id
+fn discover(plugins_dir: &Path) -> Result<HashMap<String, (PathBuf, PluginManifest)>> {
+    let mut plugins = HashMap::new();
+    if !plugins_dir.exists() {
+        return Ok(plugins);
+    }
+
+    for entry in std::fs::read_dir(plugins_dir)? {
+        let dir = entry?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        match load_manifest(&dir) {
+            Ok(manifest) => {
+                plugins.insert(manifest.id.clone(), (dir, manifest));
+            }
+            Err(e) => log::warn!("Skipping plugin in {:?}: {}", dir, e),
+        }
+    }
+    Ok(plugins)
+}
+
+fn find(plugins_dir: &Path, plugin_id: &str) -> Result<(PathBuf, PluginManifest)> {
+    discover(plugins_dir)?
+        .remove(plugin_id)
+        .ok_or_else(|| AppError::NotFound(format!("Plugin {}", plugin_id)))
+}
+
+pub fn list(db: &Database, plugins_dir: &Path) -> Result<Vec<PluginInfo>> {
+    let mut plugins: Vec<PluginInfo> = discover(plugins_dir)?
+        .into_values()
+        .map(|(dir, manifest)| {
+            let pinned = db.get_plugin_trust(&manifest.id)?;
+            let current = executable_hash(&dir, &manifest).ok();
+            Ok(PluginInfo {
+                trusted: pinned.is_some() && pinned == current,
+                modified: pinned.is_some() && pinned != current,
+                manifest,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    Ok(plugins)
+}
+
+/// Pin the plugin's current executable as trusted
+pub fn trust(db: &Database, plugins_dir: &Path, plugin_id: &str) -> Result<PluginInfo> {
+    let (dir, manifest) = find(plugins_dir, plugin_id)?;
+    db.set_plugin_trust(&manifest.id, &executable_hash(&dir, &manifest)?)?;
+    Ok(PluginInfo {
+        manifest,
+        trusted: true,
+        modified: false,
+    })
+}
+
+pub fn revoke(db: &Database, plugin_id: &str) -> Result<()> {
+    db.delete_plugin_trust(plugin_id)
+}
+
+fn ensure_trusted(db: &Database, dir: &Path, manifest: &PluginManifest) -> Result<()> {
+    let pinned = db.get_plugin_trust(&manifest.id)?;
+    if pinned.is_none() || pinned != Some(executable_hash(dir, manifest)?) {
+        return Err(AppError::InvalidOperation(format!(
+            "Plugin {} is not trusted, or has changed since it was trusted",
+            manifest.name
+        )));
+    }
+    Ok(())
+}
+
+// ============================================
+// Consent and Invocation
+// ============================================
+
+/// Step one: describe what the action will receive and issue a single-use token
+pub fn request_consent(
+    db: &Database,
+    plugins_dir: &Path,
+    plugin_id: &str,
+    action_id: &str,
+    item_id: &str,
+) -> Result<(PluginConsentRequest, PendingConsent)> {
+    let (dir, manifest) = find(plugins_dir, plugin_id)?;
+    ensure_trusted(db, &dir, &manifest)?;
+
+    let action = manifest
+        .actions
+        .iter()
+        .find(|a| a.id == action_id)
+        .ok_or_else(|| AppError::NotFound(format!("Plugin action {}", action_id)))?;
+    let item = db
+        .get_vault_item(item_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Vault item {}", item_id)))?;
+    if !action.item_types.is_empty() && !action.item_types.contains(&item.item_type) {
+        return Err(AppError::InvalidOperation(format!(
+            "{} doesn't apply to {} items",
+            action.label, item.item_type
+        )));
+    }
+
+    let expires_at = Utc::now() + Duration::seconds(CONSENT_TTL_SECONDS);
+    let request = PluginConsentRequest {
+        token: Uuid::new_v4().to_string(),
+        plugin_name: manifest.name.clone(),
+        action_label: action.label.clone(),
+        item_id: item_id.to_string(),
+        fields: action.fields.clone(),
+        expires_at: expires_at.to_rfc3339(),
+    };
+    let pending = PendingConsent {
+        plugin_id: manifest.id.clone(),
+        plugin_name: manifest.name.clone(),
+        action_id: action.id.clone(),
+        action_label: action.label.clone(),
+        item_id: item_id.to_string(),
+        fields: action.fields.clone(),
+        expires_at,
+    };
+    Ok((request, pending))
+}
+
+/// Ask the user, in a native dialog the webview can't answer, whether the
+/// plugin may have the fields; whether they agreed
+pub async fn confirm(app: &AppHandle, consent: &PendingConsent) -> bool {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .message(i18n::t_with(
+            "plugins.consent_body",
+            &[
+                ("plugin", &consent.plugin_name),
+                ("action", &consent.action_label),
+                ("fields", &consent.fields.join(", ")),
+            ],
+        ))
+        .title(i18n::t("plugins.consent_title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t("plugins.consent_allow"),
+            i18n::t("plugins.consent_deny"),
+        ))
+        .show(move |confirmed| {
+            let _ = tx.send(confirmed);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Only the requested paths, keyed by path; missing fields are left out
+fn select_fields(item: &Value, paths: &[String]) -> Map<String, Value> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let pointer = format!("/{}", path.replace('.', "/"));
+            item.pointer(&pointer)
+                .filter(|v| !v.is_null())
+                .map(|v| (path.clone(), v.clone()))
+        })
+        .collect()
+}
+
+/// Step two: run the action the user consented to
+pub async fn invoke(
+    db: &Database,
+    plugins_dir: &Path,
+    consent: PendingConsent,
+    vault_key: &[u8],
+) -> Result<PluginActionResult> {
+    if consent.expires_at < Utc::now() {
+        return Err(AppError::InvalidOperation(
+            "Consent has expired".to_string(),
+        ));
+    }
+
+    // Re-check trust: the binary may have been swapped since consent was given
+    let (dir, manifest) = find(plugins_dir, &consent.plugin_id)?;
+    ensure_trusted(db, &dir, &manifest)?;
+    let action = manifest
+        .actions
+        .iter()
+        .find(|a| a.id == consent.action_id)
+        .ok_or_else(|| AppError::NotFound(format!("Plugin action {}", consent.action_id)))?;
+
+    let item = db
+        .get_vault_item(&consent.item_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Vault item {}", consent.item_id)))?;
+    let data: Value =
+        serde_json::from_str(&crypto::decrypt_from_json(&item.encrypted_data, vault_key)?)?;
+    let input = serde_json::json!({
+        "action": action.id,
+        "itemType": item.item_type,
+        "fields": select_fields(&data, &action.fields),
+    });
+
+    let mut command = tokio::process::Command::new(dir.join(&manifest.executable));
+    command
+        .current_dir(&dir)
+        .env_clear()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+
+    let mut output = String::new();
+    // Writing counts towards the timeout too: a plugin that never reads its
+    // input would otherwise hold this up once the pipe fills
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.to_string().as_bytes()).await?;
+        }
+        if let Some(mut stdout) = child.stdout.take() {
+            (&mut stdout)
+                .take(MAX_OUTPUT_BYTES)
+                .read_to_string(&mut output)
+                .await?;
+            // Anything past the cap is discarded, so the plugin isn't left
+            // blocked on a full pipe until the timeout
+            tokio::io::copy(&mut stdout, &mut tokio::io::sink()).await?;
+        }
+        child.wait().await
+    };
+    let status = tokio::time::timeout(tokio::time::Duration::from_secs(RUN_TIMEOUT_SECS), run)
+        .await
+        .map_err(|_| AppError::InvalidOperation(format!("{} timed out", manifest.name)))??;
+
+    Ok(PluginActionResult {
+        success: status.success(),
+        output: output.trim().to_string(),
+    })
+}
@@ -0,0 +1,33 @@
+// ============================================
+// BirchVault Desktop - Screen Capture Protection
+// ============================================
+//
+// Marks the main window as excluded from capture while the vault is unlocked:
+// WDA_EXCLUDEFROMCAPTURE on Windows, NSWindowSharingNone on macOS. Linux
+// compositors have no equivalent, so the setting is a no-op there.
+
+use crate::commands::AppState;
+use tauri::{AppHandle, Manager};
+
+/// Apply the current setting and lock state to the main window (call after lock/unlock)
+pub fn refresh(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<AppState>() else {
+            return;
+        };
+
+        let enabled = state
+            .db
+            .get_settings()
+            .map(|s| s.screen_capture_protection)
+            .unwrap_or(true);
+        let protect = enabled && !*state.is_locked.read().await;
+
+        if let Some(window) = app.get_webview_window("main") {
+            if let Err(e) = window.set_content_protected(protect) {
+                log::warn!("Failed to update screen capture protection: {}", e);
+            }
+        }
+    });
+}
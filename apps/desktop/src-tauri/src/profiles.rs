@@ -0,0 +1,223 @@
+// ============================================
+// BirchVault Desktop - Account Profiles
+// ============================================
+//
+// Each profile is one account (or offline vault) with its own SQLCipher
+// database, attachment store and keyring entries. The registry lives in
+// `profiles.json` in the app data directory, since it has to be read before
+// any database is opened. The default profile keeps the original `vault.db`
+// and unprefixed keyring entries, so existing installs carry on unchanged.
+
+use crate::error::{AppError, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+const DEFAULT_PROFILE_LABEL: &str = "Default";
+const REGISTRY_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const DATABASE_FILE: &str = "vault.db";
+const MAX_LABEL_LENGTH: usize = 64;
+
+/// Profile whose database is open; empty means the default
+static ACTIVE_PROFILE: RwLock<String> = RwLock::new(String::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub label: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Registry {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                label: DEFAULT_PROFILE_LABEL.to_string(),
+                created_at: Utc::now().to_rfc3339(),
+            }],
+        }
+    }
+}
+
+impl Registry {
+    pub fn get(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+}
+
+// ============================================
+// Active Profile
+// ============================================
+
+pub fn active_id() -> String {
+    let active = ACTIVE_PROFILE.read().map(|a| a.clone()).unwrap_or_default();
+    if active.is_empty() {
+        DEFAULT_PROFILE_ID.to_string()
+    } else {
+        active
+    }
+}
+
+pub fn set_active(id: &str) {
+    if let Ok(mut active) = ACTIVE_PROFILE.write() {
+        *active = id.to_string();
+    }
+}
+
+/// Keyring user name for `name` in the active profile
+pub fn keyring_user(name: &str) -> String {
+    keyring_user_for(&active_id(), name)
+}
+
+/// Keyring user name for `name` in `profile_id`; the default profile's
+/// entries predate profiles and keep their names
+pub fn keyring_user_for(profile_id: &str, name: &str) -> String {
+    if profile_id == DEFAULT_PROFILE_ID {
+        name.to_string()
+    } else {
+        format!("{}:{}", profile_id, name)
+    }
+}
+
+/// Where a profile's database, attachments and backups live
+pub fn data_dir(app_data_dir: &Path, profile_id: &str) -> PathBuf {
+    if profile_id == DEFAULT_PROFILE_ID {
+        app_data_dir.to_path_buf()
+    } else {
+        profile_dir(app_data_dir, profile_id)
+    }
+}
+
+pub fn database_path(app_data_dir: &Path, profile_id: &str) -> PathBuf {
+    data_dir(app_data_dir, profile_id).join(DATABASE_FILE)
+}
+
+fn profile_dir(app_data_dir: &Path, profile_id: &str) -> PathBuf {
+    app_data_dir.join(PROFILES_DIR).join(profile_id)
+}
+
+// ============================================
+// Registry
+// ============================================
+
+/// The registry, or one holding just the default profile on first run. An
+/// active profile that has gone missing falls back to the default.
+pub fn load(app_data_dir: &Path) -> Result<Registry> {
+    let path = app_data_dir.join(REGISTRY_FILE);
+    let mut registry = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str::<Registry>(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Registry::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    if registry.get(DEFAULT_PROFILE_ID).is_none() {
+        registry
+            .profiles
+            .insert(0, Registry::default().profiles.remove(0));
+    }
+    if registry.get(&registry.active).is_none() {
+        registry.active = DEFAULT_PROFILE_ID.to_string();
+    }
+    Ok(registry)
+}
+
+fn save(app_data_dir: &Path, registry: &Registry) -> Result<()> {
+    std::fs::create_dir_all(app_data_dir)?;
+    let path = app_data_dir.join(REGISTRY_FILE);
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_vec_pretty(registry)?)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+pub fn create(app_data_dir: &Path, label: &str) -> Result<Profile> {
+    let label = validate_label(label)?;
+    let mut registry = load(app_data_dir)?;
+
+    let profile = Profile {
+        id: Uuid::new_v4().to_string(),
+        label,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    registry.profiles.push(profile.clone());
+    save(app_data_dir, &registry)?;
+    Ok(profile)
+}
+
+pub fn rename(app_data_dir: &Path, id: &str, label: &str) -> Result<Profile> {
+    let label = validate_label(label)?;
+    let mut registry = load(app_data_dir)?;
+
+    let profile = registry
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Profile {}", id)))?;
+    profile.label = label;
+    let profile = profile.clone();
+    save(app_data_dir, &registry)?;
+    Ok(profile)
+}
+
+/// Remember which profile to open at the next launch
+pub fn save_active(app_data_dir: &Path, id: &str) -> Result<()> {
+    let mut registry = load(app_data_dir)?;
+    if registry.get(id).is_none() {
+        return Err(AppError::NotFound(format!("Profile {}", id)));
+    }
+    registry.active = id.to_string();
+    save(app_data_dir, &registry)
+}
+
+/// Drop a profile and its files. Keyring entries are the caller's to forget,
+/// since only the profile's own database knows which ones it has.
+pub fn remove(app_data_dir: &Path, id: &str) -> Result<()> {
+    if id == DEFAULT_PROFILE_ID {
+        return Err(AppError::InvalidOperation(
+            "The default profile can't be removed".to_string(),
+        ));
+    }
+    if id == active_id() {
+        return Err(AppError::InvalidOperation(
+            "Switch to another profile before removing this one".to_string(),
+        ));
+    }
+
+    let mut registry = load(app_data_dir)?;
+    if registry.get(id).is_none() {
+        return Err(AppError::NotFound(format!("Profile {}", id)));
+    }
+    registry.profiles.retain(|p| p.id != id);
+    save(app_data_dir, &registry)?;
+
+    let dir = profile_dir(app_data_dir, id);
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+fn validate_label(label: &str) -> Result<String> {
+    let label = label.trim();
+    if label.is_empty() || label.chars().count() > MAX_LABEL_LENGTH {
+        return Err(AppError::InvalidOperation(format!(
+            "Profile names must be between 1 and {} characters",
+            MAX_LABEL_LENGTH
+        )));
+    }
+    Ok(label.to_string())
+}
@@ -0,0 +1,337 @@
+// ============================================
+// BirchVault Desktop - Realtime Sync
+// ============================================
+//
+// Subscribes to Supabase Realtime for changes to the account's vault items
+// and folders, so edits made on other devices are pulled within seconds
+// instead of at the next sync. Realtime speaks the Phoenix channel protocol
+// over a websocket. A notification only says which table changed; the rows
+// still come through the usual paged pull, so nothing in the message itself
+// is trusted or stored.
+
+use crate::commands::AppState;
+use crate::db::UserSession;
+use crate::error::{AppError, Result};
+use crate::offline;
+use crate::sync::{PullTable, SupabaseConfig};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::net::TcpStream;
+use tokio::time::{Duration, Instant, Interval};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+const CHANNEL_TOPIC: &str = "realtime:vault";
+
+/// Server tables watched, and what to pull when each changes
+const WATCHED_TABLES: [(&str, PullTable); 3] = [
+    ("vault_folders", PullTable::Folders),
+    ("vault_items", PullTable::VaultItems),
+    ("vault_tombstones", PullTable::Deletions),
+];
+
+/// Realtime drops sockets that stay quiet for much longer than this
+const HEARTBEAT_SECS: u64 = 25;
+
+/// Changes arriving this close together are pulled once
+const SETTLE_MS: u64 = 1500;
+
+/// How often to check the subscription still belongs to the signed-in account
+const SESSION_CHECK_SECS: u64 = 30;
+
+const RECONNECT_MIN_SECS: u64 = 5;
+const RECONNECT_MAX_SECS: u64 = 300;
+
+/// Renew this long before the access token expires; inside the five minutes
+/// in which `SyncEngine::active_session` refreshes it
+const TOKEN_MARGIN_SECS: i64 = 240;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Serialize)]
+struct OutgoingMessage<'a> {
+    topic: &'a str,
+    event: &'a str,
+    payload: serde_json::Value,
+    #[serde(rename = "ref")]
+    reference: &'a str,
+}
+
+#[derive(Deserialize)]
+struct IncomingMessage {
+    event: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+    #[serde(rename = "ref", default)]
+    reference: Option<String>,
+}
+
+/// A joined Realtime channel for one account's vault changes
+pub struct Subscription {
+    socket: Socket,
+    heartbeat: Interval,
+    next_ref: u64,
+    user_id: String,
+    renew_at: Instant,
+}
+
+impl Subscription {
+    pub async fn open(config: &SupabaseConfig, session: &UserSession) -> Result<Self> {
+        let base = config
+            .url
+            .trim_end_matches('/')
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1);
+        let url = format!(
+            "{}/realtime/v1/websocket?apikey={}&vsn=1.0.0",
+            base, config.anon_key
+        );
+        let (socket, _) = connect_async(url).await.map_err(realtime_error)?;
+
+        let period = Duration::from_secs(HEARTBEAT_SECS);
+        let mut subscription = Self {
+            socket,
+            heartbeat: tokio::time::interval_at(Instant::now() + period, period),
+            next_ref: 0,
+            user_id: session.user_id.clone(),
+            renew_at: renew_at(&session.expires_at),
+        };
+
+        let changes: Vec<serde_json::Value> = WATCHED_TABLES
+            .iter()
+            .map(|(table, _)| {
+                serde_json::json!({
+                    "event": "*",
+                    "schema": "public",
+                    "table": table,
+                    "filter": format!("user_id=eq.{}", session.user_id),
+                })
+            })
+            .collect();
+        let join_ref = subscription
+            .send(
+                CHANNEL_TOPIC,
+                "phx_join",
+                serde_json::json!({
+                    "config": {
+                        "broadcast": { "self": false },
+                        "presence": { "key": "" },
+                        "postgres_changes": changes,
+                    },
+                    "access_token": session.access_token,
+                }),
+            )
+            .await?;
+
+        loop {
+            let message = recv(&mut subscription.socket)
+                .await?
+                .ok_or_else(|| AppError::Sync("Realtime closed the connection".to_string()))?;
+            if message.event != "phx_reply" || message.reference.as_deref() != Some(&join_ref) {
+                continue;
+            }
+            if message.payload["status"] == "ok" {
+                return Ok(subscription);
+            }
+            return Err(AppError::Sync(format!(
+                "Realtime refused the subscription: {}",
+                message.payload["response"]
+            )));
+        }
+    }
+
+    /// Tables changed since the last call, once a burst of changes has
+    /// settled; `None` if nothing changed by `until`
+    pub async fn next_changes(&mut self, until: Instant) -> Result<Option<Vec<PullTable>>> {
+        let mut changed: Vec<PullTable> = Vec::new();
+        let mut deadline = until;
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {
+                    return Ok((!changed.is_empty()).then_some(changed));
+                }
+                _ = self.heartbeat.tick() => {
+                    self.send("phoenix", "heartbeat", serde_json::json!({})).await?;
+                }
+                message = recv(&mut self.socket) => {
+                    let message = message?
+                        .ok_or_else(|| AppError::Sync("Realtime closed the connection".to_string()))?;
+                    match message.event.as_str() {
+                        "postgres_changes" => {
+                            let table = message.payload["data"]["table"].as_str();
+                            let pull = WATCHED_TABLES
+                                .iter()
+                                .find(|(name, _)| Some(*name) == table)
+                                .map(|(_, pull)| *pull);
+                            if let Some(pull) = pull {
+                                if changed.is_empty() {
+                                    deadline = Instant::now() + Duration::from_millis(SETTLE_MS);
+                                }
+                                if !changed.contains(&pull) {
+                                    changed.push(pull);
+                                }
+                            }
+                        }
+                        "phx_error" | "phx_close" => {
+                            return Err(AppError::Sync("Realtime closed the channel".to_string()));
+                        }
+                        "system" if message.payload["status"] == "error" => {
+                            return Err(AppError::Sync(format!(
+                                "Realtime error: {}",
+                                message.payload["message"]
+                            )));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// When to reconnect with a fresh access token
+    pub fn renew_at(&self) -> Instant {
+        self.renew_at
+    }
+
+    async fn send(
+        &mut self,
+        topic: &str,
+        event: &str,
+        payload: serde_json::Value,
+    ) -> Result<String> {
+        self.next_ref += 1;
+        let reference = self.next_ref.to_string();
+        let text = serde_json::to_string(&OutgoingMessage {
+            topic,
+            event,
+            payload,
+            reference: &reference,
+        })?;
+        self.socket
+            .send(Message::Text(text))
+            .await
+            .map_err(realtime_error)?;
+        Ok(reference)
+    }
+}
+
+/// The next Phoenix message, or `None` once the socket has closed
+async fn recv(socket: &mut Socket) -> Result<Option<IncomingMessage>> {
+    while let Some(frame) = socket.next().await {
+        match frame.map_err(realtime_error)? {
+            Message::Text(text) => match serde_json::from_str(&text) {
+                Ok(message) => return Ok(Some(message)),
+                Err(e) => log::debug!("Ignoring unreadable Realtime message: {}", e),
+            },
+            Message::Close(_) => return Ok(None),
+            // Pings are answered by the websocket layer
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn renew_at(expires_at: &str) -> Instant {
+    let remaining = DateTime::parse_from_rfc3339(expires_at)
+        .map(|expiry| expiry.timestamp() - Utc::now().timestamp() - TOKEN_MARGIN_SECS)
+        .unwrap_or(0);
+    Instant::now() + Duration::from_secs(remaining.max(0) as u64)
+}
+
+fn realtime_error(e: tokio_tungstenite::tungstenite::Error) -> AppError {
+    AppError::Sync(format!("Realtime connection failed: {}", e))
+}
+
+// ============================================
+// Listener
+// ============================================
+
+/// Keeps a subscription open while signed in and online, pulling whatever it
+/// reports. Changes missed while disconnected are caught up on reconnecting.
+pub fn spawn_listener(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut backoff = RECONNECT_MIN_SECS;
+        let mut reconnecting = false;
+
+        loop {
+            let state = app.state::<AppState>();
+            let signed_in = matches!(state.db.get_session(), Ok(Some(_)));
+            if !signed_in
+                || offline::is_active(&state.db)
+                || !state.sync_engine.get_status().await.is_online
+            {
+                tokio::time::sleep(Duration::from_secs(SESSION_CHECK_SECS)).await;
+                continue;
+            }
+
+            let mut subscription = match state.sync_engine.subscribe_changes().await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    log::warn!("Realtime subscription failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(backoff)).await;
+                    backoff = (backoff * 2).min(RECONNECT_MAX_SECS);
+                    reconnecting = true;
+                    continue;
+                }
+            };
+            backoff = RECONNECT_MIN_SECS;
+
+            if reconnecting {
+                pull(&app, &WATCHED_TABLES.map(|(_, table)| table)).await;
+            }
+            reconnecting = true;
+
+            if let Err(e) = watch(&app, &mut subscription).await {
+                log::warn!("Realtime connection lost: {}", e);
+                tokio::time::sleep(Duration::from_secs(backoff)).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX_SECS);
+            }
+        }
+    });
+}
+
+/// Pull reported changes until the subscription needs renewing, or no longer
+/// matches the signed-in account (sign-out, a profile switch, offline mode)
+async fn watch(app: &AppHandle, subscription: &mut Subscription) -> Result<()> {
+    loop {
+        let until =
+            (Instant::now() + Duration::from_secs(SESSION_CHECK_SECS)).min(subscription.renew_at());
+        if let Some(tables) = subscription.next_changes(until).await? {
+            pull(app, &tables).await;
+            continue;
+        }
+
+        let state = app.state::<AppState>();
+        let same_account = matches!(
+            state.db.get_session(),
+            Ok(Some(session)) if session.user_id == subscription.user_id()
+        );
+        if !same_account
+            || offline::is_active(&state.db)
+            || Instant::now() >= subscription.renew_at()
+        {
+            return Ok(());
+        }
+    }
+}
+
+async fn pull(app: &AppHandle, tables: &[PullTable]) {
+    let state = app.state::<AppState>();
+    // The sync after unlocking catches up
+    if *state.is_locked.read().await {
+        return;
+    }
+    match state.sync_engine.pull_tables("realtime", tables).await {
+        Ok(status) => {
+            let _ = app.emit("sync-completed", status);
+        }
+        Err(e) => log::warn!("Realtime pull failed: {}", e),
+    }
+}
@@ -0,0 +1,142 @@
+// ============================================
+// BirchVault Desktop - Recovery Key
+// ============================================
+//
+// A random 256-bit key shown once for the user to print. It wraps the master
+// key; the server keeps the wrapped copy with a SHA-256 of the recovery key
+// to find it by, since recovery starts signed out. The recovered master key
+// signs in (the auth hash derives from it) and unwraps the vault key, which
+// is then re-wrapped under a new master password.
+//
+// A recovery key only fits the master key it wrapped, so changing the master
+// password removes it and recovering replaces it.
+
+use crate::crypto::{self, EncryptedData};
+use crate::error::{AppError, Result};
+use crate::sync::SyncEngine;
+use reqwest::Method;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// RFC 4648 base32: no characters that look alike once printed
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Characters between dashes in the printed key
+const GROUP_LENGTH: usize = 4;
+
+#[derive(Debug, Deserialize)]
+struct LookupRow {
+    email: String,
+    encrypted_master_key: String,
+}
+
+/// An account's master key, recovered with its recovery key
+pub struct RecoveredAccount {
+    pub email: String,
+    pub master_key: Vec<u8>,
+}
+
+/// Make a new recovery key for `master_key`, replacing any earlier one, and
+/// return it formatted for printing
+pub async fn create(engine: &SyncEngine, master_key: &[u8]) -> Result<String> {
+    let session = engine.active_session().await?;
+    let recovery_key = crypto::generate_key();
+
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_recovery_keys?on_conflict=user_id",
+            Some(&serde_json::json!({
+                "user_id": session.user_id,
+                "lookup_hash": lookup_hash(&recovery_key),
+                "encrypted_master_key":
+                    serde_json::to_string(&crypto::encrypt(master_key, &recovery_key)?)?,
+            })),
+            Some("resolution=merge-duplicates"),
+        )
+        .await?;
+
+    Ok(format(&recovery_key))
+}
+
+/// Forget the signed-in account's recovery key
+pub async fn remove(engine: &SyncEngine) -> Result<()> {
+    let session = engine.active_session().await?;
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_recovery_keys?user_id=eq.{}", session.user_id),
+            None,
+            None,
+        )
+        .await
+}
+
+/// The account and master key a printed recovery key belongs to
+pub async fn unwrap(engine: &SyncEngine, recovery_key: &str) -> Result<RecoveredAccount> {
+    let no_match = || AppError::Auth("That recovery key doesn't match an account".to_string());
+    let recovery_key = parse(recovery_key).ok_or_else(no_match)?;
+
+    let rows: Vec<LookupRow> = engine
+        .public_rpc_query(
+            "vault_recovery_key_lookup",
+            serde_json::json!({ "p_lookup_hash": lookup_hash(&recovery_key) }),
+        )
+        .await?;
+    let row = rows.into_iter().next().ok_or_else(no_match)?;
+
+    let encrypted: EncryptedData = serde_json::from_str(&row.encrypted_master_key)?;
+    let master_key = crypto::decrypt(&encrypted, &recovery_key).map_err(|_| no_match())?;
+    Ok(RecoveredAccount {
+        email: row.email,
+        master_key,
+    })
+}
+
+fn lookup_hash(recovery_key: &[u8]) -> String {
+    Sha256::digest(recovery_key)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Base32 in dash-separated groups
+fn format(key: &[u8]) -> String {
+    let mut chars = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for byte in key {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            chars.push(ALPHABET[((buffer >> bits) & 31) as usize]);
+        }
+    }
+    if bits > 0 {
+        chars.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize]);
+    }
+
+    chars
+        .chunks(GROUP_LENGTH)
+        .map(|group| String::from_utf8_lossy(group).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The key in a typed recovery key, ignoring case, dashes and spaces
+fn parse(text: &str) -> Option<Vec<u8>> {
+    let mut key = Vec::with_capacity(crypto::KEY_LENGTH);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '-') {
+        let value = ALPHABET
+            .iter()
+            .position(|a| char::from(*a) == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            key.push((buffer >> bits) as u8);
+        }
+    }
+    (key.len() == crypto::KEY_LENGTH).then_some(key)
+}
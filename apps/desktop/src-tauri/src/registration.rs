@@ -0,0 +1,72 @@
+// ============================================
+// BirchVault Desktop - Registration
+// ============================================
+//
+// Signing up creates the Supabase user (its profile row comes from a server
+// trigger) and a new random vault key, wrapped with the master key for the
+// profile's `encrypted_symmetric_key`. The wrapped key waits in `app_meta`
+// until there is a session to upload it with: straight away, or at the
+// first sign-in if the server wants the email address confirmed first.
+
+use crate::crypto;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::sync::SyncEngine;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+const META_PENDING_REGISTRATION: &str = "pending_registration";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingRegistration {
+    email: String,
+    /// The new vault key wrapped with the master key
+    encrypted_symmetric_key: String,
+    created_at: String,
+}
+
+/// Generate the new account's vault key and keep it, wrapped with
+/// `master_key`, until `complete` can upload it
+pub fn start(db: &Database, email: &str, master_key: &[u8]) -> Result<()> {
+    let vault_key = crypto::generate_key();
+    let pending = PendingRegistration {
+        email: email.trim().to_string(),
+        encrypted_symmetric_key: crypto::encrypt_to_json(&BASE64.encode(vault_key), master_key)?,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    db.set_meta(META_PENDING_REGISTRATION, &serde_json::to_string(&pending)?)
+}
+
+/// Upload the wrapped vault key of an account registered here, once signed
+/// in as it. Returns whether there was one. A profile that already has a
+/// key keeps it.
+pub async fn complete(engine: &SyncEngine, db: &Database, email: &str) -> Result<bool> {
+    let Some(json) = db.get_meta(META_PENDING_REGISTRATION)? else {
+        return Ok(false);
+    };
+    let pending: PendingRegistration = serde_json::from_str(&json).map_err(AppError::from)?;
+    if !pending.email.eq_ignore_ascii_case(email.trim()) {
+        return Ok(false);
+    }
+
+    let session = engine.active_session().await?;
+    engine
+        .rest_write(
+            Method::PATCH,
+            &format!(
+                "vault_profiles?id=eq.{}&encrypted_symmetric_key=is.null",
+                session.user_id
+            ),
+            Some(&serde_json::json!({
+                "encrypted_symmetric_key": pending.encrypted_symmetric_key,
+            })),
+            None,
+        )
+        .await?;
+
+    db.delete_meta(META_PENDING_REGISTRATION)?;
+    Ok(true)
+}
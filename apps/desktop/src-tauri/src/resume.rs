@@ -0,0 +1,78 @@
+// ============================================
+// BirchVault Desktop - Session Resume
+// ============================================
+//
+// Lets the app come back unlocked after a restart (e.g. auto-update). Tokens
+// are single-use, expire quickly and are keyed from the OS keyring.
+
+use crate::crypto::{self, EncryptedData};
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::profiles;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Duration, Utc};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+const KEYRING_SERVICE: &str = "birchvault-resume";
+const KEYRING_USER: &str = "resume-key";
+
+pub const DEFAULT_GRACE_SECONDS: i64 = 60;
+pub const MAX_GRACE_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumePayload {
+    pub master_key_hash: String,
+    /// Exported vault key from the webview, so it can decrypt again after restart
+    pub encryption_key: Option<String>,
+}
+
+/// Create a resume token valid for `grace_seconds`, returning its expiry
+pub fn create(db: &Database, payload: &ResumePayload, grace_seconds: i64) -> Result<String> {
+    let grace = grace_seconds.clamp(1, MAX_GRACE_SECONDS);
+    let expires_at = (Utc::now() + Duration::seconds(grace)).to_rfc3339();
+
+    let key = crypto::generate_key();
+    Entry::new(KEYRING_SERVICE, &profiles::keyring_user(KEYRING_USER))?.set_password(&BASE64.encode(key))?;
+
+    let plaintext = serde_json::to_vec(payload)?;
+    let encrypted = serde_json::to_string(&crypto::encrypt(&plaintext, &key)?)?;
+    db.save_resume_token(&encrypted, &expires_at)?;
+
+    Ok(expires_at)
+}
+
+/// Consume the pending resume token, if any. Always single-use.
+pub fn take(db: &Database) -> Result<Option<ResumePayload>> {
+    let token = db.take_resume_token()?;
+    let key = take_key();
+
+    let (Some((encrypted, expires_at)), Some(key)) = (token, key) else {
+        return Ok(None);
+    };
+
+    let expires_at = DateTime::parse_from_rfc3339(&expires_at)
+        .map_err(|_| AppError::InvalidOperation("Invalid resume token expiry".to_string()))?;
+    if expires_at < Utc::now() {
+        return Ok(None);
+    }
+
+    let encrypted: EncryptedData = serde_json::from_str(&encrypted)?;
+    let plaintext = crypto::decrypt(&encrypted, &key)?;
+    Ok(Some(serde_json::from_slice(&plaintext)?))
+}
+
+/// Drop any pending token (on lock/logout)
+pub fn discard(db: &Database) -> Result<()> {
+    db.take_resume_token()?;
+    take_key();
+    Ok(())
+}
+
+fn take_key() -> Option<Vec<u8>> {
+    let entry = Entry::new(KEYRING_SERVICE, &profiles::keyring_user(KEYRING_USER)).ok()?;
+    let encoded = entry.get_password().ok();
+    let _ = entry.delete_password();
+    encoded.and_then(|k| BASE64.decode(k).ok())
+}
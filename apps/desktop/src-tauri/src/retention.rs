@@ -0,0 +1,156 @@
+// ============================================
+// BirchVault Desktop - Folder Retention Policies
+// ============================================
+
+use crate::commands::AppState;
+use crate::db::{Database, RetentionCandidate};
+use crate::error::{AppError, Result};
+use crate::i18n;
+use crate::notify::{self, NotificationCategory};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const MAX_RETENTION_DAYS: i64 = 3650;
+
+/// How far ahead of expiry an item is reported as a warning
+pub const WARNING_DAYS: i64 = 3;
+
+const CHECK_INTERVAL_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionWarning {
+    pub item_id: String,
+    pub folder_id: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    pub trashed_item_ids: Vec<String>,
+    pub warnings: Vec<RetentionWarning>,
+}
+
+pub fn validate_retention_days(retention_days: Option<i64>) -> Result<()> {
+    match retention_days {
+        Some(days) if !(1..=MAX_RETENTION_DAYS).contains(&days) => {
+            Err(AppError::InvalidOperation(format!(
+                "Retention must be between 1 and {} days",
+                MAX_RETENTION_DAYS
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+// ============================================
+// Evaluation
+// ============================================
+
+/// Expiry is counted from the item's last change, so editing an item restarts its clock
+fn expires_at(candidate: &RetentionCandidate) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&candidate.local_updated_at)
+        .ok()
+        .map(|updated_at| updated_at.with_timezone(&Utc) + Duration::days(candidate.retention_days))
+}
+
+/// Items that will be trashed within `WARNING_DAYS`, without changing anything
+pub fn upcoming(db: &Database) -> Result<Vec<RetentionWarning>> {
+    let cutoff = Utc::now() + Duration::days(WARNING_DAYS);
+
+    Ok(db
+        .get_retention_candidates()?
+        .into_iter()
+        .filter_map(|candidate| {
+            let expires_at = expires_at(&candidate)?;
+            (expires_at <= cutoff).then(|| RetentionWarning {
+                item_id: candidate.item_id,
+                folder_id: candidate.folder_id,
+                expires_at: expires_at.to_rfc3339(),
+            })
+        })
+        .collect())
+}
+
+/// Trash expired items and report the ones about to expire
+pub fn enforce(db: &Database) -> Result<RetentionReport> {
+    let now = Utc::now();
+    let mut report = RetentionReport::default();
+
+    for warning in upcoming(db)? {
+        let expired = DateTime::parse_from_rfc3339(&warning.expires_at)
+            .map(|expires_at| expires_at <= now)
+            .unwrap_or(false);
+
+        if expired {
+            // Soft delete, so it still syncs and can be restored from the trash
+            db.soft_delete_vault_item(&warning.item_id)?;
+            report.trashed_item_ids.push(warning.item_id);
+        } else {
+            report.warnings.push(warning);
+        }
+    }
+
+    Ok(report)
+}
+
+// ============================================
+// Background Task
+// ============================================
+
+/// Enforce policies hourly, emitting `retention-trashed` and `retention-warning`
+pub fn spawn_policy_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        // Warn about each item once per run of the app
+        let mut warned: HashSet<String> = HashSet::new();
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppState>();
+            let report = match enforce(&state.db) {
+                Ok(report) => report,
+                Err(e) => {
+                    log::warn!("Failed to apply retention policies: {}", e);
+                    continue;
+                }
+            };
+
+            if !report.trashed_item_ids.is_empty() {
+                let _ = app.emit("retention-trashed", report.trashed_item_ids);
+            }
+
+            let new_warnings: Vec<RetentionWarning> = report
+                .warnings
+                .into_iter()
+                .filter(|w| warned.insert(w.item_id.clone()))
+                .collect();
+            if !new_warnings.is_empty() {
+                let first = new_warnings
+                    .iter()
+                    .filter_map(|w| DateTime::parse_from_rfc3339(&w.expires_at).ok())
+                    .map(|at| at.with_timezone(&Utc))
+                    .min()
+                    .unwrap_or_else(Utc::now);
+                notify::dispatch(
+                    &app,
+                    NotificationCategory::ExpiryReminder,
+                    &i18n::t("notify.expiry_title"),
+                    &i18n::t_with(
+                        "notify.expiry_body",
+                        &[
+                            ("count", &i18n::format_number(new_warnings.len() as i64)),
+                            ("date", &i18n::format_date(&first)),
+                        ],
+                    ),
+                );
+                let _ = app.emit("retention-warning", new_warnings);
+            }
+        }
+    });
+}
@@ -0,0 +1,474 @@
+// ============================================
+// BirchVault Desktop - Vault Key Rotation
+// ============================================
+//
+// Replaces the vault key with a new random one. Everything under the old key
+// is re-encrypted: items with their revisions and recoverable deletions,
+// attachment names and keys and cached organization keys here, and on the
+// server the items, attachments, the account's private key, the owner's copy
+// of each shared item's key, each send's key and the key escrowed for
+// emergency contacts. The server swaps its copies in one call
+// (`vault_rotate_key`), then the local database in one transaction.
+//
+// The new key is recorded before anything changes, sealed with the old key
+// (and the old key with the new), so a rotation cut short is finished with
+// whichever key the next unlock produces. The PIN is turned off afterwards:
+// its sealed copy of the key can't be re-sealed without the PIN.
+
+use crate::backup;
+use crate::crypto::{self, EncryptedData};
+use crate::db::Database;
+use crate::deferred;
+use crate::error::{AppError, Result};
+use crate::icons;
+use crate::keypair;
+use crate::offline;
+use crate::pin;
+use crate::resume;
+use crate::sync::SyncEngine;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const META_KEY_ROTATION: &str = "key_rotation";
+
+/// Rows fetched per request when collecting what to re-encrypt on the server
+const FETCH_PAGE_SIZE: usize = 500;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RotationRecord {
+    /// The new key, sealed with the old one
+    next_key: EncryptedData,
+    /// The old key, sealed with the new one
+    previous_key: EncryptedData,
+    /// The new key wrapped with the master key, as stored on the profile
+    encrypted_symmetric_key: String,
+    started_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationSummary {
+    /// Values re-encrypted on this device
+    pub local_values: usize,
+    pub server_items: usize,
+    pub server_attachments: usize,
+    pub shared_items: usize,
+    pub emergency_contacts: usize,
+    pub sends: usize,
+    /// The new vault key (base64), for the webview to use from now on
+    pub encryption_key: String,
+}
+
+/// A finished rotation
+pub struct Rotation {
+    pub vault_key: Vec<u8>,
+    pub summary: RotationSummary,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileKeysRow {
+    encrypted_symmetric_key: Option<String>,
+    encrypted_private_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemRow {
+    id: String,
+    encrypted_data: String,
+    updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AttachmentRow {
+    id: String,
+    encrypted_name: String,
+    encrypted_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedKeyRow {
+    vault_item_id: String,
+    owner_encrypted_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendKeyRow {
+    id: String,
+    encrypted_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EscrowRow {
+    access_id: String,
+    vault_emergency_access: EscrowContact,
+}
+
+#[derive(Debug, Deserialize)]
+struct EscrowContact {
+    grantee_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RotatedItem {
+    item_id: String,
+    server_updated_at: String,
+}
+
+fn load(db: &Database) -> Result<Option<RotationRecord>> {
+    db.get_meta(META_KEY_ROTATION)?
+        .map(|json| serde_json::from_str(&json).map_err(AppError::from))
+        .transpose()
+}
+
+/// The old and new keys, given either one
+fn open(record: &RotationRecord, vault_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    if let Ok(new_key) = crypto::decrypt(&record.next_key, vault_key) {
+        return Ok((vault_key.to_vec(), new_key));
+    }
+    if let Ok(old_key) = crypto::decrypt(&record.previous_key, vault_key) {
+        return Ok((old_key, vault_key.to_vec()));
+    }
+    Err(AppError::Encryption(
+        "The unfinished key rotation doesn't belong to this vault key".to_string(),
+    ))
+}
+
+/// `json` (an `EncryptedData`) under the new key. A value the old key can't
+/// open is left as it is: either it's already under the new key (pulled after
+/// the server's half finished), or it was unreadable to begin with.
+fn reencrypt(json: &str, old_key: &[u8], new_key: &[u8]) -> String {
+    let plaintext = serde_json::from_str::<EncryptedData>(json)
+        .map_err(AppError::from)
+        .and_then(|encrypted| crypto::decrypt(&encrypted, old_key));
+    let Ok(plaintext) = plaintext else {
+        return json.to_string();
+    };
+    crypto::encrypt(&plaintext, new_key)
+        .and_then(|encrypted| Ok(serde_json::to_string(&encrypted)?))
+        .unwrap_or_else(|_| json.to_string())
+}
+
+// ============================================
+// Rotation
+// ============================================
+
+/// Rotate the vault key, or finish a rotation that was cut short. `master_key`
+/// wraps the new key for the profile, as the webview's sign-in unwraps it.
+pub async fn rotate(
+    engine: &SyncEngine,
+    db: &Database,
+    vault_key: &[u8],
+    master_key: &[u8],
+) -> Result<Rotation> {
+    offline::ensure_network_allowed(db)?;
+
+    // Anything queued has to reach the server before its copies are swapped
+    engine.sync("key_rotation").await?;
+
+    engine
+        .while_paused(|| async {
+            let pending = db.get_pending_sync_items()?.len();
+            if pending > 0 {
+                return Err(AppError::InvalidOperation(format!(
+                    "{} changes are still waiting to sync; try again once they have",
+                    pending
+                )));
+            }
+
+            let record = match load(db)? {
+                Some(record) => record,
+                None => start(engine, db, vault_key, master_key).await?,
+            };
+            let (old_key, new_key) = open(&record, vault_key)?;
+
+            let profile = profile_keys(engine).await?;
+            let mut summary = RotationSummary {
+                local_values: 0,
+                server_items: 0,
+                server_attachments: 0,
+                shared_items: 0,
+                emergency_contacts: 0,
+                sends: 0,
+                encryption_key: BASE64.encode(&new_key),
+            };
+            let versions = if profile.encrypted_symmetric_key.as_deref()
+                == Some(record.encrypted_symmetric_key.as_str())
+            {
+                None
+            } else {
+                Some(
+                    rotate_server(engine, &record, &profile, &old_key, &new_key, &mut summary)
+                        .await?,
+                )
+            };
+
+            summary.local_values = finish_local(db, &old_key, &new_key, versions.as_ref())?;
+            deferred::remember_wrapped_key(db, &record.encrypted_symmetric_key);
+            Ok(Rotation {
+                vault_key: new_key,
+                summary,
+            })
+        })
+        .await
+}
+
+/// Whether a rotation was started and hasn't finished here yet
+pub fn is_pending(db: &Database) -> Result<bool> {
+    Ok(db.get_meta(META_KEY_ROTATION)?.is_some())
+}
+
+/// Finish the local half of a rotation whose server half is done, if the
+/// vault was unlocked with the new key. Returns whether there was one.
+pub fn finish_pending(db: &Database, vault_key: &[u8]) -> Result<bool> {
+    let Some(record) = load(db)? else {
+        return Ok(false);
+    };
+    let (old_key, new_key) = open(&record, vault_key)?;
+    // Still on the old key: the server may not have switched yet, which
+    // only `rotate` (with the master key) can find out and finish
+    if new_key != vault_key {
+        return Ok(false);
+    }
+
+    finish_local(db, &old_key, &new_key, None)?;
+    Ok(true)
+}
+
+/// Generate the new key and record it before anything is re-encrypted
+async fn start(
+    engine: &SyncEngine,
+    db: &Database,
+    vault_key: &[u8],
+    master_key: &[u8],
+) -> Result<RotationRecord> {
+    // A wrong master key would leave the profile with a key nobody can unwrap
+    let wrapped = profile_keys(engine)
+        .await?
+        .encrypted_symmetric_key
+        .ok_or_else(|| AppError::InvalidOperation("The account has no vault key".to_string()))?;
+    let current = crypto::decrypt_from_json(&wrapped, master_key)
+        .map_err(|_| AppError::Auth("The master key doesn't unlock this vault".to_string()))?;
+    if !crypto::constant_time_eq(current.as_bytes(), BASE64.encode(vault_key).as_bytes()) {
+        return Err(AppError::Auth(
+            "The master key doesn't unlock this vault".to_string(),
+        ));
+    }
+
+    let new_key = crypto::generate_key();
+    let record = RotationRecord {
+        next_key: crypto::encrypt(&new_key, vault_key)?,
+        previous_key: crypto::encrypt(vault_key, &new_key)?,
+        encrypted_symmetric_key: crypto::encrypt_to_json(&BASE64.encode(new_key), master_key)?,
+        started_at: Utc::now().to_rfc3339(),
+    };
+    db.set_meta(META_KEY_ROTATION, &serde_json::to_string(&record)?)?;
+    Ok(record)
+}
+
+async fn profile_keys(engine: &SyncEngine) -> Result<ProfileKeysRow> {
+    let session = engine.active_session().await?;
+    let rows: Vec<ProfileKeysRow> = engine
+        .rest_get(&format!(
+            "vault_profiles?id=eq.{}&select=encrypted_symmetric_key,encrypted_private_key",
+            session.user_id
+        ))
+        .await?;
+    rows.into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("Account profile".to_string()))
+}
+
+/// Re-encrypt the server's copies and swap them in, returning the items' new
+/// server versions
+async fn rotate_server(
+    engine: &SyncEngine,
+    record: &RotationRecord,
+    profile: &ProfileKeysRow,
+    old_key: &[u8],
+    new_key: &[u8],
+    summary: &mut RotationSummary,
+) -> Result<HashMap<String, String>> {
+    let session = engine.active_session().await?;
+    let owner = format!("user_id=eq.{}", session.user_id);
+
+    let items: Vec<serde_json::Value> = fetch_all::<ItemRow>(
+        engine,
+        "vault_items",
+        &owner,
+        "id,encrypted_data,updated_at",
+        |row| &row.id,
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        serde_json::json!({
+            "id": row.id,
+            "encrypted_data": reencrypt(&row.encrypted_data, old_key, new_key),
+            "updated_at": row.updated_at,
+        })
+    })
+    .collect();
+
+    let attachments: Vec<serde_json::Value> = fetch_all::<AttachmentRow>(
+        engine,
+        "vault_attachments",
+        &owner,
+        "id,encrypted_name,encrypted_key",
+        |row| &row.id,
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        serde_json::json!({
+            "id": row.id,
+            "encrypted_name": reencrypt(&row.encrypted_name, old_key, new_key),
+            "encrypted_key": reencrypt(&row.encrypted_key, old_key, new_key),
+        })
+    })
+    .collect();
+
+    let shared_keys: Vec<serde_json::Value> = fetch_all::<SharedKeyRow>(
+        engine,
+        "vault_shared_item_data",
+        &format!("owner_id=eq.{}", session.user_id),
+        "vault_item_id,owner_encrypted_key",
+        |row| &row.vault_item_id,
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        serde_json::json!({
+            "vault_item_id": row.vault_item_id,
+            "owner_encrypted_key": reencrypt(&row.owner_encrypted_key, old_key, new_key),
+        })
+    })
+    .collect();
+
+    let send_keys: Vec<serde_json::Value> = fetch_all::<SendKeyRow>(
+        engine,
+        "vault_sends",
+        &owner,
+        "id,encrypted_key",
+        |row| &row.id,
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        serde_json::json!({
+            "id": row.id,
+            "encrypted_key": reencrypt(&row.encrypted_key, old_key, new_key),
+        })
+    })
+    .collect();
+
+    // Escrowed keys are wrapped to each contact's public key, not ours
+    let escrows: Vec<EscrowRow> = fetch_all(
+        engine,
+        "vault_emergency_access_keys",
+        &format!("vault_emergency_access.grantor_id=eq.{}", session.user_id),
+        "access_id,vault_emergency_access!inner(grantee_id)",
+        |row: &EscrowRow| &row.access_id,
+    )
+    .await?;
+    let mut emergency_keys = Vec::new();
+    for escrow in escrows {
+        let public_key = match escrow.vault_emergency_access.grantee_id.as_deref() {
+            Some(grantee_id) => keypair::public_key_for(engine, grantee_id).await?,
+            None => None,
+        }
+        .ok_or_else(|| {
+            AppError::InvalidOperation(
+                "An emergency contact has no key pair to escrow the new key with".to_string(),
+            )
+        })?;
+        emergency_keys.push(serde_json::json!({
+            "access_id": escrow.access_id,
+            "encrypted_key": keypair::wrap(&public_key, new_key)?,
+        }));
+    }
+
+    summary.server_items = items.len();
+    summary.server_attachments = attachments.len();
+    summary.shared_items = shared_keys.len();
+    summary.emergency_contacts = emergency_keys.len();
+    summary.sends = send_keys.len();
+
+    let rotated: Vec<RotatedItem> = engine
+        .rpc_query(
+            "vault_rotate_key",
+            serde_json::json!({
+                "p_encrypted_symmetric_key": record.encrypted_symmetric_key,
+                "p_encrypted_private_key": profile
+                    .encrypted_private_key
+                    .as_deref()
+                    .map(|key| reencrypt(key, old_key, new_key)),
+                "p_items": items,
+                "p_attachments": attachments,
+                "p_shared_keys": shared_keys,
+                "p_emergency_keys": emergency_keys,
+                "p_send_keys": send_keys,
+            }),
+        )
+        .await?;
+
+    Ok(rotated
+        .into_iter()
+        .map(|item| (item.item_id, item.server_updated_at))
+        .collect())
+}
+
+/// Every row of `table` matching `filter`, a page at a time in `id` order
+async fn fetch_all<T: DeserializeOwned>(
+    engine: &SyncEngine,
+    table: &str,
+    filter: &str,
+    select: &str,
+    id: impl Fn(&T) -> &String,
+) -> Result<Vec<T>> {
+    let id_column = select.split(',').next().unwrap_or("id");
+    let mut rows = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let mut path = format!(
+            "{}?{}&select={}&order={}.asc&limit={}",
+            table, filter, select, id_column, FETCH_PAGE_SIZE
+        );
+        if let Some(after) = &after {
+            path.push_str(&format!("&{}=gt.{}", id_column, after));
+        }
+
+        let page: Vec<T> = engine.rest_get(&path).await?;
+        let full = page.len() == FETCH_PAGE_SIZE;
+        after = page.last().map(|row| id(row).clone());
+        rows.extend(page);
+        if !full {
+            return Ok(rows);
+        }
+    }
+}
+
+/// Re-encrypt this device's copies and clear what only held the old key
+fn finish_local(
+    db: &Database,
+    old_key: &[u8],
+    new_key: &[u8],
+    server_versions: Option<&HashMap<String, String>>,
+) -> Result<usize> {
+    let rewritten = db.reencrypt_vault(
+        |value| reencrypt(value, old_key, new_key),
+        server_versions,
+        META_KEY_ROTATION,
+    )?;
+
+    pin::disable(db)?;
+    resume::discard(db)?;
+    // Cached icons are keyed by the vault key, so they'd only be misses now
+    icons::clear_cache(db)?;
+    backup::run_soon(db)?;
+    Ok(rewritten)
+}
@@ -0,0 +1,283 @@
+// ============================================
+// BirchVault Desktop - Search Index
+// ============================================
+//
+// An in-memory index of item names, usernames and URLs, built from the
+// decrypted vault after unlock and dropped on lock. It is never written to
+// disk. The index records the database's vault revision (bumped by triggers on
+// every `vault_items` write, sync included) and is rebuilt when that moves on.
+
+use crate::crypto;
+use crate::db::{Database, VaultItem};
+use crate::error::Result;
+use crate::import::url_host;
+use crate::urimatch::{self, LoginUri, PageUrl, UriMatch};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// Per-field weights; a name hit outranks the same hit in a username or URL
+const NAME_WEIGHT: u32 = 3;
+const USERNAME_WEIGHT: u32 = 2;
+const URL_WEIGHT: u32 = 2;
+const FAVORITE_BONUS: u32 = 5;
+
+#[derive(Debug, Clone)]
+pub struct SearchEntry {
+    pub id: String,
+    pub item_type: String,
+    pub folder_id: Option<String>,
+    pub is_favorite: bool,
+    pub deleted_at: Option<String>,
+    pub name: String,
+    pub username: Option<String>,
+    name_key: String,
+    username_key: String,
+    url_keys: Vec<String>,
+    /// Saved login URIs with their match types, for autofill lookups
+    uris: Vec<LoginUri>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub id: String,
+    pub item_type: String,
+    pub folder_id: Option<String>,
+    pub is_favorite: bool,
+    pub deleted_at: Option<String>,
+    pub name: String,
+    pub username: Option<String>,
+    pub score: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// Vault revision the index was built at
+    pub revision: i64,
+    entries: Vec<SearchEntry>,
+}
+
+impl SearchIndex {
+    /// Index live and trashed items; items that fail to decrypt are left out
+    pub fn build(db: &Database, key: &[u8], revision: i64) -> Result<Self> {
+        let items = db
+            .get_all_vault_items()?
+            .into_iter()
+            .chain(db.get_trashed_items()?);
+
+        let entries = items
+            .filter_map(
+                |item| match crypto::decrypt_from_json(&item.encrypted_data, key) {
+                    Ok(json) => serde_json::from_str(&json)
+                        .ok()
+                        .map(|data| entry(item, &data)),
+                    Err(e) => {
+                        log::warn!("Leaving item {} out of the search index: {}", item.id, e);
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        Ok(Self { revision, entries })
+    }
+
+    /// Entries accepted by `filter` that match every query token, best first.
+    /// An empty query matches everything, by name.
+    pub fn search(
+        &self,
+        query: &str,
+        filter: impl Fn(&SearchEntry) -> bool,
+        limit: usize,
+    ) -> Vec<SearchResult> {
+        self.search_page(query, filter, 0, limit).0
+    }
+
+    /// `limit` of the results of `search` from `offset` on, with how many
+    /// there are in all
+    pub fn search_page(
+        &self,
+        query: &str,
+        filter: impl Fn(&SearchEntry) -> bool,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<SearchResult>, usize) {
+        let tokens: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+
+        let mut results: Vec<SearchResult> = self
+            .entries
+            .iter()
+            .filter(|entry| filter(entry))
+            .filter_map(|entry| {
+                let score = tokens
+                    .iter()
+                    .map(|token| score_entry(entry, token))
+                    .sum::<Option<u32>>()?;
+                let bonus = if entry.is_favorite { FAVORITE_BONUS } else { 0 };
+                Some(result(entry, score + bonus))
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        let total = results.len();
+        let page = results.into_iter().skip(offset).take(limit).collect();
+        (page, total)
+    }
+
+    /// Every indexed item's id and name
+    pub fn names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry.name.as_str()))
+    }
+
+    /// Live logins with a URI matching `page`, most specific match first
+    pub fn match_url(&self, page: &PageUrl, limit: usize) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.deleted_at.is_none())
+            .filter_map(|entry| {
+                let best = urimatch::best_match(page, &entry.uris, UriMatch::Domain)?;
+                let bonus = if entry.is_favorite { FAVORITE_BONUS } else { 0 };
+                Some(result(entry, best.strength() + bonus))
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+fn entry(item: VaultItem, data: &Value) -> SearchEntry {
+    let name = data
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let username = data
+        .pointer("/login/username")
+        .or_else(|| data.pointer("/identity/username"))
+        .or_else(|| data.pointer("/card/cardholderName"))
+        .or_else(|| data.pointer("/passkey/userName"))
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    let uris: Vec<LoginUri> = data
+        .pointer("/login/uris")
+        .and_then(|uris| serde_json::from_value(uris.clone()).ok())
+        .unwrap_or_default();
+    let url_keys = data
+        .pointer("/login/uris")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|uri| uri.get("uri").and_then(Value::as_str))
+        .chain(data.pointer("/apiKey/endpoint").and_then(Value::as_str))
+        .chain(data.pointer("/wifi/routerAdminUrl").and_then(Value::as_str))
+        .chain(data.pointer("/passkey/rpId").and_then(Value::as_str))
+        .filter_map(url_host)
+        .collect();
+
+    SearchEntry {
+        name_key: name.to_lowercase(),
+        username_key: username.as_deref().unwrap_or_default().to_lowercase(),
+        url_keys,
+        uris,
+        id: item.id,
+        item_type: item.item_type,
+        folder_id: item.folder_id,
+        is_favorite: item.is_favorite,
+        deleted_at: item.deleted_at,
+        name,
+        username,
+    }
+}
+
+fn result(entry: &SearchEntry, score: u32) -> SearchResult {
+    SearchResult {
+        id: entry.id.clone(),
+        item_type: entry.item_type.clone(),
+        folder_id: entry.folder_id.clone(),
+        is_favorite: entry.is_favorite,
+        deleted_at: entry.deleted_at.clone(),
+        name: entry.name.clone(),
+        username: entry.username.clone(),
+        score,
+    }
+}
+
+// ============================================
+// Ranking
+// ============================================
+
+/// Best weighted score for one token across the entry's fields
+fn score_entry(entry: &SearchEntry, token: &str) -> Option<u32> {
+    let name = score_field(&entry.name_key, token).map(|s| s * NAME_WEIGHT);
+    let username = score_field(&entry.username_key, token).map(|s| s * USERNAME_WEIGHT);
+    let url = entry
+        .url_keys
+        .iter()
+        .filter_map(|host| score_field(host, token))
+        .max()
+        .map(|s| s * URL_WEIGHT);
+
+    [name, username, url].into_iter().flatten().max()
+}
+
+/// Exact > prefix > word prefix > substring > in-order fuzzy match
+fn score_field(field: &str, token: &str) -> Option<u32> {
+    if field.is_empty() {
+        return None;
+    }
+    if field == token {
+        return Some(100);
+    }
+    if field.starts_with(token) {
+        return Some(80);
+    }
+    if field
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(token))
+    {
+        return Some(60);
+    }
+    if field.contains(token) {
+        return Some(40);
+    }
+    fuzzy_score(field, token)
+}
+
+/// Characters of `token` in order within `field`; fewer gaps score higher
+fn fuzzy_score(field: &str, token: &str) -> Option<u32> {
+    if token.chars().count() < 2 {
+        return None;
+    }
+
+    let mut chars = field.chars();
+    let mut gaps = 0u32;
+    for wanted in token.chars() {
+        let mut skipped = 0u32;
+        loop {
+            match chars.next() {
+                Some(c) if c == wanted => break,
+                Some(_) => skipped += 1,
+                None => return None,
+            }
+        }
+        gaps += skipped.min(1);
+    }
+
+    Some(30u32.saturating_sub(gaps * 4).max(5))
+}
@@ -0,0 +1,281 @@
+// ============================================
+// BirchVault Desktop - Send Links
+// ============================================
+//
+// A text or file shared by link, with someone who needn't have an account.
+// Each send gets its own random key and is uploaded to the `sends` bucket
+// as `EncryptedData` JSON, the shape @birchvault/core already decrypts. The
+// key only travels in the link's fragment, which browsers never send to a
+// server. The `vault_sends` row holds the expiry and view limit, enforced by
+// `vault_send_open` on the server, plus the key wrapped with the vault key
+// so the owner can copy the link again from the list.
+
+use crate::crypto::{self, EncryptedData};
+use crate::error::{AppError, Result};
+use crate::sync::SyncEngine;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+const DEFAULT_LINK_BASE: &str = "https://birchvault.co.uk/send";
+
+/// Largest text or file that can be sent; the bucket allows for it growing
+/// by a third once base64-encoded
+pub const MAX_SEND_BYTES: u64 = 10 * 1024 * 1024;
+
+pub const DEFAULT_EXPIRY_HOURS: u32 = 7 * 24;
+const MAX_EXPIRY_HOURS: u32 = 31 * 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SendKind {
+    Text,
+    File,
+}
+
+/// What to send, as given by the send form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SendContent {
+    Text { text: String },
+    File { path: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSend {
+    pub content: SendContent,
+    /// Shown to the recipient; a file's own name when left out
+    pub name: Option<String>,
+    pub expires_in_hours: Option<u32>,
+    /// `None` for no limit
+    pub max_views: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Send {
+    pub id: String,
+    pub kind: SendKind,
+    pub name: Option<String>,
+    pub size: i64,
+    pub max_views: Option<u32>,
+    pub view_count: u32,
+    pub expires_at: String,
+    pub created_at: String,
+    pub link: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendRow {
+    id: String,
+    kind: SendKind,
+    encrypted_name: Option<String>,
+    encrypted_key: String,
+    size: i64,
+    max_views: Option<u32>,
+    view_count: u32,
+    expires_at: String,
+    created_at: String,
+}
+
+impl SendRow {
+    /// Past its expiry or out of views: nobody can open it any more
+    fn is_finished(&self) -> bool {
+        let expired = DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|at| at.with_timezone(&Utc) <= Utc::now())
+            .unwrap_or(false);
+        expired || self.max_views.is_some_and(|max| self.view_count >= max)
+    }
+
+    fn into_send(self, vault_key: &[u8]) -> Result<Send> {
+        let wrapped: EncryptedData = serde_json::from_str(&self.encrypted_key)?;
+        let key = crypto::decrypt(&wrapped, vault_key)?;
+        let name = match &self.encrypted_name {
+            Some(name) => Some(crypto::decrypt_from_json(name, &key)?),
+            None => None,
+        };
+
+        Ok(Send {
+            link: link(&self.id, &key),
+            id: self.id,
+            kind: self.kind,
+            name,
+            size: self.size,
+            max_views: self.max_views,
+            view_count: self.view_count,
+            expires_at: self.expires_at,
+            created_at: self.created_at,
+        })
+    }
+}
+
+const ROW_COLUMNS: &str =
+    "id,kind,encrypted_name,encrypted_key,size,max_views,view_count,expires_at,created_at";
+
+/// Where recipients open sends: the web app's send page
+fn link(id: &str, key: &[u8]) -> String {
+    let base =
+        std::env::var("BIRCHVAULT_SEND_URL").unwrap_or_else(|_| DEFAULT_LINK_BASE.to_string());
+    format!(
+        "{}/{}#{}",
+        base.trim_end_matches('/'),
+        id,
+        URL_SAFE_NO_PAD.encode(key)
+    )
+}
+
+fn validate(request: &NewSend) -> Result<u32> {
+    let hours = request.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+    if !(1..=MAX_EXPIRY_HOURS).contains(&hours) {
+        return Err(AppError::InvalidOperation(format!(
+            "A send can last between 1 hour and {} days",
+            MAX_EXPIRY_HOURS / 24
+        )));
+    }
+    if request.max_views == Some(0) {
+        return Err(AppError::InvalidOperation(
+            "A send has to allow at least one view".to_string(),
+        ));
+    }
+    Ok(hours)
+}
+
+fn too_large() -> AppError {
+    AppError::InvalidOperation(format!(
+        "A send can be at most {} MB",
+        MAX_SEND_BYTES / (1024 * 1024)
+    ))
+}
+
+/// Encrypt and upload a send, returning it with its link
+pub async fn create(engine: &SyncEngine, vault_key: &[u8], request: NewSend) -> Result<Send> {
+    let hours = validate(&request)?;
+    let session = engine.active_session().await?;
+
+    let name = request
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let (kind, plaintext, name) = match request.content {
+        SendContent::Text { text } => (SendKind::Text, text.into_bytes(), name),
+        SendContent::File { path } => {
+            let path = Path::new(&path);
+            if std::fs::metadata(path)?.len() > MAX_SEND_BYTES {
+                return Err(too_large());
+            }
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| AppError::InvalidOperation("Not a file".to_string()))?;
+            (
+                SendKind::File,
+                tokio::fs::read(path).await?,
+                Some(name.unwrap_or(file_name)),
+            )
+        }
+    };
+    if plaintext.is_empty() {
+        return Err(AppError::InvalidOperation(
+            "There's nothing to send".to_string(),
+        ));
+    }
+    if plaintext.len() as u64 > MAX_SEND_BYTES {
+        return Err(too_large());
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let key = crypto::generate_key();
+    let blob = serde_json::to_vec(&crypto::encrypt(&plaintext, &key)?)?;
+    let encrypted_name = match &name {
+        Some(name) => Some(crypto::encrypt_to_json(name, &key)?),
+        None => None,
+    };
+    let row = SendRow {
+        id: id.clone(),
+        kind,
+        encrypted_name,
+        encrypted_key: serde_json::to_string(&crypto::encrypt(&key, vault_key)?)?,
+        size: plaintext.len() as i64,
+        max_views: request.max_views,
+        view_count: 0,
+        expires_at: (Utc::now() + Duration::hours(hours as i64)).to_rfc3339(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    engine.upload_send(&id, blob).await?;
+    let inserted = engine
+        .rest_write(
+            Method::POST,
+            "vault_sends",
+            Some(&serde_json::json!({
+                "id": row.id,
+                "user_id": session.user_id,
+                "kind": row.kind,
+                "encrypted_name": row.encrypted_name,
+                "encrypted_key": row.encrypted_key,
+                "size": row.size,
+                "max_views": row.max_views,
+                "expires_at": row.expires_at,
+            })),
+            None,
+        )
+        .await;
+    if let Err(e) = inserted {
+        // Without its row the upload could never be opened or cleaned up
+        if let Err(e) = engine.delete_send(&id).await {
+            log::warn!("Failed to remove the upload of send {}: {}", id, e);
+        }
+        return Err(e);
+    }
+
+    row.into_send(vault_key)
+}
+
+/// Sends that can still be opened, newest first. Finished ones (expired or
+/// out of views) are deleted along the way.
+pub async fn list(engine: &SyncEngine, vault_key: &[u8]) -> Result<Vec<Send>> {
+    let rows: Vec<SendRow> = engine
+        .rest_get(&format!(
+            "vault_sends?select={}&order=created_at.desc",
+            ROW_COLUMNS
+        ))
+        .await?;
+
+    let mut sends = Vec::new();
+    for row in rows {
+        if row.is_finished() {
+            if let Err(e) = revoke(engine, &row.id).await {
+                log::warn!("Failed to clean up finished send {}: {}", row.id, e);
+            }
+            continue;
+        }
+        // One unreadable key shouldn't hide every other send
+        let id = row.id.clone();
+        match row.into_send(vault_key) {
+            Ok(send) => sends.push(send),
+            Err(e) => log::warn!("Skipping send {} that can't be decrypted: {}", id, e),
+        }
+    }
+    Ok(sends)
+}
+
+/// Stop a send working before it expires, deleting its upload
+pub async fn revoke(engine: &SyncEngine, id: &str) -> Result<()> {
+    let id = Uuid::parse_str(id)
+        .map_err(|_| AppError::NotFound("Send not found".to_string()))?
+        .to_string();
+
+    engine.delete_send(&id).await?;
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_sends?id=eq.{}", id),
+            None,
+            None,
+        )
+        .await
+}
@@ -0,0 +1,64 @@
+// ============================================
+// BirchVault Desktop - Session Refresh
+// ============================================
+//
+// Keeps the Supabase access token fresh while signed in, so a session left
+// idle (nothing to sync, vault locked) is still valid when next needed. The
+// refresh itself is `SyncEngine::active_session`, which renews a token near
+// expiry and saves the new pair. A refresh token the server refuses can only
+// be replaced by signing in again, which `reauth-required` asks the UI for.
+
+use crate::commands::AppState;
+use crate::error::AppError;
+use crate::offline;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Well inside the margin in which `active_session` refreshes
+const CHECK_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReauthRequired {
+    pub email: String,
+}
+
+/// Refresh the access token before it expires; emits `reauth-required` once
+/// per refused refresh token
+pub fn spawn_refresh_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        let mut refused_token: Option<String> = None;
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppState>();
+            let Ok(Some(session)) = state.db.get_session() else {
+                continue;
+            };
+            if offline::is_active(&state.db)
+                || refused_token.as_deref() == Some(session.refresh_token.as_str())
+                || !state.sync_engine.get_status().await.is_online
+            {
+                continue;
+            }
+
+            match state.sync_engine.active_session().await {
+                Ok(_) => {}
+                Err(AppError::SessionExpired) => {
+                    log::warn!("The server refused the refresh token; sign-in needed");
+                    let _ = app.emit(
+                        "reauth-required",
+                        ReauthRequired {
+                            email: session.email,
+                        },
+                    );
+                    refused_token = Some(session.refresh_token);
+                }
+                Err(e) => log::warn!("Background token refresh failed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,573 @@
+// ============================================
+// BirchVault Desktop - Item Sharing
+// ============================================
+//
+// Vault items are encrypted with the vault key, which is never shared, so a
+// shared item gets a random item key of its own. The payload is stored once
+// on the server under that key (`vault_shared_item_data`), the owner keeps
+// the item key encrypted with their vault key, and each share carries it
+// wrapped with the recipient's public key (see keypair.rs).
+//
+// The owner's local item stays the source of truth. `reconcile` pushes local
+// edits to the shared copy and pulls in edits made by recipients with write
+// permission, last writer wins. Revoking a share re-keys the item so the
+// removed recipient's wrapped key no longer opens future versions.
+
+use crate::crypto;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::fields;
+use crate::items::{self, ItemType};
+use crate::keypair;
+use crate::sync::SyncEngine;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SharePermission {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ShareRow {
+    id: String,
+    vault_item_id: String,
+    shared_by_user_id: String,
+    shared_by_email: Option<String>,
+    shared_with_user_id: Option<String>,
+    shared_with_email: Option<String>,
+    encrypted_key: String,
+    permission: SharePermission,
+    accepted_at: Option<String>,
+    created_at: String,
+    expires_at: Option<String>,
+}
+
+impl ShareRow {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .is_some_and(|at| at.with_timezone(&Utc) <= Utc::now())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SharedDataRow {
+    vault_item_id: String,
+    item_type: String,
+    encrypted_data: String,
+    owner_encrypted_key: String,
+    updated_by: Option<String>,
+    updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recipient {
+    user_id: String,
+    public_key: String,
+}
+
+/// A share of one of our items, as the owner sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemShare {
+    pub id: String,
+    pub item_id: String,
+    pub shared_with_email: Option<String>,
+    pub permission: SharePermission,
+    pub accepted: bool,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+}
+
+impl From<ShareRow> for ItemShare {
+    fn from(row: ShareRow) -> Self {
+        ItemShare {
+            id: row.id,
+            item_id: row.vault_item_id,
+            shared_with_email: row.shared_with_email,
+            permission: row.permission,
+            accepted: row.accepted_at.is_some(),
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+/// Someone else's item shared with this account, decrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedItem {
+    pub share_id: String,
+    pub item_id: String,
+    pub item_type: String,
+    pub data: Value,
+    /// Where the item came from
+    pub shared_by_email: Option<String>,
+    pub permission: SharePermission,
+    /// Accepted with write permission, so `update_shared` will be allowed
+    pub editable: bool,
+    pub accepted: bool,
+    pub updated_at: String,
+}
+
+const SHARE_COLUMNS: &str = "id,vault_item_id,shared_by_user_id,shared_by_email,\
+                             shared_with_user_id,shared_with_email,encrypted_key,\
+                             permission,accepted_at,created_at,expires_at";
+const DATA_COLUMNS: &str =
+    "vault_item_id,item_type,encrypted_data,owner_encrypted_key,updated_by,updated_at";
+
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+async fn get_share(engine: &SyncEngine, id: &str) -> Result<ShareRow> {
+    let rows: Vec<ShareRow> = engine
+        .rest_get(&format!(
+            "vault_shared_items?id=eq.{}&select={}",
+            id, SHARE_COLUMNS
+        ))
+        .await?;
+    rows.into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("Share not found".to_string()))
+}
+
+async fn shares_of(engine: &SyncEngine, item_id: &str) -> Result<Vec<ShareRow>> {
+    engine
+        .rest_get(&format!(
+            "vault_shared_items?vault_item_id=eq.{}&select={}&order=created_at.asc",
+            item_id, SHARE_COLUMNS
+        ))
+        .await
+}
+
+async fn get_data(engine: &SyncEngine, item_id: &str) -> Result<Option<SharedDataRow>> {
+    let rows: Vec<SharedDataRow> = engine
+        .rest_get(&format!(
+            "vault_shared_item_data?vault_item_id=eq.{}&select={}",
+            item_id, DATA_COLUMNS
+        ))
+        .await?;
+    Ok(rows.into_iter().next())
+}
+
+async fn delete_data(engine: &SyncEngine, item_id: &str) -> Result<()> {
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_shared_item_data?vault_item_id=eq.{}", item_id),
+            None,
+            None,
+        )
+        .await
+}
+
+fn decrypt_data(row: &SharedDataRow, item_key: &[u8]) -> Result<Value> {
+    Ok(serde_json::from_str(&crypto::decrypt_from_json(
+        &row.encrypted_data,
+        item_key,
+    )?)?)
+}
+
+// ============================================
+// Owner
+// ============================================
+
+/// Share one of our items with another account by email
+pub async fn share(
+    engine: &SyncEngine,
+    db: &Database,
+    vault_key: &[u8],
+    item_id: &str,
+    email: &str,
+    permission: SharePermission,
+    expires_at: Option<String>,
+) -> Result<ItemShare> {
+    let session = engine.active_session().await?;
+    let email = email.trim().to_lowercase();
+    if email == session.email.to_lowercase() {
+        return Err(AppError::InvalidOperation(
+            "You can't share an item with yourself".to_string(),
+        ));
+    }
+    if let Some(at) = expires_at.as_deref() {
+        if timestamp(at).is_none_or(|at| at <= Utc::now()) {
+            return Err(AppError::InvalidOperation(
+                "The expiry must be a future date".to_string(),
+            ));
+        }
+    }
+
+    let item = db
+        .get_vault_item(item_id)?
+        .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+    if item.deleted_at.is_some() {
+        return Err(AppError::InvalidOperation(
+            "Items in the trash can't be shared".to_string(),
+        ));
+    }
+    // The shared copy references the server row
+    if item.synced_at.is_none() {
+        return Err(AppError::InvalidOperation(
+            "Sync the item before sharing it".to_string(),
+        ));
+    }
+
+    let recipients: Vec<Recipient> = engine
+        .rpc_query(
+            "lookup_sharing_recipient",
+            serde_json::json!({ "p_email": email }),
+        )
+        .await?;
+    let recipient = recipients.into_iter().next().ok_or_else(|| {
+        AppError::NotFound(
+            "No BirchVault account with sharing set up uses that email address".to_string(),
+        )
+    })?;
+
+    let item_key = match get_data(engine, item_id).await? {
+        Some(row) => owner_key(&row, vault_key)?,
+        None => {
+            let item_key = crypto::generate_key().to_vec();
+            let payload = crypto::decrypt_from_json(&item.encrypted_data, vault_key)?;
+            engine
+                .rest_write(
+                    Method::POST,
+                    "vault_shared_item_data",
+                    Some(&serde_json::json!({
+                        "vault_item_id": item_id,
+                        "owner_id": session.user_id,
+                        "item_type": item.item_type,
+                        "encrypted_data": crypto::encrypt_to_json(&payload, &item_key)?,
+                        "owner_encrypted_key": wrap_for_owner(&item_key, vault_key)?,
+                        "updated_by": session.user_id,
+                    })),
+                    None,
+                )
+                .await?;
+            item_key
+        }
+    };
+
+    let row = ShareRow {
+        id: Uuid::new_v4().to_string(),
+        vault_item_id: item_id.to_string(),
+        shared_by_user_id: session.user_id.clone(),
+        shared_by_email: Some(session.email.to_lowercase()),
+        shared_with_user_id: Some(recipient.user_id),
+        shared_with_email: Some(email),
+        encrypted_key: keypair::wrap(&recipient.public_key, &item_key)?,
+        permission,
+        accepted_at: None,
+        created_at: Utc::now().to_rfc3339(),
+        expires_at,
+    };
+    engine
+        .rest_write(
+            Method::POST,
+            "vault_shared_items",
+            Some(&serde_json::json!({
+                "id": row.id,
+                "vault_item_id": row.vault_item_id,
+                "shared_by_user_id": row.shared_by_user_id,
+                "shared_by_email": row.shared_by_email,
+                "shared_with_user_id": row.shared_with_user_id,
+                "shared_with_email": row.shared_with_email,
+                "encrypted_key": row.encrypted_key,
+                "permission": row.permission,
+                "expires_at": row.expires_at,
+            })),
+            None,
+        )
+        .await?;
+
+    Ok(row.into())
+}
+
+fn wrap_for_owner(item_key: &[u8], vault_key: &[u8]) -> Result<String> {
+    Ok(serde_json::to_string(&crypto::encrypt(
+        item_key, vault_key,
+    )?)?)
+}
+
+fn owner_key(row: &SharedDataRow, vault_key: &[u8]) -> Result<Vec<u8>> {
+    crypto::decrypt(&serde_json::from_str(&row.owner_encrypted_key)?, vault_key)
+}
+
+/// Who an item of ours is shared with
+pub async fn list_item_shares(engine: &SyncEngine, item_id: &str) -> Result<Vec<ItemShare>> {
+    let session = engine.active_session().await?;
+    Ok(shares_of(engine, item_id)
+        .await?
+        .into_iter()
+        .filter(|row| row.shared_by_user_id == session.user_id)
+        .map(ItemShare::from)
+        .collect())
+}
+
+/// Remove a share. Recipients use this to leave a share; owners to revoke it,
+/// in which case the item is re-keyed for whoever still has access, or its
+/// shared copy removed when nobody does.
+pub async fn revoke(engine: &SyncEngine, vault_key: &[u8], share_id: &str) -> Result<()> {
+    let session = engine.active_session().await?;
+    let share = get_share(engine, share_id).await?;
+    engine
+        .rest_write(
+            Method::DELETE,
+            &format!("vault_shared_items?id=eq.{}", share_id),
+            None,
+            None,
+        )
+        .await?;
+
+    if share.shared_by_user_id != session.user_id {
+        return Ok(());
+    }
+
+    let remaining = shares_of(engine, &share.vault_item_id).await?;
+    if remaining.is_empty() {
+        return delete_data(engine, &share.vault_item_id).await;
+    }
+    let Some(data) = get_data(engine, &share.vault_item_id).await? else {
+        return Ok(());
+    };
+
+    let payload = crypto::decrypt_from_json(&data.encrypted_data, &owner_key(&data, vault_key)?)?;
+    let item_key = crypto::generate_key().to_vec();
+    engine
+        .rest_write(
+            Method::PATCH,
+            &format!(
+                "vault_shared_item_data?vault_item_id=eq.{}",
+                share.vault_item_id
+            ),
+            Some(&serde_json::json!({
+                "encrypted_data": crypto::encrypt_to_json(&payload, &item_key)?,
+                "owner_encrypted_key": wrap_for_owner(&item_key, vault_key)?,
+                "updated_by": session.user_id,
+            })),
+            None,
+        )
+        .await?;
+
+    for other in remaining {
+        let public_key = match other.shared_with_user_id.as_deref() {
+            Some(user_id) => keypair::public_key_for(engine, user_id).await?,
+            None => None,
+        };
+        match public_key {
+            Some(public_key) => {
+                engine
+                    .rest_write(
+                        Method::PATCH,
+                        &format!("vault_shared_items?id=eq.{}", other.id),
+                        Some(&serde_json::json!({
+                            "encrypted_key": keypair::wrap(&public_key, &item_key)?,
+                        })),
+                        None,
+                    )
+                    .await?
+            }
+            // Can't re-wrap without a key, and the old one no longer opens the item
+            None => {
+                log::warn!("Dropping share {}: recipient has no public key", other.id);
+                engine
+                    .rest_write(
+                        Method::DELETE,
+                        &format!("vault_shared_items?id=eq.{}", other.id),
+                        None,
+                        None,
+                    )
+                    .await?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Bring the shared copies of our items in line with the local vault: push
+/// local edits, pull in edits from recipients, whichever is newer. Returns
+/// how many local items changed.
+pub async fn reconcile(engine: &SyncEngine, db: &Database, vault_key: &[u8]) -> Result<usize> {
+    let session = engine.active_session().await?;
+    let rows: Vec<SharedDataRow> = engine
+        .rest_get(&format!(
+            "vault_shared_item_data?owner_id=eq.{}&select={}",
+            session.user_id, DATA_COLUMNS
+        ))
+        .await?;
+
+    let mut pulled = 0;
+    for row in rows {
+        let Some(mut item) = db.get_vault_item(&row.vault_item_id)? else {
+            continue;
+        };
+        if item.deleted_at.is_some() {
+            continue;
+        }
+        let item_key = match owner_key(&row, vault_key) {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("Skipping shared item {}: {}", row.vault_item_id, e);
+                continue;
+            }
+        };
+
+        let local_at = timestamp(&item.local_updated_at);
+        let remote_at = timestamp(&row.updated_at);
+        let edited_by_recipient = row
+            .updated_by
+            .as_deref()
+            .is_some_and(|by| by != session.user_id);
+
+        if edited_by_recipient && remote_at > local_at {
+            let payload = crypto::decrypt_from_json(&row.encrypted_data, &item_key)?;
+            item.encrypted_data = crypto::encrypt_to_json(&payload, vault_key)?;
+            item.item_type = row.item_type;
+            db.update_vault_item(&item)?;
+            pulled += 1;
+        } else if local_at > remote_at {
+            let payload = crypto::decrypt_from_json(&item.encrypted_data, vault_key)?;
+            engine
+                .rest_write(
+                    Method::PATCH,
+                    &format!(
+                        "vault_shared_item_data?vault_item_id=eq.{}",
+                        row.vault_item_id
+                    ),
+                    Some(&serde_json::json!({
+                        "item_type": item.item_type,
+                        "encrypted_data": crypto::encrypt_to_json(&payload, &item_key)?,
+                        "updated_by": session.user_id,
+                    })),
+                    None,
+                )
+                .await?;
+        }
+    }
+    Ok(pulled)
+}
+
+// ============================================
+// Recipient
+// ============================================
+
+/// Items other accounts have shared with us, pending ones included so they
+/// can be previewed before accepting
+pub async fn list_shared_with_me(engine: &SyncEngine, vault_key: &[u8]) -> Result<Vec<SharedItem>> {
+    let session = engine.active_session().await?;
+    let shares: Vec<ShareRow> = engine
+        .rest_get(&format!(
+            "vault_shared_items?shared_with_user_id=eq.{}&select={}&order=created_at.desc",
+            session.user_id, SHARE_COLUMNS
+        ))
+        .await?;
+    let shares: Vec<ShareRow> = shares.into_iter().filter(|s| !s.is_expired()).collect();
+    if shares.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = shares
+        .iter()
+        .map(|s| s.vault_item_id.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    let data: Vec<SharedDataRow> = engine
+        .rest_get(&format!(
+            "vault_shared_item_data?vault_item_id=in.({})&select={}",
+            ids, DATA_COLUMNS
+        ))
+        .await?;
+
+    let private_key = keypair::ensure(engine, vault_key).await?;
+    let mut items = Vec::with_capacity(shares.len());
+    for share in shares {
+        let Some(row) = data.iter().find(|d| d.vault_item_id == share.vault_item_id) else {
+            continue;
+        };
+        let decrypted = keypair::unwrap(&private_key, &share.encrypted_key)
+            .and_then(|item_key| decrypt_data(row, &item_key));
+        match decrypted {
+            Ok(value) => {
+                let accepted = share.accepted_at.is_some();
+                items.push(SharedItem {
+                    share_id: share.id,
+                    item_id: share.vault_item_id,
+                    item_type: row.item_type.clone(),
+                    data: value,
+                    shared_by_email: share.shared_by_email,
+                    permission: share.permission,
+                    editable: accepted && share.permission == SharePermission::Write,
+                    accepted,
+                    updated_at: row.updated_at.clone(),
+                })
+            }
+            Err(e) => log::warn!("Skipping shared item {}: {}", share.vault_item_id, e),
+        }
+    }
+    Ok(items)
+}
+
+/// Accept a share addressed to this account
+pub async fn accept(engine: &SyncEngine, share_id: &str) -> Result<()> {
+    engine
+        .rpc(
+            "shared_item_accept",
+            serde_json::json!({ "share_id": share_id }),
+        )
+        .await
+}
+
+/// Save an edit to an item shared with us with write permission
+pub async fn update_shared(
+    engine: &SyncEngine,
+    vault_key: &[u8],
+    share_id: &str,
+    data: &Value,
+) -> Result<()> {
+    let session = engine.active_session().await?;
+    let share = get_share(engine, share_id).await?;
+    if share.shared_with_user_id.as_deref() != Some(session.user_id.as_str())
+        || share.accepted_at.is_none()
+        || share.permission != SharePermission::Write
+        || share.is_expired()
+    {
+        return Err(AppError::InvalidOperation(
+            "This shared item is read-only".to_string(),
+        ));
+    }
+
+    let row = get_data(engine, &share.vault_item_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shared item not found".to_string()))?;
+    let item_type: ItemType = row.item_type.parse()?;
+
+    let private_key = keypair::ensure(engine, vault_key).await?;
+    let item_key = keypair::unwrap(&private_key, &share.encrypted_key)?;
+    let previous = decrypt_data(&row, &item_key).ok();
+    fields::validate(data, previous.as_ref())?;
+    items::validate(item_type, data, previous.as_ref())?;
+    engine
+        .rest_write(
+            Method::PATCH,
+            &format!(
+                "vault_shared_item_data?vault_item_id=eq.{}",
+                share.vault_item_id
+            ),
+            Some(&serde_json::json!({
+                "encrypted_data": crypto::encrypt_to_json(&data.to_string(), &item_key)?,
+                "updated_by": session.user_id,
+            })),
+            None,
+        )
+        .await
+}
@@ -0,0 +1,323 @@
+// ============================================
+// BirchVault Desktop - Password Strength
+// ============================================
+//
+// A local, zxcvbn-style estimate: character-set entropy with penalties for
+// common passwords, sequences, repeats and personal info. Scores use the
+// familiar 0-4 scale.
+
+use crate::error::{AppError, Result};
+use crate::i18n;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const MAX_SCORE: u8 = 4;
+
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "123456", "12345678", "123456789", "1234567890", "qwerty", "qwertyuiop",
+    "abc123", "111111", "123123", "letmein", "welcome", "monkey", "dragon", "football",
+    "baseball", "iloveyou", "admin", "login", "master", "sunshine", "princess", "shadow",
+    "trustno1", "passw0rd", "password1", "starwars", "whatever", "superman", "hello",
+    "freedom", "secret", "birchvault", "changeme", "default", "mypassword",
+];
+
+const SEQUENCES: &[&str] = &[
+    "abcdefghijklmnopqrstuvwxyz",
+    "0123456789",
+    "qwertyuiop",
+    "asdfghjkl",
+    "zxcvbnm",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrengthReport {
+    /// 0 (very weak) to 4 (very strong)
+    pub score: u8,
+    pub entropy_bits: f64,
+    /// Suggestions for a stronger password, in the current locale
+    pub feedback: Vec<String>,
+}
+
+// ============================================
+// Estimation
+// ============================================
+
+fn charset_size(password: &str) -> f64 {
+    let mut size = 0.0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        size += 26.0;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        size += 26.0;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        size += 10.0;
+    }
+    if password.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        size += 33.0;
+    }
+    if !password.is_ascii() {
+        size += 100.0;
+    }
+    size
+}
+
+/// Length of the longest run of characters that follow a keyboard/alphabet sequence
+fn longest_sequence(lower: &str) -> usize {
+    let chars: Vec<char> = lower.chars().collect();
+    let mut longest = 0;
+
+    for sequence in SEQUENCES {
+        let forward: Vec<char> = sequence.chars().collect();
+        let backward: Vec<char> = sequence.chars().rev().collect();
+        for seq in [&forward, &backward] {
+            for start in 0..chars.len() {
+                let Some(pos) = seq.iter().position(|c| *c == chars[start]) else {
+                    continue;
+                };
+                let run = chars[start..]
+                    .iter()
+                    .zip(&seq[pos..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                longest = longest.max(run);
+            }
+        }
+    }
+
+    longest
+}
+
+/// Number of characters that repeat the one before them ("aaa" -> 2)
+fn repeated_chars(password: &str) -> usize {
+    let chars: Vec<char> = password.chars().collect();
+    chars.windows(2).filter(|w| w[0] == w[1]).count()
+}
+
+/// Estimate strength. `user_inputs` are personal strings (email, name) to penalise.
+pub fn estimate(password: &str, user_inputs: &[&str]) -> StrengthReport {
+    let length = password.chars().count();
+    let lower = password.to_lowercase();
+    let mut feedback = Vec::new();
+
+    let mut effective_length = length as f64;
+
+    let sequence = longest_sequence(&lower);
+    if sequence >= 4 {
+        effective_length -= (sequence - 1) as f64;
+        feedback.push(i18n::t("strength.sequence"));
+    }
+
+    let repeats = repeated_chars(password);
+    if repeats >= 2 {
+        effective_length -= repeats as f64 * 0.75;
+        feedback.push(i18n::t("strength.repeated"));
+    }
+
+    let common = COMMON_PASSWORDS.iter().find(|common| lower.contains(*common));
+    if let Some(common) = common {
+        effective_length -= (common.len() - 1) as f64;
+        feedback.push(i18n::t("strength.common"));
+    }
+
+    let personal = user_inputs
+        .iter()
+        .flat_map(|input| input.split(['@', '.', ' ', '_', '-']))
+        .filter(|part| part.len() >= 3)
+        .find(|part| lower.contains(&part.to_lowercase()));
+    if let Some(part) = personal {
+        effective_length -= (part.len() - 1) as f64;
+        feedback.push(i18n::t("strength.personal_info"));
+    }
+
+    let charset = charset_size(password);
+    let entropy_bits = if charset > 0.0 {
+        effective_length.max(0.0) * charset.log2()
+    } else {
+        0.0
+    };
+
+    if length < 12 {
+        feedback.push(i18n::t("strength.too_short"));
+    }
+    if charset < 60.0 {
+        feedback.push(i18n::t("strength.add_variety"));
+    }
+
+    let mut score = match entropy_bits {
+        e if e < 28.0 => 0,
+        e if e < 40.0 => 1,
+        e if e < 60.0 => 2,
+        e if e < 80.0 => 3,
+        _ => MAX_SCORE,
+    };
+    // A well-known password is weak however it is decorated
+    if common.is_some() && length < 16 {
+        score = score.min(1);
+    }
+
+    StrengthReport {
+        score,
+        entropy_bits: (entropy_bits * 10.0).round() / 10.0,
+        feedback,
+    }
+}
+
+// ============================================
+// Crack-time Analysis
+// ============================================
+
+/// Attack scenarios, in guesses per second (the zxcvbn set)
+const ONLINE_THROTTLED: f64 = 100.0 / 3600.0;
+const ONLINE_UNTHROTTLED: f64 = 10.0;
+const OFFLINE_SLOW_HASHING: f64 = 1e4;
+const OFFLINE_FAST_HASHING: f64 = 1e10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrackTimes<T> {
+    pub online_throttled: T,
+    pub online_unthrottled: T,
+    pub offline_slow_hashing: T,
+    pub offline_fast_hashing: T,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAnalysis {
+    #[serde(flatten)]
+    pub strength: StrengthReport,
+    pub guesses_log10: f64,
+    pub crack_times_seconds: CrackTimes<f64>,
+    /// Localised, e.g. "3 hours" or "centuries"
+    pub crack_times_display: CrackTimes<String>,
+}
+
+/// Human-readable duration in the current locale
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (key, unit) = match seconds {
+        s if s < 1.0 => return i18n::t("strength.time_instant"),
+        s if s < MINUTE => ("strength.time_seconds", 1.0),
+        s if s < HOUR => ("strength.time_minutes", MINUTE),
+        s if s < DAY => ("strength.time_hours", HOUR),
+        s if s < MONTH => ("strength.time_days", DAY),
+        s if s < YEAR => ("strength.time_months", MONTH),
+        s if s < CENTURY => ("strength.time_years", YEAR),
+        _ => return i18n::t("strength.time_centuries"),
+    };
+    let count = (seconds / unit).round().max(1.0) as i64;
+    i18n::t_with(key, &[("count", &i18n::format_number(count))])
+}
+
+/// Strength estimate plus how long each attack scenario would take on average
+pub fn analyze(password: &str, user_inputs: &[&str]) -> PasswordAnalysis {
+    let strength = estimate(password, user_inputs);
+    // On average an attacker searches half the space
+    let guesses_log10 = (strength.entropy_bits - 1.0).max(0.0) * 2f64.log10();
+    let guesses = 10f64.powf(guesses_log10);
+
+    let seconds = CrackTimes {
+        online_throttled: guesses / ONLINE_THROTTLED,
+        online_unthrottled: guesses / ONLINE_UNTHROTTLED,
+        offline_slow_hashing: guesses / OFFLINE_SLOW_HASHING,
+        offline_fast_hashing: guesses / OFFLINE_FAST_HASHING,
+    };
+    let display = CrackTimes {
+        online_throttled: display_time(seconds.online_throttled),
+        online_unthrottled: display_time(seconds.online_unthrottled),
+        offline_slow_hashing: display_time(seconds.offline_slow_hashing),
+        offline_fast_hashing: display_time(seconds.offline_fast_hashing),
+    };
+
+    PasswordAnalysis {
+        strength,
+        guesses_log10: (guesses_log10 * 100.0).round() / 100.0,
+        crack_times_seconds: seconds,
+        crack_times_display: display,
+    }
+}
+
+// ============================================
+// Master Password Gate
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterPasswordCheck {
+    pub strength: StrengthReport,
+    pub min_score: u8,
+    /// False if the password is below `min_score` and must be refused
+    pub acceptable: bool,
+    /// The password is also stored as a vault item password
+    pub reused: bool,
+    /// Refusal reason or reuse warning, in the current locale
+    pub message: Option<String>,
+}
+
+pub fn check_master_password(
+    password: &str,
+    user_inputs: &[&str],
+    min_score: u8,
+    item_password_hashes: &[String],
+) -> MasterPasswordCheck {
+    let strength = estimate(password, user_inputs);
+    let min_score = min_score.min(MAX_SCORE);
+    let acceptable = strength.score >= min_score;
+    let reused = is_reused(password, item_password_hashes);
+
+    let message = if !acceptable {
+        Some(i18n::t("strength.master_too_weak"))
+    } else if reused {
+        Some(i18n::t("strength.master_reused"))
+    } else {
+        None
+    };
+
+    MasterPasswordCheck {
+        strength,
+        min_score,
+        acceptable,
+        reused,
+        message,
+    }
+}
+
+/// Refuse a master password scoring below `min_score`. Reuse is only warned
+/// about, by `check_master_password`, since this side can't see item passwords.
+pub fn require_master_password(password: &str, user_inputs: &[&str], min_score: u8) -> Result<()> {
+    let check = check_master_password(password, user_inputs, min_score, &[]);
+    if !check.acceptable {
+        return Err(AppError::Validation(
+            check
+                .message
+                .unwrap_or_else(|| i18n::t("strength.master_too_weak")),
+        ));
+    }
+    Ok(())
+}
+
+// ============================================
+// Reuse Detection
+// ============================================
+
+/// SHA-256 hex digest, the format the frontend uses for item password hashes
+pub fn password_hash(password: &str) -> String {
+    Sha256::digest(password.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// True if `password` matches any of the given SHA-256 hex digests
+pub fn is_reused(password: &str, hashes: &[String]) -> bool {
+    let hash = password_hash(password);
+    hashes.iter().any(|h| h.eq_ignore_ascii_case(&hash))
+}
@@ -2,22 +2,79 @@
 // BirchVault Desktop - Sync Engine
 // ============================================
 
-use crate::db::{Database, Folder, UserSession, VaultItem};
+use crate::db::{
+    Attachment, Collection, Database, Folder, OrgItem, Organization, SyncCursor, SyncHistoryEntry,
+    SyncQueueItem, Tombstone, UserSession, VaultItem,
+};
 use crate::error::{AppError, Result};
+use crate::filesync::{self, BackendConfig};
+use crate::i18n;
+use crate::offline;
+use crate::realtime::Subscription;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::{Client, Method, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+
+/// Sync history is pruned to this many entries and days
+const SYNC_HISTORY_MAX_ENTRIES: u32 = 500;
+const SYNC_HISTORY_MAX_DAYS: i64 = 30;
+
+/// Supabase Storage bucket for encrypted attachment blobs, keyed `<user id>/<attachment id>`
+const ATTACHMENTS_BUCKET: &str = "vault-attachments";
+const SUPPORT_BUNDLES_BUCKET: &str = "support-bundles";
+const SENDS_BUCKET: &str = "sends";
+
+/// Rows per request when pulling vault items and folders
+const PULL_PAGE_SIZE: usize = 500;
+
+/// Access tokens are refreshed once they're this close to expiring
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// How long a server being set up gets to answer the connection test
+const CONFIG_TEST_TIMEOUT_SECS: u64 = 10;
+
+/// Server schema version (`schema_meta.version`) this build was written against
+pub const EXPECTED_SCHEMA_VERSION: i64 = 3;
 
 // ============================================
 // Supabase API Types
 // ============================================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SupabaseConfig {
     pub url: String,
     pub anon_key: String,
+    /// PostgREST hosted apart from `url`
+    #[serde(default)]
+    pub rest_url: Option<String>,
+    /// Auth (GoTrue) hosted apart from `url`
+    #[serde(default)]
+    pub auth_url: Option<String>,
+}
+
+impl SupabaseConfig {
+    pub fn rest_endpoint(&self) -> String {
+        self.rest_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/rest/v1", self.url))
+    }
+
+    pub fn auth_endpoint(&self) -> String {
+        self.auth_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/auth/v1", self.url))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,11 +90,132 @@ struct SupabaseVaultItem {
     updated_at: String,
 }
 
+impl SupabaseVaultItem {
+    fn into_local(self, synced_at: &str) -> VaultItem {
+        VaultItem {
+            id: self.id,
+            encrypted_data: self.encrypted_data,
+            item_type: self.item_type,
+            folder_id: self.folder_id,
+            is_favorite: false, // Favorite flag is stored in encrypted_data
+            deleted_at: self.deleted_at,
+            synced_at: Some(synced_at.to_string()),
+            local_updated_at: self.updated_at.clone(),
+            server_updated_at: Some(self.updated_at),
+            sort_index: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SupabaseOrgItem {
+    id: String,
+    user_id: String,
+    organization_id: String,
+    encrypted_data: String,
+    #[serde(rename = "type")]
+    item_type: String,
+    deleted_at: Option<String>,
+    updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseMembership {
+    organization_id: String,
+    role: String,
+    status: String,
+    encrypted_org_key: Option<String>,
+    vault_organizations: Option<SupabaseOrgName>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseOrgName {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseCollection {
+    id: String,
+    organization_id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseCollectionAccess {
+    collection_id: String,
+    permission: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseCollectionItem {
+    collection_id: String,
+    vault_item_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SupabaseFolder {
     id: String,
     user_id: String,
     name: String,
+    #[serde(default)]
+    retention_days: Option<i64>,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SupabaseTombstone {
+    id: String,
+    table_name: String,
+    record_id: String,
+    deleted_at: String,
+}
+
+/// A row pulled in `(POSITION_COLUMN, id)` order by `SyncEngine::pull_paged`
+trait PagedRow {
+    /// Timestamp column the pull pages along; stored as the cursor's `updated_at`
+    const POSITION_COLUMN: &'static str = "updated_at";
+
+    fn cursor(&self) -> SyncCursor;
+}
+
+impl PagedRow for SupabaseVaultItem {
+    fn cursor(&self) -> SyncCursor {
+        SyncCursor {
+            updated_at: self.updated_at.clone(),
+            last_id: self.id.clone(),
+        }
+    }
+}
+
+impl PagedRow for SupabaseFolder {
+    fn cursor(&self) -> SyncCursor {
+        SyncCursor {
+            updated_at: self.updated_at.clone(),
+            last_id: self.id.clone(),
+        }
+    }
+}
+
+impl PagedRow for SupabaseTombstone {
+    const POSITION_COLUMN: &'static str = "deleted_at";
+
+    fn cursor(&self) -> SyncCursor {
+        SyncCursor {
+            updated_at: self.deleted_at.clone(),
+            last_id: self.id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SupabaseAttachment {
+    id: String,
+    user_id: String,
+    item_id: String,
+    encrypted_name: String,
+    encrypted_key: String,
+    size: i64,
     created_at: String,
     updated_at: String,
 }
@@ -63,6 +241,79 @@ struct SupabaseError {
     error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SupabaseSignedUrl {
+    #[serde(rename = "signedURL")]
+    signed_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SupabaseSchemaMeta {
+    version: i64,
+    min_client_version: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaCompatibility {
+    Compatible,
+    /// The server is missing migrations this app relies on
+    ServerOutdated,
+    /// The server has moved past what this app understands
+    AppOutdated,
+}
+
+/// Outcome of comparing our expected schema version with the server's `schema_meta`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaStatus {
+    pub compatibility: SchemaCompatibility,
+    pub expected_version: i64,
+    /// 0 when the server predates `schema_meta`
+    pub server_version: i64,
+    pub min_client_version: i64,
+}
+
+impl SchemaStatus {
+    fn new(meta: SupabaseSchemaMeta) -> Self {
+        let compatibility = if meta.version < EXPECTED_SCHEMA_VERSION {
+            SchemaCompatibility::ServerOutdated
+        } else if meta.min_client_version > EXPECTED_SCHEMA_VERSION {
+            SchemaCompatibility::AppOutdated
+        } else {
+            SchemaCompatibility::Compatible
+        };
+
+        SchemaStatus {
+            compatibility,
+            expected_version: EXPECTED_SCHEMA_VERSION,
+            server_version: meta.version,
+            min_client_version: meta.min_client_version,
+        }
+    }
+
+    /// An actionable error instead of whatever PostgREST would fail with later
+    pub fn ensure_compatible(&self) -> Result<()> {
+        let versions = [
+            ("server", self.server_version.to_string()),
+            ("expected", self.expected_version.to_string()),
+        ];
+        let args: Vec<(&str, &str)> = versions.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        match self.compatibility {
+            SchemaCompatibility::Compatible => Ok(()),
+            SchemaCompatibility::ServerOutdated => Err(AppError::Sync(i18n::t_with(
+                "sync.schema_server_outdated",
+                &args,
+            ))),
+            SchemaCompatibility::AppOutdated => Err(AppError::Sync(i18n::t_with(
+                "sync.schema_app_outdated",
+                &args,
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncStatus {
@@ -72,6 +323,186 @@ pub struct SyncStatus {
     pub is_online: bool,
 }
 
+/// A table pulled on its own when Realtime reports a change to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PullTable {
+    Folders,
+    VaultItems,
+    /// Tombstones of permanently deleted items and folders
+    Deletions,
+}
+
+/// Why the server refused a pushed record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessDenial {
+    /// 401: the access token was rejected
+    ExpiredToken,
+    /// A row-level security check failed: the row's `user_id` isn't ours, or
+    /// the id already belongs to another account's row
+    OwnershipMismatch,
+    /// 403 without an RLS violation: no grant or policy for the operation at all
+    MissingPolicy,
+}
+
+impl AccessDenial {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccessDenial::ExpiredToken => "expired_token",
+            AccessDenial::OwnershipMismatch => "ownership_mismatch",
+            AccessDenial::MissingPolicy => "missing_policy",
+        }
+    }
+}
+
+/// PostgREST (`code`, `message`) and Storage (`error`, `message`) error bodies
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ApiErrorBody {
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Pass a successful response through; otherwise surface the server's message
+async fn check_rest_response(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body: ApiErrorBody = response.json().await.unwrap_or_default();
+    Err(AppError::Sync(body.message.unwrap_or_else(|| {
+        format!("Request failed with status {}", status)
+    })))
+}
+
+fn classify_denial(status: StatusCode, body: &ApiErrorBody) -> Option<AccessDenial> {
+    let message = body.message.as_deref().unwrap_or_default().to_lowercase();
+
+    if status == StatusCode::UNAUTHORIZED || message.contains("jwt expired") {
+        Some(AccessDenial::ExpiredToken)
+    } else if message.contains("row-level security") {
+        // Storage reports these as 400, PostgREST as 403 / 42501
+        Some(AccessDenial::OwnershipMismatch)
+    } else if status == StatusCode::FORBIDDEN || body.code.as_deref() == Some("42501") {
+        Some(AccessDenial::MissingPolicy)
+    } else {
+        None
+    }
+}
+
+/// The `sub` claim of a Supabase access token, without verifying it
+fn jwt_subject(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let claims: serde_json::Value =
+        serde_json::from_slice(&BASE64URL.decode(payload.trim_end_matches('=')).ok()?).ok()?;
+    claims.get("sub")?.as_str().map(str::to_string)
+}
+
+/// A failed push, keeping authorisation refusals and conflicts apart from
+/// everything else
+#[derive(Debug)]
+enum PushError {
+    Denied(AccessDenial, String),
+    /// The record changed on the server since we last pulled it; the local
+    /// version has been kept as a conflicted copy
+    Conflict,
+    Other(AppError),
+}
+
+impl From<AppError> for PushError {
+    fn from(e: AppError) -> Self {
+        PushError::Other(e)
+    }
+}
+
+impl From<reqwest::Error> for PushError {
+    fn from(e: reqwest::Error) -> Self {
+        PushError::Other(e.into())
+    }
+}
+
+impl From<std::io::Error> for PushError {
+    fn from(e: std::io::Error) -> Self {
+        PushError::Other(e.into())
+    }
+}
+
+async fn check_push_response(
+    response: reqwest::Response,
+    what: &str,
+) -> std::result::Result<Response, PushError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let text = response.text().await.unwrap_or_default();
+    let body: ApiErrorBody = serde_json::from_str(&text).unwrap_or_default();
+    match classify_denial(status, &body) {
+        Some(denial) => Err(PushError::Denied(
+            denial,
+            body.message.unwrap_or_else(|| status.to_string()),
+        )),
+        None => Err(PushError::Other(AppError::Sync(format!(
+            "Failed to sync {}: {} - {}",
+            what, status, text
+        )))),
+    }
+}
+
+fn is_html(response: &Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("text/html"))
+        .unwrap_or(false)
+}
+
+fn ensure_idle(status: &SyncStatus) -> Result<()> {
+    if status.is_syncing {
+        return Err(AppError::InvalidOperation(
+            "Wait for the current sync to finish".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Result of a connectivity probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Online,
+    Offline,
+    /// Reachable network, but requests are intercepted (hotel/airport Wi-Fi)
+    CaptivePortal,
+}
+
+/// Held for the length of a sync run. The in-memory `is_syncing` flag only
+/// covers this process; another window or the CLI sharing vault.db would
+/// otherwise push the same queue entries a second time.
+struct SyncRunLock {
+    _file: File,
+}
+
+impl SyncRunLock {
+    /// `None` when another process is already syncing; released on drop
+    fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(SyncRunLock { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
+
 // ============================================
 // Sync Engine
 // ============================================
@@ -79,8 +510,16 @@ pub struct SyncStatus {
 pub struct SyncEngine {
     db: Arc<Database>,
     client: Client,
-    config: SupabaseConfig,
+    /// Replaced by `set_config`; read afresh for every request
+    config: std::sync::RwLock<SupabaseConfig>,
     status: Arc<RwLock<SyncStatus>>,
+    /// Set once the server schema has been found compatible
+    schema_checked: Arc<RwLock<bool>>,
+    /// Asks the running sync to stop at the next record boundary
+    abort_requested: Arc<AtomicBool>,
+    /// Held while refreshing the access token, so a refresh token is only
+    /// spent once when the background task and a sync both find it expiring
+    refresh_lock: Arc<Mutex<()>>,
 }
 
 impl SyncEngine {
@@ -88,14 +527,86 @@ impl SyncEngine {
         Self {
             db,
             client: Client::new(),
-            config,
+            config: std::sync::RwLock::new(config),
             status: Arc::new(RwLock::new(SyncStatus {
                 is_syncing: false,
                 last_sync_at: None,
                 pending_changes: 0,
                 is_online: true,
             })),
+            schema_checked: Arc::new(RwLock::new(false)),
+            abort_requested: Arc::new(AtomicBool::new(false)),
+            refresh_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// The server requests currently go to
+    pub fn config(&self) -> SupabaseConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Point later requests at another server, e.g. the one a profile just
+    /// switched to uses. Its schema has to be checked afresh.
+    pub async fn set_config(&self, config: SupabaseConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        *self.schema_checked.write().await = false;
+    }
+
+    /// Stop the running sync, if any, between records so nothing is left half-pushed
+    pub fn abort(&self) {
+        self.abort_requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Run `f` while no sync is running and none can start
+    pub async fn while_idle<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let status = self.status.write().await;
+        ensure_idle(&status)?;
+        f()
+    }
+
+    /// Run `f` with syncs held off, in this process and any other sharing the
+    /// database; a sync started meanwhile returns as if one were running.
+    /// For work that rewrites what a sync would push or pull (key rotation).
+    pub async fn while_paused<T, F>(&self, f: impl FnOnce() -> F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let Some(_run_lock) = SyncRunLock::try_acquire(&self.db.sync_lock_path())? else {
+            return Err(AppError::InvalidOperation(
+                "Wait for the current sync to finish".to_string(),
+            ));
+        };
+        {
+            let mut status = self.status.write().await;
+            ensure_idle(&status)?;
+            status.is_syncing = true;
+        }
+
+        let result = f().await;
+        self.status.write().await.is_syncing = false;
+        result
+    }
+
+    /// Like `while_idle`, for swapping the database for another profile's.
+    /// The last sync time goes with it.
+    pub async fn switch_database<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let mut status = self.status.write().await;
+        ensure_idle(&status)?;
+        let output = f()?;
+        status.last_sync_at = None;
+        Ok(output)
+    }
+
+    fn check_aborted(&self) -> Result<()> {
+        if self.abort_requested.load(Ordering::SeqCst) {
+            return Err(AppError::Sync(
+                "Sync stopped because the vault was locked".to_string(),
+            ));
         }
+        Ok(())
     }
 
     pub async fn get_status(&self) -> SyncStatus {
@@ -111,25 +622,148 @@ impl SyncEngine {
 
     /// Check if we're online by pinging Supabase
     pub async fn check_connectivity(&self) -> bool {
-        let url = format!("{}/rest/v1/", self.config.url);
-        match self.client.head(&url).send().await {
-            Ok(resp) => {
-                let online = resp.status().is_success() || resp.status().as_u16() == 401;
-                let mut status = self.status.write().await;
-                status.is_online = online;
-                online
-            }
-            Err(_) => {
-                let mut status = self.status.write().await;
-                status.is_online = false;
-                false
-            }
+        let online = self.probe().await == Connectivity::Online;
+        self.set_online(online).await;
+        online
+    }
+
+    /// Ping Supabase without touching the status. A portal answers in HTML or
+    /// redirects elsewhere, where Supabase would answer with JSON.
+    pub async fn probe(&self) -> Connectivity {
+        let url = format!("{}/", self.config().rest_endpoint());
+        let Ok(resp) = self.client.head(&url).send().await else {
+            return Connectivity::Offline;
+        };
+
+        let expected_host = reqwest::Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string));
+
+        if resp.url().host_str().map(str::to_string) != expected_host || is_html(&resp) {
+            Connectivity::CaptivePortal
+        } else if resp.status().is_success() || resp.status().as_u16() == 401 {
+            Connectivity::Online
+        } else {
+            Connectivity::Offline
+        }
+    }
+
+    /// Check `config` before switching to it: the REST endpoint has to answer
+    /// as PostgREST does, and the auth endpoint serve its settings for the
+    /// anon key (which a wrong key is refused)
+    pub async fn test_config(&self, config: &SupabaseConfig) -> Result<()> {
+        let timeout = std::time::Duration::from_secs(CONFIG_TEST_TIMEOUT_SECS);
+        let unreachable = |url: &str, e: reqwest::Error| {
+            AppError::InvalidOperation(format!("Couldn't reach {}: {}", url, e))
+        };
+
+        let rest_url = format!("{}/", config.rest_endpoint());
+        let rest = self
+            .client
+            .get(&rest_url)
+            .header("apikey", &config.anon_key)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| unreachable(&rest_url, e))?;
+        let rest_ok = rest.status().is_success() || rest.status() == StatusCode::UNAUTHORIZED;
+        if !rest_ok || is_html(&rest) {
+            return Err(AppError::InvalidOperation(format!(
+                "{} doesn't look like a PostgREST API ({})",
+                rest_url,
+                rest.status()
+            )));
+        }
+
+        let auth_url = format!("{}/settings", config.auth_endpoint());
+        let auth = self
+            .client
+            .get(&auth_url)
+            .header("apikey", &config.anon_key)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| unreachable(&auth_url, e))?;
+        if matches!(auth.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return Err(AppError::InvalidOperation(
+                "The server refused the anon key".to_string(),
+            ));
+        }
+        let is_auth_api = auth.status().is_success()
+            && auth
+                .json::<serde_json::Value>()
+                .await
+                .map(|settings| settings.get("external").is_some())
+                .unwrap_or(false);
+        if !is_auth_api {
+            return Err(AppError::InvalidOperation(format!(
+                "{} doesn't look like a Supabase auth API",
+                auth_url
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn set_online(&self, online: bool) {
+        let mut status = self.status.write().await;
+        status.is_online = online;
+    }
+
+    /// Read the server's `schema_meta` and compare it with what this build expects
+    pub async fn schema_status(&self) -> Result<SchemaStatus> {
+        let url = format!(
+            "{}/schema_meta?select=version,min_client_version&id=eq.1",
+            self.config().rest_endpoint()
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header("apikey", &self.config().anon_key)
+            .header(
+                "Authorization",
+                format!("Bearer {}", self.config().anon_key),
+            )
+            .send()
+            .await?;
+
+        // No table at all: the server predates versioning, so it needs migrating
+        let meta = if response.status() == StatusCode::NOT_FOUND {
+            None
+        } else if response.status().is_success() {
+            response
+                .json::<Vec<SupabaseSchemaMeta>>()
+                .await?
+                .into_iter()
+                .next()
+        } else {
+            return Err(AppError::Sync(
+                "Failed to read server schema version".to_string(),
+            ));
+        };
+
+        Ok(SchemaStatus::new(meta.unwrap_or(SupabaseSchemaMeta {
+            version: 0,
+            min_client_version: 0,
+        })))
+    }
+
+    /// Fail early on a schema mismatch; a compatible result is remembered
+    async fn ensure_schema(&self) -> Result<()> {
+        if *self.schema_checked.read().await {
+            return Ok(());
         }
+
+        self.schema_status().await?.ensure_compatible()?;
+        *self.schema_checked.write().await = true;
+        Ok(())
     }
 
     /// Authenticate with Supabase and get tokens
     pub async fn authenticate(&self, email: &str, password_hash: &str) -> Result<UserSession> {
-        let url = format!("{}/auth/v1/token?grant_type=password", self.config.url);
+        let url = format!(
+            "{}/token?grant_type=password",
+            self.config().auth_endpoint()
+        );
         println!("[Auth] Authenticating user: {}", email);
 
         let body = serde_json::json!({
@@ -140,7 +774,7 @@ impl SyncEngine {
         let response = self
             .client
             .post(&url)
-            .header("apikey", &self.config.anon_key)
+            .header("apikey", &self.config().anon_key)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
@@ -150,7 +784,7 @@ impl SyncEngine {
 
         if !response.status().is_success() {
             let error: SupabaseError = response.json().await.unwrap_or(SupabaseError {
-                message: "Authentication failed".to_string(),
+                message: i18n::t("auth.failed"),
                 error: None,
             });
             println!("[Auth] Error: {}", error.message);
@@ -172,11 +806,58 @@ impl SyncEngine {
         })
     }
 
+    /// Create a Supabase user. Returns its session, or `None` if the server
+    /// wants the email address confirmed before signing in.
+    pub async fn register(&self, email: &str, password_hash: &str) -> Result<Option<UserSession>> {
+        let url = format!("{}/signup", self.config().auth_endpoint());
+
+        let body = serde_json::json!({
+            "email": email,
+            "password": password_hash,
+        });
+
+        let response = self
+            .client
+            .post(&url)
+            .header("apikey", &self.config().anon_key)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error: SupabaseError = response.json().await.unwrap_or(SupabaseError {
+                message: i18n::t("auth.failed"),
+                error: None,
+            });
+            return Err(AppError::Auth(error.message));
+        }
+
+        // With confirmations on, the reply is just the new user
+        let body: serde_json::Value = response.json().await?;
+        if body.get("access_token").is_none() {
+            return Ok(None);
+        }
+
+        let auth_response: SupabaseAuthResponse = serde_json::from_value(body)?;
+        let expires_at =
+            DateTime::from_timestamp(auth_response.expires_at, 0).unwrap_or(Utc::now());
+
+        Ok(Some(UserSession {
+            user_id: auth_response.user.id,
+            email: auth_response.user.email,
+            access_token: auth_response.access_token,
+            refresh_token: auth_response.refresh_token,
+            expires_at: expires_at.to_rfc3339(),
+            last_sync_at: None,
+        }))
+    }
+
     /// Refresh the access token
     pub async fn refresh_token(&self, session: &UserSession) -> Result<UserSession> {
         let url = format!(
-            "{}/auth/v1/token?grant_type=refresh_token",
-            self.config.url
+            "{}/token?grant_type=refresh_token",
+            self.config().auth_endpoint()
         );
 
         let body = serde_json::json!({
@@ -186,14 +867,21 @@ impl SyncEngine {
         let response = self
             .client
             .post(&url)
-            .header("apikey", &self.config.anon_key)
+            .header("apikey", &self.config().anon_key)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
             .await?;
 
+        // A refused refresh token won't work on a retry either
+        if matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) {
+            return Err(AppError::SessionExpired);
+        }
         if !response.status().is_success() {
-            return Err(AppError::Auth("Failed to refresh token".to_string()));
+            return Err(AppError::Auth(i18n::t("auth.refresh_failed")));
         }
 
         let auth_response: SupabaseAuthResponse = response.json().await?;
@@ -210,119 +898,398 @@ impl SyncEngine {
         })
     }
 
-    /// Full bidirectional sync
-    pub async fn sync(&self) -> Result<SyncStatus> {
-        // Set syncing status
-        {
-            let mut status = self.status.write().await;
-            if status.is_syncing {
-                return Ok(status.clone());
-            }
-            status.is_syncing = true;
-        }
+    /// Change the signed-in account's password. The session stays valid.
+    pub async fn update_password(&self, password_hash: &str) -> Result<()> {
+        let session = self.active_session().await?;
+        let url = format!("{}/user", self.config().auth_endpoint());
 
-        let result = self.perform_sync().await;
+        let response = self
+            .client
+            .put(&url)
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "password": password_hash }))
+            .send()
+            .await?;
 
-        // Update status
-        {
-            let mut status = self.status.write().await;
-            status.is_syncing = false;
-            if result.is_ok() {
-                status.last_sync_at = Some(Utc::now().to_rfc3339());
-            }
+        if !response.status().is_success() {
+            let error: SupabaseError = response.json().await.unwrap_or(SupabaseError {
+                message: i18n::t("auth.failed"),
+                error: None,
+            });
+            return Err(AppError::Auth(error.message));
+        }
+        Ok(())
+    }
+
+    /// Full bidirectional sync. `trigger` is recorded in the sync history.
+    pub async fn sync(&self, trigger: &str) -> Result<SyncStatus> {
+        self.run(trigger, None).await
+    }
+
+    /// Pull just `tables`, e.g. after Realtime reports a change. Queued local
+    /// changes wait for the next full sync, and the last sync time is left
+    /// alone since attachments and organizations weren't pulled.
+    pub async fn pull_tables(&self, trigger: &str, tables: &[PullTable]) -> Result<SyncStatus> {
+        self.run(trigger, Some(tables)).await
+    }
+
+    /// A full sync, or with `tables` only a pull of those
+    async fn run(&self, trigger: &str, tables: Option<&[PullTable]>) -> Result<SyncStatus> {
+        // An offline profile syncs through a folder or WebDAV, if anything
+        let file_backend = filesync::configured(&self.db)?;
+        if file_backend.is_none() {
+            // Nothing to sync with; changes just stay local
+            if offline::is_active(&self.db) {
+                return Ok(self.get_status().await);
+            }
+
+            // Paused while offline; re-probe in case the monitor hasn't caught up yet
+            let is_online = self.status.read().await.is_online;
+            if !is_online && !self.check_connectivity().await {
+                return Err(AppError::NetworkUnavailable);
+            }
+        }
+
+        // Another process sharing the database is syncing; it will push our queue too
+        let Some(_run_lock) = SyncRunLock::try_acquire(&self.db.sync_lock_path())? else {
+            log::info!("Skipping sync: another BirchVault process is already syncing");
+            return Ok(self.get_status().await);
+        };
+
+        // Set syncing status
+        {
+            let mut status = self.status.write().await;
+            if status.is_syncing {
+                return Ok(status.clone());
+            }
+            status.is_syncing = true;
+            // A lock before this run started doesn't apply to it
+            self.abort_requested.store(false, Ordering::SeqCst);
+        }
+
+        let started_at = Utc::now();
+        let timer = Instant::now();
+        let result = match (&file_backend, tables) {
+            (Some(backend), _) => self.perform_file_sync(backend).await,
+            (None, None) => self.perform_sync().await,
+            (None, Some(tables)) => self.perform_pull(tables).await,
+        };
+        self.record_history(trigger, started_at, timer, &result);
+        let full = tables.is_none() || file_backend.is_some();
+
+        // Update status
+        {
+            let mut status = self.status.write().await;
+            status.is_syncing = false;
+            if result.is_ok() && full {
+                status.last_sync_at = Some(Utc::now().to_rfc3339());
+            }
         }
 
         match result {
             Ok(_) => {
-                self.db.update_last_sync()?;
+                if full {
+                    self.db.update_last_sync()?;
+                }
                 Ok(self.get_status().await)
             }
             Err(e) => Err(e),
         }
     }
 
-    async fn perform_sync(&self) -> Result<()> {
+    /// Returns the number of records pushed and pulled
+    async fn perform_sync(&self) -> Result<(usize, usize)> {
+        let mut session = self.active_session().await?;
+        self.ensure_schema().await?;
+
+        // 1. Push local changes to server (may refresh or repair the session)
+        let pushed = self.push_changes(&mut session).await?;
+
+        // 2. Pull server changes
+        self.check_aborted()?;
+        let pulled = self.pull_changes(&session).await?;
+
+        Ok((pushed, pulled))
+    }
+
+    /// Returns the number of records pushed and pulled
+    async fn perform_file_sync(&self, backend: &BackendConfig) -> Result<(usize, usize)> {
+        filesync::sync(backend, &self.client, &self.db, || self.check_aborted()).await
+    }
+
+    /// Returns the number of records pulled, as `(0, pulled)`
+    async fn perform_pull(&self, tables: &[PullTable]) -> Result<(usize, usize)> {
+        let session = self.active_session().await?;
+        self.ensure_schema().await?;
+        let since = session.last_sync_at.as_deref();
+
+        // Folders first, so pulled items never point at a folder we don't have yet
+        let mut pulled = 0;
+        if tables.contains(&PullTable::Folders) {
+            pulled += self.pull_folders(&session, since).await?;
+        }
+        if tables.contains(&PullTable::VaultItems) {
+            pulled += self.pull_vault_items(&session, since).await?;
+        }
+        if tables.contains(&PullTable::Deletions) {
+            pulled += self.pull_tombstones(&session, since).await?;
+        }
+
+        Ok((0, pulled))
+    }
+
+    fn record_history(
+        &self,
+        trigger: &str,
+        started_at: DateTime<Utc>,
+        timer: Instant,
+        result: &Result<(usize, usize)>,
+    ) {
+        let (pushed, pulled) = result.as_ref().map(|counts| *counts).unwrap_or((0, 0));
+        let entry = SyncHistoryEntry {
+            id: 0,
+            trigger: trigger.to_string(),
+            started_at: started_at.to_rfc3339(),
+            duration_ms: timer.elapsed().as_millis() as i64,
+            pushed: pushed as i64,
+            pulled: pulled as i64,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+
+        let cutoff = (Utc::now() - chrono::Duration::days(SYNC_HISTORY_MAX_DAYS)).to_rfc3339();
+        let recorded = self
+            .db
+            .insert_sync_history(&entry)
+            .and_then(|_| self.db.prune_sync_history(SYNC_HISTORY_MAX_ENTRIES, &cutoff));
+        if let Err(e) = recorded {
+            log::warn!("Failed to record sync history: {}", e);
+        }
+    }
+
+    /// The stored session, with its access token refreshed if needed
+    pub async fn active_session(&self) -> Result<UserSession> {
         let session = self
             .db
             .get_session()?
-            .ok_or(AppError::Auth("Not logged in".to_string()))?;
+            .ok_or(AppError::Auth(i18n::t("auth.not_logged_in")))?;
 
-        // Check if token needs refresh
-        let session = self.ensure_valid_token(session).await?;
+        self.ensure_valid_token(session).await
+    }
 
-        // 1. Push local changes to server
-        self.push_changes(&session).await?;
+    /// Subscribe to Realtime notifications of this account's vault item and
+    /// folder changes
+    pub async fn subscribe_changes(&self) -> Result<Subscription> {
+        let session = self.active_session().await?;
+        Subscription::open(&self.config(), &session).await
+    }
 
-        // 2. Pull server changes
-        self.pull_changes(&session).await?;
+    /// Publish a message to a Supabase Realtime broadcast topic
+    pub async fn broadcast(
+        &self,
+        topic: &str,
+        event: &str,
+        payload: serde_json::Value,
+    ) -> Result<()> {
+        let session = self.active_session().await?;
+        let url = format!("{}/realtime/v1/api/broadcast", self.config().url);
+        let body = serde_json::json!({
+            "messages": [{
+                "topic": topic,
+                "event": event,
+                "payload": payload,
+            }],
+        });
+
+        let response = self
+            .client
+            .post(&url)
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AppError::Sync(format!(
+                "Failed to broadcast: {} - {}",
+                status, text
+            )));
+        }
 
         Ok(())
     }
 
     async fn ensure_valid_token(&self, session: UserSession) -> Result<UserSession> {
         let expires_at = DateTime::parse_from_rfc3339(&session.expires_at)
-            .map_err(|_| AppError::Auth("Invalid token expiry".to_string()))?;
+            .map_err(|_| AppError::Auth(i18n::t("auth.invalid_token_expiry")))?;
 
-        // Refresh if token expires in less than 5 minutes
-        if expires_at.timestamp() < Utc::now().timestamp() + 300 {
-            let new_session = self.refresh_token(&session).await?;
-            self.db.save_session(&new_session)?;
-            Ok(new_session)
-        } else {
-            Ok(session)
+        if expires_at.timestamp() >= Utc::now().timestamp() + TOKEN_REFRESH_MARGIN_SECS {
+            return Ok(session);
+        }
+
+        let _refreshing = self.refresh_lock.lock().await;
+        // Someone else may have refreshed it while we waited
+        if let Some(current) = self.db.get_session()? {
+            if current.refresh_token != session.refresh_token {
+                return Ok(current);
+            }
         }
+        let new_session = self.refresh_token(&session).await?;
+        self.db.save_session(&new_session)?;
+        Ok(new_session)
     }
 
-    /// Push local changes to the server
-    async fn push_changes(&self, session: &UserSession) -> Result<()> {
+    /// Push local changes to the server, returning how many were pushed.
+    /// Records refused by row-level security get one remediation attempt;
+    /// those that still fail are parked for the sync errors view.
+    async fn push_changes(&self, session: &mut UserSession) -> Result<usize> {
         let pending_items = self.db.get_pending_sync_items()?;
+        let mut refreshed = false;
+        let mut pushed = 0;
 
         for item in pending_items {
-            let result = match item.operation.as_str() {
-                "create" | "update" => {
-                    self.push_upsert(&session, &item.table_name, &item.record_id)
-                        .await
-                }
-                "delete" => {
-                    self.push_delete(&session, &item.table_name, &item.record_id)
-                        .await
+            self.check_aborted()?;
+            let mut result = self.push_record(session, &item).await;
+
+            if let Err(PushError::Denied(denial, _)) = &result {
+                if let Some(remedied) = self.remediate(*denial, session, &mut refreshed).await {
+                    *session = remedied;
+                    result = self.push_record(session, &item).await;
                 }
-                _ => Ok(()),
-            };
+            }
 
             match result {
                 Ok(_) => {
                     self.db.remove_from_sync_queue(item.id)?;
                     self.db.mark_item_synced(&item.table_name, &item.record_id)?;
+                    pushed += 1;
                 }
-                Err(e) => {
+                Err(PushError::Denied(denial, message)) => {
+                    log::warn!(
+                        "Server refused {} {} ({}): {}",
+                        item.table_name,
+                        item.record_id,
+                        denial.as_str(),
+                        message
+                    );
+                    // An expired token is the session's problem, not the record's
+                    let blocked = (denial != AccessDenial::ExpiredToken).then(|| denial.as_str());
+                    self.db.record_sync_failure(item.id, &message, blocked)?;
+                }
+                Err(PushError::Conflict) => {
+                    log::info!(
+                        "Vault item {} changed on the server; kept local changes as a conflicted copy",
+                        item.record_id
+                    );
+                }
+                Err(PushError::Other(e)) => {
                     log::warn!("Failed to sync item {}: {}", item.record_id, e);
                     // Continue with other items, don't fail the whole sync
+                    self.db.record_sync_failure(item.id, &e.to_string(), None)?;
                 }
             }
         }
 
-        Ok(())
+        Ok(pushed)
     }
 
-    async fn push_upsert(&self, session: &UserSession, table: &str, id: &str) -> Result<()> {
+    async fn push_record(
+        &self,
+        session: &UserSession,
+        item: &SyncQueueItem,
+    ) -> std::result::Result<(), PushError> {
+        match item.operation.as_str() {
+            "create" | "update" => {
+                self.push_upsert(session, &item.table_name, &item.record_id)
+                    .await
+            }
+            "delete" => {
+                self.push_delete(session, &item.table_name, &item.record_id)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Try to fix the cause of a denial; `Some` is the session to retry with
+    async fn remediate(
+        &self,
+        denial: AccessDenial,
+        session: &UserSession,
+        refreshed: &mut bool,
+    ) -> Option<UserSession> {
+        match denial {
+            // The expiry check can be fooled by clock skew or a revoked token; refresh once per run
+            AccessDenial::ExpiredToken if !*refreshed => {
+                *refreshed = true;
+                let result = self
+                    .refresh_token(session)
+                    .await
+                    .and_then(|fresh| self.db.save_session(&fresh).map(|_| fresh));
+                result
+                    .map_err(|e| log::warn!("Token refresh after a 401 failed: {}", e))
+                    .ok()
+            }
+            // Rows are pushed with the stored user id. If that has drifted from the
+            // token's subject (an account switch, a restored session) every write fails RLS.
+            AccessDenial::OwnershipMismatch => {
+                let subject = jwt_subject(&session.access_token)?;
+                if subject == session.user_id {
+                    return None;
+                }
+
+                log::info!(
+                    "Backfilling session user id {} -> {}",
+                    session.user_id,
+                    subject
+                );
+                let fixed = UserSession {
+                    user_id: subject,
+                    ..session.clone()
+                };
+                self.db
+                    .save_session(&fixed)
+                    .map_err(|e| log::warn!("Failed to save repaired session: {}", e))
+                    .ok()?;
+                Some(fixed)
+            }
+            _ => None,
+        }
+    }
+
+    async fn push_upsert(
+        &self,
+        session: &UserSession,
+        table: &str,
+        id: &str,
+    ) -> std::result::Result<(), PushError> {
         match table {
             "vault_items" => {
                 if let Some(item) = self.db.get_vault_item(id)? {
-                    let url = format!("{}/rest/v1/vault_items", self.config.url);
+                    self.push_vault_item(session, &item).await?;
+                }
+            }
+            "folders" => {
+                let folders = self.db.get_all_folders()?;
+                if let Some(folder) = folders.iter().find(|f| f.id == id) {
+                    let url = format!("{}/folders", self.config().rest_endpoint());
                     let body = serde_json::json!({
-                        "id": item.id,
+                        "id": folder.id,
                         "user_id": session.user_id,
-                        "encrypted_data": item.encrypted_data,
-                        "type": item.item_type,
-                        "folder_id": item.folder_id,
-                        "deleted_at": item.deleted_at,
+                        "name": folder.name,
+                        "retention_days": folder.retention_days,
                     });
 
                     let response = self
                         .client
                         .post(&url)
-                        .header("apikey", &self.config.anon_key)
+                        .header("apikey", &self.config().anon_key)
                         .header("Authorization", format!("Bearer {}", session.access_token))
                         .header("Content-Type", "application/json")
                         .header("Prefer", "resolution=merge-duplicates")
@@ -330,30 +1297,45 @@ impl SyncEngine {
                         .send()
                         .await?;
 
-                    if !response.status().is_success() {
-                        let status = response.status();
-                        let text = response.text().await.unwrap_or_default();
-                        return Err(AppError::Sync(format!(
-                            "Failed to sync vault item: {} - {}",
-                            status, text
-                        )));
-                    }
+                    check_push_response(response, "folder").await?;
                 }
             }
-            "folders" => {
-                let folders = self.db.get_all_folders()?;
-                if let Some(folder) = folders.iter().find(|f| f.id == id) {
-                    let url = format!("{}/rest/v1/folders", self.config.url);
+            "org_items" => {
+                if let Some(item) = self.db.get_org_item(id)? {
+                    self.push_org_item(session, &item).await?;
+                }
+            }
+            "attachments" => {
+                if let Some(attachment) = self.db.get_attachment(id)? {
+                    // Blob first, so the record never points at a missing object
+                    let blob = tokio::fs::read(self.db.attachment_blob_path(id)).await?;
+                    let response = self
+                        .client
+                        .post(self.attachment_object_url(session, id))
+                        .header("apikey", &self.config().anon_key)
+                        .header("Authorization", format!("Bearer {}", session.access_token))
+                        .header("Content-Type", "application/octet-stream")
+                        .header("x-upsert", "true")
+                        .body(blob)
+                        .send()
+                        .await?;
+
+                    check_push_response(response, "attachment blob").await?;
+
+                    let url = format!("{}/vault_attachments", self.config().rest_endpoint());
                     let body = serde_json::json!({
-                        "id": folder.id,
+                        "id": attachment.id,
                         "user_id": session.user_id,
-                        "name": folder.name,
+                        "item_id": attachment.item_id,
+                        "encrypted_name": attachment.encrypted_name,
+                        "encrypted_key": attachment.encrypted_key,
+                        "size": attachment.size,
                     });
 
                     let response = self
                         .client
                         .post(&url)
-                        .header("apikey", &self.config.anon_key)
+                        .header("apikey", &self.config().anon_key)
                         .header("Authorization", format!("Bearer {}", session.access_token))
                         .header("Content-Type", "application/json")
                         .header("Prefer", "resolution=merge-duplicates")
@@ -361,9 +1343,7 @@ impl SyncEngine {
                         .send()
                         .await?;
 
-                    if !response.status().is_success() {
-                        return Err(AppError::Sync("Failed to sync folder".to_string()));
-                    }
+                    check_push_response(response, "attachment").await?;
                 }
             }
             _ => {}
@@ -372,42 +1352,533 @@ impl SyncEngine {
         Ok(())
     }
 
-    async fn push_delete(&self, session: &UserSession, table: &str, id: &str) -> Result<()> {
-        let url = format!("{}/rest/v1/{}?id=eq.{}", self.config.url, table, id);
+    /// An item the server already has is only updated while its `updated_at`
+    /// still matches the version our changes were made to. If it has moved on,
+    /// the server's version is stored through the usual conflict handling and
+    /// ours is kept as a conflicted copy. Either way the server's new
+    /// `updated_at` is recorded for the next push.
+    async fn push_vault_item(
+        &self,
+        session: &UserSession,
+        item: &VaultItem,
+    ) -> std::result::Result<(), PushError> {
+        let url = format!("{}/vault_items", self.config().rest_endpoint());
+        let mut body = serde_json::json!({
+            "encrypted_data": item.encrypted_data,
+            "type": item.item_type,
+            "folder_id": item.folder_id,
+            "deleted_at": item.deleted_at,
+        });
+
+        if let Some(base) = item.server_updated_at.as_deref() {
+            let response = self
+                .client
+                .patch(&url)
+                .query(&[
+                    ("id", format!("eq.{}", item.id)),
+                    ("updated_at", format!("eq.{}", base)),
+                ])
+                .header("apikey", &self.config().anon_key)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .header("Content-Type", "application/json")
+                .header("Prefer", "return=representation")
+                .json(&body)
+                .send()
+                .await?;
+
+            let updated: Vec<SupabaseVaultItem> = check_push_response(response, "vault item")
+                .await?
+                .json()
+                .await?;
+            if let Some(server) = updated.into_iter().next() {
+                self.db
+                    .set_vault_item_server_version(&item.id, &server.updated_at)?;
+                return Ok(());
+            }
+
+            // No match: changed on the server, or deleted there (then recreated below)
+            let response = self
+                .client
+                .get(&url)
+                .query(&[("id", format!("eq.{}", item.id))])
+                .header("apikey", &self.config().anon_key)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .send()
+                .await?;
+            let current: Vec<SupabaseVaultItem> = check_push_response(response, "vault item")
+                .await?
+                .json()
+                .await?;
+            if let Some(server) = current.into_iter().next() {
+                let now = Utc::now().to_rfc3339();
+                let conflicts = self
+                    .db
+                    .bulk_upsert_vault_items(&[server.into_local(&now)])?;
+                if !conflicts.is_empty() {
+                    return Err(PushError::Conflict);
+                }
+                // Same contents either way; nothing left to push
+                return Ok(());
+            }
+        }
 
+        body["id"] = serde_json::json!(item.id);
+        body["user_id"] = serde_json::json!(session.user_id);
         let response = self
             .client
-            .delete(&url)
-            .header("apikey", &self.config.anon_key)
+            .post(&url)
+            .header("apikey", &self.config().anon_key)
             .header("Authorization", format!("Bearer {}", session.access_token))
+            .header("Content-Type", "application/json")
+            .header(
+                "Prefer",
+                "resolution=merge-duplicates,return=representation",
+            )
+            .json(&body)
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            return Err(AppError::Sync(format!("Failed to delete {} {}", table, id)));
+        let stored: Vec<SupabaseVaultItem> = check_push_response(response, "vault item")
+            .await?
+            .json()
+            .await?;
+        if let Some(server) = stored.into_iter().next() {
+            self.db
+                .set_vault_item_server_version(&item.id, &server.updated_at)?;
+        }
+        Ok(())
+    }
+
+    /// Org items keep their creator's `user_id`, so only new ones are inserted;
+    /// changes are patched, which RLS checks against our collection access
+    async fn push_org_item(
+        &self,
+        session: &UserSession,
+        item: &OrgItem,
+    ) -> std::result::Result<(), PushError> {
+        let body = serde_json::json!({
+            "encrypted_data": item.encrypted_data,
+            "type": item.item_type,
+            "deleted_at": item.deleted_at,
+        });
+
+        if item.server_updated_at.is_some() {
+            let response = self
+                .client
+                .patch(format!(
+                    "{}/vault_items?id=eq.{}",
+                    self.config().rest_endpoint(),
+                    item.id
+                ))
+                .header("apikey", &self.config().anon_key)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .json(&body)
+                .send()
+                .await?;
+
+            check_push_response(response, "organization item").await?;
+            return Ok(());
+        }
+
+        let mut body = body;
+        body["id"] = serde_json::json!(item.id);
+        body["user_id"] = serde_json::json!(session.user_id);
+        body["organization_id"] = serde_json::json!(item.organization_id);
+
+        let response = self
+            .client
+            .post(format!("{}/vault_items", self.config().rest_endpoint()))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .json(&body)
+            .send()
+            .await?;
+        check_push_response(response, "organization item").await?;
+
+        if !item.collection_ids.is_empty() {
+            let links: Vec<serde_json::Value> = item
+                .collection_ids
+                .iter()
+                .map(|collection_id| {
+                    serde_json::json!({ "collection_id": collection_id, "vault_item_id": item.id })
+                })
+                .collect();
+            let response = self
+                .client
+                .post(format!(
+                    "{}/vault_collection_items",
+                    self.config().rest_endpoint()
+                ))
+                .header("apikey", &self.config().anon_key)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .header("Prefer", "resolution=ignore-duplicates")
+                .json(&links)
+                .send()
+                .await?;
+            check_push_response(response, "collection membership").await?;
         }
 
         Ok(())
     }
 
-    /// Pull changes from the server
-    async fn pull_changes(&self, session: &UserSession) -> Result<()> {
+    fn attachment_object_url(&self, session: &UserSession, id: &str) -> String {
+        format!(
+            "{}/storage/v1/object/{}/{}/{}",
+            self.config().url,
+            ATTACHMENTS_BUCKET,
+            session.user_id,
+            id
+        )
+    }
+
+    async fn push_delete(
+        &self,
+        session: &UserSession,
+        table: &str,
+        id: &str,
+    ) -> std::result::Result<(), PushError> {
+        let table = match table {
+            "attachments" => {
+                let response = self
+                    .client
+                    .delete(self.attachment_object_url(session, id))
+                    .header("apikey", &self.config().anon_key)
+                    .header("Authorization", format!("Bearer {}", session.access_token))
+                    .send()
+                    .await?;
+
+                // Never uploaded is as good as deleted
+                if response.status() != StatusCode::NOT_FOUND {
+                    check_push_response(response, "attachment blob deletion").await?;
+                }
+                "vault_attachments"
+            }
+            "org_items" => "vault_items",
+            other => other,
+        };
+
+        let url = format!("{}/{}?id=eq.{}", self.config().rest_endpoint(), table, id);
+
+        let response = self
+            .client
+            .delete(&url)
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .send()
+            .await?;
+
+        check_push_response(response, &format!("{} deletion", table)).await?;
+        Ok(())
+    }
+
+    /// Pull changes from the server, returning how many records were pulled
+    async fn pull_changes(&self, session: &UserSession) -> Result<usize> {
         // Get last sync timestamp
         let last_sync = session.last_sync_at.clone();
 
         // Pull folders
-        self.pull_folders(session, last_sync.as_deref()).await?;
+        let folders = self.pull_folders(session, last_sync.as_deref()).await?;
+
+        // Pull vault items, then drop those deleted for good elsewhere
+        let items = self.pull_vault_items(session, last_sync.as_deref()).await?;
+        let deletions = self.pull_tombstones(session, last_sync.as_deref()).await?;
 
-        // Pull vault items
-        self.pull_vault_items(session, last_sync.as_deref()).await?;
+        // Pull attachment records (blobs are downloaded on demand)
+        let attachments = self.pull_attachments(session, last_sync.as_deref()).await?;
 
+        // Pull organizations and their items
+        let org_items = self
+            .pull_organizations(session, last_sync.as_deref())
+            .await?;
+
+        Ok(folders + items + deletions + attachments + org_items)
+    }
+
+    /// Public entry point for commands that change memberships or collections
+    pub async fn refresh_organizations(&self) -> Result<()> {
+        let session = self.active_session().await?;
+        let session = self.ensure_valid_token(session).await?;
+        self.pull_organizations(&session, None).await?;
         Ok(())
     }
 
-    async fn pull_folders(&self, session: &UserSession, since: Option<&str>) -> Result<()> {
+    /// Memberships, collections and collection membership are small, so they
+    /// are replaced wholesale; org items are pulled incrementally, except for
+    /// organizations we've only just joined. Returns the org items pulled.
+    async fn pull_organizations(&self, session: &UserSession, since: Option<&str>) -> Result<usize> {
+        let memberships: Vec<SupabaseMembership> = self
+            .rest_get(&format!(
+                "vault_org_members?user_id=eq.{}&select=organization_id,role,status,\
+                 encrypted_org_key,vault_organizations(name)",
+                session.user_id
+            ))
+            .await?;
+        let now = Utc::now().to_rfc3339();
+        let orgs: Vec<Organization> = memberships
+            .into_iter()
+            .map(|m| Organization {
+                id: m.organization_id,
+                name: m.vault_organizations.map(|o| o.name).unwrap_or_default(),
+                role: m.role,
+                status: m.status,
+                wrapped_key: m.encrypted_org_key,
+                encrypted_key: None,
+                synced_at: Some(now.clone()),
+            })
+            .collect();
+        let joined = self.db.replace_organizations(&orgs)?;
+
+        let accepted: Vec<&str> = orgs
+            .iter()
+            .filter(|o| o.status == "accepted")
+            .map(|o| o.id.as_str())
+            .collect();
+        if accepted.is_empty() {
+            self.db.replace_collections(&[], &[])?;
+            return Ok(0);
+        }
+
+        let collections: Vec<SupabaseCollection> = self
+            .rest_get("vault_org_collections?select=id,organization_id,name")
+            .await?;
+        let access: Vec<SupabaseCollectionAccess> = self
+            .rest_get(&format!(
+                "vault_collection_access?user_id=eq.{}&select=collection_id,permission",
+                session.user_id
+            ))
+            .await?;
+        let links: Vec<SupabaseCollectionItem> = self
+            .rest_get("vault_collection_items?select=collection_id,vault_item_id")
+            .await?;
+
+        let collections: Vec<Collection> = collections
+            .into_iter()
+            .map(|c| Collection {
+                permission: access
+                    .iter()
+                    .find(|a| a.collection_id == c.id)
+                    .map(|a| a.permission.clone()),
+                id: c.id,
+                organization_id: c.organization_id,
+                name: c.name,
+            })
+            .collect();
+        let links: Vec<(String, String)> = links
+            .into_iter()
+            .map(|l| (l.collection_id, l.vault_item_id))
+            .collect();
+        self.db.replace_collections(&collections, &links)?;
+
+        let mut pulled = 0;
+        for org_id in accepted {
+            let since = if joined.iter().any(|id| id == org_id) {
+                None
+            } else {
+                since
+            };
+            pulled += self.pull_org_items(org_id, since).await?;
+        }
+        Ok(pulled)
+    }
+
+    async fn pull_org_items(&self, organization_id: &str, since: Option<&str>) -> Result<usize> {
+        let mut path = format!(
+            "vault_items?organization_id=eq.{}\
+             &select=id,user_id,organization_id,encrypted_data,type,deleted_at,updated_at",
+            organization_id
+        );
+        if let Some(since) = since {
+            path.push_str(&format!("&updated_at=gt.{}", since));
+        }
+
+        let server_items: Vec<SupabaseOrgItem> = self.rest_get(&path).await?;
+        let now = Utc::now().to_rfc3339();
+
+        let items: Vec<OrgItem> = server_items
+            .into_iter()
+            .map(|i| OrgItem {
+                id: i.id,
+                organization_id: i.organization_id,
+                encrypted_data: i.encrypted_data,
+                item_type: i.item_type,
+                created_by: Some(i.user_id),
+                deleted_at: i.deleted_at,
+                synced_at: Some(now.clone()),
+                local_updated_at: i.updated_at.clone(),
+                server_updated_at: Some(i.updated_at),
+                // Membership comes from replace_collections
+                collection_ids: Vec::new(),
+            })
+            .collect();
+
+        self.db.bulk_upsert_org_items(&items)?;
+
+        Ok(items.len())
+    }
+
+    /// Page through `table` in `(POSITION_COLUMN, id)` order, starting after the
+    /// table's stored cursor. Each page is stored before the cursor moves past
+    /// it, so a pull that is interrupted (or stopped by a lock) resumes from the
+    /// last stored page. Without a cursor, e.g. the first pull after an
+    /// upgrade, it starts after `since`, or from the beginning.
+    async fn pull_paged<T: DeserializeOwned + PagedRow>(
+        &self,
+        session: &UserSession,
+        table: &str,
+        filters: &[(&str, String)],
+        since: Option<&str>,
+        mut store: impl FnMut(Vec<T>) -> Result<()>,
+    ) -> Result<usize> {
+        let url = format!("{}/{}", self.config().rest_endpoint(), table);
+        let column = T::POSITION_COLUMN;
+        let mut cursor = self.db.get_sync_cursor(table)?;
+        let mut pulled = 0;
+
+        loop {
+            let mut query = filters.to_vec();
+            query.push(("order", format!("{}.asc,id.asc", column)));
+            match (&cursor, since) {
+                // Quoted, since timestamps contain PostgREST's reserved characters
+                (Some(cursor), _) => query.push((
+                    "or",
+                    format!(
+                        "({0}.gt.\"{1}\",and({0}.eq.\"{1}\",id.gt.{2}))",
+                        column, cursor.updated_at, cursor.last_id
+                    ),
+                )),
+                (None, Some(since)) => query.push((column, format!("gt.{}", since))),
+                (None, None) => {}
+            }
+
+            let response = self
+                .client
+                .get(&url)
+                .query(&query)
+                .header("apikey", &self.config().anon_key)
+                .header("Authorization", format!("Bearer {}", session.access_token))
+                .header("Range-Unit", "items")
+                .header("Range", format!("0-{}", PULL_PAGE_SIZE - 1))
+                .send()
+                .await?;
+
+            let page: Vec<T> = check_rest_response(response).await?.json().await?;
+            let count = page.len();
+            let Some(last) = page.last().map(PagedRow::cursor) else {
+                break;
+            };
+
+            store(page)?;
+            self.db.set_sync_cursor(table, &last)?;
+            cursor = Some(last);
+            pulled += count;
+
+            if count < PULL_PAGE_SIZE {
+                break;
+            }
+            self.check_aborted()?;
+        }
+
+        Ok(pulled)
+    }
+
+    async fn pull_folders(&self, session: &UserSession, since: Option<&str>) -> Result<usize> {
+        let filters = [("user_id", format!("eq.{}", session.user_id))];
+
+        self.pull_paged(
+            session,
+            "folders",
+            &filters,
+            since,
+            |page: Vec<SupabaseFolder>| {
+                let now = Utc::now().to_rfc3339();
+                let folders: Vec<Folder> = page
+                    .into_iter()
+                    .map(|f| Folder {
+                        id: f.id,
+                        name: f.name,
+                        synced_at: Some(now.clone()),
+                        local_updated_at: f.updated_at,
+                        retention_days: f.retention_days,
+                    })
+                    .collect();
+
+                self.db.bulk_upsert_folders(&folders)
+            },
+        )
+        .await
+    }
+
+    async fn pull_vault_items(&self, session: &UserSession, since: Option<&str>) -> Result<usize> {
+        let filters = [
+            ("user_id", format!("eq.{}", session.user_id)),
+            ("organization_id", "is.null".to_string()),
+        ];
+        let mut conflicts = 0;
+
+        let pulled = self
+            .pull_paged(
+                session,
+                "vault_items",
+                &filters,
+                since,
+                |page: Vec<SupabaseVaultItem>| {
+                    let now = Utc::now().to_rfc3339();
+                    let items: Vec<VaultItem> =
+                        page.into_iter().map(|i| i.into_local(&now)).collect();
+
+                    conflicts += self.db.bulk_upsert_vault_items(&items)?.len();
+                    Ok(())
+                },
+            )
+            .await?;
+        log::debug!("Stored {} items in local database", pulled);
+        if conflicts > 0 {
+            log::info!(
+                "{} vault items changed on both sides; kept local versions as conflicted copies",
+                conflicts
+            );
+        }
+
+        Ok(pulled)
+    }
+
+    /// Apply tombstones of items and folders permanently deleted elsewhere
+    async fn pull_tombstones(&self, session: &UserSession, since: Option<&str>) -> Result<usize> {
+        let filters = [("user_id", format!("eq.{}", session.user_id))];
+        let mut removed = 0;
+
+        let pulled = self
+            .pull_paged(
+                session,
+                "vault_tombstones",
+                &filters,
+                since,
+                |page: Vec<SupabaseTombstone>| {
+                    let tombstones: Vec<Tombstone> = page
+                        .into_iter()
+                        .map(|t| Tombstone {
+                            table_name: t.table_name,
+                            record_id: t.record_id,
+                        })
+                        .collect();
+
+                    removed += self.db.apply_tombstones(&tombstones)?;
+                    Ok(())
+                },
+            )
+            .await?;
+        if removed > 0 {
+            log::info!("Removed {} records deleted on other devices", removed);
+        }
+
+        Ok(pulled)
+    }
+
+    async fn pull_attachments(&self, session: &UserSession, since: Option<&str>) -> Result<usize> {
         let mut url = format!(
-            "{}/rest/v1/folders?user_id=eq.{}",
-            self.config.url, session.user_id
+            "{}/vault_attachments?user_id=eq.{}",
+            self.config().rest_endpoint(),
+            session.user_id
         );
 
         if let Some(since) = since {
@@ -417,91 +1888,328 @@ impl SyncEngine {
         let response = self
             .client
             .get(&url)
-            .header("apikey", &self.config.anon_key)
+            .header("apikey", &self.config().anon_key)
             .header("Authorization", format!("Bearer {}", session.access_token))
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(AppError::Sync("Failed to pull folders".to_string()));
+            return Err(AppError::Sync("Failed to pull attachments".to_string()));
         }
 
-        let server_folders: Vec<SupabaseFolder> = response.json().await?;
+        let server_attachments: Vec<SupabaseAttachment> = response.json().await?;
         let now = Utc::now().to_rfc3339();
 
-        let folders: Vec<Folder> = server_folders
+        let attachments: Vec<Attachment> = server_attachments
             .into_iter()
-            .map(|f| Folder {
-                id: f.id,
-                name: f.name,
+            .map(|a| Attachment {
+                id: a.id,
+                item_id: a.item_id,
+                encrypted_name: a.encrypted_name,
+                encrypted_key: a.encrypted_key,
+                size: a.size,
+                created_at: a.created_at,
                 synced_at: Some(now.clone()),
-                local_updated_at: f.updated_at,
             })
             .collect();
 
-        self.db.bulk_upsert_folders(&folders)?;
+        self.db.bulk_upsert_attachments(&attachments)?;
+
+        Ok(attachments.len())
+    }
+
+    // ============================================
+    // Account API
+    // ============================================
+    //
+    // PostgREST calls for account features outside the sync loop (key pairs,
+    // emergency access). They run as the signed-in user, so row-level
+    // security decides what each one may see or change.
+
+    /// GET `/rest/v1/{path}` and decode the JSON response
+    pub async fn rest_get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let session = self.active_session().await?;
+        let response = self
+            .client
+            .get(format!("{}/{}", self.config().rest_endpoint(), path))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .send()
+            .await?;
 
+        Ok(check_rest_response(response).await?.json().await?)
+    }
+
+    /// POST, PATCH or DELETE against `/rest/v1/{path}`, with an optional
+    /// `Prefer` header (e.g. `resolution=merge-duplicates` for upserts)
+    pub async fn rest_write(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        prefer: Option<&str>,
+    ) -> Result<()> {
+        self.rest_send(method, path, body, prefer).await?;
         Ok(())
     }
 
-    async fn pull_vault_items(&self, session: &UserSession, since: Option<&str>) -> Result<()> {
-        let mut url = format!(
-            "{}/rest/v1/vault_items?user_id=eq.{}",
-            self.config.url, session.user_id
-        );
+    /// Call a Postgres function through `/rest/v1/rpc/{name}`; any result is ignored
+    pub async fn rpc(&self, name: &str, args: serde_json::Value) -> Result<()> {
+        self.rest_write(Method::POST, &format!("rpc/{}", name), Some(&args), None)
+            .await
+    }
 
-        if let Some(since) = since {
-            url.push_str(&format!("&updated_at=gt.{}", since));
+    /// The profile's vault key, wrapped with the master key
+    pub async fn wrapped_vault_key(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct WrappedKeyRow {
+            encrypted_symmetric_key: Option<String>,
         }
 
-        println!("[Sync] Pulling vault items from: {}", url);
+        let session = self.active_session().await?;
+        self.rest_get::<Vec<WrappedKeyRow>>(&format!(
+            "vault_profiles?id=eq.{}&select=encrypted_symmetric_key",
+            session.user_id
+        ))
+        .await?
+        .into_iter()
+        .next()
+        .and_then(|row| row.encrypted_symmetric_key)
+        .ok_or_else(|| AppError::InvalidOperation("The account has no vault key".to_string()))
+    }
+
+    /// Call a Postgres function and decode what it returns
+    pub async fn rpc_query<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> Result<T> {
+        let response = self
+            .rest_send(Method::POST, &format!("rpc/{}", name), Some(&args), None)
+            .await?;
+        Ok(response.json().await?)
+    }
 
+    /// `rpc_query` without a session, as the anonymous role (e.g. recovery,
+    /// which starts signed out)
+    pub async fn public_rpc_query<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> Result<T> {
         let response = self
             .client
-            .get(&url)
-            .header("apikey", &self.config.anon_key)
+            .post(format!("{}/rpc/{}", self.config().rest_endpoint(), name))
+            .header("apikey", &self.config().anon_key)
+            .header(
+                "Authorization",
+                format!("Bearer {}", self.config().anon_key),
+            )
+            .json(&args)
+            .send()
+            .await?;
+
+        Ok(check_rest_response(response).await?.json().await?)
+    }
+
+    async fn rest_send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        prefer: Option<&str>,
+    ) -> Result<Response> {
+        let session = self.active_session().await?;
+        let mut request = self
+            .client
+            .request(
+                method,
+                format!("{}/{}", self.config().rest_endpoint(), path),
+            )
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        if let Some(prefer) = prefer {
+            request = request.header("Prefer", prefer);
+        }
+
+        check_rest_response(request.send().await?).await
+    }
+
+    /// Fetch an attachment's encrypted blob from Storage, reporting bytes received and expected
+    pub async fn download_attachment(
+        &self,
+        attachment: &Attachment,
+        on_progress: impl Fn(u64, u64),
+    ) -> Result<()> {
+        let session = self.active_session().await?;
+        let mut response = self
+            .client
+            .get(self.attachment_object_url(&session, &attachment.id))
+            .header("apikey", &self.config().anon_key)
             .header("Authorization", format!("Bearer {}", session.access_token))
             .send()
             .await?;
 
-        println!("[Sync] Response status: {}", response.status());
+        if !response.status().is_success() {
+            return Err(AppError::Sync("Failed to download attachment".to_string()));
+        }
+
+        let path = self.db.attachment_blob_path(&attachment.id);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Stream to a temporary file so a dropped connection leaves no partial blob
+        let total = response.content_length().unwrap_or(attachment.size as u64);
+        let temp = path.with_extension("bvat.part");
+        let mut file = tokio::fs::File::create(&temp).await?;
+        let mut received = 0u64;
+        let streamed: Result<()> = async {
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk).await?;
+                received += chunk.len() as u64;
+                on_progress(received, total);
+            }
+            file.flush().await?;
+            Ok(())
+        }
+        .await;
+
+        match streamed {
+            Ok(()) => Ok(tokio::fs::rename(&temp, &path).await?),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Upload a support bundle to the user's folder and return a download link
+    /// that stops working after `expires_in_secs`
+    pub async fn share_support_bundle(
+        &self,
+        file_name: &str,
+        bytes: Vec<u8>,
+        expires_in_secs: u64,
+    ) -> Result<String> {
+        let session = self.active_session().await?;
+        let object = format!("{}/{}/{}", SUPPORT_BUNDLES_BUCKET, session.user_id, file_name);
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/storage/v1/object/{}",
+                self.config().url,
+                object
+            ))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .header("Content-Type", "application/zip")
+            .header("x-upsert", "true")
+            .body(bytes)
+            .send()
+            .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            println!("[Sync] Error response: {}", error_text);
-            return Err(AppError::Sync("Failed to pull vault items".to_string()));
+            return Err(AppError::Sync("Failed to upload support bundle".to_string()));
         }
 
-        let server_items: Vec<SupabaseVaultItem> = response.json().await?;
-        println!("[Sync] Received {} vault items from server", server_items.len());
-        let now = Utc::now().to_rfc3339();
+        let response = self
+            .client
+            .post(format!(
+                "{}/storage/v1/object/sign/{}",
+                self.config().url,
+                object
+            ))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .json(&serde_json::json!({ "expiresIn": expires_in_secs }))
+            .send()
+            .await?;
 
-        let items: Vec<VaultItem> = server_items
-            .into_iter()
-            .map(|i| VaultItem {
-                id: i.id,
-                encrypted_data: i.encrypted_data,
-                item_type: i.item_type,
-                folder_id: i.folder_id,
-                is_favorite: false, // Favorite flag is stored in encrypted_data
-                deleted_at: i.deleted_at,
-                synced_at: Some(now.clone()),
-                local_updated_at: i.updated_at.clone(),
-                server_updated_at: Some(i.updated_at),
-            })
-            .collect();
+        if !response.status().is_success() {
+            return Err(AppError::Sync(
+                "Failed to create support bundle link".to_string(),
+            ));
+        }
+
+        // Storage returns the path relative to its own root
+        let signed: SupabaseSignedUrl = response.json().await?;
+        Ok(format!(
+            "{}/storage/v1{}",
+            self.config().url,
+            signed.signed_url
+        ))
+    }
+
+    fn send_object_url(&self, session: &UserSession, id: &str) -> String {
+        format!(
+            "{}/storage/v1/object/{}/{}/{}",
+            self.config().url,
+            SENDS_BUCKET,
+            session.user_id,
+            id
+        )
+    }
 
-        self.db.bulk_upsert_vault_items(&items)?;
-        println!("[Sync] Stored {} items in local database", items.len());
+    /// Upload a send's ciphertext to the user's folder
+    pub async fn upload_send(&self, id: &str, bytes: Vec<u8>) -> Result<()> {
+        let session = self.active_session().await?;
+        let response = self
+            .client
+            .post(self.send_object_url(&session, id))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .header("Content-Type", "application/json")
+            .body(bytes)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Sync("Failed to upload send".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Delete a send's ciphertext; one already gone is as good as deleted
+    pub async fn delete_send(&self, id: &str) -> Result<()> {
+        let session = self.active_session().await?;
+        let response = self
+            .client
+            .delete(self.send_object_url(&session, id))
+            .header("apikey", &self.config().anon_key)
+            .header("Authorization", format!("Bearer {}", session.access_token))
+            .send()
+            .await?;
 
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND {
+            return Err(AppError::Sync("Failed to delete send".to_string()));
+        }
         Ok(())
     }
 
     /// Initial full sync when logging in
     pub async fn initial_sync(&self, session: &UserSession) -> Result<()> {
+        let started_at = Utc::now();
+        let timer = Instant::now();
+
         // Pull all data from server
-        self.pull_folders(session, None).await?;
-        self.pull_vault_items(session, None).await?;
+        let result = async {
+            self.ensure_schema().await?;
+            self.db.clear_sync_cursors()?;
+            let folders = self.pull_folders(session, None).await?;
+            let items = self.pull_vault_items(session, None).await?;
+            let attachments = self.pull_attachments(session, None).await?;
+            let org_items = self.pull_organizations(session, None).await?;
+            Ok((0, folders + items + attachments + org_items))
+        }
+        .await;
+        self.record_history("login", started_at, timer, &result);
+        result?;
 
         // Clear sync queue as we just synced everything
         self.db.clear_sync_queue()?;
@@ -0,0 +1,220 @@
+// ============================================
+// BirchVault Desktop - System Tray
+// ============================================
+
+use crate::autolock::{self, LockReason};
+use crate::commands::AppState;
+use crate::i18n;
+use crate::notify::{self, NotificationCategory};
+use crate::offline;
+use serde::Serialize;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+const TRAY_ID: &str = "main";
+
+const MENU_SHOW: &str = "show";
+const MENU_COPY_LAST_USED: &str = "copy_last_used";
+const MENU_SYNC: &str = "sync";
+const MENU_LOCK: &str = "lock";
+const MENU_QUIT: &str = "quit";
+
+/// What the tray menu reflects; read from `AppState` on every refresh
+#[derive(Debug, Clone, Default)]
+struct TrayStatus {
+    is_locked: bool,
+    email: Option<String>,
+    has_last_used: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayCopyLastUsedPayload {
+    pub item_id: String,
+}
+
+// ============================================
+// Setup
+// ============================================
+
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(
+        app,
+        &TrayStatus {
+            is_locked: true,
+            ..Default::default()
+        },
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("BirchVault")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+
+    builder.build(app)?;
+    refresh(app);
+
+    Ok(())
+}
+
+/// Rebuild the tray menu from current state (call after lock/unlock/login/logout)
+pub fn refresh(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<AppState>() else {
+            return;
+        };
+
+        let status = TrayStatus {
+            is_locked: *state.is_locked.read().await,
+            email: match state.db.get_session().ok().flatten() {
+                Some(session) => Some(session.email),
+                None => offline::get(&state.db).ok().flatten().map(|p| p.name),
+            },
+            has_last_used: state.last_copied_item_id.read().await.is_some(),
+        };
+
+        let Some(tray) = app.tray_by_id(TRAY_ID) else {
+            return;
+        };
+
+        match build_menu(&app, &status) {
+            Ok(menu) => {
+                let _ = tray.set_menu(Some(menu));
+                let _ = tray.set_tooltip(Some(status_label(&status)));
+            }
+            Err(e) => log::warn!("Failed to rebuild tray menu: {}", e),
+        }
+    });
+}
+
+// ============================================
+// Menu
+// ============================================
+
+fn status_label(status: &TrayStatus) -> String {
+    if status.is_locked {
+        i18n::t("tray.status_locked")
+    } else {
+        i18n::t("tray.status_unlocked")
+    }
+}
+
+fn build_menu(app: &AppHandle, status: &TrayStatus) -> tauri::Result<Menu<tauri::Wry>> {
+    let signed_in = status.email.is_some();
+    let unlocked = signed_in && !status.is_locked;
+
+    let status_item = MenuItem::with_id(app, "status", status_label(status), false, None::<&str>)?;
+    let account_item = MenuItem::with_id(
+        app,
+        "account",
+        status
+            .email
+            .clone()
+            .unwrap_or_else(|| i18n::t("tray.signed_out")),
+        false,
+        None::<&str>,
+    )?;
+    let show_item = MenuItem::with_id(app, MENU_SHOW, i18n::t("tray.show"), true, None::<&str>)?;
+    let copy_item = MenuItem::with_id(
+        app,
+        MENU_COPY_LAST_USED,
+        i18n::t("tray.copy_last_used"),
+        unlocked && status.has_last_used,
+        None::<&str>,
+    )?;
+    let sync_item = MenuItem::with_id(
+        app,
+        MENU_SYNC,
+        i18n::t("tray.sync_now"),
+        unlocked,
+        None::<&str>,
+    )?;
+    let lock_item =
+        MenuItem::with_id(app, MENU_LOCK, i18n::t("tray.lock"), unlocked, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, MENU_QUIT, i18n::t("tray.quit"), true, None::<&str>)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &status_item,
+            &account_item,
+            &PredefinedMenuItem::separator(app)?,
+            &show_item,
+            &copy_item,
+            &sync_item,
+            &lock_item,
+            &PredefinedMenuItem::separator(app)?,
+            &quit_item,
+        ],
+    )
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        MENU_SHOW => show_main_window(app),
+        MENU_COPY_LAST_USED => {
+            // The webview holds the vault key, so it does the decrypt and copy
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                let item_id = state.last_copied_item_id.read().await.clone();
+                if let Some(item_id) = item_id {
+                    let _ = app.emit("tray-copy-last-used", TrayCopyLastUsedPayload { item_id });
+                }
+            });
+        }
+        MENU_SYNC => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                match state.sync_engine.sync("tray").await {
+                    Ok(status) => {
+                        let _ = app.emit("sync-completed", status);
+                    }
+                    Err(e) => {
+                        notify::dispatch(
+                            &app,
+                            NotificationCategory::SyncFailure,
+                            &i18n::t("notify.sync_failed_title"),
+                            &i18n::t_with("notify.sync_failed_body", &[("error", &e.to_string())]),
+                        );
+                        let _ = app.emit("sync-failed", e.to_string());
+                    }
+                }
+            });
+        }
+        MENU_LOCK => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                autolock::lock(&app, LockReason::Tray).await;
+            });
+        }
+        MENU_QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
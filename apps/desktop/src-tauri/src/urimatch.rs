@@ -0,0 +1,175 @@
+// ============================================
+// BirchVault Desktop - URI Matching
+// ============================================
+//
+// Decides which logins belong to a page. Each saved URI carries a match
+// type (`login.uris[].match`, numbered as in @birchvault/core and other
+// password managers); URIs without one fall back to base-domain matching.
+// Base domains come from the Public Suffix List, so `a.example.co.uk` and
+// `b.example.co.uk` match but `example.co.uk` and `other.co.uk` don't.
+
+use crate::error::{AppError, Result};
+use regex::RegexBuilder;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Compiled size cap for user-supplied patterns
+const REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UriMatch {
+    /// Same registrable domain: `login.example.com` matches `www.example.com`
+    Domain,
+    /// Same host and port
+    Host,
+    /// The page URL starts with the saved URI
+    StartsWith,
+    /// The page URL is the saved URI
+    Exact,
+    /// The saved URI is a regular expression over the page URL
+    Regex,
+    /// Never offered for autofill
+    Never,
+}
+
+impl UriMatch {
+    /// The numeric code stored in item payloads
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(UriMatch::Domain),
+            1 => Some(UriMatch::Host),
+            2 => Some(UriMatch::StartsWith),
+            3 => Some(UriMatch::Exact),
+            4 => Some(UriMatch::Regex),
+            5 => Some(UriMatch::Never),
+            _ => None,
+        }
+    }
+
+    /// How specific a hit of this kind is, for ranking
+    pub fn strength(self) -> u32 {
+        match self {
+            UriMatch::Exact => 100,
+            UriMatch::StartsWith => 90,
+            UriMatch::Regex => 80,
+            UriMatch::Host => 70,
+            UriMatch::Domain => 60,
+            UriMatch::Never => 0,
+        }
+    }
+}
+
+/// One entry of `login.uris`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginUri {
+    pub uri: String,
+    #[serde(default, rename = "match")]
+    pub match_code: Option<u8>,
+}
+
+impl LoginUri {
+    /// Unknown codes are treated as the default rather than dropping the URI
+    pub fn match_type(&self, default: UriMatch) -> UriMatch {
+        self.match_code
+            .and_then(UriMatch::from_code)
+            .unwrap_or(default)
+    }
+}
+
+/// A parsed page URL, prepared once and matched against many URIs
+#[derive(Debug, Clone)]
+pub struct PageUrl {
+    raw: String,
+    host: Option<String>,
+    port: Option<u16>,
+    base_domain: Option<String>,
+}
+
+impl PageUrl {
+    pub fn parse(url: &str) -> Result<Self> {
+        let raw = url.trim().to_string();
+        let parsed = parse_lenient(&raw)
+            .ok_or_else(|| AppError::InvalidOperation(format!("Invalid URL: {}", raw)))?;
+        let host = parsed.host_str().map(str::to_lowercase);
+
+        Ok(PageUrl {
+            base_domain: host.as_deref().map(base_domain),
+            port: parsed.port_or_known_default(),
+            host,
+            raw,
+        })
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+}
+
+/// Saved URIs are often bare hosts ("example.com"); assume https for those
+fn parse_lenient(url: &str) -> Option<Url> {
+    match Url::parse(url) {
+        Ok(parsed) if parsed.has_host() => Some(parsed),
+        _ => Url::parse(&format!("https://{}", url)).ok(),
+    }
+}
+
+/// The registrable domain for `host`. IP addresses, `localhost` and other
+/// hosts without a public suffix are their own base domain.
+pub fn base_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+    psl::domain_str(&host).map(str::to_string).unwrap_or(host)
+}
+
+/// Whether a saved URI matches the page under the given match type
+pub fn uri_matches(page: &PageUrl, uri: &str, match_type: UriMatch) -> bool {
+    let uri = uri.trim();
+    if uri.is_empty() {
+        return false;
+    }
+
+    match match_type {
+        UriMatch::Never => false,
+        UriMatch::Exact => page.raw == uri,
+        UriMatch::StartsWith => page.raw.starts_with(uri),
+        UriMatch::Regex => RegexBuilder::new(uri)
+            .case_insensitive(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .map(|re| re.is_match(&page.raw))
+            .unwrap_or(false),
+        UriMatch::Host => parse_lenient(uri).is_some_and(|saved| {
+            saved.host_str().map(str::to_lowercase).as_deref() == page.host()
+                && saved.port_or_known_default() == page.port
+        }),
+        UriMatch::Domain => {
+            let Some(saved_host) =
+                parse_lenient(uri).and_then(|u| u.host_str().map(str::to_lowercase))
+            else {
+                return false;
+            };
+            page.base_domain.as_deref() == Some(base_domain(&saved_host).as_str())
+        }
+    }
+}
+
+/// The strongest match among a login's URIs, if any
+pub fn best_match(page: &PageUrl, uris: &[LoginUri], default: UriMatch) -> Option<UriMatch> {
+    uris.iter()
+        .map(|uri| (uri, uri.match_type(default)))
+        .filter(|(uri, match_type)| uri_matches(page, &uri.uri, *match_type))
+        .map(|(_, match_type)| match_type)
+        .max_by_key(|match_type| match_type.strength())
+}
+
+/// A regex URI has to compile, or its login would silently never match
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map(|_| ())
+        .map_err(|e| AppError::InvalidOperation(format!("Invalid URI pattern: {}", e)))
+}
@@ -0,0 +1,178 @@
+// ============================================
+// BirchVault Desktop - WebDAV Sync Backend
+// ============================================
+//
+// Keeps folder sync's record files on a WebDAV server (Nextcloud, ownCloud,
+// Apache mod_dav and the like) with basic auth. Only the little of WebDAV
+// that needs is used: PROPFIND to list a collection with each file's etag,
+// MKCOL, and plain GET/PUT/DELETE.
+
+use crate::error::{AppError, Result};
+use crate::filesync::{RemoteFile, SyncBackend};
+use regex::Regex;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:getetag/><d:getlastmodified/><d:getcontentlength/><d:resourcetype/></d:prop>
+</d:propfind>"#;
+
+pub struct WebDavBackend {
+    client: Client,
+    /// Collection URL, ending in `/`
+    base: String,
+    username: String,
+    password: String,
+}
+
+impl WebDavBackend {
+    pub fn open(client: Client, url: &str, username: &str, password: &str) -> Result<Self> {
+        let url = url.trim();
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|_| AppError::InvalidOperation("The WebDAV URL isn't valid".to_string()))?;
+        if parsed.scheme() != "https" {
+            return Err(AppError::InvalidOperation(
+                "The WebDAV server has to use HTTPS".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            client,
+            base: format!("{}/", url.trim_end_matches('/')),
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base, path))
+            .basic_auth(&self.username, Some(&self.password))
+    }
+}
+
+fn method(name: &[u8]) -> Method {
+    Method::from_bytes(name).expect("valid WebDAV method")
+}
+
+fn check(response: Response, what: &str) -> Result<Response> {
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(AppError::Auth(
+            "The WebDAV server refused the username or password".to_string(),
+        )),
+        status if status.is_success() => Ok(response),
+        status => Err(AppError::Sync(format!(
+            "WebDAV {} failed: {}",
+            what, status
+        ))),
+    }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+/// A PROPFIND listing's entries, as the text of each of their leaf
+/// elements by name, whatever namespace prefix the server uses. Collections
+/// (the listed one itself, or any below it) are left out.
+fn entries(xml: &str) -> Vec<HashMap<&str, &str>> {
+    static RESPONSE: OnceLock<Regex> = OnceLock::new();
+    static LEAF: OnceLock<Regex> = OnceLock::new();
+    static COLLECTION: OnceLock<Regex> = OnceLock::new();
+    let response = regex(
+        &RESPONSE,
+        r"(?s)<(?:[\w-]+:)?response\b[^>]*>(.*?)</(?:[\w-]+:)?response>",
+    );
+    let leaf = regex(&LEAF, r"<(?:[\w-]+:)?([\w-]+)(?:\s[^>]*)?>([^<]*)</");
+    let collection = regex(&COLLECTION, r"<(?:[\w-]+:)?collection\s*/?>");
+
+    response
+        .captures_iter(xml)
+        .filter_map(|c| c.get(1).map(|m| m.as_str()))
+        .filter(|entry| !collection.is_match(entry))
+        .map(|entry| {
+            leaf.captures_iter(entry)
+                .filter_map(|c| Some((c.get(1)?.as_str(), c.get(2)?.as_str().trim())))
+                .collect()
+        })
+        .collect()
+}
+
+impl SyncBackend for WebDavBackend {
+    async fn create_dir(&self, dir: &str) -> Result<()> {
+        let mut path = String::new();
+        for part in dir.split('/') {
+            path.push_str(part);
+            path.push('/');
+            let response = self.request(method(b"MKCOL"), &path).send().await?;
+            // 405: it's already there
+            if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+                check(response, "folder creation")?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteFile>> {
+        let response = self
+            .request(method(b"PROPFIND"), &format!("{}/", dir))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let xml = check(response, "listing")?.text().await?;
+
+        let files = entries(&xml)
+            .into_iter()
+            .filter_map(|entry| {
+                let name = entry.get("href")?.rsplit('/').next()?.to_string();
+                if name.starts_with('.') || !name.ends_with(".json") {
+                    return None;
+                }
+                let version = match entry.get("getetag") {
+                    Some(etag) => etag.to_string(),
+                    None => format!(
+                        "{}-{}",
+                        entry.get("getcontentlength").unwrap_or(&""),
+                        entry.get("getlastmodified")?
+                    ),
+                };
+                Some(RemoteFile { name, version })
+            })
+            .collect();
+        Ok(files)
+    }
+
+    async fn read(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.request(Method::GET, path).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(check(response, "download")?.bytes().await?.to_vec()))
+    }
+
+    async fn write(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let response = self
+            .request(Method::PUT, path)
+            .header("Content-Type", "application/json")
+            .body(contents.to_vec())
+            .send()
+            .await?;
+        check(response, "upload")?;
+        Ok(())
+    }
+
+    async fn remove(&self, path: &str) -> Result<()> {
+        let response = self.request(Method::DELETE, path).send().await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, "deletion")?;
+        }
+        Ok(())
+    }
+}
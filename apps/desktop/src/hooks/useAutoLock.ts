@@ -1,4 +1,5 @@
 import { useEffect, useRef, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { useAuthStore } from '../store/auth';
 import { useSettingsStore } from '../store/settings';
 
@@ -10,10 +11,17 @@ export function useAutoLock() {
   const { settings } = useSettingsStore();
   const timeoutRef = useRef<NodeJS.Timeout | null>(null);
   const lastActivityRef = useRef<number>(Date.now());
+  const lastReportRef = useRef<number>(0);
 
   const resetTimer = useCallback(() => {
     lastActivityRef.current = Date.now();
 
+    // The backend enforces auto-lock too; keep its idle clock current
+    if (!isLocked && lastActivityRef.current - lastReportRef.current > 30 * 1000) {
+      lastReportRef.current = lastActivityRef.current;
+      invoke('report_activity').catch(() => {});
+    }
+
     if (timeoutRef.current) {
       clearTimeout(timeoutRef.current);
     }
@@ -87,6 +95,24 @@ export function useAutoLock() {
     };
   }, [settings.autoLockMinutes, lock, resetTimer]);
 
+  // The backend locked the vault (idle, sleep, session lock or tray)
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    import('@tauri-apps/api/event')
+      .then(({ listen }) => listen('vault-locked', () => useAuthStore.setState({ isLocked: true })))
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((err) => console.error('Failed to listen for vault lock:', err));
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
   return {
     resetTimer,
     lastActivity: lastActivityRef.current,
@@ -100,17 +100,11 @@ export function UnlockPage() {
       // Store encryption key for vault decryption
       useVaultStore.getState().setEncryptionKey(encryptionKey);
       
-      // Fetch subscription data
-      await fetchSubscription(result.userId, result.accessToken);
-      
-      // Sync vault data from server
-      try {
-        await useVaultStore.getState().sync();
-      } catch (syncErr) {
-        console.error('Sync after unlock failed:', syncErr);
-        // Continue anyway - offline mode
-      }
-      
+      // Show the vault straight away; the vault page starts syncing once it has painted
+      fetchSubscription(result.userId, result.accessToken).catch((subErr) => {
+        console.error('Failed to fetch subscription:', subErr);
+      });
+
       navigate('/vault');
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to unlock vault');
@@ -1,5 +1,7 @@
 import { useEffect, useState } from 'react';
 import { useNavigate } from 'react-router-dom';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
   Plus,
   Key,
@@ -95,11 +97,30 @@ export function VaultPage() {
     console.log('[VaultPage] Loading vault...');
     loadVault().then(() => {
       console.log('[VaultPage] Vault loaded');
+      // Connectivity check and sync wait until the list has painted
+      requestAnimationFrame(() => {
+        invoke('vault_ready').catch((err) => {
+          console.error('[VaultPage] Failed to start background sync:', err);
+        });
+      });
     }).catch((err) => {
       console.error('[VaultPage] Failed to load vault:', err);
     });
   }, [loadVault]);
 
+  // Pick up whatever the background sync pulled
+  useEffect(() => {
+    const unlisten = listen('sync-completed', () => {
+      loadVault().catch((err) => {
+        console.error('[VaultPage] Failed to reload vault after sync:', err);
+      });
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [loadVault]);
+
   // Decrypt regular items when rawItems or encryption key changes
   useEffect(() => {
     console.log('[VaultPage] Decrypt regular items effect triggered:', {
@@ -180,7 +180,7 @@ export const useAuthStore = create<AuthState>()((set, get) => ({
   checkSession: async () => {
     try {
       const hasSession = await invoke<boolean>('has_stored_session');
-      const isLocked = await invoke<boolean>('is_vault_locked');
+      const isLocked = (await invoke<string>('get_lock_state')) !== 'unlocked';
       
       if (hasSession && !isLocked) {
         const session = await invoke<{ userId: string; email: string; accessToken: string } | null>('get_session');
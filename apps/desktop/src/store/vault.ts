@@ -31,6 +31,29 @@ interface RawFolder {
   localUpdatedAt: string;
 }
 
+interface RawItemPage {
+  items: RawVaultItem[];
+  total: number;
+}
+
+// The largest page the backend hands out
+const PAGE_SIZE = 500;
+
+// Every item of a paged listing, a page at a time
+async function loadAllPages(
+  command: 'get_vault_items' | 'get_trashed_items',
+  args: (offset: number) => Record<string, unknown>,
+): Promise<RawVaultItem[]> {
+  const items: RawVaultItem[] = [];
+  for (;;) {
+    const page = await invoke<RawItemPage>(command, args(items.length));
+    items.push(...page.items);
+    if (page.items.length === 0 || items.length >= page.total) {
+      return items;
+    }
+  }
+}
+
 interface SyncStatus {
   isSyncing: boolean;
   lastSyncAt: string | null;
@@ -199,15 +222,13 @@ export const useVaultStore = create<VaultState>()((set, get) => ({
   loadVault: async () => {
     set({ isLoading: true });
     try {
-      const [rawItems, rawTrashedItems, rawFolders] = await Promise.all([
-        invoke<RawVaultItem[]>('get_vault_items'),
-        invoke<RawVaultItem[]>('get_trashed_items'),
+      const [rawItems, rawFolders] = await Promise.all([
+        loadAllPages('get_vault_items', (offset) => ({ limit: PAGE_SIZE, offset })),
         invoke<RawFolder[]>('get_folders'),
       ]);
       
       set({
         rawItems,
-        rawTrashedItems,
         folders: rawFolders.map((f) => ({
           id: f.id,
           name: f.name,
@@ -216,6 +237,9 @@ export const useVaultStore = create<VaultState>()((set, get) => ({
         })),
         isLoading: false,
       });
+
+      // The trash isn't on screen at first, so don't hold up the list for it
+      get().loadTrashedItems().catch(() => {});
       
       // Note: Items need to be decrypted by the caller using encryptionKey
     } catch (error) {
@@ -227,7 +251,9 @@ export const useVaultStore = create<VaultState>()((set, get) => ({
 
   loadTrashedItems: async () => {
     try {
-      const rawTrashedItems = await invoke<RawVaultItem[]>('get_trashed_items');
+      const rawTrashedItems = await loadAllPages('get_trashed_items', (offset) => ({
+        query: { limit: PAGE_SIZE, offset },
+      }));
       set({ rawTrashedItems }); // Replace, don't append
       // Note: Items need to be decrypted by the caller
     } catch (error) {
@@ -13,6 +13,8 @@ export interface VaultItemBase {
   organizationId?: string;
   favorite: boolean;
   notes?: string;
+  /** Desktop auto-type keystrokes, e.g. "{USERNAME}{TAB}{PASSWORD}{ENTER}" */
+  autoTypeSequence?: string;
   createdAt: string;
   updatedAt: string;
 }
@@ -152,6 +152,26 @@ export interface Database {
           created_at?: string;
         };
       };
+      schema_meta: {
+        Row: {
+          id: number;
+          version: number;
+          min_client_version: number;
+          updated_at: string;
+        };
+        Insert: {
+          id?: number;
+          version: number;
+          min_client_version: number;
+          updated_at?: string;
+        };
+        Update: {
+          id?: number;
+          version?: number;
+          min_client_version?: number;
+          updated_at?: string;
+        };
+      };
     };
     Views: {};
     Functions: {};
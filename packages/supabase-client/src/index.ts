@@ -68,6 +68,83 @@ export function createServerClient(
   });
 }
 
+// ============================================
+// Schema Version Handshake
+// ============================================
+
+/**
+ * Server schema version (`schema_meta.version`) this package was written against.
+ * Keep in step with the desktop backend's EXPECTED_SCHEMA_VERSION.
+ */
+export const SCHEMA_VERSION = 1;
+
+export type SchemaCompatibility = 'compatible' | 'serverOutdated' | 'appOutdated';
+
+export interface SchemaStatus {
+  compatibility: SchemaCompatibility;
+  expectedVersion: number;
+  /** 0 when the server predates `schema_meta` */
+  serverVersion: number;
+  minClientVersion: number;
+}
+
+export class SchemaMismatchError extends Error {
+  constructor(public readonly status: SchemaStatus) {
+    super(
+      status.compatibility === 'serverOutdated'
+        ? `The server's database schema (version ${status.serverVersion}) is older than this app needs (version ${status.expectedVersion}). Run the latest migrations.`
+        : `The server's database schema (version ${status.serverVersion}) is newer than this app supports. Please update the app.`
+    );
+    this.name = 'SchemaMismatchError';
+  }
+}
+
+/**
+ * Compare the server's `schema_meta` with the version an app expects
+ */
+export async function getSchemaStatus(
+  client: SupabaseClient<Database>,
+  expectedVersion: number = SCHEMA_VERSION
+): Promise<SchemaStatus> {
+  const { data, error } = await client
+    .from('schema_meta')
+    .select('version, min_client_version')
+    .eq('id', 1)
+    .maybeSingle();
+
+  // A missing table means the server predates versioning
+  const missingTable = error?.code === '42P01' || error?.code === 'PGRST205';
+  if (error && !missingTable) {
+    throw new Error(`Failed to read server schema version: ${error.message}`);
+  }
+
+  const serverVersion = data?.version ?? 0;
+  const minClientVersion = data?.min_client_version ?? 0;
+
+  let compatibility: SchemaCompatibility = 'compatible';
+  if (serverVersion < expectedVersion) {
+    compatibility = 'serverOutdated';
+  } else if (minClientVersion > expectedVersion) {
+    compatibility = 'appOutdated';
+  }
+
+  return { compatibility, expectedVersion, serverVersion, minClientVersion };
+}
+
+/**
+ * Throw a SchemaMismatchError unless the server schema matches
+ */
+export async function ensureSchemaCompatible(
+  client: SupabaseClient<Database>,
+  expectedVersion: number = SCHEMA_VERSION
+): Promise<SchemaStatus> {
+  const status = await getSchemaStatus(client, expectedVersion);
+  if (status.compatibility !== 'compatible') {
+    throw new SchemaMismatchError(status);
+  }
+  return status;
+}
+
 // Re-export Supabase types for convenience
 export type { SupabaseClient, Session, User } from '@supabase/supabase-js';
 
@@ -0,0 +1,10 @@
+-- Optional per-folder retention: items are moved to the trash this many
+-- days after their last change. Enforced client-side, synced across devices.
+ALTER TABLE public.vault_folders
+ADD COLUMN IF NOT EXISTS retention_days INTEGER;
+
+ALTER TABLE public.vault_folders
+ADD CONSTRAINT valid_retention_days
+CHECK (retention_days IS NULL OR (retention_days >= 1 AND retention_days <= 3650));
+
+COMMENT ON COLUMN public.vault_folders.retention_days IS 'Auto-trash items this many days after their last change (NULL = keep forever)';
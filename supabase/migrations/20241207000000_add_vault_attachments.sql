@@ -0,0 +1,87 @@
+-- ============================================
+-- Encrypted File Attachments
+-- Blobs are encrypted client-side with a per-attachment key, which is itself
+-- encrypted with the vault key. The server never sees names or contents.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.vault_attachments (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES public.vault_items(id) ON DELETE CASCADE,
+    encrypted_name TEXT NOT NULL,
+    encrypted_key TEXT NOT NULL,
+    size BIGINT NOT NULL CHECK (size >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_attachments_user_id ON public.vault_attachments(user_id);
+CREATE INDEX IF NOT EXISTS idx_vault_attachments_item_id ON public.vault_attachments(item_id);
+
+CREATE TRIGGER update_vault_attachments_updated_at
+    BEFORE UPDATE ON public.vault_attachments
+    FOR EACH ROW EXECUTE FUNCTION public.update_updated_at();
+
+ALTER TABLE public.vault_attachments ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Users can view own attachments"
+    ON public.vault_attachments FOR SELECT
+    USING ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can insert own attachments"
+    ON public.vault_attachments FOR INSERT
+    WITH CHECK ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can update own attachments"
+    ON public.vault_attachments FOR UPDATE
+    USING ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can delete own attachments"
+    ON public.vault_attachments FOR DELETE
+    USING ((select auth.uid()) = user_id);
+
+-- ============================================
+-- Storage bucket: objects live at <user_id>/<attachment_id>
+-- ============================================
+
+INSERT INTO storage.buckets (id, name, public, file_size_limit, allowed_mime_types)
+VALUES (
+  'vault-attachments',
+  'vault-attachments',
+  false,
+  104923136, -- 100MB plus per-chunk encryption overhead
+  NULL -- Contents are encrypted, so the type is always opaque
+)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE POLICY "Users can upload their own attachments"
+ON storage.objects FOR INSERT
+TO authenticated
+WITH CHECK (
+  bucket_id = 'vault-attachments' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can view their own attachments"
+ON storage.objects FOR SELECT
+TO authenticated
+USING (
+  bucket_id = 'vault-attachments' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can update their own attachments"
+ON storage.objects FOR UPDATE
+TO authenticated
+USING (
+  bucket_id = 'vault-attachments' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can delete their own attachments"
+ON storage.objects FOR DELETE
+TO authenticated
+USING (
+  bucket_id = 'vault-attachments' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
@@ -0,0 +1,12 @@
+-- ============================================
+-- Add Passkey vault item type
+-- Passkey private keys live inside encrypted_data like any other secret;
+-- only the type needs to be allowed through.
+-- ============================================
+
+ALTER TABLE public.vault_items
+DROP CONSTRAINT IF EXISTS vault_items_type_check;
+
+ALTER TABLE public.vault_items
+ADD CONSTRAINT vault_items_type_check
+CHECK (type IN ('login', 'card', 'identity', 'securenote', 'apikey', 'wifi', 'document', 'passkey'));
@@ -0,0 +1,34 @@
+-- ============================================
+-- Schema Version Handshake
+-- A single row declaring which schema version this database is at and the
+-- oldest client schema version it still serves. Apps compare these with the
+-- version they were built against before syncing, so a mismatch reads as
+-- "update the app" or "run migrations" rather than a PostgREST error.
+--
+-- Bump `version` in any migration that changes what clients read or write,
+-- and `min_client_version` when older clients can no longer work safely.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.schema_meta (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    version INTEGER NOT NULL,
+    min_client_version INTEGER NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE public.schema_meta ENABLE ROW LEVEL SECURITY;
+
+-- Read before sign-in, so the anon role needs it too; nobody writes via the API
+CREATE POLICY "Anyone can read schema meta"
+    ON public.schema_meta FOR SELECT
+    USING (true);
+
+CREATE TRIGGER update_schema_meta_updated_at
+    BEFORE UPDATE ON public.schema_meta
+    FOR EACH ROW EXECUTE FUNCTION public.update_updated_at();
+
+INSERT INTO public.schema_meta (id, version, min_client_version)
+VALUES (1, 1, 1)
+ON CONFLICT (id) DO UPDATE
+SET version = EXCLUDED.version,
+    min_client_version = EXCLUDED.min_client_version;
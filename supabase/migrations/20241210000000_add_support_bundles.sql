@@ -0,0 +1,50 @@
+-- ============================================
+-- Support Bundles
+-- Redacted diagnostics uploaded from the desktop app so users can attach
+-- them to bug reports. Objects live at <user_id>/<file name>; they are
+-- shared through signed URLs, so the bucket stays private and only the
+-- owner can write or read directly.
+-- ============================================
+
+INSERT INTO storage.buckets (id, name, public, file_size_limit, allowed_mime_types)
+VALUES (
+  'support-bundles',
+  'support-bundles',
+  false,
+  10485760, -- 10MB; bundles are a few hundred KB at most
+  ARRAY['application/zip']
+)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE POLICY "Users can upload their own support bundles"
+ON storage.objects FOR INSERT
+TO authenticated
+WITH CHECK (
+  bucket_id = 'support-bundles' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can view their own support bundles"
+ON storage.objects FOR SELECT
+TO authenticated
+USING (
+  bucket_id = 'support-bundles' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+-- Re-sharing a bundle with the same name overwrites it (x-upsert)
+CREATE POLICY "Users can update their own support bundles"
+ON storage.objects FOR UPDATE
+TO authenticated
+USING (
+  bucket_id = 'support-bundles' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can delete their own support bundles"
+ON storage.objects FOR DELETE
+TO authenticated
+USING (
+  bucket_id = 'support-bundles' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
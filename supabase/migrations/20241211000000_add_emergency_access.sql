@@ -0,0 +1,233 @@
+-- ============================================
+-- Emergency Access
+-- A grantor names a trusted contact (the grantee) who can read their vault
+-- after a wait period the grantor can cut short or deny. The grantor's vault
+-- key is escrowed wrapped with the grantee's RSA-OAEP public key, so the
+-- server never holds it in the clear.
+-- ============================================
+
+-- Private half of the account key pair (public half: vault_user_public_keys),
+-- PKCS#8 encrypted with the vault key
+ALTER TABLE public.vault_profiles
+    ADD COLUMN IF NOT EXISTS encrypted_private_key TEXT;
+
+CREATE TABLE IF NOT EXISTS public.vault_emergency_access (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    grantor_id UUID NOT NULL REFERENCES public.vault_profiles(id) ON DELETE CASCADE,
+    grantor_email TEXT NOT NULL,
+    grantee_id UUID REFERENCES public.vault_profiles(id) ON DELETE CASCADE, -- Set on acceptance
+    grantee_email TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'invited' CHECK (status IN (
+        'invited', 'accepted', 'confirmed', 'recovery_initiated', 'recovery_approved'
+    )),
+    wait_days INTEGER NOT NULL DEFAULT 7 CHECK (wait_days BETWEEN 1 AND 90),
+    recovery_initiated_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_emergency_contact UNIQUE (grantor_id, grantee_email),
+    CONSTRAINT emergency_contact_not_self CHECK (lower(grantor_email) <> lower(grantee_email))
+);
+
+-- Kept apart from the arrangement so RLS can release it on its own terms
+CREATE TABLE IF NOT EXISTS public.vault_emergency_access_keys (
+    access_id UUID PRIMARY KEY REFERENCES public.vault_emergency_access(id) ON DELETE CASCADE,
+    encrypted_key TEXT NOT NULL, -- Grantor's vault key, RSA-OAEP with the grantee's public key
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_emergency_access_grantor ON public.vault_emergency_access(grantor_id);
+CREATE INDEX IF NOT EXISTS idx_vault_emergency_access_grantee ON public.vault_emergency_access(grantee_id);
+CREATE INDEX IF NOT EXISTS idx_vault_emergency_access_grantee_email ON public.vault_emergency_access(lower(grantee_email));
+
+CREATE TRIGGER update_vault_emergency_access_updated_at
+    BEFORE UPDATE ON public.vault_emergency_access
+    FOR EACH ROW EXECUTE FUNCTION public.update_updated_at();
+
+-- ============================================
+-- Access check
+-- Approved, or requested and the wait period has passed without a denial
+-- ============================================
+
+CREATE OR REPLACE FUNCTION public.emergency_access_granted(p_access_id UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM public.vault_emergency_access ea
+        WHERE ea.id = p_access_id
+          AND ea.grantee_id = auth.uid()
+          AND (
+              ea.status = 'recovery_approved'
+              OR (
+                  ea.status = 'recovery_initiated'
+                  AND ea.recovery_initiated_at + make_interval(days => ea.wait_days) <= NOW()
+              )
+          )
+    );
+$$;
+
+-- ============================================
+-- RLS Policies
+-- Status changes go through the functions below, so there is no UPDATE policy
+-- ============================================
+
+ALTER TABLE public.vault_emergency_access ENABLE ROW LEVEL SECURITY;
+ALTER TABLE public.vault_emergency_access_keys ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Participants can view emergency access"
+    ON public.vault_emergency_access FOR SELECT
+    USING (
+        (select auth.uid()) IN (grantor_id, grantee_id)
+        OR (
+            grantee_id IS NULL
+            AND lower(grantee_email) = lower((select auth.jwt()) ->> 'email')
+        )
+    );
+
+CREATE POLICY "Users can invite emergency contacts"
+    ON public.vault_emergency_access FOR INSERT
+    WITH CHECK (
+        (select auth.uid()) = grantor_id
+        AND lower(grantor_email) = lower((select auth.jwt()) ->> 'email')
+        AND grantee_id IS NULL
+        AND status = 'invited'
+        AND recovery_initiated_at IS NULL
+    );
+
+CREATE POLICY "Participants can revoke emergency access"
+    ON public.vault_emergency_access FOR DELETE
+    USING ((select auth.uid()) IN (grantor_id, grantee_id));
+
+CREATE POLICY "Grantors and granted contacts can view escrowed keys"
+    ON public.vault_emergency_access_keys FOR SELECT
+    USING (
+        EXISTS (
+            SELECT 1 FROM public.vault_emergency_access ea
+            WHERE ea.id = access_id AND ea.grantor_id = (select auth.uid())
+        )
+        OR public.emergency_access_granted(access_id)
+    );
+
+CREATE POLICY "Granted emergency contacts can view vault items"
+    ON public.vault_items FOR SELECT
+    USING (
+        EXISTS (
+            SELECT 1 FROM public.vault_emergency_access ea
+            WHERE ea.grantor_id = vault_items.user_id
+              AND ea.grantee_id = (select auth.uid())
+              AND public.emergency_access_granted(ea.id)
+        )
+    );
+
+-- ============================================
+-- Transitions
+-- ============================================
+
+CREATE OR REPLACE FUNCTION public.emergency_access_accept(access_id UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_emergency_access
+    SET grantee_id = auth.uid(), status = 'accepted'
+    WHERE id = access_id
+      AND status = 'invited'
+      AND lower(grantee_email) = lower(auth.jwt() ->> 'email');
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'No pending invitation for this account';
+    END IF;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION public.emergency_access_confirm(access_id UUID, encrypted_key TEXT)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_emergency_access
+    SET status = 'confirmed'
+    WHERE id = access_id AND grantor_id = auth.uid() AND status = 'accepted';
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'This contact is not waiting for confirmation';
+    END IF;
+
+    INSERT INTO public.vault_emergency_access_keys (access_id, encrypted_key)
+    VALUES (emergency_access_confirm.access_id, emergency_access_confirm.encrypted_key)
+    ON CONFLICT ON CONSTRAINT vault_emergency_access_keys_pkey
+    DO UPDATE SET encrypted_key = EXCLUDED.encrypted_key, created_at = NOW();
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION public.emergency_access_initiate(access_id UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_emergency_access
+    SET status = 'recovery_initiated', recovery_initiated_at = NOW()
+    WHERE id = access_id AND grantee_id = auth.uid() AND status = 'confirmed';
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'Emergency access cannot be requested now';
+    END IF;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION public.emergency_access_approve(access_id UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_emergency_access
+    SET status = 'recovery_approved'
+    WHERE id = access_id AND grantor_id = auth.uid() AND status = 'recovery_initiated';
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'There is no pending request to approve';
+    END IF;
+END;
+$$;
+
+-- Denies a pending request or withdraws access already granted
+CREATE OR REPLACE FUNCTION public.emergency_access_reject(access_id UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_emergency_access
+    SET status = 'confirmed', recovery_initiated_at = NULL
+    WHERE id = access_id
+      AND grantor_id = auth.uid()
+      AND status IN ('recovery_initiated', 'recovery_approved');
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'There is no request to deny';
+    END IF;
+END;
+$$;
+
+REVOKE ALL ON FUNCTION public.emergency_access_accept(UUID) FROM PUBLIC, anon;
+REVOKE ALL ON FUNCTION public.emergency_access_confirm(UUID, TEXT) FROM PUBLIC, anon;
+REVOKE ALL ON FUNCTION public.emergency_access_initiate(UUID) FROM PUBLIC, anon;
+REVOKE ALL ON FUNCTION public.emergency_access_approve(UUID) FROM PUBLIC, anon;
+REVOKE ALL ON FUNCTION public.emergency_access_reject(UUID) FROM PUBLIC, anon;
+GRANT EXECUTE ON FUNCTION public.emergency_access_accept(UUID) TO authenticated;
+GRANT EXECUTE ON FUNCTION public.emergency_access_confirm(UUID, TEXT) TO authenticated;
+GRANT EXECUTE ON FUNCTION public.emergency_access_initiate(UUID) TO authenticated;
+GRANT EXECUTE ON FUNCTION public.emergency_access_approve(UUID) TO authenticated;
+GRANT EXECUTE ON FUNCTION public.emergency_access_reject(UUID) TO authenticated;
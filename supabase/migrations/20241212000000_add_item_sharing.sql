@@ -0,0 +1,147 @@
+-- ============================================
+-- Item Sharing
+-- A shared item gets its own random item key. The item's payload is stored
+-- once, encrypted with that key, in vault_shared_item_data; each share in
+-- vault_shared_items carries the item key wrapped with the recipient's
+-- RSA-OAEP public key (vault_user_public_keys). The owner keeps a copy of
+-- the item key encrypted with their vault key.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.vault_shared_item_data (
+    vault_item_id UUID PRIMARY KEY REFERENCES public.vault_items(id) ON DELETE CASCADE,
+    owner_id UUID NOT NULL REFERENCES public.vault_profiles(id) ON DELETE CASCADE,
+    item_type TEXT NOT NULL,
+    encrypted_data TEXT NOT NULL, -- Encrypted with the item key
+    owner_encrypted_key TEXT NOT NULL, -- Item key encrypted with the owner's vault key
+    updated_by UUID REFERENCES public.vault_profiles(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_shared_item_data_owner ON public.vault_shared_item_data(owner_id);
+
+CREATE TRIGGER update_vault_shared_item_data_updated_at
+    BEFORE UPDATE ON public.vault_shared_item_data
+    FOR EACH ROW EXECUTE FUNCTION public.update_updated_at();
+
+-- Where a share came from, for recipients who can't read the owner's profile
+ALTER TABLE public.vault_shared_items
+    ADD COLUMN IF NOT EXISTS shared_by_email TEXT;
+
+-- ============================================
+-- Recipient lookup
+-- Profiles are private, so sharing by email goes through this function. It
+-- only returns what sharing needs: the account id and its public key.
+-- ============================================
+
+CREATE OR REPLACE FUNCTION public.lookup_sharing_recipient(p_email TEXT)
+RETURNS TABLE (user_id UUID, public_key TEXT)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT p.id, k.public_key
+    FROM public.vault_profiles p
+    JOIN public.vault_user_public_keys k ON k.user_id = p.id
+    WHERE lower(p.email) = lower(p_email);
+$$;
+
+REVOKE ALL ON FUNCTION public.lookup_sharing_recipient(TEXT) FROM PUBLIC, anon;
+GRANT EXECUTE ON FUNCTION public.lookup_sharing_recipient(TEXT) TO authenticated;
+
+CREATE OR REPLACE FUNCTION public.shared_item_accept(share_id UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_shared_items
+    SET accepted_at = NOW()
+    WHERE id = share_id AND shared_with_user_id = auth.uid() AND accepted_at IS NULL;
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'No pending share for this account';
+    END IF;
+END;
+$$;
+
+REVOKE ALL ON FUNCTION public.shared_item_accept(UUID) FROM PUBLIC, anon;
+GRANT EXECUTE ON FUNCTION public.shared_item_accept(UUID) TO authenticated;
+
+-- ============================================
+-- RLS Policies - Shared Item Data
+-- ============================================
+
+ALTER TABLE public.vault_shared_item_data ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Owners and recipients can view shared item data"
+    ON public.vault_shared_item_data FOR SELECT
+    USING (
+        (select auth.uid()) = owner_id
+        OR EXISTS (
+            SELECT 1 FROM public.vault_shared_items s
+            WHERE s.vault_item_id = vault_shared_item_data.vault_item_id
+              AND s.shared_with_user_id = (select auth.uid())
+              AND (s.expires_at IS NULL OR s.expires_at > NOW())
+        )
+    );
+
+CREATE POLICY "Owners can insert shared item data"
+    ON public.vault_shared_item_data FOR INSERT
+    WITH CHECK (
+        (select auth.uid()) = owner_id
+        AND EXISTS (
+            SELECT 1 FROM public.vault_items i
+            WHERE i.id = vault_item_id AND i.user_id = (select auth.uid())
+        )
+    );
+
+CREATE POLICY "Owners and editors can update shared item data"
+    ON public.vault_shared_item_data FOR UPDATE
+    USING (
+        (select auth.uid()) = owner_id
+        OR EXISTS (
+            SELECT 1 FROM public.vault_shared_items s
+            WHERE s.vault_item_id = vault_shared_item_data.vault_item_id
+              AND s.shared_with_user_id = (select auth.uid())
+              AND s.permission = 'write'
+              AND s.accepted_at IS NOT NULL
+              AND (s.expires_at IS NULL OR s.expires_at > NOW())
+        )
+    )
+    -- Only the owner can re-key
+    WITH CHECK (
+        (select auth.uid()) = owner_id
+        OR owner_encrypted_key = (
+            SELECT d.owner_encrypted_key FROM public.vault_shared_item_data d
+            WHERE d.vault_item_id = vault_shared_item_data.vault_item_id
+        )
+    );
+
+CREATE POLICY "Owners can delete shared item data"
+    ON public.vault_shared_item_data FOR DELETE
+    USING ((select auth.uid()) = owner_id);
+
+-- ============================================
+-- RLS Policies - Shares
+-- Pending shares are visible to the invited email; recipients can leave
+-- ============================================
+
+DROP POLICY IF EXISTS "Users can view items shared with them" ON public.vault_shared_items;
+DROP POLICY IF EXISTS "Owners can delete shared items" ON public.vault_shared_items;
+
+CREATE POLICY "Users can view items shared with them"
+    ON public.vault_shared_items FOR SELECT
+    USING (
+        (select auth.uid()) IN (shared_with_user_id, shared_by_user_id)
+        OR (
+            shared_with_user_id IS NULL
+            AND lower(shared_with_email) = lower((select auth.jwt()) ->> 'email')
+        )
+    );
+
+CREATE POLICY "Owners and recipients can delete shared items"
+    ON public.vault_shared_items FOR DELETE
+    USING ((select auth.uid()) IN (shared_by_user_id, shared_with_user_id));
@@ -0,0 +1,293 @@
+-- ============================================
+-- Organizations and Collections
+-- Each organization has a random org key. Org items are vault_items rows
+-- with organization_id set, encrypted with the org key; every member holds
+-- the org key wrapped with their RSA-OAEP public key (vault_user_public_keys).
+-- Owners and admins manage the org and can edit any of its items; members
+-- can edit items in collections where they have write access.
+-- ============================================
+
+ALTER TABLE public.vault_org_members
+    ADD COLUMN IF NOT EXISTS email TEXT,
+    ADD COLUMN IF NOT EXISTS encrypted_org_key TEXT; -- Org key, RSA-OAEP with the member's public key
+
+CREATE INDEX IF NOT EXISTS idx_vault_collection_items_item ON public.vault_collection_items(vault_item_id);
+CREATE INDEX IF NOT EXISTS idx_vault_collection_access_user ON public.vault_collection_access(user_id);
+
+-- ============================================
+-- Access helpers
+-- SECURITY DEFINER so policies on vault_org_members can use them without
+-- recursing into themselves
+-- ============================================
+
+-- The caller's role in an organization, NULL unless they are an accepted member
+CREATE OR REPLACE FUNCTION public.vault_org_role(p_org UUID)
+RETURNS TEXT
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT role FROM public.vault_org_members
+    WHERE organization_id = p_org AND user_id = auth.uid() AND status = 'accepted';
+$$;
+
+CREATE OR REPLACE FUNCTION public.vault_org_is_admin(p_org UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT COALESCE(public.vault_org_role(p_org) IN ('owner', 'admin'), FALSE);
+$$;
+
+CREATE OR REPLACE FUNCTION public.vault_collection_writable(p_collection UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM public.vault_org_collections c
+        WHERE c.id = p_collection
+          AND (
+              public.vault_org_is_admin(c.organization_id)
+              OR (
+                  public.vault_org_role(c.organization_id) IS NOT NULL
+                  AND EXISTS (
+                      SELECT 1 FROM public.vault_collection_access ca
+                      WHERE ca.collection_id = c.id
+                        AND ca.user_id = auth.uid()
+                        AND ca.permission IN ('write', 'admin')
+                  )
+              )
+          )
+    );
+$$;
+
+-- Admins can write any org item; members any item in a collection they can write to
+CREATE OR REPLACE FUNCTION public.vault_org_item_writable(p_org UUID, p_item UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT public.vault_org_is_admin(p_org)
+        OR EXISTS (
+            SELECT 1 FROM public.vault_collection_items ci
+            WHERE ci.vault_item_id = p_item
+              AND public.vault_collection_writable(ci.collection_id)
+        );
+$$;
+
+-- Members can create items as long as they can write to some collection
+CREATE OR REPLACE FUNCTION public.vault_org_can_create_items(p_org UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT public.vault_org_is_admin(p_org)
+        OR EXISTS (
+            SELECT 1 FROM public.vault_org_collections c
+            WHERE c.organization_id = p_org
+              AND public.vault_collection_writable(c.id)
+        );
+$$;
+
+-- ============================================
+-- Functions
+-- ============================================
+
+-- Create an organization with the caller as owner, holding the org key
+CREATE OR REPLACE FUNCTION public.create_vault_organization(
+    p_name TEXT,
+    p_email TEXT,
+    p_encrypted_org_key TEXT
+)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    new_org_id UUID;
+BEGIN
+    INSERT INTO public.vault_organizations (name)
+    VALUES (p_name)
+    RETURNING id INTO new_org_id;
+
+    INSERT INTO public.vault_org_members (organization_id, user_id, role, status, email, encrypted_org_key)
+    VALUES (new_org_id, auth.uid(), 'owner', 'accepted', lower(p_email), p_encrypted_org_key);
+
+    RETURN new_org_id;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION public.vault_org_accept(p_org UUID)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE public.vault_org_members
+    SET status = 'accepted'
+    WHERE organization_id = p_org AND user_id = auth.uid() AND status = 'invited';
+
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'No pending invitation to this organization';
+    END IF;
+END;
+$$;
+
+REVOKE ALL ON FUNCTION public.create_vault_organization(TEXT, TEXT, TEXT) FROM PUBLIC, anon;
+REVOKE ALL ON FUNCTION public.vault_org_accept(UUID) FROM PUBLIC, anon;
+GRANT EXECUTE ON FUNCTION public.create_vault_organization(TEXT, TEXT, TEXT) TO authenticated;
+GRANT EXECUTE ON FUNCTION public.vault_org_accept(UUID) TO authenticated;
+
+-- ============================================
+-- RLS Policies - Members
+-- The previous policies queried vault_org_members from its own policies
+-- ============================================
+
+DROP POLICY IF EXISTS "Users can view org members" ON public.vault_org_members;
+DROP POLICY IF EXISTS "Admins can insert org members" ON public.vault_org_members;
+
+CREATE POLICY "Users can view org members"
+    ON public.vault_org_members FOR SELECT
+    USING (
+        (select auth.uid()) = user_id
+        OR public.vault_org_role(organization_id) IS NOT NULL
+    );
+
+-- Invitations only; owners come from create_vault_organization
+CREATE POLICY "Admins can invite org members"
+    ON public.vault_org_members FOR INSERT
+    WITH CHECK (
+        public.vault_org_is_admin(organization_id)
+        AND role <> 'owner'
+        AND status = 'invited'
+    );
+
+CREATE POLICY "Admins can change member roles"
+    ON public.vault_org_members FOR UPDATE
+    USING (public.vault_org_is_admin(organization_id) AND role <> 'owner')
+    WITH CHECK (role <> 'owner');
+
+-- Admins remove members; anyone but the owner can leave
+CREATE POLICY "Admins and members can remove memberships"
+    ON public.vault_org_members FOR DELETE
+    USING (
+        role <> 'owner'
+        AND (
+            (select auth.uid()) = user_id
+            OR public.vault_org_is_admin(organization_id)
+        )
+    );
+
+-- ============================================
+-- RLS Policies - Collections
+-- ============================================
+
+CREATE POLICY "Org members can view collection items"
+    ON public.vault_collection_items FOR SELECT
+    USING (
+        EXISTS (
+            SELECT 1 FROM public.vault_org_collections c
+            WHERE c.id = collection_id
+              AND public.vault_org_role(c.organization_id) IS NOT NULL
+        )
+    );
+
+CREATE POLICY "Collection writers can add items"
+    ON public.vault_collection_items FOR INSERT
+    WITH CHECK (public.vault_collection_writable(collection_id));
+
+CREATE POLICY "Collection writers can remove items"
+    ON public.vault_collection_items FOR DELETE
+    USING (public.vault_collection_writable(collection_id));
+
+CREATE POLICY "Users can view their collection access"
+    ON public.vault_collection_access FOR SELECT
+    USING (
+        (select auth.uid()) = user_id
+        OR EXISTS (
+            SELECT 1 FROM public.vault_org_collections c
+            WHERE c.id = collection_id AND public.vault_org_is_admin(c.organization_id)
+        )
+    );
+
+CREATE POLICY "Org admins can manage collection access"
+    ON public.vault_collection_access FOR ALL
+    USING (
+        EXISTS (
+            SELECT 1 FROM public.vault_org_collections c
+            WHERE c.id = collection_id AND public.vault_org_is_admin(c.organization_id)
+        )
+    );
+
+-- ============================================
+-- RLS Policies - Vault Items
+-- ============================================
+
+DROP POLICY IF EXISTS "Users can view own vault items" ON public.vault_items;
+DROP POLICY IF EXISTS "Users can insert own vault items" ON public.vault_items;
+DROP POLICY IF EXISTS "Users can update own vault items" ON public.vault_items;
+DROP POLICY IF EXISTS "Users can delete own vault items" ON public.vault_items;
+
+CREATE POLICY "Users can view own vault items"
+    ON public.vault_items FOR SELECT
+    USING (
+        (select auth.uid()) = user_id
+        OR (
+            organization_id IS NOT NULL
+            AND public.vault_org_role(organization_id) IS NOT NULL
+        )
+    );
+
+CREATE POLICY "Users can insert own vault items"
+    ON public.vault_items FOR INSERT
+    WITH CHECK (
+        (select auth.uid()) = user_id
+        AND (
+            organization_id IS NULL
+            OR public.vault_org_can_create_items(organization_id)
+        )
+    );
+
+-- Org items keep their creator in user_id, so access goes by organization
+CREATE POLICY "Users can update own vault items"
+    ON public.vault_items FOR UPDATE
+    USING (
+        CASE WHEN organization_id IS NULL
+            THEN (select auth.uid()) = user_id
+            ELSE public.vault_org_item_writable(organization_id, id)
+        END
+    )
+    WITH CHECK (
+        CASE WHEN organization_id IS NULL
+            THEN (select auth.uid()) = user_id
+            ELSE public.vault_org_item_writable(organization_id, id)
+        END
+    );
+
+CREATE POLICY "Users can delete own vault items"
+    ON public.vault_items FOR DELETE
+    USING (
+        CASE WHEN organization_id IS NULL
+            THEN (select auth.uid()) = user_id
+            ELSE public.vault_org_item_writable(organization_id, id)
+        END
+    );
+
+-- ============================================
+-- Schema version
+-- Clients now sync organization items; older clients keep working
+-- ============================================
+
+UPDATE public.schema_meta SET version = 2 WHERE id = 1 AND version < 2;
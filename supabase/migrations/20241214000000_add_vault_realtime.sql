@@ -0,0 +1,9 @@
+-- ============================================
+-- Realtime change feed for vault items and folders
+-- Desktop clients subscribe to these so changes made on another device are
+-- pulled within seconds. Notifications only prompt a pull; RLS still
+-- decides which rows a subscriber hears about.
+-- ============================================
+
+ALTER PUBLICATION supabase_realtime ADD TABLE public.vault_items;
+ALTER PUBLICATION supabase_realtime ADD TABLE public.vault_folders;
@@ -0,0 +1,55 @@
+-- ============================================
+-- Tombstones for permanent deletes
+-- A deleted row can't be pulled, so other devices never learned that an
+-- item or folder was gone for good. Each delete now leaves a tombstone the
+-- clients pull after their items and apply locally.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.vault_tombstones (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    table_name TEXT NOT NULL,   -- vault_items or vault_folders
+    record_id UUID NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_tombstones_user_deleted
+    ON public.vault_tombstones(user_id, deleted_at);
+
+ALTER TABLE public.vault_tombstones ENABLE ROW LEVEL SECURITY;
+
+-- Written only by the trigger below
+CREATE POLICY "Users can view own tombstones"
+    ON public.vault_tombstones FOR SELECT
+    USING ((select auth.uid()) = user_id);
+
+CREATE OR REPLACE FUNCTION public.vault_record_tombstone()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    INSERT INTO public.vault_tombstones (user_id, table_name, record_id)
+    VALUES (OLD.user_id, TG_TABLE_NAME, OLD.id);
+    RETURN OLD;
+END;
+$$;
+
+CREATE TRIGGER vault_items_tombstone
+    AFTER DELETE ON public.vault_items
+    FOR EACH ROW EXECUTE FUNCTION public.vault_record_tombstone();
+
+CREATE TRIGGER vault_folders_tombstone
+    AFTER DELETE ON public.vault_folders
+    FOR EACH ROW EXECUTE FUNCTION public.vault_record_tombstone();
+
+-- Deletes reach other devices as quickly as edits
+ALTER PUBLICATION supabase_realtime ADD TABLE public.vault_tombstones;
+
+-- ============================================
+-- Schema version
+-- Clients now pull tombstones; older clients keep working
+-- ============================================
+
+UPDATE public.schema_meta SET version = 3 WHERE id = 1 AND version < 3;
@@ -0,0 +1,53 @@
+-- ============================================
+-- Recovery Keys
+-- A recovery key is a random key the user prints and keeps. It wraps their
+-- master key, so a forgotten master password can be replaced without losing
+-- the vault. Only the wrapped master key and a hash of the recovery key are
+-- stored; the key itself never leaves the client.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.vault_recovery_keys (
+    user_id UUID PRIMARY KEY REFERENCES public.vault_profiles(id) ON DELETE CASCADE,
+    lookup_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the recovery key, hex
+    encrypted_master_key TEXT NOT NULL, -- Master key encrypted with the recovery key
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE public.vault_recovery_keys ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Users can view own recovery key"
+    ON public.vault_recovery_keys FOR SELECT
+    USING ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can insert own recovery key"
+    ON public.vault_recovery_keys FOR INSERT
+    WITH CHECK ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can update own recovery key"
+    ON public.vault_recovery_keys FOR UPDATE
+    USING ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can delete own recovery key"
+    ON public.vault_recovery_keys FOR DELETE
+    USING ((select auth.uid()) = user_id);
+
+-- ============================================
+-- Lookup
+-- Recovery starts signed out, so this is open to anon. Finding a row takes
+-- the recovery key itself, which is what the wrapped master key needs too.
+-- ============================================
+
+CREATE OR REPLACE FUNCTION public.vault_recovery_key_lookup(p_lookup_hash TEXT)
+RETURNS TABLE (email TEXT, encrypted_master_key TEXT)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT p.email, r.encrypted_master_key
+    FROM public.vault_recovery_keys r
+    JOIN public.vault_profiles p ON p.id = r.user_id
+    WHERE r.lookup_hash = p_lookup_hash;
+$$;
+
+GRANT EXECUTE ON FUNCTION public.vault_recovery_key_lookup(TEXT) TO anon, authenticated;
@@ -0,0 +1,132 @@
+-- ============================================
+-- Sends
+-- A text or file shared by link with someone who needn't have an account.
+-- It is encrypted with a random key that only travels in the link's
+-- fragment, so neither the server nor the web app ever sees it. The
+-- ciphertext lives in the `sends` bucket at <user_id>/<send id>, as the
+-- same EncryptedData JSON the rest of the vault uses. The row keeps the
+-- expiry and view limit, plus the send key wrapped with the owner's vault
+-- key so they can copy the link again later.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public.vault_sends (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES public.vault_profiles(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('text', 'file')),
+    encrypted_name TEXT, -- File name or label, encrypted with the send key
+    encrypted_key TEXT NOT NULL, -- Send key encrypted with the owner's vault key
+    size BIGINT NOT NULL,
+    max_views INTEGER CHECK (max_views > 0), -- NULL: no limit
+    view_count INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_opened_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_sends_user_id ON public.vault_sends(user_id);
+
+ALTER TABLE public.vault_sends ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Users can view own sends"
+    ON public.vault_sends FOR SELECT
+    USING ((select auth.uid()) = user_id);
+
+CREATE POLICY "Users can insert own sends"
+    ON public.vault_sends FOR INSERT
+    WITH CHECK ((select auth.uid()) = user_id AND view_count = 0);
+
+CREATE POLICY "Users can delete own sends"
+    ON public.vault_sends FOR DELETE
+    USING ((select auth.uid()) = user_id);
+
+-- ============================================
+-- Storage
+-- ============================================
+
+INSERT INTO storage.buckets (id, name, public, file_size_limit, allowed_mime_types)
+VALUES (
+  'sends',
+  'sends',
+  false,
+  16777216, -- 16MB; the app's 10MB limit grows by a third as base64
+  ARRAY['application/json']
+)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE POLICY "Users can upload their own sends"
+ON storage.objects FOR INSERT
+TO authenticated
+WITH CHECK (
+  bucket_id = 'sends' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+-- Storage needs this as well to delete an object
+CREATE POLICY "Users can view their own sends"
+ON storage.objects FOR SELECT
+TO authenticated
+USING (
+  bucket_id = 'sends' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+CREATE POLICY "Users can delete their own sends"
+ON storage.objects FOR DELETE
+TO authenticated
+USING (
+  bucket_id = 'sends' AND
+  (storage.foldername(name))[1] = auth.uid()::text
+);
+
+-- Recipients download for a few minutes after opening the send, which is
+-- what counts the view; past the expiry nothing is served at all. Checked
+-- as the definer, since anon can't see `vault_sends` itself.
+CREATE OR REPLACE FUNCTION public.vault_send_downloadable(p_object TEXT)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM public.vault_sends s
+        WHERE s.id::text = storage.filename(p_object)
+          AND s.user_id::text = (storage.foldername(p_object))[1]
+          AND s.expires_at > NOW()
+          AND s.last_opened_at > NOW() - INTERVAL '5 minutes'
+    );
+$$;
+
+GRANT EXECUTE ON FUNCTION public.vault_send_downloadable(TEXT) TO anon, authenticated;
+
+CREATE POLICY "Anyone can download a send they just opened"
+ON storage.objects FOR SELECT
+TO anon, authenticated
+USING (
+  bucket_id = 'sends' AND
+  public.vault_send_downloadable(name)
+);
+
+-- ============================================
+-- Opening
+-- Open to anon: the link is all a recipient has. Each call counts a view,
+-- and none are left once the limit is reached or the send has expired.
+-- ============================================
+
+CREATE OR REPLACE FUNCTION public.vault_send_open(p_id UUID)
+RETURNS TABLE (user_id UUID, kind TEXT, encrypted_name TEXT, size BIGINT)
+LANGUAGE sql
+VOLATILE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    UPDATE public.vault_sends s
+    SET view_count = s.view_count + 1,
+        last_opened_at = NOW()
+    WHERE s.id = p_id
+      AND s.expires_at > NOW()
+      AND (s.max_views IS NULL OR s.view_count < s.max_views)
+    RETURNING s.user_id, s.kind, s.encrypted_name, s.size;
+$$;
+
+GRANT EXECUTE ON FUNCTION public.vault_send_open(UUID) TO anon, authenticated;
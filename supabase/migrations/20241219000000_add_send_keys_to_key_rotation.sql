@@ -0,0 +1,99 @@
+-- ============================================
+-- Send Keys in Key Rotation
+-- Each send's key is wrapped with the owner's vault key so the link can be
+-- copied again later, so rotation has to re-wrap those too. Same function
+-- as before, with the sends' keys swapped in the same transaction.
+-- ============================================
+
+DROP FUNCTION IF EXISTS public.vault_rotate_key(TEXT, TEXT, JSONB, JSONB, JSONB, JSONB);
+
+CREATE OR REPLACE FUNCTION public.vault_rotate_key(
+    p_encrypted_symmetric_key TEXT,
+    p_encrypted_private_key TEXT,   -- NULL if the account has no key pair yet
+    p_items JSONB,                  -- [{ id, encrypted_data, updated_at }], every item
+    p_attachments JSONB,            -- [{ id, encrypted_name, encrypted_key }], every attachment
+    p_shared_keys JSONB,            -- [{ vault_item_id, owner_encrypted_key }], every shared item
+    p_emergency_keys JSONB,         -- [{ access_id, encrypted_key }], every escrowed key
+    p_send_keys JSONB               -- [{ id, encrypted_key }], every send
+)
+RETURNS TABLE (item_id UUID, server_updated_at TIMESTAMPTZ)
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID := auth.uid();
+    v_expected INTEGER;
+    v_updated INTEGER;
+BEGIN
+    IF v_user_id IS NULL THEN
+        RAISE EXCEPTION 'Not signed in';
+    END IF;
+
+    -- Items: each must still be the version the client re-encrypted
+    SELECT COUNT(*) INTO v_expected FROM public.vault_items i WHERE i.user_id = v_user_id;
+    UPDATE public.vault_items i
+    SET encrypted_data = e.encrypted_data
+    FROM jsonb_to_recordset(p_items) AS e(id UUID, encrypted_data TEXT, updated_at TIMESTAMPTZ)
+    WHERE i.id = e.id AND i.user_id = v_user_id AND i.updated_at = e.updated_at;
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    IF v_updated <> v_expected OR v_updated <> jsonb_array_length(p_items) THEN
+        RAISE EXCEPTION 'The vault changed during key rotation; sync and try again';
+    END IF;
+
+    SELECT COUNT(*) INTO v_expected FROM public.vault_attachments a WHERE a.user_id = v_user_id;
+    UPDATE public.vault_attachments a
+    SET encrypted_name = e.encrypted_name, encrypted_key = e.encrypted_key
+    FROM jsonb_to_recordset(p_attachments) AS e(id UUID, encrypted_name TEXT, encrypted_key TEXT)
+    WHERE a.id = e.id AND a.user_id = v_user_id;
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    IF v_updated <> v_expected THEN
+        RAISE EXCEPTION 'Attachments changed during key rotation; sync and try again';
+    END IF;
+
+    SELECT COUNT(*) INTO v_expected FROM public.vault_shared_item_data d WHERE d.owner_id = v_user_id;
+    UPDATE public.vault_shared_item_data d
+    SET owner_encrypted_key = e.owner_encrypted_key
+    FROM jsonb_to_recordset(p_shared_keys) AS e(vault_item_id UUID, owner_encrypted_key TEXT)
+    WHERE d.vault_item_id = e.vault_item_id AND d.owner_id = v_user_id;
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    IF v_updated <> v_expected THEN
+        RAISE EXCEPTION 'Shared items changed during key rotation; try again';
+    END IF;
+
+    SELECT COUNT(*) INTO v_expected
+    FROM public.vault_emergency_access_keys k
+    JOIN public.vault_emergency_access ea ON ea.id = k.access_id
+    WHERE ea.grantor_id = v_user_id;
+    UPDATE public.vault_emergency_access_keys k
+    SET encrypted_key = e.encrypted_key, created_at = NOW()
+    FROM jsonb_to_recordset(p_emergency_keys) AS e(access_id UUID, encrypted_key TEXT),
+         public.vault_emergency_access ea
+    WHERE k.access_id = e.access_id AND ea.id = k.access_id AND ea.grantor_id = v_user_id;
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    IF v_updated <> v_expected THEN
+        RAISE EXCEPTION 'Emergency contacts changed during key rotation; try again';
+    END IF;
+
+    SELECT COUNT(*) INTO v_expected FROM public.vault_sends s WHERE s.user_id = v_user_id;
+    UPDATE public.vault_sends s
+    SET encrypted_key = e.encrypted_key
+    FROM jsonb_to_recordset(p_send_keys) AS e(id UUID, encrypted_key TEXT)
+    WHERE s.id = e.id AND s.user_id = v_user_id;
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    IF v_updated <> v_expected THEN
+        RAISE EXCEPTION 'Sends changed during key rotation; try again';
+    END IF;
+
+    UPDATE public.vault_profiles p
+    SET encrypted_symmetric_key = p_encrypted_symmetric_key,
+        encrypted_private_key = COALESCE(p_encrypted_private_key, p.encrypted_private_key)
+    WHERE p.id = v_user_id;
+
+    -- New server versions, so the client's next guarded push matches
+    RETURN QUERY
+    SELECT i.id, i.updated_at FROM public.vault_items i WHERE i.user_id = v_user_id;
+END;
+$$;
+
+GRANT EXECUTE ON FUNCTION public.vault_rotate_key(TEXT, TEXT, JSONB, JSONB, JSONB, JSONB, JSONB) TO authenticated;